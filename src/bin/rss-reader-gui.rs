@@ -1,8 +1,8 @@
 //! GUI binary for the RSS reader. Uses the same storage as the CLI.
 
 fn main() {
-    let path = rss_reader::gui::default_config_path();
-    if let Err(e) = rss_reader::gui::run(path) {
+    let result = rss_reader::settings::Settings::from_env(None).and_then(rss_reader::gui::run);
+    if let Err(e) = result {
         eprintln!("{}", e);
         std::process::exit(1);
     }
@@ -1,12 +1,68 @@
-//! Add feed by URL.
+//! Add feed by URL. A website URL is searched for the feeds it offers; the user picks one
+//! interactively, `--all` adds every one, and JSON output lists them as candidates.
 
-use crate::fetch::fetch_feed;
+use crate::discover::{discover_feed, Discovery, FeedCandidate};
+use crate::fetch::{fetch_feed, HttpOptions};
+use crate::store::Storage;
 use crate::Error;
 use crate::Result;
 use crate::SubscriptionList;
-use std::path::Path;
+use std::io::{BufRead, Write};
 
-pub fn run(store: &mut SubscriptionList, url: &str, path: &Path, output_json: bool) -> Result<()> {
+pub fn run(
+    store: &mut SubscriptionList,
+    url: &str,
+    all: bool,
+    storage: &dyn Storage,
+    http: &HttpOptions,
+    output_json: bool,
+) -> Result<()> {
+    let added = match add(store, url, all, http)? {
+        Added::Feeds(urls) => urls,
+        Added::Candidates(candidates) if output_json => {
+            let obj = candidates_json(url.trim(), &candidates);
+            println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+            return Err(Error::MultipleFeeds(candidates.len()));
+        }
+        Added::Candidates(candidates) => {
+            let chosen = choose(&candidates)?;
+            if chosen.is_empty() {
+                println!("No feed added.");
+                return Ok(());
+            }
+            add_candidates(store, &chosen, http)?
+        }
+    };
+    storage.save(store)?;
+    if output_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&added_json(&added)).unwrap()
+        );
+    } else {
+        for url in &added {
+            println!("Added feed: {}", url);
+        }
+    }
+    Ok(())
+}
+
+/// Outcome of [`add`].
+pub(crate) enum Added {
+    /// Urls of the feeds added to the store.
+    Feeds(Vec<String>),
+    /// A website offering several feeds; nothing was added.
+    Candidates(Vec<FeedCandidate>),
+}
+
+/// Add `url` to `store` (without saving). A website's feeds are discovered; a single one is
+/// added, several only with `all`, otherwise they are returned for the caller to choose from.
+pub(crate) fn add(
+    store: &mut SubscriptionList,
+    url: &str,
+    all: bool,
+    http: &HttpOptions,
+) -> Result<Added> {
     let url = url.trim();
     if url.is_empty() {
         return Err(Error::InvalidUrl("empty URL".to_string()));
@@ -14,14 +70,117 @@ pub fn run(store: &mut SubscriptionList, url: &str, path: &Path, output_json: bo
     if let Err(e) = url.parse::<url::Url>() {
         return Err(Error::InvalidUrl(e.to_string()));
     }
-    let (feed, items) = fetch_feed(url)?;
-    store.add_feed(feed, items);
-    store.save(path)?;
-    if output_json {
-        let obj = serde_json::json!({ "success": true, "message": format!("Added feed: {}", url) });
-        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
-    } else {
-        println!("Added feed: {}", url);
+    let candidates = match discover_feed(url, all, http)? {
+        Discovery::Feed(feed, items) => {
+            store.add_feed(feed, items);
+            return Ok(Added::Feeds(vec![url.to_string()]));
+        }
+        Discovery::Candidates(candidates) => candidates,
+    };
+    match candidates.len() {
+        0 => Err(Error::NotFound(format!("no feeds found at {}", url))),
+        1 => add_candidates(store, &[&candidates[0]], http).map(Added::Feeds),
+        _ if all => {
+            let chosen: Vec<&FeedCandidate> = candidates.iter().collect();
+            add_candidates(store, &chosen, http).map(Added::Feeds)
+        }
+        _ => Ok(Added::Candidates(candidates)),
     }
-    Ok(())
+}
+
+/// Fetch and add each of `chosen`; returns their urls.
+fn add_candidates(
+    store: &mut SubscriptionList,
+    chosen: &[&FeedCandidate],
+    http: &HttpOptions,
+) -> Result<Vec<String>> {
+    let mut added = Vec::new();
+    for candidate in chosen {
+        let (feed, items) = fetch_feed(&candidate.url, http)?;
+        store.add_feed(feed, items);
+        added.push(candidate.url.clone());
+    }
+    Ok(added)
+}
+
+/// Numbered list of `candidates` and a prompt on stdin; empty input (or end of input) cancels.
+fn choose(candidates: &[FeedCandidate]) -> Result<Vec<&FeedCandidate>> {
+    println!("Found {} feeds:", candidates.len());
+    for (i, c) in candidates.iter().enumerate() {
+        let mut line = format!("  {}) {}", i + 1, c.title.as_deref().unwrap_or(&c.url));
+        if c.title.is_some() {
+            line.push_str(&format!(" ({})", c.url));
+        }
+        if let Some(kind) = &c.kind {
+            line.push_str(&format!(" [{}]", kind));
+        }
+        println!("{}", line);
+    }
+    loop {
+        print!(
+            "Add which feed? [1-{}, a = all, empty = cancel]: ",
+            candidates.len()
+        );
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+            println!();
+            return Ok(Vec::new());
+        }
+        match answer.trim() {
+            "" => return Ok(Vec::new()),
+            "a" | "A" | "all" => return Ok(candidates.iter().collect()),
+            n => match n.parse::<usize>() {
+                Ok(i) if (1..=candidates.len()).contains(&i) => {
+                    return Ok(vec![&candidates[i - 1]]);
+                }
+                _ => println!("Please enter a number between 1 and {}.", candidates.len()),
+            },
+        }
+    }
+}
+
+/// Object printed by `add -o json` when a website offers several feeds.
+pub(crate) fn candidates_json(url: &str, candidates: &[FeedCandidate]) -> serde_json::Value {
+    let list: Vec<serde_json::Value> = candidates
+        .iter()
+        .map(|c| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("url".into(), serde_json::Value::String(c.url.clone()));
+            obj.insert(
+                "title".into(),
+                c.title
+                    .clone()
+                    .map(serde_json::Value::String)
+                    .unwrap_or(serde_json::Value::Null),
+            );
+            obj.insert(
+                "type".into(),
+                c.kind
+                    .clone()
+                    .map(serde_json::Value::String)
+                    .unwrap_or(serde_json::Value::Null),
+            );
+            serde_json::Value::Object(obj)
+        })
+        .collect();
+    serde_json::json!({
+        "success": false,
+        "message": format!(
+            "Found {} feeds at {}; add one by url or pass --all",
+            candidates.len(),
+            url
+        ),
+        "candidates": list,
+    })
+}
+
+/// Object printed by `add -o json` after adding `urls`.
+pub(crate) fn added_json(urls: &[String]) -> serde_json::Value {
+    let message = urls
+        .iter()
+        .map(|u| format!("Added feed: {}", u))
+        .collect::<Vec<_>>()
+        .join("\n");
+    serde_json::json!({ "success": true, "message": message, "added": urls })
 }
@@ -0,0 +1,165 @@
+//! Long-running refresh daemon: refreshes each feed when its schedule says it is due and
+//! writes results into the shared store, then works through the download queue. Logs one
+//! line per refreshed feed and download (one JSON object per line with `-o json`).
+
+use crate::media::download::{apply_retention, run_queue};
+use crate::refresh::{refresh_feeds, RefreshOptions};
+use crate::schedule::{due_feeds, next_refresh, next_wake, ScheduleOptions};
+use crate::settings::Settings;
+use crate::store::{Download, Storage};
+use crate::{Feed, FetchOutcome};
+use chrono::Utc;
+use std::time::Duration;
+
+/// Longest sleep between store reloads, so feeds added meanwhile are picked up.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+pub fn run(
+    storage: &dyn Storage,
+    schedule: &ScheduleOptions,
+    options: &RefreshOptions,
+    settings: &Settings,
+    once: bool,
+    output_json: bool,
+) -> crate::Result<()> {
+    if !output_json && !once {
+        println!("Refresh daemon started; press Ctrl-C to stop.");
+    }
+    let download_dir = settings.download_queue_dir();
+    loop {
+        // Reload every round: other processes (CLI, GUI) may have changed the store.
+        let mut store = storage.load()?;
+        let due: Vec<Feed> = due_feeds(&store, Utc::now(), schedule)
+            .into_iter()
+            .cloned()
+            .collect();
+        if !due.is_empty() {
+            for done in refresh_feeds(due, options) {
+                let (status, detail) = match &done.result {
+                    Ok(FetchOutcome::Updated(_, items)) => {
+                        ("fetched", format!("{} item(s)", items.len()))
+                    }
+                    Ok(FetchOutcome::NotModified { .. }) => ("unchanged", String::new()),
+                    Err(e) => ("failed", e.to_string()),
+                };
+                let url = done.url.clone();
+                done.apply(&mut store);
+                let next = store
+                    .feeds
+                    .iter()
+                    .find(|f| f.url == url)
+                    .and_then(|f| next_refresh(f, schedule));
+                log(&url, status, &detail, next, settings, output_json);
+            }
+            storage.save(&mut store)?;
+        }
+        if !store.due_downloads(Utc::now()).is_empty() {
+            // Saved after each download, so the queue survives a restart mid-way. Failed
+            // downloads wait for their retry time.
+            let finished = run_queue(
+                &mut store,
+                storage,
+                &download_dir,
+                None,
+                false,
+                &options.http,
+                &mut |_, _, _| {},
+            )?;
+            for download in finished {
+                log_download(&download, settings, output_json);
+            }
+            if !apply_retention(&mut store)?.is_empty() {
+                storage.save(&mut store)?;
+            }
+        }
+        if once {
+            return Ok(());
+        }
+        let sleep = next_wake(&store, schedule)
+            .and_then(|at| (at - Utc::now()).to_std().ok())
+            .unwrap_or(MAX_SLEEP)
+            .min(MAX_SLEEP);
+        std::thread::sleep(sleep.max(Duration::from_secs(1)));
+    }
+}
+
+fn log_download(download: &Download, settings: &Settings, output_json: bool) {
+    let now = Utc::now();
+    if output_json {
+        let mut obj = serde_json::Map::new();
+        obj.insert("time".into(), serde_json::Value::String(now.to_rfc3339()));
+        obj.insert(
+            "url".into(),
+            serde_json::Value::String(download.url.clone()),
+        );
+        obj.insert(
+            "status".into(),
+            serde_json::Value::String(download.status.as_str().into()),
+        );
+        if let Some(path) = &download.path {
+            obj.insert(
+                "path".into(),
+                serde_json::Value::String(path.display().to_string()),
+            );
+        }
+        if let Some(error) = &download.error {
+            obj.insert("error".into(), serde_json::Value::String(error.clone()));
+        }
+        println!("{}", serde_json::Value::Object(obj));
+    } else {
+        let detail = match (&download.path, &download.error) {
+            (_, Some(error)) => error.clone(),
+            (Some(path), None) => path.display().to_string(),
+            (None, None) => String::new(),
+        };
+        println!(
+            "{}  {:<9}  {}  {}",
+            settings.format_date(&now),
+            if download.error.is_some() {
+                "failed"
+            } else {
+                "download"
+            },
+            download.url,
+            detail
+        );
+    }
+}
+
+fn log(
+    url: &str,
+    status: &str,
+    detail: &str,
+    next: Option<chrono::DateTime<Utc>>,
+    settings: &Settings,
+    output_json: bool,
+) {
+    let now = Utc::now();
+    if output_json {
+        let mut obj = serde_json::Map::new();
+        obj.insert("time".into(), serde_json::Value::String(now.to_rfc3339()));
+        obj.insert("url".into(), serde_json::Value::String(url.to_string()));
+        obj.insert("status".into(), serde_json::Value::String(status.into()));
+        if status == "failed" {
+            obj.insert("error".into(), serde_json::Value::String(detail.into()));
+        }
+        obj.insert(
+            "next_refresh".into(),
+            next.map(|d| serde_json::Value::String(d.to_rfc3339()))
+                .unwrap_or(serde_json::Value::Null),
+        );
+        println!("{}", serde_json::Value::Object(obj));
+    } else {
+        let next = next
+            .map(|d| settings.format_date(&d))
+            .unwrap_or_else(|| "now".to_string());
+        println!(
+            "{}  {:<9}  {}  {} (next: {})",
+            settings.format_date(&now),
+            status,
+            url,
+            detail,
+            next
+        );
+    }
+}
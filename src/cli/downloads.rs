@@ -0,0 +1,255 @@
+//! Manage the enclosure download queue: list, queue, run and remove downloads, set a
+//! feed's auto-download and retention policy, and apply retention.
+
+use crate::media::download::{apply_retention, run_queue};
+use crate::settings::Settings;
+use crate::store::{Download, Storage};
+use crate::{DownloadPolicy, SubscriptionList};
+use std::io::{IsTerminal, Write};
+
+/// `downloads` subcommands.
+#[derive(clap::Subcommand, Debug)]
+pub enum DownloadsCommand {
+    /// List queued, failed and finished downloads.
+    List,
+    /// Queue an item's enclosure for download (again, after it failed).
+    Add {
+        item_id: String,
+        /// Enclosure index (0-based).
+        #[arg(long, default_value_t = 0)]
+        index: usize,
+    },
+    /// Download the queued entries and retry the failed ones at once (resuming partial
+    /// files), then apply the feeds' retention policies. Entries that failed too often are
+    /// skipped until queued again with `add`.
+    Run {
+        /// Download at most this many.
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Remove an item's downloads from the queue.
+    Remove {
+        item_id: String,
+        /// Delete the downloaded files too.
+        #[arg(long)]
+        delete_file: bool,
+    },
+    /// Set a feed's download policy; options not given are turned off.
+    Policy {
+        url: String,
+        /// Queue the enclosure of every new item.
+        #[arg(long)]
+        auto: bool,
+        /// Keep only the files of the N newest items.
+        #[arg(long)]
+        keep: Option<usize>,
+        /// Delete files once their item is marked read (played).
+        #[arg(long)]
+        delete_played: bool,
+    },
+    /// Delete the files the feeds' retention policies no longer keep.
+    Clean,
+}
+
+pub fn run(
+    store: &mut SubscriptionList,
+    cmd: &DownloadsCommand,
+    storage: &dyn Storage,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    let message = match cmd {
+        DownloadsCommand::List => return list(store, settings, output_json),
+        DownloadsCommand::Run { limit } => {
+            return run_downloads(store, *limit, storage, settings, output_json)
+        }
+        DownloadsCommand::Add { item_id, index } => {
+            if store.queue_download(item_id, *index)? {
+                format!("Queued enclosure {} of {}", index, item_id)
+            } else {
+                format!(
+                    "Already queued or downloaded: enclosure {} of {}",
+                    index, item_id
+                )
+            }
+        }
+        DownloadsCommand::Remove {
+            item_id,
+            delete_file,
+        } => {
+            let removed = store.remove_downloads(item_id);
+            if removed.is_empty() {
+                return Err(crate::Error::NotFound(format!(
+                    "no downloads for item: {}",
+                    item_id
+                )));
+            }
+            if *delete_file {
+                for path in removed.iter().filter_map(|d| d.path.as_ref()) {
+                    match std::fs::remove_file(path) {
+                        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                        _ => {}
+                    }
+                }
+            }
+            format!("Removed {} download(s) of {}", removed.len(), item_id)
+        }
+        DownloadsCommand::Policy {
+            url,
+            auto,
+            keep,
+            delete_played,
+        } => {
+            let policy = DownloadPolicy {
+                auto: *auto,
+                keep: *keep,
+                delete_played: *delete_played,
+            };
+            store.set_download_policy(url, policy.clone())?;
+            format!("Download policy of {}: {}", url, describe(&policy))
+        }
+        DownloadsCommand::Clean => {
+            let removed = apply_retention(store)?;
+            format!("Deleted {} download(s)", removed.len())
+        }
+    };
+    storage.save(store)?;
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "message": message });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("{}", message);
+    }
+    Ok(())
+}
+
+fn run_downloads(
+    store: &mut SubscriptionList,
+    limit: Option<usize>,
+    storage: &dyn Storage,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    let show_progress = !output_json && std::io::stderr().is_terminal();
+    let dir = settings.download_queue_dir();
+    let finished = run_queue(
+        store,
+        storage,
+        &dir,
+        limit,
+        true,
+        &settings.http_options(),
+        &mut |download, written, total| {
+            if show_progress {
+                let percent = total
+                    .filter(|t| *t > 0)
+                    .map(|t| format!(" ({}%)", written * 100 / t))
+                    .unwrap_or_default();
+                eprint!("\r\x1b[K{}: {}{}", label(download), size(written), percent);
+                let _ = std::io::stderr().flush();
+            }
+        },
+    )?;
+    if show_progress && !finished.is_empty() {
+        eprintln!();
+    }
+    let removed = apply_retention(store)?;
+    storage.save(store)?;
+    if output_json {
+        let obj = serde_json::json!({
+            "downloads": finished.iter().map(download_json).collect::<Vec<_>>(),
+            "deleted": removed.iter().map(download_json).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+        return Ok(());
+    }
+    if finished.is_empty() {
+        println!("Nothing to download");
+    }
+    for download in &finished {
+        match (&download.path, &download.error) {
+            (Some(path), None) => println!("Downloaded {}", path.display()),
+            (_, error) => println!(
+                "Failed {}: {}",
+                download.url,
+                error.as_deref().unwrap_or("unknown error")
+            ),
+        }
+    }
+    for download in &removed {
+        if let Some(path) = &download.path {
+            println!("Deleted {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn list(store: &SubscriptionList, settings: &Settings, output_json: bool) -> crate::Result<()> {
+    if output_json {
+        let arr: Vec<serde_json::Value> = store.downloads.iter().map(download_json).collect();
+        println!("{}", serde_json::to_string_pretty(&arr).unwrap());
+        return Ok(());
+    }
+    for download in &store.downloads {
+        let detail = match (&download.path, &download.error) {
+            (_, Some(error)) => match download.retry_at {
+                Some(at) => format!("{} (retry after {})", error, settings.format_date(&at)),
+                None => format!("{} (gave up; queue it again with `add`)", error),
+            },
+            (Some(path), None) => format!("{} ({})", path.display(), size(download.bytes)),
+            (None, None) => download.url.clone(),
+        };
+        println!(
+            "{:<6} | {} | {} | {}",
+            download.status.as_str(),
+            download.item_id,
+            label(download),
+            detail
+        );
+    }
+    Ok(())
+}
+
+fn download_json(download: &Download) -> serde_json::Value {
+    serde_json::to_value(download).unwrap_or(serde_json::Value::Null)
+}
+
+fn label(download: &Download) -> &str {
+    download.title.as_deref().unwrap_or(&download.url)
+}
+
+fn describe(policy: &DownloadPolicy) -> String {
+    let mut parts = Vec::new();
+    if policy.auto {
+        parts.push("auto-download".to_string());
+    }
+    if let Some(keep) = policy.keep {
+        parts.push(format!("keep {}", keep));
+    }
+    if policy.delete_played {
+        parts.push("delete played".to_string());
+    }
+    if parts.is_empty() {
+        "manual".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// `bytes` in KB, MB or GB.
+fn size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    if value < 1024.0 {
+        return format!("{} B", bytes);
+    }
+    let mut unit = UNITS[0];
+    for u in UNITS {
+        value /= 1024.0;
+        unit = u;
+        if value < 1024.0 {
+            break;
+        }
+    }
+    format!("{:.1} {}", value, unit)
+}
@@ -0,0 +1,25 @@
+//! Export subscriptions as OPML to a file, or to stdout when no file is given.
+
+use crate::SubscriptionList;
+use std::path::Path;
+
+pub fn run(store: &SubscriptionList, file: Option<&Path>, output_json: bool) -> crate::Result<()> {
+    let xml = crate::opml::export_opml(store);
+    let Some(file) = file else {
+        println!("{}", xml);
+        return Ok(());
+    };
+    std::fs::write(file, xml)?;
+    let message = format!(
+        "Exported {} feed(s) to {}",
+        store.feeds.len(),
+        file.display()
+    );
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "message": message });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("{}", message);
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Manage feed folders: list, create, move/rename and remove folders, and move feeds
+//! between them.
+
+use crate::store::Storage;
+use crate::SubscriptionList;
+
+/// `folder` subcommands.
+#[derive(clap::Subcommand, Debug)]
+pub enum FolderCommand {
+    /// List folders with their feed counts.
+    List,
+    /// Create an empty folder (e.g. `Tech/Rust`).
+    Create { path: String },
+    /// Move or rename a folder with its feeds and subfolders ("" = top level).
+    Move { from: String, to: String },
+    /// Delete a folder; its feeds and subfolders move up to its parent.
+    Remove { path: String },
+}
+
+pub fn run(
+    store: &mut SubscriptionList,
+    cmd: &FolderCommand,
+    storage: &dyn Storage,
+    output_json: bool,
+) -> crate::Result<()> {
+    let message = match cmd {
+        FolderCommand::List => return list(store, output_json),
+        FolderCommand::Create { path } => {
+            if store.create_folder(path)? {
+                format!("Created folder: {}", path)
+            } else {
+                format!("Folder already exists: {}", path)
+            }
+        }
+        FolderCommand::Move { from, to } => {
+            let n = store.move_folder(from, to)?;
+            format!("Moved folder {} to {} ({} feed(s))", from, display(to), n)
+        }
+        FolderCommand::Remove { path } => {
+            let n = store.remove_folder(path)?;
+            format!("Removed folder {} ({} feed(s) moved up)", path, n)
+        }
+    };
+    storage.save(store)?;
+    print_result(&message, output_json);
+    Ok(())
+}
+
+/// Move feed `url` into `folder` (None = top level).
+pub fn move_feed(
+    store: &mut SubscriptionList,
+    url: &str,
+    folder: Option<&str>,
+    storage: &dyn Storage,
+    output_json: bool,
+) -> crate::Result<()> {
+    store.set_folder(url, folder)?;
+    storage.save(store)?;
+    print_result(
+        &format!("Moved {} to {}", url, display(folder.unwrap_or(""))),
+        output_json,
+    );
+    Ok(())
+}
+
+fn list(store: &SubscriptionList, output_json: bool) -> crate::Result<()> {
+    let folders = store.folders();
+    if output_json {
+        let arr: Vec<serde_json::Value> = folders
+            .iter()
+            .map(|path| {
+                let mut obj = serde_json::Map::new();
+                obj.insert("path".into(), serde_json::Value::String(path.clone()));
+                obj.insert(
+                    "feed_count".into(),
+                    serde_json::Value::Number(store.feeds_in(Some(path)).len().into()),
+                );
+                serde_json::Value::Object(obj)
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&arr).unwrap());
+    } else {
+        for path in &folders {
+            println!("{} ({} feed(s))", path, store.feeds_in(Some(path)).len());
+        }
+    }
+    Ok(())
+}
+
+fn display(folder: &str) -> &str {
+    if folder.trim().is_empty() {
+        "top level"
+    } else {
+        folder
+    }
+}
+
+fn print_result(message: &str, output_json: bool) {
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "message": message });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("{}", message);
+    }
+}
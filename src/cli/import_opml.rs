@@ -0,0 +1,30 @@
+//! Import subscriptions from an OPML file (titles and folders; items come with `refresh`).
+
+use crate::store::Storage;
+use crate::SubscriptionList;
+use std::path::Path;
+
+pub fn run(
+    store: &mut SubscriptionList,
+    file: &Path,
+    storage: &dyn Storage,
+    output_json: bool,
+) -> crate::Result<()> {
+    let xml = std::fs::read_to_string(file)?;
+    let report = crate::opml::import_opml(store, &xml)?;
+    storage.save(store)?;
+    if output_json {
+        let obj = serde_json::json!({
+            "success": true,
+            "added": report.added,
+            "skipped": report.skipped,
+        });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!(
+            "Imported {} feed(s) ({} already subscribed). Run `rss-reader refresh` to fetch items.",
+            report.added, report.skipped
+        );
+    }
+    Ok(())
+}
@@ -1,26 +1,104 @@
-//! List subscribed feeds.
+//! List subscribed feeds with unread counts, folders and tags; failing feeds are marked with their last error.
 
+use crate::settings::Settings;
 use crate::SubscriptionList;
 
-pub fn run(store: &SubscriptionList, output_json: bool) -> crate::Result<()> {
+pub fn run(store: &SubscriptionList, settings: &Settings, output_json: bool) -> crate::Result<()> {
     if output_json {
-        let arr: Vec<serde_json::Value> = store
-            .feeds
-            .iter()
-            .map(|f| {
-                let mut obj = serde_json::Map::new();
-                obj.insert("url".into(), serde_json::Value::String(f.url.clone()));
-                if let Some(t) = &f.title {
-                    obj.insert("title".into(), serde_json::Value::String(t.clone()));
-                }
-                serde_json::Value::Object(obj)
-            })
-            .collect();
-        println!("{}", serde_json::to_string_pretty(&arr).unwrap());
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&feeds_json(store)).unwrap()
+        );
     } else {
         for f in &store.feeds {
-            println!("{} ({})", f.title.as_deref().unwrap_or(&f.url), f.url);
+            let mut line = format!(
+                "{} ({}) [{} unread]",
+                f.title.as_deref().unwrap_or(&f.url),
+                f.url,
+                store.unread_count(Some(&f.url))
+            );
+            if let Some(folder) = &f.folder {
+                line.push_str(&format!(" [folder: {}]", folder));
+            }
+            if !f.tags.is_empty() {
+                line.push_str(&format!(" [tags: {}]", f.tags.join(", ")));
+            }
+            if f.is_failing() {
+                let since = f
+                    .last_success
+                    .map(|d| settings.format_date(&d))
+                    .unwrap_or_else(|| "never".to_string());
+                line.push_str(&format!(
+                    " [FAILING x{}, last success: {}: {}]",
+                    f.consecutive_failures,
+                    since,
+                    f.last_error.as_deref().unwrap_or("?")
+                ));
+            }
+            println!("{}", line);
         }
     }
     Ok(())
 }
+
+/// JSON array of feeds as printed by `list-feeds -o json` (also served by the HTTP API).
+pub(crate) fn feeds_json(store: &SubscriptionList) -> serde_json::Value {
+    let arr: Vec<serde_json::Value> = store
+        .feeds
+        .iter()
+        .map(|f| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("url".into(), serde_json::Value::String(f.url.clone()));
+            if let Some(t) = &f.title {
+                obj.insert("title".into(), serde_json::Value::String(t.clone()));
+            }
+            for (key, value) in [
+                ("site_url", &f.site_url),
+                ("icon", &f.icon),
+                ("logo", &f.logo),
+                ("language", &f.language),
+                ("generator", &f.generator),
+            ] {
+                if let Some(v) = value {
+                    obj.insert(key.into(), serde_json::Value::String(v.clone()));
+                }
+            }
+            obj.insert(
+                "folder".into(),
+                f.folder
+                    .clone()
+                    .map(serde_json::Value::String)
+                    .unwrap_or(serde_json::Value::Null),
+            );
+            obj.insert(
+                "tags".into(),
+                serde_json::Value::Array(
+                    f.tags
+                        .iter()
+                        .map(|t| serde_json::Value::String(t.clone()))
+                        .collect(),
+                ),
+            );
+            obj.insert(
+                "unread_count".into(),
+                serde_json::Value::Number(store.unread_count(Some(&f.url)).into()),
+            );
+            obj.insert("failing".into(), serde_json::Value::Bool(f.is_failing()));
+            obj.insert(
+                "consecutive_failures".into(),
+                serde_json::Value::Number(f.consecutive_failures.into()),
+            );
+            if let Some(e) = &f.last_error {
+                obj.insert("last_error".into(), serde_json::Value::String(e.clone()));
+            }
+            obj.insert(
+                "last_success".into(),
+                f.last_success
+                    .map(|d| serde_json::Value::String(d.to_rfc3339()))
+                    .unwrap_or(serde_json::Value::Null),
+            );
+            serde_json::Value::Object(obj)
+        })
+        .collect();
+    serde_json::Value::Array(arr)
+}
@@ -1,45 +1,69 @@
-//! List items (all or for one feed).
+//! List items matching an [`ItemQuery`] (feed, read/starred state, dates, enclosures,
+//! author, category), sorted and paged.
 
-use crate::SubscriptionList;
+use super::show::insert_metadata;
+use crate::settings::Settings;
+use crate::store::ItemQuery;
+use crate::{FeedItem, SubscriptionList};
 
 pub fn run(
     store: &SubscriptionList,
-    feed_url: Option<&str>,
+    query: &ItemQuery,
+    settings: &Settings,
     output_json: bool,
 ) -> crate::Result<()> {
-    let items: Vec<_> = store.items(feed_url).into_iter().collect();
+    let items = query.run(store);
     if output_json {
-        let arr: Vec<serde_json::Value> = items
-            .iter()
-            .map(|i| {
-                let mut obj = serde_json::Map::new();
-                obj.insert("id".into(), serde_json::Value::String(i.id.clone()));
-                obj.insert(
-                    "feed_url".into(),
-                    serde_json::Value::String(i.feed_url.clone()),
-                );
-                obj.insert("title".into(), serde_json::Value::String(i.title.clone()));
-                obj.insert(
-                    "published".into(),
-                    i.published
-                        .map(|d| serde_json::Value::String(d.to_rfc3339()))
-                        .unwrap_or(serde_json::Value::Null),
-                );
-                if let Some(l) = &i.link {
-                    obj.insert("link".into(), serde_json::Value::String(l.clone()));
-                }
-                serde_json::Value::Object(obj)
-            })
-            .collect();
-        println!("{}", serde_json::to_string_pretty(&arr).unwrap());
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&items_json(store, &items)).unwrap()
+        );
     } else {
         for i in &items {
             let date = i
                 .published
-                .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                .map(|d| settings.format_date(&d))
                 .unwrap_or_else(|| "?".to_string());
-            println!("{} | {} | {}", date, i.title, i.feed_url);
+            let marker = if store.is_read(&i.id) { ' ' } else { '*' };
+            let mut line = format!("{} {} | {} | {}", marker, date, i.title, i.feed_url);
+            if !i.authors.is_empty() {
+                line.push_str(&format!(" | by {}", i.authors.join(", ")));
+            }
+            println!("{}", line);
         }
     }
     Ok(())
 }
+
+/// JSON array of `items` as printed by `list-items -o json` (also served by the HTTP API).
+pub(crate) fn items_json(store: &SubscriptionList, items: &[&FeedItem]) -> serde_json::Value {
+    let arr: Vec<serde_json::Value> = items
+        .iter()
+        .map(|i| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("id".into(), serde_json::Value::String(i.id.clone()));
+            obj.insert(
+                "feed_url".into(),
+                serde_json::Value::String(i.feed_url.clone()),
+            );
+            obj.insert("title".into(), serde_json::Value::String(i.title.clone()));
+            obj.insert(
+                "published".into(),
+                i.published
+                    .map(|d| serde_json::Value::String(d.to_rfc3339()))
+                    .unwrap_or(serde_json::Value::Null),
+            );
+            if let Some(l) = &i.link {
+                obj.insert("link".into(), serde_json::Value::String(l.clone()));
+            }
+            insert_metadata(&mut obj, i);
+            obj.insert("read".into(), serde_json::Value::Bool(store.is_read(&i.id)));
+            obj.insert(
+                "starred".into(),
+                serde_json::Value::Bool(store.is_starred(&i.id)),
+            );
+            serde_json::Value::Object(obj)
+        })
+        .collect();
+    serde_json::Value::Array(arr)
+}
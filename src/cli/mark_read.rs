@@ -0,0 +1,48 @@
+//! Mark items read or unread (by id, or every item of a feed).
+
+use crate::store::Storage;
+use crate::SubscriptionList;
+
+pub fn run(
+    store: &mut SubscriptionList,
+    item_ids: &[String],
+    feed_url: Option<&str>,
+    read: bool,
+    storage: &dyn Storage,
+    output_json: bool,
+) -> crate::Result<()> {
+    let ids: Vec<String> = if item_ids.is_empty() {
+        let Some(url) = feed_url else {
+            return Err(crate::Error::NotFound(
+                "no item ids given (pass ids or --feed <url>)".to_string(),
+            ));
+        };
+        if !store.feeds.iter().any(|f| f.url == url) {
+            return Err(crate::Error::NotFound(format!("feed not found: {}", url)));
+        }
+        storage.feed_items(url)?.into_iter().map(|i| i.id).collect()
+    } else {
+        if let Some(id) = store.unknown_item(item_ids) {
+            return Err(crate::Error::NotFound(format!("item not found: {}", id)));
+        }
+        item_ids.to_vec()
+    };
+
+    if read {
+        store.mark_all_read(ids.iter().cloned());
+    } else {
+        for id in &ids {
+            store.set_read_unchecked(id, false);
+        }
+    }
+    storage.save(store)?;
+
+    let state = if read { "read" } else { "unread" };
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "updated_count": ids.len() });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("Marked {} item(s) {}", ids.len(), state);
+    }
+    Ok(())
+}
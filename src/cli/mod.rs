@@ -1,18 +1,31 @@
-//! CLI subcommands: add, remove, list-feeds, list-items, show, refresh.
+//! CLI subcommands: add, remove, list-feeds, list-items, show, search, refresh, daemon,
+//! serve, sync, tui, mark-read, mark-unread, star, unstar, folder, move-feed, tag, untag,
+//! downloads, play, import-opml, export-opml.
 
-use crate::SubscriptionList;
+use crate::format::ColorChoice;
+use crate::refresh::RefreshOptions;
+use crate::schedule::ScheduleOptions;
+use crate::settings::Settings;
+use crate::store::{parse_date_bound, ItemQuery, SortKey};
+use chrono::{DateTime, Utc};
 use clap::Parser;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "rss-reader", about = "Full-featured RSS reader CLI")]
 pub struct Args {
-    #[arg(long, short, default_value = "human")]
-    pub output: String,
+    /// Output format: human or json (default from the settings file, else human).
+    #[arg(long, short)]
+    pub output: Option<String>,
 
+    /// Data file or database (default from the settings file).
     #[arg(long, short)]
     pub config: Option<PathBuf>,
 
+    /// Settings file (default ~/.config/rss-reader/config.toml).
+    #[arg(long, env = "RSS_READER_SETTINGS")]
+    pub settings: Option<PathBuf>,
+
     #[command(subcommand)]
     pub cmd: Command,
 }
@@ -20,20 +33,168 @@ pub struct Args {
 #[derive(clap::Subcommand, Debug)]
 pub enum Command {
     Add {
+        /// Feed url, or a website url to discover its feeds.
         url: String,
+        /// Add every feed discovered on a website instead of asking.
+        #[arg(long)]
+        all: bool,
     },
     Remove {
         url: String,
     },
     ListFeeds,
     ListItems {
+        /// Same as --feed.
+        #[arg(conflicts_with = "feed_filter")]
         feed: Option<String>,
+        /// Only items of the feed with this url, or of feeds whose title contains this text.
+        #[arg(long = "feed", id = "feed_filter", value_name = "FEED")]
+        feed_filter: Option<String>,
+        /// Only items of feeds in this folder or its subfolders.
+        #[arg(long)]
+        folder: Option<String>,
+        /// Only items of feeds with this tag, or items a rule tagged with it.
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only list items not yet marked read.
+        #[arg(long)]
+        unread: bool,
+        /// Only list starred items.
+        #[arg(long)]
+        starred: bool,
+        /// Only items published at or after this date (YYYY-MM-DD, RFC 3339, or an age like 7d).
+        #[arg(long, value_parser = since_bound)]
+        since: Option<DateTime<Utc>>,
+        /// Only items published at or before this date (a bare date includes the whole day).
+        #[arg(long, value_parser = until_bound)]
+        until: Option<DateTime<Utc>>,
+        /// Only items with media enclosures.
+        #[arg(long)]
+        has_enclosure: bool,
+        /// Only items with an author whose name contains this text.
+        #[arg(long)]
+        author: Option<String>,
+        /// Only items with a category containing this text.
+        #[arg(long)]
+        category: Option<String>,
+        /// Also list items hidden by rules.
+        #[arg(long)]
+        hidden: bool,
+        /// Sort by date (newest first), title or feed.
+        #[arg(long, default_value_t = SortKey::Date)]
+        sort: SortKey,
+        /// Reverse the sort order.
+        #[arg(long)]
+        reverse: bool,
+        /// Skip this many items.
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        /// List at most this many items.
+        #[arg(long)]
+        limit: Option<usize>,
     },
     Show {
         item_id: String,
+        /// Column width to wrap the article to (default: settings, else the terminal's).
+        #[arg(long)]
+        width: Option<usize>,
+        /// Style the article with ANSI escapes: auto (when stdout is a terminal), always or
+        /// never.
+        #[arg(long, default_value_t = ColorChoice::Auto)]
+        color: ColorChoice,
+        /// Print straight to stdout instead of through $PAGER.
+        #[arg(long)]
+        no_pager: bool,
+    },
+    /// Read unread items one after another in the pager (n next, p previous, q quit);
+    /// viewed items are marked read on exit.
+    Read {
+        /// Only items of the feed with this url, or of feeds whose title contains this text.
+        #[arg(long)]
+        feed: Option<String>,
+        /// Only items of feeds in this folder or its subfolders.
+        #[arg(long)]
+        folder: Option<String>,
+        /// Only items of feeds with this tag.
+        #[arg(long)]
+        tag: Option<String>,
+        /// Column width to wrap articles to (default: settings, else the terminal's).
+        #[arg(long)]
+        width: Option<usize>,
+        /// Style articles with ANSI escapes: auto, always or never.
+        #[arg(long, default_value_t = ColorChoice::Auto)]
+        color: ColorChoice,
+        /// Print articles straight to stdout instead of through $PAGER.
+        #[arg(long)]
+        no_pager: bool,
+    },
+    /// Full-text search of cached items: words, `"phrases"`, `title:<text>`, `feed:<text>`.
+    Search {
+        query: String,
+        /// Maximum number of results.
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
     },
     Refresh {
+        /// Feed url, or text the feed title contains.
         feed: Option<String>,
+        /// Only refresh feeds in this folder or its subfolders.
+        #[arg(long)]
+        folder: Option<String>,
+        /// Only refresh feeds with this tag.
+        #[arg(long)]
+        tag: Option<String>,
+        /// Maximum number of feeds fetched at the same time.
+        #[arg(long, default_value_t = RefreshOptions::default().concurrency)]
+        concurrency: usize,
+        /// Maximum number of simultaneous requests to one host.
+        #[arg(long, default_value_t = RefreshOptions::default().per_host)]
+        per_host: usize,
+    },
+    /// Keep running and refresh each feed on its own schedule (feed ttl / sy:updatePeriod,
+    /// Cache-Control, Retry-After, backoff on failures) and download queued enclosures.
+    Daemon {
+        /// Minutes between refreshes of feeds that give no hint.
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+        /// Never refresh a feed more often than this (minutes).
+        #[arg(long, default_value_t = 5)]
+        min_interval: u64,
+        /// Never wait longer than this between refreshes of a healthy feed (minutes).
+        #[arg(long, default_value_t = 1440)]
+        max_interval: u64,
+        /// Longest wait before retrying a failing feed (minutes).
+        #[arg(long, default_value_t = 1440)]
+        max_backoff: u64,
+        /// Maximum number of feeds fetched at the same time.
+        #[arg(long, default_value_t = RefreshOptions::default().concurrency)]
+        concurrency: usize,
+        /// Maximum number of simultaneous requests to one host.
+        #[arg(long, default_value_t = RefreshOptions::default().per_host)]
+        per_host: usize,
+        /// Refresh the feeds that are due now, then exit (e.g. from cron).
+        #[arg(long)]
+        once: bool,
+    },
+    /// Serve a JSON HTTP API over the store (feeds, items, add/remove, refresh, read/star),
+    /// plus the Fever and Google Reader APIs for mobile apps when a login is set.
+    Serve {
+        /// Address to listen on; keep it on localhost unless a token is set.
+        #[arg(long, default_value = crate::server::DEFAULT_BIND)]
+        bind: String,
+        /// Require `Authorization: Bearer <token>` on every request.
+        #[arg(long)]
+        token: Option<String>,
+        /// User name mobile apps sign in with (enables `/fever/` and `/greader`).
+        #[arg(long, requires = "password")]
+        user: Option<String>,
+        /// Password for `--user`.
+        #[arg(long, env = "RSS_READER_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+        /// Also accept requests addressed to this host name (repeatable); the bound
+        /// address and localhost always work.
+        #[arg(long = "allow-host")]
+        allow_hosts: Vec<String>,
     },
     /// Open or download a media enclosure by item id and enclosure index (0-based).
     OpenEnclosure {
@@ -44,46 +205,321 @@ pub enum Command {
         #[arg(long)]
         output_dir: Option<PathBuf>,
     },
+    /// Play an audio enclosure with the built-in player (feature `player`), resuming where
+    /// it stopped: Enter/p pause, f/b `[secs]` skip, s `<speed>`, g `<time>`, q quit.
+    Play {
+        #[command(flatten)]
+        args: play::PlayArgs,
+    },
+    /// Mark items read by id, or every item of a feed with --feed.
+    MarkRead {
+        item_ids: Vec<String>,
+        #[arg(long)]
+        feed: Option<String>,
+    },
+    /// Mark items unread by id, or every item of a feed with --feed.
+    MarkUnread {
+        item_ids: Vec<String>,
+        #[arg(long)]
+        feed: Option<String>,
+    },
+    /// Star items so they are kept regardless of the per-feed cap or feed removal.
+    Star {
+        #[arg(required = true)]
+        item_ids: Vec<String>,
+    },
+    /// Remove the star from items.
+    Unstar {
+        #[arg(required = true)]
+        item_ids: Vec<String>,
+    },
+    /// Manage filter rules run on new items (list, add, remove, test).
+    Rules {
+        #[command(subcommand)]
+        cmd: rules::RulesCommand,
+    },
+    /// Manage folders (list, create, move, remove).
+    Folder {
+        #[command(subcommand)]
+        cmd: folder::FolderCommand,
+    },
+    /// Manage the enclosure download queue (list, add, run, remove, policy, clean).
+    Downloads {
+        #[command(subcommand)]
+        cmd: downloads::DownloadsCommand,
+    },
+    /// Move a feed into a folder, or to the top level when no folder is given.
+    MoveFeed {
+        url: String,
+        folder: Option<String>,
+    },
+    /// Add tags to a feed.
+    Tag {
+        url: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a feed.
+    Untag {
+        url: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Subscribe to every feed in an OPML file (keeps titles and folders).
+    ImportOpml {
+        file: PathBuf,
+    },
+    /// Write subscriptions as OPML to a file, or to stdout.
+    ExportOpml {
+        file: Option<PathBuf>,
+    },
+    /// Full-screen terminal UI with feed, article and reader panes (vim-style keys).
+    Tui {
+        /// Where enclosures are downloaded (`d`); defaults to `download_dir` in the settings.
+        #[arg(long)]
+        download_dir: Option<PathBuf>,
+    },
+    /// Two-way sync with Miniflux, FreshRSS (Google Reader API) or Nextcloud News.
+    Sync {
+        #[command(subcommand)]
+        cmd: sync::SyncCommand,
+    },
 }
 
-fn config_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("rss-reader")
-        .join("data.json")
+fn since_bound(s: &str) -> Result<DateTime<Utc>, String> {
+    parse_date_bound(s, false).map_err(|e| e.to_string())
 }
 
-/// Whether stdout should be JSON (from --output json).
-pub fn output_json(args: &Args) -> bool {
-    args.output.to_lowercase() == "json"
+fn until_bound(s: &str) -> Result<DateTime<Utc>, String> {
+    parse_date_bound(s, true).map_err(|e| e.to_string())
+}
+
+/// Settings from the file and environment, with --config and --output applied on top.
+pub fn settings(args: &Args) -> crate::Result<Settings> {
+    let mut settings = Settings::from_env(args.settings.as_deref())?;
+    if let Some(path) = &args.config {
+        settings.data_path = path.clone();
+    }
+    if let Some(output) = &args.output {
+        settings.output = output.to_lowercase();
+    }
+    Ok(settings)
 }
 
 pub fn run() -> crate::Result<()> {
     let args = Args::parse();
-    let json = output_json(&args);
-    let path = args.config.unwrap_or_else(config_path);
-    let mut store = SubscriptionList::load(path.as_path())?;
+    let settings = settings(&args)?;
+    let json = settings.output_json();
+    let path = settings.data_path.clone();
+    let storage = crate::store::open_with(&path, &settings.store)?;
+    let storage = storage.as_ref();
 
+    // Single-item lookups go straight to the backend instead of loading everything.
     match &args.cmd {
-        Command::Add { url } => add::run(&mut store, url, &path, json),
-        Command::Remove { url } => remove::run(&mut store, url, &path, json),
-        Command::ListFeeds => list_feeds::run(&store, json),
-        Command::ListItems { feed } => list_items::run(&store, feed.as_deref(), json),
-        Command::Show { item_id } => show::run(&store, item_id, json),
-        Command::Refresh { feed } => refresh::run(&mut store, feed.as_deref(), &path, json),
+        Command::Show {
+            item_id,
+            width,
+            color,
+            no_pager,
+        } => {
+            let mut settings = settings.clone();
+            settings.width = width.or(settings.width);
+            return show::run(storage, item_id, &settings, *color, !no_pager, json);
+        }
         Command::OpenEnclosure {
             item_id,
             index,
             download,
             output_dir,
-        } => open_enclosure::run(&store, item_id, *index, *download, output_dir.as_deref()),
+        } => {
+            let output_dir = output_dir.as_deref().or(settings.download_dir.as_deref());
+            let http = settings.http_options();
+            return open_enclosure::run(storage, item_id, *index, *download, output_dir, &http);
+        }
+        Command::Daemon {
+            interval,
+            min_interval,
+            max_interval,
+            max_backoff,
+            concurrency,
+            per_host,
+            once,
+        } => {
+            let minutes = |m: u64| std::time::Duration::from_secs(m * 60);
+            let schedule = ScheduleOptions {
+                default_interval: minutes(*interval),
+                min_interval: minutes(*min_interval),
+                max_interval: minutes(*max_interval),
+                max_backoff: minutes(*max_backoff),
+            };
+            let options = RefreshOptions {
+                concurrency: *concurrency,
+                per_host: *per_host,
+                http: settings.http_options(),
+            };
+            return daemon::run(storage, &schedule, &options, &settings, *once, json);
+        }
+        Command::Serve {
+            bind,
+            token,
+            user,
+            password,
+            allow_hosts,
+        } => {
+            let login = user.as_deref().zip(password.as_deref());
+            return serve::run(&settings, bind, token.clone(), login, allow_hosts);
+        }
+        Command::Tui { download_dir } => {
+            let mut settings = settings.clone();
+            if let Some(dir) = download_dir {
+                settings.download_dir = Some(dir.clone());
+            }
+            return crate::tui::run(&settings);
+        }
+        _ => {}
+    }
+    let mut store = storage.load()?;
+
+    match &args.cmd {
+        Command::Add { url, all } => add::run(
+            &mut store,
+            url,
+            *all,
+            storage,
+            &settings.http_options(),
+            json,
+        ),
+        Command::Remove { url } => remove::run(&mut store, url, storage, json),
+        Command::ListFeeds => list_feeds::run(&store, &settings, json),
+        Command::ListItems {
+            feed,
+            feed_filter,
+            folder,
+            tag,
+            unread,
+            starred,
+            since,
+            until,
+            has_enclosure,
+            author,
+            category,
+            hidden,
+            sort,
+            reverse,
+            offset,
+            limit,
+        } => {
+            let query = ItemQuery {
+                feed: feed.clone().or_else(|| feed_filter.clone()),
+                folder: folder.clone(),
+                tag: tag.clone(),
+                unread: *unread,
+                starred: *starred,
+                since: *since,
+                until: *until,
+                has_enclosure: *has_enclosure,
+                author: author.clone(),
+                category: category.clone(),
+                hidden: *hidden,
+                sort: *sort,
+                reverse: *reverse,
+                offset: *offset,
+                limit: *limit,
+            };
+            list_items::run(&store, &query, &settings, json)
+        }
+        Command::Refresh {
+            feed,
+            folder,
+            tag,
+            concurrency,
+            per_host,
+        } => {
+            let selection = ItemQuery {
+                feed: feed.clone(),
+                folder: folder.clone(),
+                tag: tag.clone(),
+                ..ItemQuery::default()
+            };
+            let options = RefreshOptions {
+                concurrency: *concurrency,
+                per_host: *per_host,
+                http: settings.http_options(),
+            };
+            refresh::run(&mut store, &selection, &options, storage, json)
+        }
+        Command::Search { query, limit } => {
+            search::run(&store, &path, query, *limit, &settings, json)
+        }
+        Command::Read {
+            feed,
+            folder,
+            tag,
+            width,
+            color,
+            no_pager,
+        } => {
+            let query = ItemQuery {
+                feed: feed.clone(),
+                folder: folder.clone(),
+                tag: tag.clone(),
+                ..ItemQuery::default()
+            };
+            let mut settings = settings.clone();
+            settings.width = width.or(settings.width);
+            read::run(
+                &mut store, &query, &settings, *color, !no_pager, storage, json,
+            )
+        }
+        Command::Show { .. }
+        | Command::OpenEnclosure { .. }
+        | Command::Daemon { .. }
+        | Command::Serve { .. }
+        | Command::Tui { .. } => {
+            unreachable!("handled above")
+        }
+        Command::MarkRead { item_ids, feed } => {
+            mark_read::run(&mut store, item_ids, feed.as_deref(), true, storage, json)
+        }
+        Command::MarkUnread { item_ids, feed } => {
+            mark_read::run(&mut store, item_ids, feed.as_deref(), false, storage, json)
+        }
+        Command::Star { item_ids } => star::run(&mut store, item_ids, true, storage, json),
+        Command::Unstar { item_ids } => star::run(&mut store, item_ids, false, storage, json),
+        Command::Rules { cmd } => rules::run(&mut store, cmd, storage, &settings, json),
+        Command::Folder { cmd } => folder::run(&mut store, cmd, storage, json),
+        Command::Downloads { cmd } => downloads::run(&mut store, cmd, storage, &settings, json),
+        Command::Play { args } => play::run(&mut store, args, storage, &settings, json),
+        Command::MoveFeed { url, folder } => {
+            folder::move_feed(&mut store, url, folder.as_deref(), storage, json)
+        }
+        Command::Tag { url, tags } => tag::run(&mut store, url, tags, true, storage, json),
+        Command::Untag { url, tags } => tag::run(&mut store, url, tags, false, storage, json),
+        Command::ImportOpml { file } => import_opml::run(&mut store, file, storage, json),
+        Command::ExportOpml { file } => export_opml::run(&store, file.as_deref(), json),
+        Command::Sync { cmd } => sync::run(&mut store, cmd, storage, &settings, json),
     }
 }
 
 pub mod add;
+pub mod daemon;
+pub mod downloads;
+pub mod export_opml;
+pub mod folder;
+pub mod import_opml;
 pub mod list_feeds;
 pub mod list_items;
+pub mod mark_read;
 pub mod open_enclosure;
+mod pager;
+pub mod play;
+pub mod read;
 pub mod refresh;
 pub mod remove;
+pub mod rules;
+pub mod search;
+pub mod serve;
 pub mod show;
+pub mod star;
+pub mod sync;
+pub mod tag;
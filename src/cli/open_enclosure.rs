@@ -1,25 +1,27 @@
 //! Open or download a media enclosure by item id and index.
 
+use crate::fetch::HttpOptions;
 use crate::media;
-use crate::SubscriptionList;
+use crate::store::Storage;
 use std::path::Path;
 
 pub fn run(
-    store: &SubscriptionList,
+    storage: &dyn Storage,
     item_id: &str,
     index: usize,
     download: bool,
     output_dir: Option<&Path>,
+    http: &HttpOptions,
 ) -> crate::Result<()> {
-    let item = store
-        .get_item(item_id, None)
+    let item = storage
+        .get_item(item_id)?
         .ok_or_else(|| crate::Error::NotFound(format!("item not found: {}", item_id)))?;
     let enclosure = item
         .enclosures
         .get(index)
         .ok_or_else(|| crate::Error::NotFound(format!("enclosure index {} not found", index)))?;
     if download {
-        let path = media::download_enclosure(enclosure, output_dir)?;
+        let path = media::download_enclosure(enclosure, output_dir, http)?;
         println!("Downloaded to {}", path.display());
     } else {
         media::open_enclosure(enclosure)?;
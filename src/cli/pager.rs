@@ -0,0 +1,55 @@
+//! Page long output through `$PAGER` (default `less -R`) when stdout is a terminal.
+
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Pager used when `$PAGER` is unset or empty.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Exit status of `sh -c` when the command is not found.
+const NOT_FOUND: i32 = 127;
+
+/// Show `text` in the pager when `enabled` and stdout is a terminal; print it otherwise
+/// (also, after saying why, when the pager cannot be started). Returns once the pager exits.
+pub(crate) fn page(text: &str, enabled: bool) -> crate::Result<()> {
+    if enabled && std::io::stdout().is_terminal() {
+        let pager = std::env::var("PAGER")
+            .ok()
+            .filter(|p| !p.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+        match spawn(&pager) {
+            Ok(mut child) => {
+                if let Some(mut stdin) = child.stdin.take() {
+                    // The pager closes its input when quit before the end.
+                    match stdin.write_all(text.as_bytes()) {
+                        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+                            return Err(e.into())
+                        }
+                        _ => {}
+                    }
+                }
+                // The shell reports a missing pager on stderr; the text still gets shown.
+                if child.wait()?.code() != Some(NOT_FOUND) {
+                    return Ok(());
+                }
+            }
+            Err(e) => eprintln!("Cannot start pager {:?}: {}", pager, e),
+        }
+    }
+    print!("{}", text);
+    std::io::stdout().flush()?;
+    Ok(())
+}
+
+/// Start `pager` with `sh -c`, as git does, so quoting and arguments in `$PAGER` work.
+/// `less` gets `LESS=FRX` unless set, so it keeps colors and exits at once when the text
+/// fits on one screen.
+fn spawn(pager: &str) -> std::io::Result<std::process::Child> {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(pager).stdin(Stdio::piped());
+    let program = pager.split_whitespace().next().unwrap_or_default();
+    if program.ends_with("less") && std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    command.spawn()
+}
@@ -0,0 +1,267 @@
+//! Play an audio enclosure with the built-in player (feature `player`), resuming where
+//! listening stopped. Commands are typed as lines: Enter or `p` pauses and resumes, `f` / `b`
+//! `[secs]` skip forward / back, `s <speed>` sets the speed, `g <time>` goes to a time and `q`
+//! quits. The position is saved while playing and on exit; playing to the end marks the
+//! item read.
+
+use crate::settings::Settings;
+use crate::store::Storage;
+use crate::{media, FeedItem, SubscriptionList};
+
+/// `play` arguments.
+#[derive(clap::Args, Debug)]
+pub struct PlayArgs {
+    item_id: String,
+    /// Enclosure index (0-based).
+    #[arg(long, default_value_t = 0)]
+    index: usize,
+    /// Start here (H:MM:SS, M:SS or seconds) instead of the saved position.
+    #[arg(long, value_parser = start_time)]
+    start: Option<u64>,
+    /// Playback speed (1.0 is normal); the pitch changes with it.
+    #[arg(long, default_value_t = 1.0)]
+    speed: f32,
+}
+
+fn start_time(s: &str) -> Result<u64, String> {
+    crate::parse_duration(s)
+        .ok_or_else(|| format!("not a time: {:?} (use H:MM:SS, M:SS or seconds)", s))
+}
+
+pub fn run(
+    store: &mut SubscriptionList,
+    args: &PlayArgs,
+    storage: &dyn Storage,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    let item = store
+        .get_item(&args.item_id, None)
+        .cloned()
+        .ok_or_else(|| crate::Error::NotFound(format!("item not found: {}", args.item_id)))?;
+    let enclosure = item.enclosures.get(args.index).ok_or_else(|| {
+        crate::Error::NotFound(format!("enclosure index {} not found", args.index))
+    })?;
+    if !media::is_audio(enclosure) {
+        return Err(crate::Error::InvalidInput(format!(
+            "enclosure {} of {} is not audio ({}); use open-enclosure",
+            args.index,
+            args.item_id,
+            enclosure.media_type.as_deref().unwrap_or("unknown type")
+        )));
+    }
+    play(store, &item, args, storage, settings, output_json)
+}
+
+#[cfg(not(feature = "player"))]
+fn play(
+    _store: &mut SubscriptionList,
+    _item: &FeedItem,
+    _args: &PlayArgs,
+    _storage: &dyn Storage,
+    _settings: &Settings,
+    _output_json: bool,
+) -> crate::Result<()> {
+    Err(crate::Error::InvalidInput(
+        "this build has no audio player; rebuild with `--features player` or use open-enclosure"
+            .to_string(),
+    ))
+}
+
+#[cfg(feature = "player")]
+fn play(
+    store: &mut SubscriptionList,
+    item: &FeedItem,
+    args: &PlayArgs,
+    storage: &dyn Storage,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    use crate::format_duration;
+    use crate::media::download::local_file;
+    use crate::media::player::Player;
+    use std::io::{BufRead, IsTerminal, Write};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    /// How often the position is saved while playing.
+    const SAVE_EVERY: Duration = Duration::from_secs(10);
+    /// How often the status line is redrawn.
+    const TICK: Duration = Duration::from_millis(500);
+
+    let enclosure = &item.enclosures[args.index];
+    let interactive = !output_json && std::io::stderr().is_terminal();
+    let mut downloading = false;
+    let path = local_file(
+        store,
+        storage,
+        item,
+        args.index,
+        &settings.download_queue_dir(),
+        &settings.http_options(),
+        &mut |written, total| {
+            if interactive {
+                downloading = true;
+                let percent = total
+                    .filter(|t| *t > 0)
+                    .map(|t| format!(" {}%", written * 100 / t))
+                    .unwrap_or_default();
+                eprint!("\r\x1b[KDownloading{}", percent);
+                let _ = std::io::stderr().flush();
+            }
+        },
+    )?;
+    if downloading {
+        eprintln!();
+    }
+
+    let start = args
+        .start
+        .or_else(|| {
+            store
+                .playback_position(&enclosure.url)
+                .map(|p| p.position_secs)
+        })
+        .unwrap_or(0);
+    let player = Player::open(&path, Duration::from_secs(start))?;
+    if args.speed != 1.0 {
+        player.set_speed(args.speed)?;
+    }
+    let duration = enclosure
+        .duration_secs
+        .or_else(|| player.duration().map(|d| d.as_secs()));
+    if interactive {
+        eprintln!(
+            "Playing {}: Enter/p pause, f/b [secs] skip, s <speed>, g <time>, q quit",
+            enclosure.title.as_deref().unwrap_or(&item.title)
+        );
+    }
+
+    let (tx, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    // Without input (e.g. stdin closed) play to the end.
+    let mut input_open = true;
+    let mut last_save = Instant::now();
+    loop {
+        let line = if input_open {
+            match lines.recv_timeout(TICK) {
+                Ok(line) => Some(line),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    input_open = false;
+                    None
+                }
+            }
+        } else {
+            std::thread::sleep(TICK);
+            None
+        };
+        if let Some(line) = line {
+            match command(&player, &line) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        if player.is_finished() {
+            break;
+        }
+        if interactive {
+            eprint!(
+                "\r\x1b[K{} {} / {}  {}x ",
+                if player.is_paused() {
+                    "Paused "
+                } else {
+                    "Playing"
+                },
+                format_duration(player.position().as_secs()),
+                duration.map(format_duration).unwrap_or_else(|| "?".into()),
+                player.speed()
+            );
+            let _ = std::io::stderr().flush();
+        }
+        if last_save.elapsed() >= SAVE_EVERY {
+            let position = player.position().as_secs();
+            store.set_playback_position(&item.id, &enclosure.url, position, duration);
+            storage.save(store)?;
+            last_save = Instant::now();
+        }
+    }
+    if interactive {
+        eprintln!();
+    }
+
+    let position = player.position().as_secs();
+    let played = if player.is_finished() {
+        store.clear_playback_position(&enclosure.url);
+        store.set_read(&item.id, true);
+        true
+    } else {
+        store.set_playback_position(&item.id, &enclosure.url, position, duration)
+    };
+    drop(player);
+    storage.save(store)?;
+    if output_json {
+        let obj = serde_json::json!({
+            "item_id": item.id,
+            "url": enclosure.url,
+            "position_secs": if played { duration.unwrap_or(position) } else { position },
+            "duration_secs": duration,
+            "played": played,
+        });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else if played {
+        println!("Played to the end; marked {} read", item.id);
+    } else {
+        println!(
+            "Stopped at {}; `rss-reader play {}` resumes",
+            format_duration(position),
+            item.id
+        );
+    }
+    Ok(())
+}
+
+/// Apply a typed player command; returns true to quit.
+#[cfg(feature = "player")]
+fn command(player: &crate::media::player::Player, line: &str) -> crate::Result<bool> {
+    let mut words = line.split_whitespace();
+    let (name, arg) = (words.next(), words.next());
+    let secs = |default: i64| -> crate::Result<i64> {
+        arg.map_or(Ok(default), |a| {
+            a.parse()
+                .map_err(|_| crate::Error::InvalidInput(format!("not a number: {}", a)))
+        })
+    };
+    match name {
+        None | Some("p") => player.toggle_pause(),
+        Some("f") => player.skip(secs(30)?)?,
+        Some("b") => player.skip(-secs(15)?)?,
+        Some("s") => {
+            let speed = arg
+                .and_then(|a| a.parse().ok())
+                .ok_or_else(|| crate::Error::InvalidInput("usage: s <speed>, e.g. s 1.5".into()))?;
+            player.set_speed(speed)?;
+        }
+        Some("g") => {
+            let to = arg.and_then(crate::parse_duration).ok_or_else(|| {
+                crate::Error::InvalidInput("usage: g <time>, e.g. g 12:30".into())
+            })?;
+            player.seek(std::time::Duration::from_secs(to))?;
+        }
+        Some("q") => return Ok(true),
+        Some(other) => {
+            return Err(crate::Error::InvalidInput(format!(
+                "unknown command: {} (p, f, b, s, g, q)",
+                other
+            )))
+        }
+    }
+    Ok(false)
+}
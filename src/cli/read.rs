@@ -0,0 +1,85 @@
+//! Read unread items one after another in the pager (oldest first). Between articles `n`
+//! (or Enter) goes to the next, `p` to the previous and `q` quits; the articles viewed are
+//! marked read on exit.
+
+use super::pager;
+use super::show::render;
+use crate::format::ColorChoice;
+use crate::settings::Settings;
+use crate::store::{ItemQuery, Storage};
+use crate::{FeedItem, SubscriptionList};
+use std::io::{BufRead, Write};
+
+pub fn run(
+    store: &mut SubscriptionList,
+    query: &ItemQuery,
+    settings: &Settings,
+    color: ColorChoice,
+    use_pager: bool,
+    storage: &dyn Storage,
+    output_json: bool,
+) -> crate::Result<()> {
+    let query = ItemQuery {
+        unread: true,
+        reverse: true,
+        ..query.clone()
+    };
+    let items: Vec<FeedItem> = query.run(store).into_iter().cloned().collect();
+    if items.is_empty() {
+        if output_json {
+            let obj = serde_json::json!({ "success": true, "updated_count": 0 });
+            println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+        } else {
+            println!("No unread items.");
+        }
+        return Ok(());
+    }
+
+    let styled = color.enabled();
+    let mut viewed = vec![false; items.len()];
+    let mut index = 0;
+    let mut show = true;
+    let mut input = std::io::stdin().lock().lines();
+    loop {
+        if show {
+            pager::page(&render(&items[index], settings, styled), use_pager)?;
+            viewed[index] = true;
+        }
+        let last = index + 1 == items.len();
+        eprint!(
+            "[{}/{}] {}, p previous, q quit: ",
+            index + 1,
+            items.len(),
+            if last { "n done" } else { "n next" }
+        );
+        std::io::stderr().flush()?;
+        // End of input quits like `q`.
+        let Some(line) = input.next().transpose()? else {
+            eprintln!();
+            break;
+        };
+        show = true;
+        match line.trim() {
+            "" | "n" if last => break,
+            "" | "n" => index += 1,
+            "p" if index > 0 => index -= 1,
+            "q" => break,
+            _ => show = false,
+        }
+    }
+
+    let ids = items
+        .iter()
+        .zip(&viewed)
+        .filter(|(_, &seen)| seen)
+        .map(|(item, _)| item.id.clone());
+    let count = store.mark_all_read(ids);
+    storage.save(store)?;
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "updated_count": count });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("Marked {} item(s) read", count);
+    }
+    Ok(())
+}
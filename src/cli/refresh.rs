@@ -1,34 +1,120 @@
-//! Refresh feed(s).
+//! Refresh feed(s) (all, or one feed, folder or tag) in parallel with conditional GET; prints a per-feed status table
+//! and fails when any feed could not be fetched.
 
-use crate::fetch::fetch_feed;
+use crate::fetch::FetchOutcome;
+use crate::refresh::{refresh_feeds, RefreshOptions};
+use crate::store::{ItemQuery, Storage};
 use crate::SubscriptionList;
-use std::path::Path;
 
 pub fn run(
     store: &mut SubscriptionList,
-    feed_url: Option<&str>,
-    path: &Path,
+    selection: &ItemQuery,
+    options: &RefreshOptions,
+    storage: &dyn Storage,
     output_json: bool,
 ) -> crate::Result<()> {
-    let urls: Vec<String> = store
-        .feeds
-        .iter()
-        .map(|f| f.url.clone())
-        .filter(|u| feed_url.map_or(true, |f| u == f))
-        .collect();
-    let mut updated = 0u32;
-    for u in urls {
-        if let Ok((feed, items)) = fetch_feed(&u) {
-            store.add_feed(feed, items);
-            updated += 1;
-        }
+    if !output_json && !selection.feeds(store).is_empty() {
+        println!("{:<9}  {:<40}  DETAIL", "STATUS", "FEED");
     }
-    store.save(path)?;
+    // Rows are printed as each feed finishes, so order follows completion.
+    let report = refresh_selected(store, selection, options, |status, name, detail| {
+        if !output_json {
+            println!("{:<9}  {:<40}  {}", status, name, detail);
+        }
+    });
+    storage.save(store)?;
     if output_json {
-        let obj = serde_json::json!({ "success": true, "updated_count": updated });
-        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+        println!("{}", serde_json::to_string_pretty(&report.json).unwrap());
     } else {
-        println!("Refreshed {} feed(s)", updated);
+        println!(
+            "Refreshed {} feed(s): {} fetched, {} unchanged, {} failed",
+            report.fetched + report.unchanged + report.failed,
+            report.fetched,
+            report.unchanged,
+            report.failed
+        );
+    }
+    if report.failed > 0 {
+        return Err(crate::Error::RefreshFailed(report.failed));
     }
     Ok(())
 }
+
+/// Counts and JSON summary of a [`refresh_selected`] run.
+pub(crate) struct RefreshReport {
+    pub fetched: u32,
+    pub unchanged: u32,
+    pub failed: u32,
+    /// Object printed by `refresh -o json` (also returned by the HTTP API).
+    pub json: serde_json::Value,
+}
+
+/// Refresh the feeds `selection` picks and merge the results into `store` (without saving).
+/// `on_row(status, feed name, detail)` is called as each feed finishes.
+pub(crate) fn refresh_selected(
+    store: &mut SubscriptionList,
+    selection: &ItemQuery,
+    options: &RefreshOptions,
+    mut on_row: impl FnMut(&str, &str, &str),
+) -> RefreshReport {
+    let feeds: Vec<crate::Feed> = selection.feeds(store).into_iter().cloned().collect();
+    let (mut fetched, mut unchanged, mut failed) = (0u32, 0u32, 0u32);
+    let mut results: Vec<serde_json::Value> = Vec::new();
+    for done in refresh_feeds(feeds, options) {
+        let (status, detail) = match &done.result {
+            Ok(FetchOutcome::Updated(_, items)) => {
+                fetched += 1;
+                ("fetched", format!("{} item(s)", items.len()))
+            }
+            Ok(FetchOutcome::NotModified { .. }) => {
+                unchanged += 1;
+                ("unchanged", String::new())
+            }
+            Err(e) => {
+                failed += 1;
+                ("failed", e.to_string())
+            }
+        };
+        let url = done.url.clone();
+        done.apply(store);
+        let feed = store.feeds.iter().find(|f| f.url == url);
+        let failures = feed.map_or(0, |f| f.consecutive_failures);
+
+        let name = feed.and_then(|f| f.title.as_deref()).unwrap_or(&url);
+        if failures > 1 {
+            on_row(
+                status,
+                name,
+                &format!("{} ({} failures in a row)", detail, failures),
+            );
+        } else {
+            on_row(status, name, &detail);
+        }
+
+        let mut obj = serde_json::Map::new();
+        obj.insert("url".into(), serde_json::Value::String(url));
+        obj.insert("status".into(), serde_json::Value::String(status.into()));
+        if status == "failed" {
+            obj.insert("error".into(), serde_json::Value::String(detail));
+        }
+        obj.insert(
+            "consecutive_failures".into(),
+            serde_json::Value::Number(failures.into()),
+        );
+        results.push(serde_json::Value::Object(obj));
+    }
+    let json = serde_json::json!({
+        "success": failed == 0,
+        "updated_count": fetched,
+        "fetched": fetched,
+        "unchanged": unchanged,
+        "failed": failed,
+        "feeds": results,
+    });
+    RefreshReport {
+        fetched,
+        unchanged,
+        failed,
+        json,
+    }
+}
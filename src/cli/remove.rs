@@ -1,16 +1,16 @@
 //! Remove feed by URL.
 
+use crate::store::Storage;
 use crate::SubscriptionList;
-use std::path::Path;
 
 pub fn run(
     store: &mut SubscriptionList,
     url: &str,
-    path: &Path,
+    storage: &dyn Storage,
     output_json: bool,
 ) -> crate::Result<()> {
     if store.remove_feed(url) {
-        store.save(path)?;
+        storage.save(store)?;
         if output_json {
             let obj =
                 serde_json::json!({ "success": true, "message": format!("Removed feed: {}", url) });
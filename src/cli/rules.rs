@@ -0,0 +1,191 @@
+//! Manage filter rules: list, add and remove rules, and test conditions against the
+//! cached items.
+
+use super::list_items::items_json;
+use crate::rules::{Condition, Rule, RuleAction, RuleField};
+use crate::settings::Settings;
+use crate::store::Storage;
+use crate::SubscriptionList;
+
+/// `rules` subcommands.
+#[derive(clap::Subcommand, Debug)]
+pub enum RulesCommand {
+    /// List rules in the order they run.
+    List,
+    /// Add a rule run on new items: all given conditions must match.
+    Add {
+        #[command(flatten)]
+        conditions: ConditionArgs,
+        /// hide, mark-read, star, drop or `tag:<name>`.
+        #[arg(long)]
+        action: RuleAction,
+    },
+    /// Remove rule N (as numbered by `rules list`).
+    Remove { number: usize },
+    /// List the cached items rule N, or the given conditions, match.
+    Test {
+        /// Rule number from `rules list` (instead of conditions).
+        number: Option<usize>,
+        #[command(flatten)]
+        conditions: ConditionArgs,
+    },
+}
+
+/// Rule conditions as flags.
+#[derive(clap::Args, Debug)]
+pub struct ConditionArgs {
+    /// The feed's url or title contains this.
+    #[arg(long)]
+    feed: Option<String>,
+    /// The title contains this.
+    #[arg(long)]
+    title: Option<String>,
+    /// The content or summary contains this.
+    #[arg(long)]
+    content: Option<String>,
+    /// An author contains this.
+    #[arg(long)]
+    author: Option<String>,
+    /// A category contains this.
+    #[arg(long)]
+    category: Option<String>,
+    /// An enclosure's media type contains this (e.g. video/).
+    #[arg(long)]
+    enclosure: Option<String>,
+    /// Patterns are regular expressions instead of case-insensitive substrings.
+    #[arg(long)]
+    regex: bool,
+}
+
+impl ConditionArgs {
+    fn conditions(&self) -> Vec<Condition> {
+        [
+            (RuleField::Feed, &self.feed),
+            (RuleField::Title, &self.title),
+            (RuleField::Content, &self.content),
+            (RuleField::Author, &self.author),
+            (RuleField::Category, &self.category),
+            (RuleField::Enclosure, &self.enclosure),
+        ]
+        .into_iter()
+        .filter_map(|(field, pattern)| {
+            Some(Condition {
+                field,
+                pattern: pattern.clone()?,
+                regex: self.regex,
+            })
+        })
+        .collect()
+    }
+}
+
+pub fn run(
+    store: &mut SubscriptionList,
+    cmd: &RulesCommand,
+    storage: &dyn Storage,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    let message = match cmd {
+        RulesCommand::List => return list(store, output_json),
+        RulesCommand::Test { number, conditions } => {
+            let rule = match number {
+                Some(n) => store
+                    .rules
+                    .get(n.wrapping_sub(1))
+                    .cloned()
+                    .ok_or_else(|| crate::Error::NotFound(format!("no rule {}", n)))?,
+                None => Rule {
+                    conditions: conditions.conditions(),
+                    action: RuleAction::Hide,
+                },
+            };
+            return test(store, &rule, settings, output_json);
+        }
+        RulesCommand::Add { conditions, action } => {
+            let rule = Rule {
+                conditions: conditions.conditions(),
+                action: action.clone(),
+            };
+            let message = format!("Added rule {}: {}", store.rules.len() + 1, rule);
+            store.add_rule(rule)?;
+            message
+        }
+        RulesCommand::Remove { number } => {
+            let rule = store.remove_rule(*number)?;
+            format!("Removed rule {}: {}", number, rule)
+        }
+    };
+    storage.save(store)?;
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "message": message });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("{}", message);
+    }
+    Ok(())
+}
+
+fn list(store: &SubscriptionList, output_json: bool) -> crate::Result<()> {
+    if output_json {
+        let arr: Vec<serde_json::Value> = store
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let conditions: Vec<serde_json::Value> = rule
+                    .conditions
+                    .iter()
+                    .map(|c| {
+                        serde_json::json!({
+                            "field": c.field.as_str(),
+                            "pattern": c.pattern,
+                            "regex": c.regex,
+                        })
+                    })
+                    .collect();
+                let mut obj = serde_json::Map::new();
+                obj.insert("number".into(), serde_json::Value::Number((i + 1).into()));
+                obj.insert("conditions".into(), serde_json::Value::Array(conditions));
+                obj.insert(
+                    "action".into(),
+                    serde_json::Value::String(rule.action.to_string()),
+                );
+                serde_json::Value::Object(obj)
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&arr).unwrap());
+    } else if store.rules.is_empty() {
+        println!("No rules.");
+    } else {
+        for (i, rule) in store.rules.iter().enumerate() {
+            println!("{}. {}", i + 1, rule);
+        }
+    }
+    Ok(())
+}
+
+fn test(
+    store: &SubscriptionList,
+    rule: &Rule,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    let items = store.test_rule(rule)?;
+    if output_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&items_json(store, &items)).unwrap()
+        );
+        return Ok(());
+    }
+    for i in &items {
+        let date = i
+            .published
+            .map(|d| settings.format_date(&d))
+            .unwrap_or_else(|| "?".to_string());
+        println!("{} | {} | {}", date, i.title, i.feed_url);
+    }
+    println!("{} cached item(s) match", items.len());
+    Ok(())
+}
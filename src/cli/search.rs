@@ -0,0 +1,80 @@
+//! Full-text search of cached items, ranked, with highlighted snippets. The index is kept
+//! next to the store and only rebuilt after items change; hidden items are left out.
+
+use crate::search::{index_path, SearchIndex, SearchQuery};
+use crate::settings::Settings;
+use crate::SubscriptionList;
+use std::path::Path;
+
+pub fn run(
+    store: &SubscriptionList,
+    store_path: &Path,
+    query: &str,
+    limit: usize,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    let parsed = SearchQuery::parse(query);
+    if parsed.is_empty() {
+        return Err(crate::Error::Parse(format!(
+            "empty search query: {:?}",
+            query
+        )));
+    }
+    let index = SearchIndex::cached(store, &index_path(store_path));
+    let hits = index.search_where(&parsed, limit, |id| !store.is_hidden(id));
+
+    if output_json {
+        let arr: Vec<serde_json::Value> = hits
+            .iter()
+            .map(|h| {
+                let mut obj = serde_json::Map::new();
+                obj.insert("id".into(), serde_json::Value::String(h.item_id.clone()));
+                obj.insert(
+                    "feed_url".into(),
+                    serde_json::Value::String(h.feed_url.clone()),
+                );
+                obj.insert("title".into(), serde_json::Value::String(h.title.clone()));
+                obj.insert(
+                    "published".into(),
+                    h.published
+                        .map(|d| serde_json::Value::String(d.to_rfc3339()))
+                        .unwrap_or(serde_json::Value::Null),
+                );
+                obj.insert("score".into(), serde_json::json!(h.score));
+                obj.insert(
+                    "snippet".into(),
+                    serde_json::Value::String(h.snippet.text.clone()),
+                );
+                obj.insert(
+                    "highlights".into(),
+                    h.snippet
+                        .highlights
+                        .iter()
+                        .map(|r| serde_json::json!([r.start, r.end]))
+                        .collect(),
+                );
+                obj.insert(
+                    "read".into(),
+                    serde_json::Value::Bool(store.is_read(&h.item_id)),
+                );
+                serde_json::Value::Object(obj)
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&arr).unwrap());
+    } else if hits.is_empty() {
+        println!("No matching items.");
+    } else {
+        for h in &hits {
+            let date = h
+                .published
+                .map(|d| settings.format_date(&d))
+                .unwrap_or_else(|| "?".to_string());
+            println!("{} | {} | {}", date, h.title, h.item_id);
+            if !h.snippet.text.is_empty() {
+                println!("    {}", h.snippet.render("[", "]"));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,33 @@
+//! Run the HTTP API server until interrupted.
+
+use crate::server::Server;
+use crate::settings::Settings;
+
+pub fn run(
+    settings: &Settings,
+    bind: &str,
+    token: Option<String>,
+    login: Option<(&str, &str)>,
+    allow_hosts: &[String],
+) -> crate::Result<()> {
+    let mut server = Server::bind_with(bind, settings, token.clone())?;
+    if let Some((user, password)) = login {
+        server = server.with_login(user, password);
+    }
+    for host in allow_hosts {
+        server = server.allow_host(host);
+    }
+    let addr = server.local_addr()?;
+    if token.is_none() && !addr.ip().is_loopback() {
+        eprintln!(
+            "Warning: serving on {} without --token; anyone who can reach it can change your feeds.",
+            addr
+        );
+    }
+    println!("Serving API on http://{}/api (Ctrl-C to stop)", addr);
+    if let Some((user, _)) = login {
+        println!("Fever API:         http://{}/fever/ (user {})", addr, user);
+        println!("Google Reader API: http://{}/greader (user {})", addr, user);
+    }
+    server.run()
+}
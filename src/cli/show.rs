@@ -1,83 +1,307 @@
-//! Show one article by id (title, date, source, formatted body, media).
+//! Show one article by id (title, date, source and other metadata, formatted body, links,
+//! media and podcast episode details).
 
-use crate::SubscriptionList;
+use crate::format::ColorChoice;
+use crate::settings::Settings;
+use crate::store::Storage;
+use crate::{FeedItem, PodcastEpisode, PodcastFile};
+use std::fmt::Write;
 
-pub fn run(store: &SubscriptionList, item_id: &str, output_json: bool) -> crate::Result<()> {
-    let item = store
-        .get_item(item_id, None)
+pub fn run(
+    storage: &dyn Storage,
+    item_id: &str,
+    settings: &Settings,
+    color: ColorChoice,
+    pager: bool,
+    output_json: bool,
+) -> crate::Result<()> {
+    let item = storage
+        .get_item(item_id)?
         .ok_or_else(|| crate::Error::NotFound(format!("item not found: {}", item_id)))?;
 
     if output_json {
-        let enclosures: Vec<serde_json::Value> = item
-            .enclosures
-            .iter()
-            .map(|e| {
-                let mut obj = serde_json::Map::new();
-                obj.insert("url".into(), serde_json::Value::String(e.url.clone()));
-                if let Some(t) = &e.media_type {
-                    obj.insert("media_type".into(), serde_json::Value::String(t.clone()));
-                }
-                if let Some(len) = e.length {
-                    obj.insert(
-                        "length".into(),
-                        serde_json::Value::Number(serde_json::Number::from(len)),
-                    );
-                }
-                serde_json::Value::Object(obj)
-            })
-            .collect();
-        let mut obj = serde_json::Map::new();
-        obj.insert(
-            "title".into(),
-            serde_json::Value::String(item.title.clone()),
-        );
-        obj.insert(
-            "published".into(),
-            item.published
-                .map(|d| serde_json::Value::String(d.to_rfc3339()))
-                .unwrap_or(serde_json::Value::Null),
-        );
-        obj.insert(
-            "feed_url".into(),
-            serde_json::Value::String(item.feed_url.clone()),
-        );
-        obj.insert(
-            "content".into(),
-            serde_json::Value::String(item.content.clone().unwrap_or_default()),
-        );
-        obj.insert("enclosures".into(), serde_json::Value::Array(enclosures));
         println!(
             "{}",
-            serde_json::to_string_pretty(&serde_json::Value::Object(obj)).unwrap()
+            serde_json::to_string_pretty(&item_json(&item)).unwrap()
         );
         return Ok(());
     }
+    super::pager::page(&render(&item, settings, color.enabled()), pager)
+}
 
+/// Human-readable article: title, metadata, formatted body, extra links and media.
+pub(crate) fn render(item: &FeedItem, settings: &Settings, styled: bool) -> String {
+    let mut out = String::new();
+    let width = settings.article_width();
     let date = item
         .published
-        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .map(|d| settings.format_date(&d))
         .unwrap_or_else(|| "?".to_string());
 
     // Title, date, source (clearly separated per FR-007)
-    println!("{}\n", item.title);
-    println!("Date:   {}", date);
-    println!("Source: {}", item.feed_url);
-    if item.link.as_deref().is_some_and(|s| !s.is_empty()) {
-        println!("Link:   {}", item.link.as_deref().unwrap());
+    if styled {
+        let _ = writeln!(out, "\x1b[1m{}\x1b[0m\n", item.title);
+    } else {
+        let _ = writeln!(out, "{}\n", item.title);
+    }
+    let mut field = |label: &str, value: &str| {
+        let _ = writeln!(out, "{:<7} {}", label, value);
+    };
+    field("Date:", &date);
+    if let Some(updated) = item.updated.filter(|u| Some(*u) != item.published) {
+        field("Updated:", &settings.format_date(&updated));
     }
-    println!("\n---\n");
+    field("Source:", &item.feed_url);
+    if let Some(link) = item.link.as_deref().filter(|s| !s.is_empty()) {
+        field("Link:", link);
+    }
+    if !item.authors.is_empty() {
+        field("Author:", &item.authors.join(", "));
+    }
+    if !item.categories.is_empty() {
+        field("Categories:", &item.categories.join(", "));
+    }
+    if let Some(source) = &item.source {
+        field("Via:", source);
+    }
+    if let Some(language) = &item.language {
+        field("Language:", language);
+    }
+    if let Some(rights) = &item.rights {
+        field("Rights:", rights);
+    }
+    if let Some(numbering) = item.podcast.numbering() {
+        field("Episode:", &numbering);
+    }
+    if !item.podcast.persons.is_empty() {
+        let people: Vec<String> = item.podcast.persons.iter().map(|p| p.label()).collect();
+        field("People:", &people.join(", "));
+    }
+    out.push_str("\n---\n\n");
 
     // Formatted body (structure preserved)
-    println!("{}", crate::format_article(item.content.as_deref(), 80));
+    let body = item.content.as_deref();
+    if styled {
+        out.push_str(&crate::format_article_styled(body, width));
+    } else {
+        out.push_str(&crate::format_article(body, width));
+    }
+    out.push('\n');
+
+    if !item.links.is_empty() {
+        out.push_str("\n---\nLinks:\n");
+        for l in &item.links {
+            let _ = write!(out, "  {}", l.href);
+            if let Some(rel) = &l.rel {
+                let _ = write!(out, " [{}]", rel);
+            }
+            if let Some(title) = &l.title {
+                let _ = write!(out, " {}", title);
+            }
+            out.push('\n');
+        }
+    }
 
     if !item.enclosures.is_empty() {
-        println!("\n---\nMedia:");
+        out.push_str("\n---\nMedia:\n");
         for (i, e) in item.enclosures.iter().enumerate() {
             let mime = e.media_type.as_deref().unwrap_or("?");
-            println!("  [{}] Open: {} ({})", i, e.url, mime);
+            let _ = writeln!(out, "  [{}] Open: {} ({})", i, e.url, mime);
+            let details: Vec<String> = [
+                e.title.clone(),
+                e.duration_secs.map(crate::format_duration),
+                e.thumbnail.as_ref().map(|t| format!("image: {}", t)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if !details.is_empty() {
+                let _ = writeln!(out, "      {}", details.join(" · "));
+            }
+        }
+        if let Some(chapters) = &item.podcast.chapters {
+            let _ = writeln!(out, "  Chapters:   {}", chapters.url);
+        }
+        for t in &item.podcast.transcripts {
+            let kind: Vec<&str> = [t.media_type.as_deref(), t.language.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect();
+            let _ = writeln!(out, "  Transcript: {} ({})", t.url, kind.join(", "));
         }
-        println!("\n  To open: rss-reader open-enclosure <item-id> <index>");
-        println!("  To download: rss-reader open-enclosure <item-id> <index> --download [--output-dir <dir>]");
+        out.push_str("\n  To open: rss-reader open-enclosure <item-id> <index>\n");
+        out.push_str("  To download: rss-reader open-enclosure <item-id> <index> --download [--output-dir <dir>]\n");
     }
-    Ok(())
+    out
+}
+
+/// JSON object of one item as printed by `show -o json` (also served by the HTTP API).
+pub(crate) fn item_json(item: &FeedItem) -> serde_json::Value {
+    let enclosures: Vec<serde_json::Value> = item
+        .enclosures
+        .iter()
+        .map(|e| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("url".into(), serde_json::Value::String(e.url.clone()));
+            if let Some(t) = &e.media_type {
+                obj.insert("media_type".into(), serde_json::Value::String(t.clone()));
+            }
+            if let Some(len) = e.length {
+                obj.insert(
+                    "length".into(),
+                    serde_json::Value::Number(serde_json::Number::from(len)),
+                );
+            }
+            if let Some(t) = &e.title {
+                obj.insert("title".into(), serde_json::Value::String(t.clone()));
+            }
+            if let Some(secs) = e.duration_secs {
+                obj.insert(
+                    "duration_secs".into(),
+                    serde_json::Value::Number(secs.into()),
+                );
+            }
+            if let Some(t) = &e.thumbnail {
+                obj.insert("thumbnail".into(), serde_json::Value::String(t.clone()));
+            }
+            serde_json::Value::Object(obj)
+        })
+        .collect();
+    let links: Vec<serde_json::Value> = item
+        .links
+        .iter()
+        .map(|l| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("href".into(), serde_json::Value::String(l.href.clone()));
+            for (key, value) in [
+                ("rel", &l.rel),
+                ("media_type", &l.media_type),
+                ("title", &l.title),
+            ] {
+                if let Some(v) = value {
+                    obj.insert(key.into(), serde_json::Value::String(v.clone()));
+                }
+            }
+            serde_json::Value::Object(obj)
+        })
+        .collect();
+    let mut obj = serde_json::Map::new();
+    obj.insert(
+        "title".into(),
+        serde_json::Value::String(item.title.clone()),
+    );
+    obj.insert(
+        "published".into(),
+        item.published
+            .map(|d| serde_json::Value::String(d.to_rfc3339()))
+            .unwrap_or(serde_json::Value::Null),
+    );
+    obj.insert(
+        "feed_url".into(),
+        serde_json::Value::String(item.feed_url.clone()),
+    );
+    if let Some(l) = &item.link {
+        obj.insert("link".into(), serde_json::Value::String(l.clone()));
+    }
+    insert_metadata(&mut obj, item);
+    for (key, value) in [
+        ("language", &item.language),
+        ("rights", &item.rights),
+        ("source", &item.source),
+    ] {
+        if let Some(v) = value {
+            obj.insert(key.into(), serde_json::Value::String(v.clone()));
+        }
+    }
+    obj.insert(
+        "content".into(),
+        serde_json::Value::String(item.content.clone().unwrap_or_default()),
+    );
+    obj.insert("links".into(), serde_json::Value::Array(links));
+    obj.insert("enclosures".into(), serde_json::Value::Array(enclosures));
+    if !item.podcast.is_empty() {
+        obj.insert("podcast".into(), podcast_json(&item.podcast));
+    }
+    serde_json::Value::Object(obj)
+}
+
+/// `podcast` object of [`item_json`]: numbering, chapters, transcripts and persons.
+fn podcast_json(podcast: &PodcastEpisode) -> serde_json::Value {
+    let file = |f: &PodcastFile| {
+        let mut obj = serde_json::Map::new();
+        obj.insert("url".into(), serde_json::Value::String(f.url.clone()));
+        for (key, value) in [
+            ("media_type", &f.media_type),
+            ("language", &f.language),
+            ("rel", &f.rel),
+        ] {
+            if let Some(v) = value {
+                obj.insert(key.into(), serde_json::Value::String(v.clone()));
+            }
+        }
+        serde_json::Value::Object(obj)
+    };
+    let persons: Vec<serde_json::Value> = podcast
+        .persons
+        .iter()
+        .map(|p| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("name".into(), serde_json::Value::String(p.name.clone()));
+            for (key, value) in [
+                ("role", &p.role),
+                ("group", &p.group),
+                ("img", &p.img),
+                ("href", &p.href),
+            ] {
+                if let Some(v) = value {
+                    obj.insert(key.into(), serde_json::Value::String(v.clone()));
+                }
+            }
+            serde_json::Value::Object(obj)
+        })
+        .collect();
+    let number = |n: Option<u32>| {
+        n.map(|n| serde_json::Value::Number(n.into()))
+            .unwrap_or(serde_json::Value::Null)
+    };
+    let mut obj = serde_json::Map::new();
+    obj.insert("episode".into(), number(podcast.episode));
+    obj.insert("season".into(), number(podcast.season));
+    obj.insert(
+        "chapters".into(),
+        podcast
+            .chapters
+            .as_ref()
+            .map(file)
+            .unwrap_or(serde_json::Value::Null),
+    );
+    obj.insert(
+        "transcripts".into(),
+        serde_json::Value::Array(podcast.transcripts.iter().map(file).collect()),
+    );
+    obj.insert("persons".into(), serde_json::Value::Array(persons));
+    serde_json::Value::Object(obj)
+}
+
+/// Add `authors`, `categories` and `updated` (shared with `list-items -o json`).
+pub(crate) fn insert_metadata(
+    obj: &mut serde_json::Map<String, serde_json::Value>,
+    item: &FeedItem,
+) {
+    let strings = |values: &[String]| {
+        serde_json::Value::Array(
+            values
+                .iter()
+                .map(|v| serde_json::Value::String(v.clone()))
+                .collect(),
+        )
+    };
+    obj.insert("authors".into(), strings(&item.authors));
+    obj.insert("categories".into(), strings(&item.categories));
+    obj.insert(
+        "updated".into(),
+        item.updated
+            .map(|d| serde_json::Value::String(d.to_rfc3339()))
+            .unwrap_or(serde_json::Value::Null),
+    );
 }
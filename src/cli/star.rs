@@ -0,0 +1,29 @@
+//! Star or unstar items by id.
+
+use crate::store::Storage;
+use crate::SubscriptionList;
+
+pub fn run(
+    store: &mut SubscriptionList,
+    item_ids: &[String],
+    starred: bool,
+    storage: &dyn Storage,
+    output_json: bool,
+) -> crate::Result<()> {
+    if let Some(id) = store.unknown_item(item_ids) {
+        return Err(crate::Error::NotFound(format!("item not found: {}", id)));
+    }
+    for id in item_ids {
+        store.set_starred_unchecked(id, starred);
+    }
+    storage.save(store)?;
+
+    let action = if starred { "Starred" } else { "Unstarred" };
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "updated_count": item_ids.len() });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("{} {} item(s)", action, item_ids.len());
+    }
+    Ok(())
+}
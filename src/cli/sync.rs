@@ -0,0 +1,198 @@
+//! Sync with a self-hosted aggregator (Miniflux, FreshRSS / Google Reader API, Nextcloud
+//! News): run a two-way sync, show what is pending, or forget the sync state.
+
+use crate::settings::Settings;
+use crate::store::Storage;
+use crate::sync::{pending, sync, Account, ConflictPolicy, ServiceKind, SyncOptions, SyncReport};
+use crate::{Error, SubscriptionList};
+
+/// Conflicts listed in the human summary; JSON output has all of them.
+const MAX_CONFLICT_LINES: usize = 20;
+
+/// `sync` subcommands.
+#[derive(clap::Subcommand, Debug)]
+pub enum SyncCommand {
+    /// Pull subscriptions, items and read/starred state and push local changes. Service,
+    /// url and user default to those of the last sync.
+    Run {
+        /// Server API: miniflux, greader (FreshRSS and other Google Reader APIs) or nextcloud.
+        #[arg(long)]
+        service: Option<ServiceKind>,
+        /// Server url (for greader the API root, e.g. https://host/api/greader.php).
+        #[arg(long)]
+        url: Option<String>,
+        #[arg(long)]
+        user: Option<String>,
+        #[arg(long, env = "RSS_READER_SYNC_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+        /// Miniflux API key (instead of user and password).
+        #[arg(long, env = "RSS_READER_SYNC_TOKEN", hide_env_values = true)]
+        api_token: Option<String>,
+        /// Which side wins when both changed (or on the first sync): remote or local.
+        #[arg(long, default_value_t = ConflictPolicy::Remote)]
+        prefer: ConflictPolicy,
+        /// Newest server entries to pull and reconcile.
+        #[arg(long, default_value_t = 1000)]
+        max_items: usize,
+    },
+    /// Show the server last synced with and the local changes not pushed yet.
+    Status,
+    /// Forget the sync state; the next run treats every difference as a conflict.
+    Forget,
+}
+
+pub fn run(
+    store: &mut SubscriptionList,
+    cmd: &SyncCommand,
+    storage: &dyn Storage,
+    settings: &Settings,
+    output_json: bool,
+) -> crate::Result<()> {
+    match cmd {
+        SyncCommand::Run {
+            service,
+            url,
+            user,
+            password,
+            api_token,
+            prefer,
+            max_items,
+        } => {
+            let last = store.sync.as_ref();
+            let account = Account {
+                service: service
+                    .or(last.map(|s| s.service))
+                    .ok_or_else(|| Error::InvalidInput("no --service given".to_string()))?,
+                url: url
+                    .clone()
+                    .or_else(|| last.map(|s| s.url.clone()))
+                    .ok_or_else(|| Error::InvalidInput("no --url given".to_string()))?,
+                user: user.clone().or_else(|| last.and_then(|s| s.user.clone())),
+                password: password.clone(),
+                api_token: api_token.clone(),
+            };
+            let options = SyncOptions {
+                prefer: *prefer,
+                max_entries: *max_items,
+            };
+            let service = account.connect(&settings.http_options())?;
+            let report = sync(store, service.as_ref(), &account, &options)?;
+            storage.save(store)?;
+            print_report(&account, &report, output_json);
+            Ok(())
+        }
+        SyncCommand::Status => status(store, settings, output_json),
+        SyncCommand::Forget => {
+            let message = match store.sync.take() {
+                Some(state) => format!("Forgot sync state for {}", state.url),
+                None => "No sync state".to_string(),
+            };
+            store.changes.sync = true;
+            storage.save(store)?;
+            if output_json {
+                let obj = serde_json::json!({ "success": true, "message": message });
+                println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+            } else {
+                println!("{}", message);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_report(account: &Account, report: &SyncReport, output_json: bool) {
+    if output_json {
+        let obj = serde_json::json!({
+            "success": true,
+            "service": account.service.as_str(),
+            "url": account.url,
+            "feeds_added_locally": report.feeds_added_locally,
+            "feeds_added_remotely": report.feeds_added_remotely,
+            "feeds_removed_locally": report.feeds_removed_locally,
+            "feeds_removed_remotely": report.feeds_removed_remotely,
+            "new_items": report.new_items,
+            "pulled_changes": report.pulled_changes,
+            "pushed_changes": report.pushed_changes,
+            "conflicts": report.conflicts,
+        });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+        return;
+    }
+    println!("Synced with {} ({})", account.url, account.service);
+    println!(
+        "  feeds: {} added here, {} added there, {} removed here, {} removed there",
+        report.feeds_added_locally,
+        report.feeds_added_remotely,
+        report.feeds_removed_locally,
+        report.feeds_removed_remotely
+    );
+    println!(
+        "  items: {} new, {} change(s) pulled, {} pushed",
+        report.new_items, report.pulled_changes, report.pushed_changes
+    );
+    if !report.conflicts.is_empty() {
+        println!("  {} conflict(s):", report.conflicts.len());
+        for line in report.conflicts.iter().take(MAX_CONFLICT_LINES) {
+            println!("    {}", line);
+        }
+        if report.conflicts.len() > MAX_CONFLICT_LINES {
+            println!(
+                "    ... and {} more (see --output json)",
+                report.conflicts.len() - MAX_CONFLICT_LINES
+            );
+        }
+    }
+}
+
+fn status(store: &SubscriptionList, settings: &Settings, output_json: bool) -> crate::Result<()> {
+    let Some(state) = &store.sync else {
+        if output_json {
+            println!("{}", serde_json::json!({ "synced": false }));
+        } else {
+            println!("Never synced (run `sync run --service .. --url ..`)");
+        }
+        return Ok(());
+    };
+    let pending = pending(store, state);
+    let last = state.last_sync.map(|d| settings.format_date(&d));
+    if output_json {
+        let obj = serde_json::json!({
+            "synced": true,
+            "service": state.service.as_str(),
+            "url": state.url,
+            "user": state.user,
+            "last_sync": state.last_sync.map(|d| d.to_rfc3339()),
+            "feeds": state.feeds.len(),
+            "items": state.items.len(),
+            "pending": {
+                "feeds_added": pending.feeds_added,
+                "feeds_removed": pending.feeds_removed,
+                "folders": pending.folders,
+                "states": pending.states,
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!(
+            "Last synced with {} ({}{}) at {}",
+            state.url,
+            state.service,
+            state
+                .user
+                .as_deref()
+                .map(|u| format!(", user {}", u))
+                .unwrap_or_default(),
+            last.as_deref().unwrap_or("-")
+        );
+        println!(
+            "  {} feed(s) and {} item(s) synced",
+            state.feeds.len(),
+            state.items.len()
+        );
+        println!(
+            "  pending: {} feed(s) added, {} removed, {} moved, {} read/starred change(s)",
+            pending.feeds_added, pending.feeds_removed, pending.folders, pending.states
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! Add or remove tags on a feed.
+
+use crate::store::Storage;
+use crate::SubscriptionList;
+
+pub fn run(
+    store: &mut SubscriptionList,
+    url: &str,
+    tags: &[String],
+    add: bool,
+    storage: &dyn Storage,
+    output_json: bool,
+) -> crate::Result<()> {
+    store.tag_feed(url, tags, add)?;
+    storage.save(store)?;
+
+    let current = store
+        .feeds
+        .iter()
+        .find(|f| f.url == url)
+        .map(|f| f.tags.clone())
+        .unwrap_or_default();
+    if output_json {
+        let obj = serde_json::json!({ "success": true, "url": url, "tags": current });
+        println!("{}", serde_json::to_string_pretty(&obj).unwrap());
+    } else {
+        println!("Tags of {}: {}", url, current.join(", "));
+    }
+    Ok(())
+}
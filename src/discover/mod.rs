@@ -0,0 +1,229 @@
+//! Feed auto-discovery: turn a website URL into the feed(s) it advertises.
+//!
+//! A URL that already serves a feed is used as is. For an HTML page, its
+//! `<link rel="alternate" type="application/rss+xml|atom+xml">` tags are collected; if there
+//! are none, a few common feed paths (`/feed`, `/rss.xml`, ...) are probed in parallel.
+
+use crate::feed::{Feed, FeedItem};
+use crate::fetch::{cache_headers, fetch_feed, parse_feed, HttpOptions};
+use crate::Error;
+use reqwest::header::CONTENT_TYPE;
+use std::sync::mpsc;
+use std::time::Duration;
+use url::Url;
+
+/// Paths tried (relative to the page, then to the site root) when a page has no feed links.
+const COMMON_PATHS: &[&str] = &[
+    "feed",
+    "rss",
+    "feed.xml",
+    "rss.xml",
+    "atom.xml",
+    "index.xml",
+];
+
+/// Longest wait for one probe of a common path.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Link `type`s that mark a feed, with the short kind reported for them.
+const FEED_TYPES: &[(&str, &str)] = &[
+    ("application/rss+xml", "rss"),
+    ("application/atom+xml", "atom"),
+    ("application/rdf+xml", "rdf"),
+];
+
+/// A feed found on a website.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeedCandidate {
+    /// Absolute feed URL.
+    pub url: String,
+    /// Title from the link tag, or from the feed itself when found by probing.
+    pub title: Option<String>,
+    /// "rss", "atom" or "rdf" when the link tag says so.
+    pub kind: Option<String>,
+}
+
+/// Outcome of [`discover_feed`].
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)] // one per discovery, matched on right away by `add`
+pub enum Discovery {
+    /// The URL is a feed; parsed metadata and items.
+    Feed(Feed, Vec<FeedItem>),
+    /// The URL is a web page; feeds it links to (possibly none).
+    Candidates(Vec<FeedCandidate>),
+}
+
+/// Fetch `url` and either parse it as a feed or discover the feeds an HTML page offers.
+/// Probing common paths stops at the first feed unless `all` is set. A response that is
+/// neither a feed nor HTML fails with the feed parse error.
+pub fn discover_feed(url: &str, all: bool, http: &HttpOptions) -> Result<Discovery, Error> {
+    let response = http.client()?.get(url).send()?.error_for_status()?;
+    let base = response.url().clone();
+    let (etag, last_modified) = cache_headers(&response);
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|t| t.contains("html"));
+    let body = response.text()?;
+    match parse_feed(url, &body) {
+        Ok((mut feed, items)) => {
+            feed.etag = etag;
+            feed.last_modified = last_modified;
+            return Ok(Discovery::Feed(feed, items));
+        }
+        Err(e) if !is_html && !looks_like_html(&body) => return Err(e),
+        Err(_) => {}
+    }
+    let mut candidates = find_feed_links(&body, &base);
+    if candidates.is_empty() {
+        candidates = probe_common_paths(&base, all, http);
+    }
+    Ok(Discovery::Candidates(candidates))
+}
+
+fn looks_like_html(body: &str) -> bool {
+    let head: String = body.chars().take(1024).collect::<String>().to_lowercase();
+    head.contains("<html") || head.contains("<!doctype html")
+}
+
+/// Feed `<link rel="alternate">` tags of an HTML page, resolved against `base`, in page order.
+pub fn find_feed_links(html: &str, base: &Url) -> Vec<FeedCandidate> {
+    // ASCII lower-casing keeps byte offsets, so positions found in `lower` index `html`.
+    let lower = html.to_ascii_lowercase();
+    let mut out: Vec<FeedCandidate> = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<link").map(|i| pos + i) {
+        let end = lower[start..].find('>').map_or(lower.len(), |i| start + i);
+        pos = end;
+        let attrs = tag_attributes(&html[start + "<link".len()..end]);
+        let attr = |name: &str| {
+            attrs
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str())
+        };
+        let is_alternate = attr("rel").is_some_and(|rel| {
+            rel.split_ascii_whitespace()
+                .any(|r| r.eq_ignore_ascii_case("alternate"))
+        });
+        let kind = attr("type").and_then(|t| {
+            let t = t.trim().to_ascii_lowercase();
+            FEED_TYPES
+                .iter()
+                .find(|(mime, _)| t == *mime)
+                .map(|(_, k)| *k)
+        });
+        let href = attr("href").and_then(|h| base.join(h.trim()).ok());
+        if let (true, Some(kind), Some(href)) = (is_alternate, kind, href) {
+            let url = href.to_string();
+            if !out.iter().any(|c| c.url == url) {
+                out.push(FeedCandidate {
+                    url,
+                    title: attr("title")
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .map(str::to_string),
+                    kind: Some(kind.to_string()),
+                });
+            }
+        }
+    }
+    out
+}
+
+/// Attributes of a tag body (`name="value" name='value' name=value name`), names lower-cased
+/// and the common character entities decoded.
+fn tag_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut rest = tag.trim_start_matches('/');
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        let name_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            break;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let inner = &after[1..];
+                        let close = inner.find(q).unwrap_or(inner.len());
+                        (&inner[..close], inner.get(close + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let stop = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..stop], &after[stop..])
+                    }
+                };
+                rest = remaining;
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+        out.push((name, value));
+    }
+    out
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Feeds served at [`COMMON_PATHS`] below the page and below the site root, probed in
+/// parallel. Unless `all` is set, only the first feed in path order is returned, as soon
+/// as every probe before it has missed.
+fn probe_common_paths(base: &Url, all: bool, http: &HttpOptions) -> Vec<FeedCandidate> {
+    let mut urls: Vec<Url> = Vec::new();
+    for path in COMMON_PATHS {
+        for u in [base.join(path), base.join(&format!("/{}", path))]
+            .into_iter()
+            .flatten()
+        {
+            if !urls.contains(&u) {
+                urls.push(u);
+            }
+        }
+    }
+    let http = HttpOptions {
+        timeout: http.timeout.min(PROBE_TIMEOUT),
+        ..http.clone()
+    };
+    let (tx, rx) = mpsc::channel();
+    for (i, url) in urls.iter().enumerate() {
+        let (tx, url, http) = (tx.clone(), url.clone(), http.clone());
+        // Not scoped: probes still running after the first hit are left to finish alone.
+        std::thread::spawn(move || {
+            let found = fetch_feed(url.as_str(), &http)
+                .ok()
+                .map(|(feed, _)| FeedCandidate {
+                    url: feed.url,
+                    title: feed.title,
+                    kind: None,
+                });
+            let _ = tx.send((i, found));
+        });
+    }
+    drop(tx);
+    // None while a probe runs, then Some(hit or miss).
+    let mut results: Vec<Option<Option<FeedCandidate>>> = vec![None; urls.len()];
+    for (i, found) in rx {
+        results[i] = Some(found);
+        if !all {
+            if let Some(Some(Some(hit))) = results.iter().find(|r| !matches!(r, Some(None))) {
+                return vec![hit.clone()];
+            }
+        }
+    }
+    results.into_iter().flatten().flatten().collect()
+}
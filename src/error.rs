@@ -21,6 +21,24 @@ pub enum Error {
 
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Server asked to retry in {1} s: {0}")]
+    RetryAfter(String, u64),
+
+    #[error("Refresh failed for {0} feed(s)")]
+    RefreshFailed(u32),
+
+    #[error("Found {0} feeds; add one by url or pass --all")]
+    MultipleFeeds(usize),
+
+    #[error("Sync failed: {0}")]
+    Sync(String),
+
+    #[error("Config error: {0}")]
+    Config(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,20 +1,103 @@
-//! Feed, FeedItem, and MediaEnclosure types (see data-model).
+//! Feed, FeedItem, MediaEnclosure and podcast episode types (see data-model).
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// A subscription source identified by URL.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Feed {
     pub url: String,
     pub title: Option<String>,
     pub description: Option<String>,
     pub last_fetched: Option<DateTime<Utc>>,
     pub created_at: Option<DateTime<Utc>>,
+    /// `ETag` response header from the last successful fetch (sent as `If-None-Match`).
+    pub etag: Option<String>,
+    /// `Last-Modified` response header from the last successful fetch (sent as `If-Modified-Since`).
+    pub last_modified: Option<String>,
+    /// Error message of the most recent failed fetch; cleared on success.
+    pub last_error: Option<String>,
+    /// Number of fetches that have failed in a row; 0 when the feed is healthy.
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// When the feed was last fetched successfully (including 304 Not Modified).
+    pub last_success: Option<DateTime<Utc>>,
+    /// Folder path with `/`-separated segments (e.g. `Tech/Rust`); None = top level.
+    pub folder: Option<String>,
+    /// Free-form user tags, sorted and without duplicates.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// How often the publisher and server want the feed polled (see [`crate::schedule`]).
+    #[serde(default)]
+    pub refresh_hints: RefreshHints,
+    /// The website the feed belongs to.
+    pub site_url: Option<String>,
+    /// Small icon (favicon) url.
+    pub icon: Option<String>,
+    /// Larger logo / channel image url.
+    pub logo: Option<String>,
+    /// Language code, e.g. `en-us`.
+    pub language: Option<String>,
+    /// Software that generated the feed (with its version, if given).
+    pub generator: Option<String>,
+    /// Automatic downloads and retention of the feed's enclosures.
+    #[serde(default, skip_serializing_if = "DownloadPolicy::is_default")]
+    pub download_policy: DownloadPolicy,
+}
+
+/// Which enclosures of a feed are downloaded automatically and which files are kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadPolicy {
+    /// Queue the first enclosure of every new item.
+    pub auto: bool,
+    /// Keep the files of only this many newest items.
+    pub keep: Option<usize>,
+    /// Delete the files of items marked read.
+    pub delete_played: bool,
+}
+
+impl DownloadPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == DownloadPolicy::default()
+    }
+}
+
+/// Refresh-interval hints collected while fetching a feed, in seconds.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshHints {
+    /// RSS `<ttl>`.
+    pub ttl_secs: Option<u64>,
+    /// `sy:updatePeriod` divided by `sy:updateFrequency`.
+    pub update_period_secs: Option<u64>,
+    /// `Cache-Control: max-age` of the last full response.
+    pub max_age_secs: Option<u64>,
+    /// `Retry-After` of the last 429/503 response; cleared on success.
+    pub retry_after_secs: Option<u64>,
+}
+
+impl Feed {
+    /// Whether the most recent fetch of this feed failed.
+    pub fn is_failing(&self) -> bool {
+        self.consecutive_failures > 0
+    }
+
+    /// Whether the feed is in folder `path` or one of its subfolders.
+    pub fn in_folder(&self, path: &str) -> bool {
+        self.folder.as_deref().is_some_and(|f| {
+            f == path
+                || f.strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// Whether the feed has `tag` (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
 }
 
 /// A single entry from a feed.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct FeedItem {
     pub id: String,
     pub feed_url: String,
@@ -24,13 +107,133 @@ pub struct FeedItem {
     pub summary: Option<String>,
     pub content: Option<String>,
     pub enclosures: Vec<MediaEnclosure>,
+    /// Author names.
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Category labels (or terms).
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Links other than `link` (related, replies, alternate formats, ...).
+    #[serde(default)]
+    pub links: Vec<ItemLink>,
+    /// When the entry was last updated (`published` falls back to this).
+    pub updated: Option<DateTime<Utc>>,
+    /// Language code, e.g. `en-us`.
+    pub language: Option<String>,
+    /// Copyright notice.
+    pub rights: Option<String>,
+    /// Where the entry was originally published (RSS `<source>`).
+    pub source: Option<String>,
+    /// Podcast episode metadata; empty for other items.
+    #[serde(default, skip_serializing_if = "PodcastEpisode::is_empty")]
+    pub podcast: PodcastEpisode,
+}
+
+/// An additional link of a feed item.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemLink {
+    pub href: String,
+    /// Relation, e.g. `related`, `replies` or `alternate`.
+    pub rel: Option<String>,
+    pub media_type: Option<String>,
+    pub title: Option<String>,
 }
 
 /// A piece of media attached to a feed item.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MediaEnclosure {
     pub url: String,
     pub media_type: Option<String>,
     pub length: Option<u64>,
     pub title: Option<String>,
+    /// Play time in seconds (`itunes:duration` or `media:content` duration).
+    pub duration_secs: Option<u64>,
+    /// Thumbnail / episode artwork url.
+    pub thumbnail: Option<String>,
+}
+
+/// iTunes episode numbering and Podcasting 2.0 tags of an episode.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PodcastEpisode {
+    /// `itunes:episode`.
+    pub episode: Option<u32>,
+    /// `itunes:season`.
+    pub season: Option<u32>,
+    /// `podcast:chapters`: a chapters file (usually JSON chapters).
+    pub chapters: Option<PodcastFile>,
+    /// `podcast:transcript`s, in feed order.
+    #[serde(default)]
+    pub transcripts: Vec<PodcastFile>,
+    /// `podcast:person`s: hosts, guests and other credits.
+    #[serde(default)]
+    pub persons: Vec<PodcastPerson>,
+}
+
+impl PodcastEpisode {
+    pub fn is_empty(&self) -> bool {
+        *self == PodcastEpisode::default()
+    }
+
+    /// "Season 2, episode 5", "Episode 5" or "Season 2"; None without numbering.
+    pub fn numbering(&self) -> Option<String> {
+        match (self.season, self.episode) {
+            (Some(s), Some(e)) => Some(format!("Season {}, episode {}", s, e)),
+            (None, Some(e)) => Some(format!("Episode {}", e)),
+            (Some(s), None) => Some(format!("Season {}", s)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A file linked from a podcast episode (chapters or transcript).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PodcastFile {
+    pub url: String,
+    pub media_type: Option<String>,
+    /// Transcript language, e.g. `en`.
+    pub language: Option<String>,
+    /// `captions` for transcripts with timing meant as captions.
+    pub rel: Option<String>,
+}
+
+/// A person credited on an episode (`podcast:person`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PodcastPerson {
+    pub name: String,
+    /// E.g. `host` or `guest` (the tag's default is `host`).
+    pub role: Option<String>,
+    pub group: Option<String>,
+    /// Picture url.
+    pub img: Option<String>,
+    /// Homepage url.
+    pub href: Option<String>,
+}
+
+impl PodcastPerson {
+    /// "Name (role)".
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.name, self.role.as_deref().unwrap_or("host"))
+    }
+}
+
+/// `seconds` as `H:MM:SS`, or `M:SS` under an hour.
+pub fn format_duration(seconds: u64) -> String {
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// Parse `H:MM:SS`, `M:SS` or plain seconds (the forms [`format_duration`] writes).
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    parts.iter().try_fold(0u64, |total, part| {
+        let value: u64 = part.trim().parse().ok()?;
+        total.checked_mul(60)?.checked_add(value)
+    })
 }
@@ -1,21 +1,219 @@
-//! HTTP fetch and parse RSS/Atom into FeedItem list.
+//! HTTP fetch and parse RSS/Atom into FeedItem list, collecting refresh-interval hints
+//! (`<ttl>`, `sy:updatePeriod`, `Cache-Control`, `Retry-After`) and podcast episode
+//! metadata on the way.
 
-use crate::feed::{Feed, FeedItem, MediaEnclosure};
+use crate::feed::{DownloadPolicy, Feed, FeedItem, ItemLink, MediaEnclosure, RefreshHints};
 use crate::Error;
 use chrono::Utc;
+use quick_xml::events::Event;
+use reqwest::header::{
+    CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+};
+use reqwest::StatusCode;
 use std::time::Duration;
 
+mod podcast;
+
+/// Result of a conditional fetch.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)] // short-lived return value; boxing would only add noise
+pub enum FetchOutcome {
+    /// Server returned a new body; parsed feed metadata and items.
+    Updated(Feed, Vec<FeedItem>),
+    /// Server answered 304 Not Modified; nothing was downloaded or parsed. The validators
+    /// are those the response resent (None keeps the stored ones), with its `max-age`.
+    NotModified {
+        etag: Option<String>,
+        last_modified: Option<String>,
+        max_age_secs: Option<u64>,
+    },
+}
+
+/// Timeout and User-Agent of the HTTP clients for feeds, discovery, downloads and sync
+/// (see [`Settings::http_options`](crate::settings::Settings::http_options)).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpOptions {
+    pub timeout: Duration,
+    pub user_agent: String,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            user_agent: "rss-reader/0.1".to_string(),
+        }
+    }
+}
+
+impl HttpOptions {
+    pub(crate) fn client(&self) -> Result<reqwest::blocking::Client, Error> {
+        Ok(reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(&self.user_agent)
+            .build()?)
+    }
+
+    /// Client for large downloads: the timeout limits connecting only, not the whole transfer.
+    pub(crate) fn download_client(&self) -> Result<reqwest::blocking::Client, Error> {
+        Ok(reqwest::blocking::Client::builder()
+            .timeout(None)
+            .connect_timeout(self.timeout)
+            .user_agent(&self.user_agent)
+            .build()?)
+    }
+}
+
 /// Fetches a feed URL and returns parsed feed metadata and items.
-pub fn fetch_feed(url: &str) -> Result<(Feed, Vec<FeedItem>), Error> {
-    let body = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .user_agent("rss-reader/0.1")
-        .build()?
-        .get(url)
-        .send()?
-        .error_for_status()?
-        .text()?;
+pub fn fetch_feed(url: &str, http: &HttpOptions) -> Result<(Feed, Vec<FeedItem>), Error> {
+    let response = check_retry_after(http.client()?.get(url).send()?)?.error_for_status()?;
+    let (etag, last_modified) = cache_headers(&response);
+    let max_age = max_age(&response);
+    let body = response.text()?;
+    let (mut feed, items) = parse_feed(url, &body)?;
+    feed.etag = etag;
+    feed.last_modified = last_modified;
+    feed.refresh_hints.max_age_secs = max_age;
+    Ok((feed, items))
+}
+
+/// Fetches `feed.url`, sending `If-None-Match` / `If-Modified-Since` from the stored
+/// `etag` / `last_modified`. A 304 response yields [`FetchOutcome::NotModified`].
+pub fn fetch_feed_conditional(feed: &Feed, http: &HttpOptions) -> Result<FetchOutcome, Error> {
+    let mut request = http.client()?.get(&feed.url);
+    if let Some(etag) = &feed.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &feed.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let response = request.send()?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        let (etag, last_modified) = cache_headers(&response);
+        return Ok(FetchOutcome::NotModified {
+            etag,
+            last_modified,
+            max_age_secs: max_age(&response),
+        });
+    }
+    let response = check_retry_after(response)?.error_for_status()?;
+    let (etag, last_modified) = cache_headers(&response);
+    let max_age = max_age(&response);
+    let body = response.text()?;
+    let (mut fetched, items) = parse_feed(&feed.url, &body)?;
+    fetched.etag = etag;
+    fetched.last_modified = last_modified;
+    fetched.refresh_hints.max_age_secs = max_age;
+    fetched.created_at = feed.created_at.or(fetched.created_at);
+    Ok(FetchOutcome::Updated(fetched, items))
+}
+
+pub(crate) fn cache_headers(
+    response: &reqwest::blocking::Response,
+) -> (Option<String>, Option<String>) {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+    };
+    (header(ETAG), header(LAST_MODIFIED))
+}
+
+/// `max-age` of the `Cache-Control` header, unless the response must not be cached.
+fn max_age(response: &reqwest::blocking::Response) -> Option<u64> {
+    let value = response.headers().get(CACHE_CONTROL)?.to_str().ok()?;
+    let directives: Vec<&str> = value.split(',').map(str::trim).collect();
+    if directives
+        .iter()
+        .any(|d| d.eq_ignore_ascii_case("no-store") || d.eq_ignore_ascii_case("no-cache"))
+    {
+        return None;
+    }
+    directives.iter().find_map(|d| {
+        let (name, secs) = d.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("max-age")
+            .then(|| secs.trim().trim_matches('"').parse().ok())
+            .flatten()
+    })
+}
+
+/// Turn a 429 / 503 response with a `Retry-After` header (seconds or HTTP date) into
+/// [`Error::RetryAfter`]; other responses pass through.
+fn check_retry_after(
+    response: reqwest::blocking::Response,
+) -> Result<reqwest::blocking::Response, Error> {
+    let status = response.status();
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return Ok(response);
+    }
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            let v = v.trim();
+            v.parse::<u64>().ok().or_else(|| {
+                let at = chrono::DateTime::parse_from_rfc2822(v).ok()?;
+                Some((at.with_timezone(&Utc) - Utc::now()).num_seconds().max(0) as u64)
+            })
+        });
+    match retry_after {
+        Some(secs) => Err(Error::RetryAfter(format!("HTTP {}", status), secs)),
+        None => Ok(response),
+    }
+}
 
+/// Interval from the RSS syndication module (`sy:updatePeriod` / `sy:updateFrequency`).
+fn syndication_period(body: &str) -> Option<u64> {
+    let mut reader = quick_xml::Reader::from_str(body);
+    reader.trim_text(true);
+    let mut current: Option<Vec<u8>> = None;
+    let (mut period, mut frequency) = (None, None);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name().as_ref().to_vec();
+                // The syndication elements belong to the channel, which precedes the items.
+                if name == b"item" || name == b"entry" {
+                    break;
+                }
+                current = Some(name);
+            }
+            Ok(Event::Text(t)) => {
+                let text = t.unescape().map(|s| s.trim().to_lowercase()).ok();
+                match current.as_deref() {
+                    Some(b"updatePeriod") => period = text,
+                    Some(b"updateFrequency") => frequency = text,
+                    _ => {}
+                }
+            }
+            Ok(Event::End(_)) => current = None,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        if period.is_some() && frequency.is_some() {
+            break;
+        }
+    }
+    let secs: u64 = match period.as_deref()? {
+        "hourly" => 3600,
+        "daily" => 86_400,
+        "weekly" => 7 * 86_400,
+        "monthly" => 30 * 86_400,
+        "yearly" => 365 * 86_400,
+        _ => return None,
+    };
+    let frequency = frequency
+        .and_then(|f| f.parse::<u64>().ok())
+        .filter(|f| *f > 0)
+        .unwrap_or(1);
+    Some(secs / frequency)
+}
+
+pub(crate) fn parse_feed(url: &str, body: &str) -> Result<(Feed, Vec<FeedItem>), Error> {
     let f = feed_rs::parser::parse(body.as_bytes()).map_err(|e| Error::Parse(e.to_string()))?;
 
     let feed = Feed {
@@ -24,12 +222,43 @@ pub fn fetch_feed(url: &str) -> Result<(Feed, Vec<FeedItem>), Error> {
         description: f.description.as_ref().map(|d| d.content.clone()),
         last_fetched: Some(Utc::now()),
         created_at: Some(Utc::now()),
+        etag: None,
+        last_modified: None,
+        last_error: None,
+        consecutive_failures: 0,
+        last_success: Some(Utc::now()),
+        folder: None,
+        tags: Vec::new(),
+        refresh_hints: RefreshHints {
+            ttl_secs: f.ttl.map(|minutes| u64::from(minutes) * 60),
+            update_period_secs: syndication_period(body),
+            ..RefreshHints::default()
+        },
+        // The channel link; Atom feeds mark their own url as rel="self".
+        site_url: f
+            .links
+            .iter()
+            .find(|l| l.rel.as_deref().map_or(true, |r| r == "alternate"))
+            .map(|l| l.href.clone()),
+        icon: f.icon.as_ref().map(|i| i.uri.clone()),
+        logo: f.logo.as_ref().map(|i| i.uri.clone()),
+        language: f.language.clone(),
+        generator: f.generator.as_ref().map(|g| match &g.version {
+            Some(v) => format!("{} {}", g.content.trim(), v),
+            None => g.content.trim().to_string(),
+        }),
+        download_policy: DownloadPolicy::default(),
     };
-
+    // Matched to the entries by position; skipped if feed-rs dropped any.
+    let mut episodes = podcast::episodes(body);
+    if episodes.len() != f.entries.len() {
+        episodes.clear();
+    }
     let items = f
         .entries
         .iter()
-        .map(|e| {
+        .enumerate()
+        .map(|(index, e)| {
             let id = if e.id.is_empty() {
                 e.links
                     .first()
@@ -54,7 +283,9 @@ pub fn fetch_feed(url: &str) -> Result<(Feed, Vec<FeedItem>), Error> {
                             url: u.to_string(),
                             media_type: c.content_type.as_ref().map(|t| t.to_string()),
                             length: c.size,
-                            title: None,
+                            title: m.title.as_ref().map(|t| t.content.clone()),
+                            duration_secs: c.duration.or(m.duration).map(|d| d.as_secs()),
+                            thumbnail: m.thumbnails.first().map(|t| t.image.uri.clone()),
                         })
                     })
                 })
@@ -72,6 +303,38 @@ pub fn fetch_feed(url: &str) -> Result<(Feed, Vec<FeedItem>), Error> {
                 summary: e.summary.as_ref().map(|s| s.content.clone()),
                 content: e.content.as_ref().and_then(|c| c.body.clone()),
                 enclosures,
+                authors: e
+                    .authors
+                    .iter()
+                    .map(|p| p.name.trim().to_string())
+                    .filter(|n| !n.is_empty())
+                    .collect(),
+                categories: e
+                    .categories
+                    .iter()
+                    .map(|c| c.label.clone().unwrap_or_else(|| c.term.clone()))
+                    .collect(),
+                links: e
+                    .links
+                    .iter()
+                    .skip(1)
+                    .map(|l| ItemLink {
+                        href: l.href.clone(),
+                        rel: l.rel.clone(),
+                        media_type: l.media_type.clone(),
+                        title: l.title.clone(),
+                    })
+                    .collect(),
+                updated: e.updated,
+                // RSS only has channel-level language and copyright.
+                language: e.language.clone().or_else(|| f.language.clone()),
+                rights: e
+                    .rights
+                    .as_ref()
+                    .or(f.rights.as_ref())
+                    .map(|r| r.content.clone()),
+                source: e.source.clone(),
+                podcast: episodes.get(index).cloned().unwrap_or_default(),
             }
         })
         .collect();
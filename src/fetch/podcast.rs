@@ -0,0 +1,108 @@
+//! Podcast tags feed-rs does not parse: `itunes:episode` / `itunes:season` and the
+//! Podcasting 2.0 `podcast:chapters`, `podcast:transcript` and `podcast:person`.
+//! Elements are matched by their conventional prefixes.
+
+use crate::feed::{PodcastEpisode, PodcastFile, PodcastPerson};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+
+/// Which element's text is being read.
+enum Text {
+    Episode,
+    Season,
+    Person,
+}
+
+/// Podcast metadata of every `<item>` / `<entry>`, in document order.
+pub(super) fn episodes(body: &str) -> Vec<PodcastEpisode> {
+    let mut reader = Reader::from_str(body);
+    reader.trim_text(true);
+    let mut out: Vec<PodcastEpisode> = Vec::new();
+    let mut in_item = false;
+    let mut text: Option<Text> = None;
+    loop {
+        let (e, empty) = match reader.read_event() {
+            Ok(Event::Start(e)) => (e, false),
+            Ok(Event::Empty(e)) => (e, true),
+            Ok(Event::Text(t)) => {
+                let (Some(episode), Some(target)) = (out.last_mut(), text.take()) else {
+                    continue;
+                };
+                let Ok(value) = t.unescape() else { continue };
+                let value = value.trim();
+                match target {
+                    Text::Episode => episode.episode = value.parse().ok(),
+                    Text::Season => episode.season = value.parse().ok(),
+                    Text::Person => {
+                        if let Some(person) = episode.persons.last_mut() {
+                            person.name = value.to_string();
+                        }
+                    }
+                }
+                continue;
+            }
+            Ok(Event::End(e)) => {
+                if matches!(e.local_name().as_ref(), b"item" | b"entry") {
+                    in_item = false;
+                }
+                text = None;
+                continue;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => continue,
+        };
+        if matches!(e.local_name().as_ref(), b"item" | b"entry") {
+            out.push(PodcastEpisode::default());
+            in_item = !empty;
+            continue;
+        }
+        let Some(episode) = out.last_mut().filter(|_| in_item) else {
+            continue;
+        };
+        match e.name().as_ref() {
+            b"itunes:episode" => text = Some(Text::Episode),
+            b"itunes:season" => text = Some(Text::Season),
+            b"podcast:chapters" => episode.chapters = file(&e, &reader),
+            b"podcast:transcript" => episode.transcripts.extend(file(&e, &reader)),
+            b"podcast:person" if !empty => {
+                let mut attrs = attributes(&e, &reader);
+                episode.persons.push(PodcastPerson {
+                    name: String::new(),
+                    role: attrs.remove("role"),
+                    group: attrs.remove("group"),
+                    img: attrs.remove("img"),
+                    href: attrs.remove("href"),
+                });
+                text = Some(Text::Person);
+            }
+            _ => {}
+        }
+    }
+    for episode in &mut out {
+        episode.persons.retain(|p| !p.name.is_empty());
+    }
+    out
+}
+
+fn file(e: &BytesStart<'_>, reader: &Reader<&[u8]>) -> Option<PodcastFile> {
+    let mut attrs = attributes(e, reader);
+    Some(PodcastFile {
+        url: attrs.remove("url")?,
+        media_type: attrs.remove("type"),
+        language: attrs.remove("language"),
+        rel: attrs.remove("rel"),
+    })
+}
+
+/// Attributes of `e` (malformed ones are skipped).
+fn attributes(e: &BytesStart<'_>, reader: &Reader<&[u8]>) -> HashMap<String, String> {
+    e.attributes()
+        .flatten()
+        .filter_map(|a| {
+            let key = String::from_utf8_lossy(a.key.as_ref()).into_owned();
+            let value = a.decode_and_unescape_value(reader).ok()?;
+            Some((key, value.trim().to_string()))
+        })
+        .collect()
+}
@@ -2,6 +2,16 @@
 //!
 //! Converts HTML to terminal-friendly text: wraps to width, preserves headings,
 //! paragraphs, lists, and makes links identifiable (URL or inline reference).
+//! On terminals, [`format_article_styled`] uses ANSI styling instead of markup.
+
+use html2text::render::text_renderer::{
+    RichAnnotation, TaggedLine, TaggedLineElement, TextDecorator,
+};
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+/// Width used when the terminal size is unknown.
+pub const DEFAULT_WIDTH: usize = 80;
 
 /// Format article body for terminal: strip/reduce HTML, preserve structure (headings, paragraphs, links).
 pub fn format_article(html: Option<&str>, width: usize) -> String {
@@ -9,7 +19,7 @@ pub fn format_article(html: Option<&str>, width: usize) -> String {
     match html {
         None | Some("") => "No content.".to_string(),
         Some(s) => {
-            let text = html2text::from_read(s.as_bytes(), width);
+            let text = strip_controls(&html2text::from_read(s.as_bytes(), width));
             let trimmed = text.trim();
             if trimmed.is_empty() {
                 "No content.".to_string()
@@ -19,3 +29,272 @@ pub fn format_article(html: Option<&str>, width: usize) -> String {
         }
     }
 }
+
+/// Like [`format_article`], but styled with ANSI escapes: bold headings, underlined links
+/// (OSC 8 hyperlinks, so no footnotes), dimmed quotes and highlighted code.
+pub fn format_article_styled(html: Option<&str>, width: usize) -> String {
+    let width = width.max(40);
+    let Some(s) = html.filter(|s| !s.trim().is_empty()) else {
+        return "No content.".to_string();
+    };
+    let lines = html2text::parse(s.as_bytes())
+        .render(width, AnsiDecorator)
+        .into_lines();
+    let blank = |l: &TaggedLine<Vec<RichAnnotation>>| l.chars().all(char::is_whitespace);
+    let start = lines.iter().position(|l| !blank(l));
+    let end = lines.iter().rposition(|l| !blank(l));
+    match (start, end) {
+        (Some(start), Some(end)) => lines[start..=end]
+            .iter()
+            .map(styled_line)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => "No content.".to_string(),
+    }
+}
+
+/// Width of the terminal on stdout, if stdout is one.
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    ratatui::crossterm::terminal::size()
+        .ok()
+        .map(|(cols, _)| usize::from(cols))
+        .filter(|&cols| cols > 0)
+}
+
+/// Whether to style output with ANSI escapes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// When stdout is a terminal, `NO_COLOR` is unset and `TERM` is not `dumb`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+
+    /// Resolve `auto` against stdout and the environment.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").map_or(true, |v| v.is_empty())
+                    && std::env::var("TERM").map_or(true, |t| t != "dumb")
+            }
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "unknown color choice {:?} (expected auto, always or never)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Keeps html2text's annotations but drops its plain-text markup (`*strong*`, `` `code` ``,
+/// link footnotes): styling replaces them.
+struct AnsiDecorator;
+
+impl TextDecorator for AnsiDecorator {
+    type Annotation = RichAnnotation;
+
+    fn decorate_link_start(&mut self, url: &str) -> (String, RichAnnotation) {
+        (String::new(), RichAnnotation::Link(url.to_string()))
+    }
+
+    fn decorate_link_end(&mut self) -> String {
+        String::new()
+    }
+
+    fn decorate_em_start(&mut self) -> (String, RichAnnotation) {
+        (String::new(), RichAnnotation::Emphasis)
+    }
+
+    fn decorate_em_end(&mut self) -> String {
+        String::new()
+    }
+
+    fn decorate_strong_start(&mut self) -> (String, RichAnnotation) {
+        (String::new(), RichAnnotation::Strong)
+    }
+
+    fn decorate_strong_end(&mut self) -> String {
+        String::new()
+    }
+
+    fn decorate_strikeout_start(&mut self) -> (String, RichAnnotation) {
+        (String::new(), RichAnnotation::Strikeout)
+    }
+
+    fn decorate_strikeout_end(&mut self) -> String {
+        String::new()
+    }
+
+    fn decorate_code_start(&mut self) -> (String, RichAnnotation) {
+        (String::new(), RichAnnotation::Code)
+    }
+
+    fn decorate_code_end(&mut self) -> String {
+        String::new()
+    }
+
+    fn decorate_preformat_first(&mut self) -> RichAnnotation {
+        RichAnnotation::Preformat(false)
+    }
+
+    fn decorate_preformat_cont(&mut self) -> RichAnnotation {
+        RichAnnotation::Preformat(true)
+    }
+
+    fn decorate_image(&mut self, title: &str) -> (String, RichAnnotation) {
+        (title.to_string(), RichAnnotation::Image)
+    }
+
+    fn make_subblock_decorator(&self) -> Self {
+        AnsiDecorator
+    }
+
+    fn finalise(self) -> Vec<TaggedLine<RichAnnotation>> {
+        Vec::new()
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+/// Whether `c` is a C0 (other than newline and tab), DEL or C1 control character, any of
+/// which could start a terminal escape sequence.
+fn is_unsafe_control(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+/// `s` without control characters, so feed text cannot drive the terminal.
+fn strip_controls(s: &str) -> String {
+    s.chars().filter(|c| !is_unsafe_control(*c)).collect()
+}
+
+/// `url` as the target of an OSC 8 hyperlink: only http(s) links, with control
+/// characters percent-encoded so they cannot end the sequence early.
+fn hyperlink_target(url: &str) -> Option<String> {
+    let scheme = url.split_once(':')?.0;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+    let mut target = String::with_capacity(url.len());
+    for c in url.chars() {
+        if c.is_control() {
+            let mut buf = [0u8; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                target.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            target.push(c);
+        }
+    }
+    Some(target)
+}
+
+/// Whether `s` is the `## ` prefix html2text puts before heading lines.
+fn is_heading_prefix(s: &str) -> bool {
+    s.strip_suffix(' ')
+        .is_some_and(|marks| !marks.is_empty() && marks.chars().all(|c| c == '#'))
+}
+
+/// One rendered line with ANSI styling. Quote (`> `) and heading (`## `) prefixes come
+/// first as untagged text; they become a dim bar and a bold line.
+fn styled_line(line: &TaggedLine<Vec<RichAnnotation>>) -> String {
+    let mut segments: Vec<(&str, &[RichAnnotation])> = line
+        .iter()
+        .filter_map(|e| match e {
+            TaggedLineElement::Str(ts) => Some((ts.s.as_str(), ts.tag.as_slice())),
+            _ => None,
+        })
+        .collect();
+    let mut out = String::new();
+    let mut quoted = false;
+    let mut heading = false;
+    while let Some((text, [])) = segments.first().copied() {
+        if let Some(rest) = text.strip_prefix("> ") {
+            out.push_str("\x1b[2m│ ");
+            out.push_str(RESET);
+            quoted = true;
+            segments[0].0 = rest;
+        } else if !heading && is_heading_prefix(text) {
+            heading = true;
+            segments.remove(0);
+        } else {
+            break;
+        }
+    }
+    for (text, tags) in segments {
+        if text.is_empty() {
+            continue;
+        }
+        let mut codes: Vec<&str> = Vec::new();
+        if heading {
+            codes.push("1");
+        }
+        if quoted {
+            codes.push("2");
+        }
+        let mut link = None;
+        let preformatted = tags
+            .iter()
+            .any(|t| matches!(t, RichAnnotation::Preformat(_)));
+        for tag in tags {
+            match tag {
+                RichAnnotation::Strong => codes.push("1"),
+                RichAnnotation::Emphasis => codes.push("3"),
+                RichAnnotation::Strikeout => codes.push("9"),
+                RichAnnotation::Image => codes.push("2"),
+                RichAnnotation::Code if !preformatted => codes.push("33"),
+                RichAnnotation::Preformat(_) => codes.push("36"),
+                RichAnnotation::Link(url) => {
+                    codes.push("4");
+                    link = hyperlink_target(url);
+                }
+                _ => {}
+            }
+        }
+        if let Some(url) = &link {
+            out.push_str(&format!("\x1b]8;;{}\x1b\\", url));
+        }
+        let text = strip_controls(text);
+        if codes.is_empty() {
+            out.push_str(&text);
+        } else {
+            out.push_str(&format!("\x1b[{}m{}{}", codes.join(";"), text, RESET));
+        }
+        if link.is_some() {
+            out.push_str("\x1b]8;;\x1b\\");
+        }
+    }
+    out
+}
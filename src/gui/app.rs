@@ -1,48 +1,76 @@
 //! eframe app: window, run loop, load store, main layout.
 
 use eframe::egui;
-use std::path::PathBuf;
 use std::sync::mpsc;
 
+use super::views::article_list::ArticleListState;
+use super::views::opml_dialog::{self, OpmlAction, OpmlMode};
+#[cfg(feature = "player")]
+use super::views::player::Playback;
+use super::views::rules_editor::{self, RulesAction, RulesEditorState};
 use super::views::{add_feed, article_detail, article_list, feed_list};
-use crate::fetch::fetch_feed;
+use crate::discover::{discover_feed, Discovery, FeedCandidate};
+use crate::refresh::{refresh_feeds, FeedRefresh, RefreshOptions};
+use crate::schedule::{due_feeds, ScheduleOptions};
+use crate::settings::Settings;
+use crate::store::{FeedSelection, Storage};
+use crate::Feed;
 use crate::SubscriptionList;
-use crate::{Feed, FeedItem};
 
-/// Channel result for add-feed background fetch (avoids type_complexity in struct).
-type AddFeedReceiver = mpsc::Receiver<Result<(Feed, Vec<FeedItem>), crate::Error>>;
-/// Channel result for refresh background fetch (avoids type_complexity in struct).
-type RefreshReceiver = mpsc::Receiver<(Vec<(Feed, Vec<FeedItem>)>, Option<String>)>;
+/// Channel result for add-feed background discovery (avoids type_complexity in struct).
+type AddFeedReceiver = mpsc::Receiver<Result<Discovery, crate::Error>>;
+/// Per-feed results streamed from the refresh engine.
+type RefreshReceiver = mpsc::Receiver<FeedRefresh>;
 
 /// Focus tag for arrow-key navigation: 0 = feed list, 1 = article list (FR-010).
 const FOCUS_FEED_LIST: u8 = 0;
 const FOCUS_ARTICLE_LIST: u8 = 1;
 
 /// Run the GUI. Load store and start eframe.
-pub fn run(config_path: PathBuf) -> crate::Result<()> {
-    let store = SubscriptionList::load(config_path.as_path())?;
+pub fn run(settings: Settings) -> crate::Result<()> {
+    let storage = crate::store::open_with(&settings.data_path, &settings.store)?;
+    let store = storage.load()?;
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "RSS Reader",
         options,
-        Box::new(move |cc| Ok(Box::new(App::new(cc, store, config_path)))),
+        Box::new(move |cc| Ok(Box::new(App::new(cc, store, storage, settings)))),
     )
     .map_err(|e| crate::Error::Store(e.to_string()))
 }
 
 struct App {
     store: SubscriptionList,
-    config_path: PathBuf,
-    selected_feed: Option<String>,
+    storage: Box<dyn Storage>,
+    /// Article width, date format and download directory.
+    settings: Settings,
+    selected_feed: FeedSelection,
+    article_list_state: ArticleListState,
     selected_item_id: Option<String>,
     add_feed_dialog_open: bool,
     add_feed_url: String,
     add_feed_error: Option<String>,
     add_feed_loading: bool,
     add_feed_pending: Option<AddFeedReceiver>,
+    /// Feeds offered by the website entered in the add-feed dialog.
+    add_feed_candidates: Vec<FeedCandidate>,
     loading: bool,
+    /// Refresh feeds on their own schedule (the `daemon` scheduler, in-process).
+    auto_refresh: bool,
+    schedule: ScheduleOptions,
     refresh_pending: Option<RefreshReceiver>,
+    /// (finished, total) feeds of the running refresh.
+    refresh_progress: (usize, usize),
+    refresh_errors: Vec<String>,
     last_error: Option<String>,
+    opml_dialog: Option<OpmlMode>,
+    opml_path: String,
+    opml_error: Option<String>,
+    /// Open rules editor window.
+    rules_editor: Option<RulesEditorState>,
+    /// The built-in player and its bar.
+    #[cfg(feature = "player")]
+    playback: Playback,
     focused_panel: Option<u8>,
 }
 
@@ -50,74 +78,185 @@ impl App {
     fn new(
         _cc: &eframe::CreationContext<'_>,
         store: SubscriptionList,
-        config_path: PathBuf,
+        storage: Box<dyn Storage>,
+        settings: Settings,
     ) -> Self {
         Self {
             store,
-            config_path,
-            selected_feed: None,
+            storage,
+            settings,
+            selected_feed: FeedSelection::All,
+            article_list_state: ArticleListState::default(),
             selected_item_id: None,
             add_feed_dialog_open: false,
             add_feed_url: String::new(),
             add_feed_error: None,
             add_feed_loading: false,
             add_feed_pending: None,
+            add_feed_candidates: Vec::new(),
             loading: false,
+            auto_refresh: false,
+            schedule: ScheduleOptions::default(),
             refresh_pending: None,
+            refresh_progress: (0, 0),
+            refresh_errors: Vec::new(),
             last_error: None,
+            opml_dialog: None,
+            opml_path: String::new(),
+            opml_error: None,
+            rules_editor: None,
+            #[cfg(feature = "player")]
+            playback: Playback::default(),
             focused_panel: None,
         }
     }
 }
 
+impl App {
+    /// Start the refresh engine for `feeds`; results are polled in `update`.
+    fn start_refresh(&mut self, feeds: Vec<Feed>) {
+        self.loading = true;
+        self.refresh_progress = (0, feeds.len());
+        self.refresh_errors.clear();
+        let options = RefreshOptions {
+            http: self.settings.http_options(),
+            ..RefreshOptions::default()
+        };
+        self.refresh_pending = Some(refresh_feeds(feeds, &options));
+    }
+
+    /// Fetch `url` in the background, discovering feeds if it is a web page; the result is
+    /// polled in `update`.
+    fn start_add_feed(&mut self, url: String) {
+        self.add_feed_loading = true;
+        self.add_feed_error = None;
+        self.add_feed_candidates.clear();
+        let (tx, rx) = mpsc::channel();
+        let http = self.settings.http_options();
+        std::thread::spawn(move || {
+            let _ = tx.send(discover_feed(&url, false, &http));
+        });
+        self.add_feed_pending = Some(rx);
+    }
+
+    /// Play enclosure `index` of the selected item in the player bar.
+    #[cfg(feature = "player")]
+    fn play(&mut self, index: usize) {
+        let Some(item) = self
+            .selected_item_id
+            .as_deref()
+            .and_then(|id| self.store.get_item(id, self.selected_feed.feed_url()))
+            .cloned()
+        else {
+            return;
+        };
+        let dir = self.settings.download_queue_dir();
+        let http = self.settings.http_options();
+        if let Err(e) = self.playback.start(
+            &mut self.store,
+            self.storage.as_ref(),
+            &item,
+            index,
+            &dir,
+            &http,
+        ) {
+            self.last_error = Some(format!("Play: {}", e));
+        }
+    }
+
+    /// Without the built-in player there is no Play button.
+    #[cfg(not(feature = "player"))]
+    fn play(&mut self, _index: usize) {}
+
+    /// Import from or export to the OPML file at `path`. Imported feeds are fetched right away.
+    fn run_opml(&mut self, mode: OpmlMode, path: &std::path::Path) -> crate::Result<()> {
+        match mode {
+            OpmlMode::Import => {
+                let xml = std::fs::read_to_string(path)?;
+                crate::opml::import_opml(&mut self.store, &xml)?;
+                self.storage.save(&mut self.store)?;
+                let new_feeds: Vec<Feed> = self
+                    .store
+                    .feeds
+                    .iter()
+                    .filter(|f| f.last_fetched.is_none())
+                    .cloned()
+                    .collect();
+                if !new_feeds.is_empty() && !self.loading {
+                    self.start_refresh(new_feeds);
+                }
+            }
+            OpmlMode::Export => {
+                std::fs::write(path, crate::opml::export_opml(&self.store))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let prev_selected_item = self.selected_item_id.clone();
         // Keyboard (FR-010): Tab/Shift+Tab follow widget order (feeds → articles → detail → enclosure buttons).
         // Arrow keys in feed/article list when that list was last clicked; Enter in add-feed dialog; Escape cancels dialog.
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             ui.horizontal(|ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("Import OPML…").clicked() {
+                        self.opml_dialog = Some(OpmlMode::Import);
+                        ui.close_menu();
+                    }
+                    if ui.button("Export OPML…").clicked() {
+                        self.opml_dialog = Some(OpmlMode::Export);
+                        ui.close_menu();
+                    }
+                });
                 if ui.button("Add feed").clicked() {
                     self.add_feed_dialog_open = true;
                 }
-                if let Some(ref url) = self.selected_feed {
+                if ui.button("Rules").clicked() && self.rules_editor.is_none() {
+                    self.rules_editor = Some(RulesEditorState::default());
+                }
+                if let FeedSelection::Feed(ref url) = self.selected_feed {
                     if ui.button("Remove feed").clicked() && self.store.remove_feed(url) {
-                        let _ = self.store.save(self.config_path.as_path());
-                        self.selected_feed = None;
+                        let _ = self.storage.save(&mut self.store);
+                        self.selected_feed = FeedSelection::All;
                         self.selected_item_id = None;
                     }
                 }
+                if let Some(ref id) = self.selected_item_id {
+                    let starred = self.store.is_starred(id);
+                    let label = if starred { "Unstar" } else { "Star" };
+                    if ui.button(label).clicked() && self.store.set_starred(id, !starred) {
+                        let _ = self.storage.save(&mut self.store);
+                    }
+                }
+                if let Some(ref id) = self.selected_item_id {
+                    if self.store.is_read(id)
+                        && ui.button("Mark unread").clicked()
+                        && self.store.set_read(id, false)
+                    {
+                        let _ = self.storage.save(&mut self.store);
+                    }
+                }
                 if ui.button("Refresh").clicked() && !self.loading {
-                    let urls: Vec<String> = self
-                        .store
-                        .feeds
-                        .iter()
-                        .map(|f| f.url.clone())
-                        .filter(|u| self.selected_feed.as_deref().map_or(true, |f| u == f))
+                    let feeds: Vec<Feed> = self
+                        .selected_feed
+                        .query()
+                        .feeds(&self.store)
+                        .into_iter()
+                        .cloned()
                         .collect();
-                    if urls.is_empty() {
+                    if feeds.is_empty() {
                         self.last_error = Some("No feeds to refresh.".to_string());
                     } else {
-                        self.loading = true;
-                        let (tx, rx) = mpsc::channel();
-                        std::thread::spawn(move || {
-                            let mut updates = vec![];
-                            let mut err_msgs = vec![];
-                            for url in urls {
-                                match fetch_feed(&url) {
-                                    Ok((feed, items)) => updates.push((feed, items)),
-                                    Err(e) => err_msgs.push(e.to_string()),
-                                }
-                            }
-                            let err = if err_msgs.is_empty() {
-                                None
-                            } else {
-                                Some(err_msgs.join("; "))
-                            };
-                            let _ = tx.send((updates, err));
-                        });
-                        self.refresh_pending = Some(rx);
+                        self.start_refresh(feeds);
                     }
                 }
+                ui.checkbox(&mut self.auto_refresh, "Auto-refresh")
+                    .on_hover_text(
+                        "Refresh each feed on its own schedule while the window is open",
+                    );
             });
         });
 
@@ -125,13 +264,25 @@ impl eframe::App for App {
         let rx_opt = self.add_feed_pending.take();
         if let Some(rx) = rx_opt {
             match rx.try_recv() {
-                Ok(Ok((feed, items))) => {
+                Ok(Ok(Discovery::Candidates(candidates))) => {
+                    self.add_feed_loading = false;
+                    match candidates.len() {
+                        0 => {
+                            self.add_feed_error =
+                                Some("No feeds found at this address.".to_string());
+                        }
+                        1 => self.start_add_feed(candidates[0].url.clone()),
+                        _ => self.add_feed_candidates = candidates,
+                    }
+                }
+                Ok(Ok(Discovery::Feed(feed, items))) => {
                     self.store.add_feed(feed, items);
-                    let _ = self.store.save(self.config_path.as_path());
+                    let _ = self.storage.save(&mut self.store);
                     self.add_feed_dialog_open = false;
                     self.add_feed_loading = false;
                     self.add_feed_url.clear();
                     self.add_feed_error = None;
+                    self.add_feed_candidates.clear();
                 }
                 Ok(Err(e)) => {
                     self.add_feed_error = Some(e.to_string());
@@ -146,31 +297,53 @@ impl eframe::App for App {
             }
         }
 
-        // Poll pending refresh result (T020)
+        // Poll pending refresh results (T020); each feed is merged as soon as it finishes.
         let refresh_rx = self.refresh_pending.take();
         if let Some(rx) = refresh_rx {
-            match rx.try_recv() {
-                Ok((updates, err_msg)) => {
-                    for (feed, items) in updates {
-                        self.store.add_feed(feed, items);
+            loop {
+                match rx.try_recv() {
+                    Ok(done) => {
+                        self.refresh_progress.0 += 1;
+                        if let Err(e) = &done.result {
+                            self.refresh_errors.push(format!("{}: {}", done.url, e));
+                        }
+                        done.apply(&mut self.store);
                     }
-                    let _ = self.store.save(self.config_path.as_path());
-                    if let Some(msg) = err_msg {
-                        self.last_error = Some(format!("Refresh: {}", msg));
+                    Err(mpsc::TryRecvError::Empty) => {
+                        self.refresh_pending = Some(rx);
+                        ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                        break;
+                    }
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        let _ = self.storage.save(&mut self.store);
+                        if !self.refresh_errors.is_empty() {
+                            self.last_error =
+                                Some(format!("Refresh: {}", self.refresh_errors.join("; ")));
+                        }
+                        self.loading = false;
+                        break;
                     }
-                    self.loading = false;
-                }
-                Err(mpsc::TryRecvError::Empty) => {
-                    self.refresh_pending = Some(rx);
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    self.loading = false;
+            }
+        }
+
+        // Scheduled refresh: start the feeds that are due whenever no refresh is running.
+        if self.auto_refresh {
+            if !self.loading {
+                let due: Vec<Feed> = due_feeds(&self.store, chrono::Utc::now(), &self.schedule)
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                if !due.is_empty() {
+                    self.start_refresh(due);
                 }
             }
+            ctx.request_repaint_after(std::time::Duration::from_secs(30));
         }
 
         if self.add_feed_dialog_open {
             let mut close_dialog = false;
+            let mut submit = None;
             egui::Window::new("Add feed")
                 .collapsible(false)
                 .resizable(false)
@@ -184,16 +357,10 @@ impl eframe::App for App {
                         &mut self.add_feed_url,
                         &mut self.add_feed_error,
                         self.add_feed_loading,
+                        &self.add_feed_candidates,
                     ) {
                         match action {
-                            add_feed::AddFeedAction::Submit(url) => {
-                                self.add_feed_loading = true;
-                                let (tx, rx) = mpsc::channel();
-                                std::thread::spawn(move || {
-                                    let _ = tx.send(fetch_feed(&url));
-                                });
-                                self.add_feed_pending = Some(rx);
-                            }
+                            add_feed::AddFeedAction::Submit(url) => submit = Some(url),
                             add_feed::AddFeedAction::Cancel => close_dialog = true,
                         }
                     }
@@ -202,6 +369,90 @@ impl eframe::App for App {
                 self.add_feed_dialog_open = false;
                 self.add_feed_url.clear();
                 self.add_feed_error = None;
+                self.add_feed_candidates.clear();
+            }
+            if let Some(url) = submit {
+                self.start_add_feed(url);
+            }
+        }
+
+        if let Some(mode) = self.opml_dialog {
+            let mut open = true;
+            let mut action = None;
+            let title = match mode {
+                OpmlMode::Import => "Import OPML",
+                OpmlMode::Export => "Export OPML",
+            };
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        action = Some(OpmlAction::Cancel);
+                    } else {
+                        action =
+                            opml_dialog::show(ui, mode, &mut self.opml_path, &mut self.opml_error);
+                    }
+                });
+            let close = match action {
+                Some(OpmlAction::Confirm(path)) => match self.run_opml(mode, &path) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        self.opml_error = Some(e.to_string());
+                        false
+                    }
+                },
+                Some(OpmlAction::Cancel) => true,
+                None => !open,
+            };
+            if close {
+                self.opml_dialog = None;
+                self.opml_error = None;
+            }
+        }
+
+        if let Some(mut state) = self.rules_editor.take() {
+            let mut open = true;
+            let mut action = None;
+            egui::Window::new("Rules")
+                .collapsible(false)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    action = rules_editor::show(ui, &self.store, &mut state);
+                });
+            if let Some(action) = action {
+                let result = match action {
+                    RulesAction::Add(rule) => self.store.add_rule(rule),
+                    RulesAction::Remove(n) => self.store.remove_rule(n).map(|_| ()),
+                };
+                if let Err(e) = result.and_then(|()| self.storage.save(&mut self.store)) {
+                    state.set_error(e.to_string());
+                }
+            }
+            if open && !ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.rules_editor = Some(state);
+            }
+        }
+
+        #[cfg(feature = "player")]
+        {
+            let mut result = self
+                .playback
+                .update(ctx, &mut self.store, self.storage.as_ref());
+            if result.is_ok() && self.playback.is_active() {
+                let mut stop = Ok(false);
+                egui::TopBottomPanel::bottom("player").show(ctx, |ui| {
+                    stop = self.playback.show(ui);
+                });
+                result = match stop {
+                    Ok(true) => self.playback.stop(&mut self.store, self.storage.as_ref()),
+                    Ok(false) => Ok(()),
+                    Err(e) => Err(e),
+                };
+            }
+            if let Err(e) = result {
+                self.last_error = Some(format!("Player: {}", e));
             }
         }
 
@@ -219,10 +470,15 @@ impl eframe::App for App {
                 );
             });
 
+        let mut play = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             super::widgets::show_error_banner(ui, &mut self.last_error);
             if self.loading {
-                super::widgets::show_loading(ui);
+                ui.horizontal(|ui| {
+                    super::widgets::show_loading(ui);
+                    let (done, total) = self.refresh_progress;
+                    ui.label(format!("{}/{} feeds", done, total));
+                });
             }
             // Side-by-side: list (left) and detail (right), each with full height so scroll works.
             let rect = ui.available_rect_before_wrap();
@@ -241,7 +497,8 @@ impl eframe::App for App {
                                 article_list::show(
                                     ui,
                                     &self.store,
-                                    self.selected_feed.as_deref(),
+                                    &self.selected_feed,
+                                    &mut self.article_list_state,
                                     &mut self.selected_item_id,
                                     &mut self.focused_panel,
                                     FOCUS_ARTICLE_LIST,
@@ -253,17 +510,22 @@ impl eframe::App for App {
                     egui::Vec2::new(detail_width, full_height),
                     egui::Layout::top_down(egui::Align::Min),
                     |ui| {
-                        article_detail::show(
+                        play = article_detail::show(
                             ui,
                             &self.store,
                             self.selected_item_id.as_deref(),
-                            self.selected_feed.as_deref(),
+                            self.selected_feed.feed_url(),
+                            &self.settings,
                         );
                     },
                 );
             });
         });
 
+        if let Some(index) = play {
+            self.play(index);
+        }
+
         // Arrow-key navigation (FR-010)
         if !self.add_feed_dialog_open {
             let (arrow_down, arrow_up) = ctx.input(|i| {
@@ -273,23 +535,25 @@ impl eframe::App for App {
                 )
             });
             if self.focused_panel == Some(FOCUS_FEED_LIST) && (arrow_down || arrow_up) {
-                let urls: Vec<Option<String>> = std::iter::once(None)
-                    .chain(self.store.feeds.iter().map(|f| Some(f.url.clone())))
-                    .collect();
-                let idx = urls
+                let entries = FeedSelection::entries(&self.store);
+                let idx = entries
                     .iter()
-                    .position(|u| u.as_deref() == self.selected_feed.as_deref())
+                    .position(|e| *e == self.selected_feed)
                     .unwrap_or(0);
                 let new_idx = if arrow_down {
-                    (idx + 1).min(urls.len().saturating_sub(1))
+                    (idx + 1).min(entries.len().saturating_sub(1))
                 } else {
                     idx.saturating_sub(1)
                 };
-                self.selected_feed = urls.get(new_idx).and_then(|o| o.clone());
+                self.selected_feed = entries.get(new_idx).cloned().unwrap_or_default();
             }
             if self.focused_panel == Some(FOCUS_ARTICLE_LIST) && (arrow_down || arrow_up) {
-                let items = self.store.items(self.selected_feed.as_deref());
-                let ids: Vec<&str> = items.iter().map(|i| i.id.as_str()).collect();
+                let items = article_list::visible_items(
+                    &self.store,
+                    &self.selected_feed,
+                    &mut self.article_list_state,
+                );
+                let ids: Vec<&str> = items.iter().map(|(i, _)| i.id.as_str()).collect();
                 let idx = self
                     .selected_item_id
                     .as_deref()
@@ -303,5 +567,14 @@ impl eframe::App for App {
                 self.selected_item_id = ids.get(new_idx).map(|s| (*s).to_string());
             }
         }
+
+        // Opening an article marks it read; only on selection change so "Mark unread" sticks.
+        if self.selected_item_id != prev_selected_item {
+            if let Some(id) = self.selected_item_id.clone() {
+                if !self.store.is_read(&id) && self.store.set_read(&id, true) {
+                    let _ = self.storage.save(&mut self.store);
+                }
+            }
+        }
     }
 }
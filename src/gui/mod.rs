@@ -4,17 +4,16 @@ mod app;
 mod views;
 mod widgets;
 
+use crate::settings::Settings;
 use std::path::PathBuf;
 
 /// Default config/storage path (same as CLI).
 pub fn default_config_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("rss-reader")
-        .join("data.json")
+    Settings::default().data_path
 }
 
-/// Run the GUI application. Loads store from `config_path` and runs the eframe event loop.
-pub fn run(config_path: PathBuf) -> crate::Result<()> {
-    app::run(config_path)
+/// Run the GUI application. Loads the store from `settings.data_path` and runs the eframe
+/// event loop.
+pub fn run(settings: Settings) -> crate::Result<()> {
+    app::run(settings)
 }
@@ -1,5 +1,7 @@
-//! Add-feed dialog: URL input, Add/Cancel, validation, loading state (FR-004, FR-009, T019).
+//! Add-feed dialog: URL input, Add/Cancel, validation, loading state (FR-004, FR-009, T019),
+//! and a pick list when a website URL offers several feeds.
 
+use crate::discover::FeedCandidate;
 use eframe::egui;
 
 /// Action returned by the add-feed dialog.
@@ -26,6 +28,7 @@ fn validate_url(url: &str) -> Option<String> {
 /// - `url`: current URL input (mutated by text edit).
 /// - `error`: in-dialog error message (mutated; cleared on new input).
 /// - `loading`: when true, show loading UI instead of form (caller sets when fetch started).
+/// - `candidates`: feeds discovered on the entered website; picking one submits its URL.
 ///
 /// Returns Some(action) when user submits valid URL or cancels; None otherwise.
 pub fn show(
@@ -33,6 +36,7 @@ pub fn show(
     url: &mut String,
     error: &mut Option<String>,
     loading: bool,
+    candidates: &[FeedCandidate],
 ) -> Option<AddFeedAction> {
     let mut action: Option<AddFeedAction> = None;
 
@@ -68,6 +72,20 @@ pub fn show(
         ui.colored_label(egui::Color32::RED, msg);
     }
 
+    if !candidates.is_empty() {
+        ui.add_space(8.0);
+        ui.label("This site offers several feeds:");
+        for c in candidates {
+            let mut label = c.title.clone().unwrap_or_else(|| c.url.clone());
+            if let Some(kind) = &c.kind {
+                label.push_str(&format!(" [{}]", kind));
+            }
+            if ui.button(label).on_hover_text(&c.url).clicked() {
+                action = Some(AddFeedAction::Submit(c.url.clone()));
+            }
+        }
+    }
+
     ui.add_space(8.0);
     ui.horizontal(|ui| {
         if ui.button("Add").clicked() {
@@ -1,25 +1,33 @@
-//! Article detail view: title, date, source, formatted body, enclosures with Open/Download (FR-003, FR-009).
+//! Article detail view: title, date, source, formatted body, enclosures with Open/Download
+//! (and Play, with the built-in player) and podcast episode details (FR-003, FR-009).
 
-use crate::{download_enclosure, format_article, open_enclosure, SubscriptionList};
+use crate::media::is_audio;
+use crate::settings::Settings;
+use crate::{
+    download_enclosure, format_article, format_duration, open_enclosure, SubscriptionList,
+};
 use eframe::egui;
 
 /// Draw article detail for `selected_item_id`; show "Not found" if item missing (FR-009).
-/// Body is scrollable; each enclosure has Open and Download buttons (FR-003).
+/// Body is scrollable; each enclosure has Open and Download buttons (FR-003), audio ones
+/// also Play. Returns the index of the enclosure to play.
 pub fn show(
     ui: &mut egui::Ui,
     store: &SubscriptionList,
     selected_item_id: Option<&str>,
     selected_feed: Option<&str>,
-) {
+    settings: &Settings,
+) -> Option<usize> {
     let Some(id) = selected_item_id else {
         ui.label("Select an article.");
-        return;
+        return None;
     };
 
     let Some(item) = store.get_item(id, selected_feed) else {
         ui.colored_label(egui::Color32::RED, "Not found.");
-        return;
+        return None;
     };
+    let mut play = None;
 
     // Reserve full panel height so the scroll area viewport fills the space and scrolls when content is long.
     let available_height = ui.available_height();
@@ -32,7 +40,7 @@ pub fn show(
             let date_str = item
                 .published
                 .as_ref()
-                .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                .map(|d| settings.format_date(d))
                 .unwrap_or_else(|| "?".to_string());
 
             ui.heading(&item.title);
@@ -43,11 +51,28 @@ pub fn show(
                     ui.hyperlink_to("Link", link);
                 }
             }
+            if let Some(numbering) = item.podcast.numbering() {
+                ui.label(numbering);
+            }
+            if !item.podcast.persons.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("People:");
+                    for p in &item.podcast.persons {
+                        match &p.href {
+                            Some(href) => ui.hyperlink_to(p.label(), href),
+                            None => ui.label(p.label()),
+                        };
+                    }
+                });
+            }
             ui.add_space(8.0);
             ui.separator();
             ui.add_space(8.0);
 
-            let body = format_article(item.content.as_deref(), 80);
+            let body = format_article(
+                item.content.as_deref(),
+                settings.width.unwrap_or(crate::format::DEFAULT_WIDTH),
+            );
             ui.label(egui::RichText::new(body).monospace());
 
             if !item.enclosures.is_empty() {
@@ -62,12 +87,45 @@ pub fn show(
                             let _ = open_enclosure(enc);
                         }
                         if ui.button("Download").clicked() {
-                            if let Ok(path) = download_enclosure(enc, None) {
+                            if let Ok(path) = download_enclosure(
+                                enc,
+                                settings.download_dir.as_deref(),
+                                &settings.http_options(),
+                            ) {
                                 ui.label(format!("Saved to {}", path.display()));
                             }
                         }
+                        if cfg!(feature = "player") && is_audio(enc) && ui.button("Play").clicked()
+                        {
+                            play = Some(idx);
+                        }
+                        if let Some(saved) = store.playback_position(&enc.url) {
+                            ui.label(format!(
+                                "Resumes at {}",
+                                format_duration(saved.position_secs)
+                            ));
+                        }
                     });
+                    ui.horizontal(|ui| {
+                        if let Some(title) = &enc.title {
+                            ui.label(title);
+                        }
+                        if let Some(secs) = enc.duration_secs {
+                            ui.label(format_duration(secs));
+                        }
+                        if let Some(thumbnail) = &enc.thumbnail {
+                            ui.hyperlink_to("Artwork", thumbnail);
+                        }
+                    });
+                }
+                if let Some(chapters) = &item.podcast.chapters {
+                    ui.hyperlink_to("Chapters", &chapters.url);
+                }
+                for t in &item.podcast.transcripts {
+                    let language = t.language.as_deref().unwrap_or("?");
+                    ui.hyperlink_to(format!("Transcript ({})", language), &t.url);
                 }
             }
         });
+    play
 }
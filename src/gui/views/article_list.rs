@@ -1,40 +1,160 @@
 //! Article list view: items for selected feed (or all), single selection, arrow keys (FR-002).
+//! Unread items are drawn in bold; starred items are prefixed with a star. A search box
+//! narrows the list to ranked full-text matches with highlighted snippets, and the filter
+//! panel offers the same [`ItemQuery`] options as `list-items`.
 
-use crate::SubscriptionList;
+use crate::search::{search_items, LiveIndex, SearchQuery, Snippet, LIST_LIMIT};
+use crate::store::{parse_date_bound, FeedSelection, ItemQuery, SortKey};
+use crate::{FeedItem, SubscriptionList};
 use eframe::egui;
 
-/// Draw article list for `selected_feed` (None = all); update `selected_item_id` on click.
-/// If no items, show empty state message (FR-008).
+/// Search box and filter state of the article list. The search index is built on the
+/// first search and rebuilt when a save changed the store's items.
+#[derive(Default)]
+pub struct ArticleListState {
+    pub search: String,
+    index: LiveIndex,
+    /// Filters and sort order applied on top of the feed-list selection.
+    pub filters: ItemQuery,
+    show_filters: bool,
+    since: String,
+    until: String,
+    date_error: Option<String>,
+}
+
+/// Items listed for `selection` after the filters: in the chosen sort order, or while
+/// searching the matching ones best first, each with its snippet.
+pub fn visible_items<'a>(
+    store: &'a SubscriptionList,
+    selection: &FeedSelection,
+    state: &mut ArticleListState,
+) -> Vec<(&'a FeedItem, Option<Snippet>)> {
+    let items = selection.filter(&state.filters).run(store);
+    let query = SearchQuery::parse(&state.search);
+    if query.is_empty() {
+        return items.into_iter().map(|i| (i, None)).collect();
+    }
+    search_items(state.index.get(store), &query, &items, LIST_LIMIT)
+        .into_iter()
+        .map(|(item, hit)| (item, Some(hit.snippet)))
+        .collect()
+}
+
+/// Filter controls: read/starred/media toggles, sort order and a date range.
+fn filters_ui(ui: &mut egui::Ui, state: &mut ArticleListState) {
+    let filters = &mut state.filters;
+    ui.horizontal_wrapped(|ui| {
+        ui.checkbox(&mut filters.unread, "Unread");
+        ui.checkbox(&mut filters.starred, "Starred");
+        ui.checkbox(&mut filters.has_enclosure, "Media");
+    });
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("article_sort")
+            .selected_text(format!("Sort: {}", filters.sort))
+            .show_ui(ui, |ui| {
+                for key in SortKey::ALL {
+                    ui.selectable_value(&mut filters.sort, key, key.as_str());
+                }
+            });
+        ui.checkbox(&mut filters.reverse, "Reverse");
+    });
+    let mut dates_changed = false;
+    ui.horizontal(|ui| {
+        for (label, text) in [("Since", &mut state.since), ("Until", &mut state.until)] {
+            ui.label(label);
+            dates_changed |= ui
+                .add(
+                    egui::TextEdit::singleline(text)
+                        .hint_text("YYYY-MM-DD / 7d")
+                        .desired_width(90.0),
+                )
+                .changed();
+        }
+    });
+    if dates_changed {
+        let bound = |text: &str, end_of_day| {
+            let text = text.trim();
+            (!text.is_empty())
+                .then(|| parse_date_bound(text, end_of_day))
+                .transpose()
+        };
+        state.date_error = None;
+        match (bound(&state.since, false), bound(&state.until, true)) {
+            (Ok(since), Ok(until)) => {
+                filters.since = since;
+                filters.until = until;
+            }
+            (Err(e), _) | (_, Err(e)) => state.date_error = Some(e.to_string()),
+        }
+    }
+    if let Some(e) = &state.date_error {
+        ui.colored_label(egui::Color32::RED, e);
+    }
+}
+
+/// Draw the search box and the article list for the feed-list `selection`; update
+/// `selected_item_id` on click. If no items, show empty state message (FR-008).
 /// Set `*focus_tag = Some(article_list_tag)` when user clicks in the list for arrow-key handling.
 pub fn show(
     ui: &mut egui::Ui,
     store: &SubscriptionList,
-    selected_feed: Option<&str>,
+    selection: &FeedSelection,
+    state: &mut ArticleListState,
     selected_item_id: &mut Option<String>,
     focus_tag: &mut Option<u8>,
     article_list_tag: u8,
 ) {
-    let items = store.items(selected_feed);
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut state.search)
+                .hint_text("Search (\"phrase\", title:, feed:)")
+                .desired_width(ui.available_width() - 52.0),
+        );
+        if !state.search.is_empty() && ui.small_button("✕").clicked() {
+            state.search.clear();
+        }
+        ui.toggle_value(&mut state.show_filters, "⚙")
+            .on_hover_text("Filters and sorting");
+    });
+    if state.show_filters {
+        filters_ui(ui, state);
+    }
+    let items = visible_items(store, selection, state);
 
     if items.is_empty() {
         ui.vertical_centered(|ui| {
             ui.add_space(20.0);
-            ui.label("No articles.");
+            ui.label(if state.search.trim().is_empty() {
+                "No articles."
+            } else {
+                "No matching articles."
+            });
         });
         return;
     }
 
     let mut list_clicked = false;
     egui::ScrollArea::vertical().show(ui, |ui| {
-        for item in items {
+        for (item, snippet) in items {
             let is_selected = selected_item_id.as_deref() == Some(item.id.as_str());
             let date_str = item
                 .published
                 .as_ref()
                 .map(|d| d.format("%Y-%m-%d").to_string())
                 .unwrap_or_else(|| "?".to_string());
-            let label = format!("{}  {}", date_str, item.title);
+            let star = if store.is_starred(&item.id) {
+                "★ "
+            } else {
+                ""
+            };
+            let mut label = egui::RichText::new(format!("{}{}  {}", star, date_str, item.title));
+            if !store.is_read(&item.id) {
+                label = label.strong();
+            }
             let resp = ui.selectable_label(is_selected, label);
+            if let Some(snippet) = snippet.filter(|s| !s.text.is_empty()) {
+                ui.label(snippet_layout(ui, &snippet));
+            }
             if resp.clicked() {
                 *selected_item_id = Some(item.id.clone());
                 list_clicked = true;
@@ -45,3 +165,23 @@ pub fn show(
         *focus_tag = Some(article_list_tag);
     }
 }
+
+/// Snippet text in small weak type with the matched words highlighted.
+fn snippet_layout(ui: &egui::Ui, snippet: &Snippet) -> egui::text::LayoutJob {
+    let font = egui::TextStyle::Small.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font.clone(), ui.visuals().weak_text_color());
+    let marked = egui::TextFormat {
+        color: ui.visuals().strong_text_color(),
+        background: ui.visuals().selection.bg_fill,
+        ..egui::TextFormat::simple(font, ui.visuals().strong_text_color())
+    };
+    let mut job = egui::text::LayoutJob::default();
+    let mut last = 0;
+    for r in &snippet.highlights {
+        job.append(&snippet.text[last..r.start], 0.0, plain.clone());
+        job.append(&snippet.text[r.clone()], 0.0, marked.clone());
+        last = r.end;
+    }
+    job.append(&snippet.text[last..], 0.0, plain);
+    job
+}
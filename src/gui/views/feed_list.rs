@@ -1,20 +1,23 @@
-//! Feed list view: "All" + subscribed feeds, single selection, arrow keys (FR-001).
+//! Feed list view: "All", "Starred", collapsible folder groups, top-level feeds and tags;
+//! single selection, arrow keys (FR-001). Feeds whose last fetch failed are drawn in red with
+//! the error on hover.
 
+use crate::store::FeedSelection;
 use crate::SubscriptionList;
 use eframe::egui;
 
-/// Draw feed list; update `selected_feed` on click (None = "All", Some(url) = feed).
+/// Draw feed list; update `selected` on click.
 /// If no feeds, show empty state and set `open_add_feed` true when "Add feed" is clicked (FR-008).
 /// Set `*focus_tag = Some(feed_list_tag)` when user clicks in the list for arrow-key handling.
 pub fn show(
     ui: &mut egui::Ui,
     store: &SubscriptionList,
-    selected_feed: &mut Option<String>,
+    selected: &mut FeedSelection,
     open_add_feed: &mut bool,
     focus_tag: &mut Option<u8>,
     feed_list_tag: u8,
 ) {
-    if store.feeds.is_empty() {
+    if store.feeds.is_empty() && store.starred_items.is_empty() {
         ui.vertical_centered(|ui| {
             ui.add_space(20.0);
             ui.label("No feeds yet.");
@@ -29,21 +32,31 @@ pub fn show(
 
     let mut list_clicked = false;
     egui::ScrollArea::vertical().show(ui, |ui| {
-        // "All" option
-        let all_selected = selected_feed.is_none();
-        let resp = ui.selectable_label(all_selected, "All");
-        if resp.clicked() {
-            *selected_feed = None;
-            list_clicked = true;
+        let starred = format!("★ Starred ({})", store.starred_items.len());
+        for (entry, label) in [
+            (FeedSelection::All, "All".to_string()),
+            (FeedSelection::Starred, starred),
+        ] {
+            let resp = ui.selectable_label(*selected == entry, label);
+            list_clicked |= select_on_click(resp, entry, selected);
         }
 
-        for feed in &store.feeds {
-            let label = feed.title.as_deref().unwrap_or(feed.url.as_str());
-            let is_selected = selected_feed.as_deref() == Some(feed.url.as_str());
-            let resp = ui.selectable_label(is_selected, label);
-            if resp.clicked() {
-                *selected_feed = Some(feed.url.clone());
-                list_clicked = true;
+        let folders = store.folders();
+        for path in folders.iter().filter(|p| !p.contains('/')) {
+            list_clicked |= folder_ui(ui, store, &folders, path, selected);
+        }
+        for feed in store.feeds_in(None) {
+            list_clicked |= feed_ui(ui, store, &feed.url, selected);
+        }
+
+        let tags = store.tags();
+        if !tags.is_empty() {
+            ui.separator();
+            ui.label(egui::RichText::new("Tags").small().weak());
+            for tag in tags {
+                let label = format!("# {}", tag);
+                let resp = ui.selectable_label(*selected == FeedSelection::Tag(tag.clone()), label);
+                list_clicked |= select_on_click(resp, FeedSelection::Tag(tag), selected);
             }
         }
     });
@@ -51,3 +64,85 @@ pub fn show(
         *focus_tag = Some(feed_list_tag);
     }
 }
+
+/// Collapsible group for folder `path`: subfolders, then its feeds. Returns true if clicked.
+fn folder_ui(
+    ui: &mut egui::Ui,
+    store: &SubscriptionList,
+    folders: &[String],
+    path: &str,
+    selected: &mut FeedSelection,
+) -> bool {
+    let mut clicked = false;
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let unread: usize = store
+        .feeds
+        .iter()
+        .filter(|f| f.in_folder(path))
+        .map(|f| store.unread_count(Some(&f.url)))
+        .sum();
+    let label = if unread > 0 {
+        format!("📁 {} ({})", name, unread)
+    } else {
+        format!("📁 {}", name)
+    };
+    let entry = FeedSelection::Folder(path.to_string());
+    let id = ui.make_persistent_id(("feed_folder", path));
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+        .show_header(ui, |ui| {
+            let resp = ui.selectable_label(*selected == entry, label);
+            clicked |= select_on_click(resp, entry.clone(), selected);
+        })
+        .body(|ui| {
+            let prefix = format!("{}/", path);
+            let children = folders.iter().filter(|p| {
+                p.strip_prefix(&prefix)
+                    .is_some_and(|rest| !rest.contains('/'))
+            });
+            for child in children {
+                clicked |= folder_ui(ui, store, folders, child, selected);
+            }
+            for feed in store.feeds_in(Some(path)) {
+                clicked |= feed_ui(ui, store, &feed.url, selected);
+            }
+        });
+    clicked
+}
+
+/// One feed row. Returns true if clicked.
+fn feed_ui(
+    ui: &mut egui::Ui,
+    store: &SubscriptionList,
+    url: &str,
+    selected: &mut FeedSelection,
+) -> bool {
+    let feed = store.feeds.iter().find(|f| f.url == url);
+    let title = feed
+        .and_then(|f| f.title.clone())
+        .unwrap_or_else(|| url.to_string());
+    let entry = FeedSelection::Feed(url.to_string());
+    let resp = match feed.filter(|f| f.is_failing()) {
+        Some(f) => {
+            let label = egui::RichText::new(format!("⚠ {}", title)).color(egui::Color32::RED);
+            ui.selectable_label(*selected == entry, label)
+                .on_hover_text(format!(
+                    "{} failed fetch(es) in a row: {}",
+                    f.consecutive_failures,
+                    f.last_error.as_deref().unwrap_or("?")
+                ))
+        }
+        None => ui.selectable_label(*selected == entry, title),
+    };
+    select_on_click(resp, entry, selected)
+}
+
+fn select_on_click(
+    resp: egui::Response,
+    entry: FeedSelection,
+    selected: &mut FeedSelection,
+) -> bool {
+    if resp.clicked() {
+        *selected = entry;
+    }
+    resp.clicked()
+}
@@ -1,7 +1,12 @@
-//! GUI views: feed list, article list, article detail, add-feed dialog.
+//! GUI views: feed list, article list, article detail, add-feed, OPML and rules dialogs,
+//! and the player bar.
 
 // Placeholder until T010–T019 implement views.
 pub mod add_feed;
 pub mod article_detail;
 pub mod article_list;
 pub mod feed_list;
+pub mod opml_dialog;
+#[cfg(feature = "player")]
+pub mod player;
+pub mod rules_editor;
@@ -0,0 +1,66 @@
+//! OPML import/export dialog: file path input, Import|Export/Cancel.
+
+use eframe::egui;
+use std::path::PathBuf;
+
+/// Which direction the dialog was opened for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpmlMode {
+    Import,
+    Export,
+}
+
+/// Action returned by the OPML dialog.
+pub enum OpmlAction {
+    /// User confirmed a non-empty path.
+    Confirm(PathBuf),
+    /// User cancelled (close dialog).
+    Cancel,
+}
+
+/// Draw the OPML dialog. Caller performs the import/export on Confirm and reports errors
+/// back through `error`.
+pub fn show(
+    ui: &mut egui::Ui,
+    mode: OpmlMode,
+    path: &mut String,
+    error: &mut Option<String>,
+) -> Option<OpmlAction> {
+    let mut action: Option<OpmlAction> = None;
+    let verb = match mode {
+        OpmlMode::Import => "Import",
+        OpmlMode::Export => "Export",
+    };
+
+    ui.label("OPML file:");
+    let path_resp = ui.add(
+        egui::TextEdit::singleline(path)
+            .hint_text("/path/to/subscriptions.opml")
+            .desired_width(320.0)
+            .id(egui::Id::new("opml_path")),
+    );
+    if path_resp.changed() {
+        *error = None;
+    }
+    let submit = path_resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+    if let Some(ref msg) = *error {
+        ui.colored_label(egui::Color32::RED, msg);
+    }
+
+    ui.add_space(8.0);
+    ui.horizontal(|ui| {
+        if ui.button(verb).clicked() || submit {
+            if path.trim().is_empty() {
+                *error = Some("Path cannot be empty.".to_string());
+            } else {
+                action = Some(OpmlAction::Confirm(PathBuf::from(path.trim())));
+            }
+        }
+        if ui.button("Cancel").clicked() {
+            action = Some(OpmlAction::Cancel);
+        }
+    });
+
+    action
+}
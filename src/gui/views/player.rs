@@ -0,0 +1,271 @@
+//! Player bar (feature `player`): downloads an audio enclosure in the background, then
+//! plays it with pause, skip, seek and speed controls. The position is saved every few
+//! seconds and when playback stops; playing to the end marks the item read.
+
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use crate::fetch::HttpOptions;
+use crate::media::download::download_file;
+use crate::media::player::Player;
+use crate::store::Storage;
+use crate::{format_duration, FeedItem, SubscriptionList};
+
+/// Speeds offered in the speed menu.
+const SPEEDS: [f32; 6] = [0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+/// How often the position is saved while playing.
+const SAVE_EVERY: Duration = Duration::from_secs(10);
+
+/// What the player bar is doing.
+#[derive(Default)]
+pub struct Playback {
+    playing: Option<Playing>,
+    download: Option<PendingDownload>,
+}
+
+struct Playing {
+    player: Player,
+    item_id: String,
+    url: String,
+    title: String,
+    duration: Option<u64>,
+    /// Slider value while the knob is dragged; seeking waits for the release.
+    dragging: Option<f32>,
+    last_saved: Instant,
+}
+
+struct PendingDownload {
+    item: FeedItem,
+    index: usize,
+    /// Bytes written and total size (0 = unknown), updated by the download thread.
+    progress: Arc<(AtomicU64, AtomicU64)>,
+    done: mpsc::Receiver<crate::Result<PathBuf>>,
+}
+
+impl Playback {
+    /// Whether the bar has anything to show.
+    pub fn is_active(&self) -> bool {
+        self.playing.is_some() || self.download.is_some()
+    }
+
+    /// Play enclosure `index` of `item` from its saved position, downloading it into `dir`
+    /// first unless it was downloaded already. Whatever played before stops.
+    pub fn start(
+        &mut self,
+        store: &mut SubscriptionList,
+        storage: &dyn Storage,
+        item: &FeedItem,
+        index: usize,
+        dir: &Path,
+        http: &HttpOptions,
+    ) -> crate::Result<()> {
+        self.stop(store, storage)?;
+        let Some(enclosure) = item.enclosures.get(index) else {
+            return Ok(());
+        };
+        if let Some(path) = store.downloaded_file(&enclosure.url) {
+            let path = path.to_path_buf();
+            return self.open(store, item, index, &path);
+        }
+        let progress = Arc::new((AtomicU64::new(0), AtomicU64::new(0)));
+        let (tx, done) = mpsc::channel();
+        let (url, dir) = (enclosure.url.clone(), dir.to_path_buf());
+        let title = enclosure
+            .title
+            .clone()
+            .unwrap_or_else(|| item.title.clone());
+        let shared = progress.clone();
+        let http = http.clone();
+        std::thread::spawn(move || {
+            let result = download_file(&url, &dir, Some(&title), &http, &mut |written, total| {
+                shared.0.store(written, Ordering::Relaxed);
+                shared.1.store(total.unwrap_or(0), Ordering::Relaxed);
+            });
+            let _ = tx.send(result);
+        });
+        self.download = Some(PendingDownload {
+            item: item.clone(),
+            index,
+            progress,
+            done,
+        });
+        Ok(())
+    }
+
+    fn open(
+        &mut self,
+        store: &SubscriptionList,
+        item: &FeedItem,
+        index: usize,
+        path: &Path,
+    ) -> crate::Result<()> {
+        let enclosure = &item.enclosures[index];
+        let start = store
+            .playback_position(&enclosure.url)
+            .map_or(0, |p| p.position_secs);
+        let player = Player::open(path, Duration::from_secs(start))?;
+        let duration = enclosure
+            .duration_secs
+            .or_else(|| player.duration().map(|d| d.as_secs()));
+        self.playing = Some(Playing {
+            player,
+            item_id: item.id.clone(),
+            url: enclosure.url.clone(),
+            title: enclosure
+                .title
+                .clone()
+                .unwrap_or_else(|| item.title.clone()),
+            duration,
+            dragging: None,
+            last_saved: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Stop playback (or the download) and save the position.
+    pub fn stop(
+        &mut self,
+        store: &mut SubscriptionList,
+        storage: &dyn Storage,
+    ) -> crate::Result<()> {
+        self.download = None;
+        match self.playing.take() {
+            Some(playing) => playing.save(store, storage),
+            None => Ok(()),
+        }
+    }
+
+    /// Pick up a finished download, save the position now and then, and notice the end.
+    /// Call once per frame.
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        store: &mut SubscriptionList,
+        storage: &dyn Storage,
+    ) -> crate::Result<()> {
+        if let Some(download) = self.download.take() {
+            match download.done.try_recv() {
+                Ok(result) => {
+                    let path = result?;
+                    let bytes = std::fs::metadata(&path)?.len();
+                    store.record_download(&download.item, download.index, path.clone(), bytes);
+                    storage.save(store)?;
+                    self.open(store, &download.item, download.index, &path)?;
+                }
+                Err(mpsc::TryRecvError::Empty) => self.download = Some(download),
+                Err(mpsc::TryRecvError::Disconnected) => {}
+            }
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+        let Some(playing) = &mut self.playing else {
+            return Ok(());
+        };
+        if playing.player.is_finished() {
+            return self.stop(store, storage);
+        }
+        if playing.last_saved.elapsed() >= SAVE_EVERY {
+            playing.last_saved = Instant::now();
+            playing.save(store, storage)?;
+        }
+        ctx.request_repaint_after(Duration::from_millis(250));
+        Ok(())
+    }
+
+    /// Draw the bar. Returns true when Stop was clicked.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> crate::Result<bool> {
+        if let Some(download) = &self.download {
+            let (written, total) = (
+                download.progress.0.load(Ordering::Relaxed),
+                download.progress.1.load(Ordering::Relaxed),
+            );
+            let mut stop = false;
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Downloading {}…", download.item.title));
+                if total > 0 {
+                    ui.add(
+                        egui::ProgressBar::new(written as f32 / total as f32)
+                            .desired_width(200.0)
+                            .show_percentage(),
+                    );
+                }
+                stop = ui.button("Cancel").clicked();
+            });
+            return Ok(stop);
+        }
+        let Some(playing) = &mut self.playing else {
+            return Ok(false);
+        };
+        let player = &playing.player;
+        let mut stop = false;
+        let mut result = Ok(());
+        ui.horizontal(|ui| {
+            let label = if player.is_paused() { "Play" } else { "Pause" };
+            if ui.button(label).clicked() {
+                player.toggle_pause();
+            }
+            if ui.button("−15s").clicked() {
+                result = player.skip(-15);
+            }
+            if ui.button("+30s").clicked() {
+                result = player.skip(30);
+            }
+            let position = player.position().as_secs_f32();
+            let length = playing.duration.map_or(position, |d| d as f32).max(1.0);
+            let mut value = playing.dragging.unwrap_or(position);
+            let response = ui.add(
+                egui::Slider::new(&mut value, 0.0..=length)
+                    .show_value(false)
+                    .trailing_fill(true),
+            );
+            if response.dragged() {
+                playing.dragging = Some(value);
+            }
+            if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                playing.dragging = None;
+                result = player.seek(Duration::from_secs_f32(value));
+            }
+            ui.label(format!(
+                "{} / {}",
+                format_duration(value as u64),
+                playing
+                    .duration
+                    .map(format_duration)
+                    .unwrap_or_else(|| "?".to_string())
+            ));
+            let speed = player.speed();
+            egui::ComboBox::from_id_salt("player_speed")
+                .width(60.0)
+                .selected_text(format!("{}x", speed))
+                .show_ui(ui, |ui| {
+                    for s in SPEEDS {
+                        if ui.selectable_label(speed == s, format!("{}x", s)).clicked() {
+                            result = player.set_speed(s);
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Playback speed (the pitch changes with it)");
+            ui.label(&playing.title);
+            stop = ui.button("Stop").clicked();
+        });
+        result.map(|()| stop)
+    }
+}
+
+impl Playing {
+    /// Save the position, or mark the item played at the end.
+    fn save(&self, store: &mut SubscriptionList, storage: &dyn Storage) -> crate::Result<()> {
+        if self.player.is_finished() {
+            store.clear_playback_position(&self.url);
+            store.set_read(&self.item_id, true);
+        } else {
+            let position = self.player.position().as_secs();
+            store.set_playback_position(&self.item_id, &self.url, position, self.duration);
+        }
+        storage.save(store)
+    }
+}
@@ -0,0 +1,172 @@
+//! Rules editor window: the rules in order with Remove buttons, and a form to test and
+//! add a rule.
+
+use eframe::egui;
+
+use crate::rules::{Condition, Rule, RuleAction, RuleField};
+use crate::SubscriptionList;
+
+/// Actions offered in the form; `tag` takes its name from a text field.
+const ACTIONS: [&str; 5] = ["hide", "mark-read", "star", "tag", "drop"];
+
+/// Draft rule and feedback of the editor form.
+pub struct RulesEditorState {
+    conditions: Vec<Condition>,
+    action: &'static str,
+    tag: String,
+    /// Result of the last Test, or why the draft is invalid.
+    message: Option<Result<String, String>>,
+}
+
+impl Default for RulesEditorState {
+    fn default() -> Self {
+        Self {
+            conditions: vec![empty_condition()],
+            action: ACTIONS[0],
+            tag: String::new(),
+            message: None,
+        }
+    }
+}
+
+fn empty_condition() -> Condition {
+    Condition {
+        field: RuleField::Title,
+        pattern: String::new(),
+        regex: false,
+    }
+}
+
+impl RulesEditorState {
+    /// Report an error from applying an action (e.g. saving failed).
+    pub fn set_error(&mut self, error: String) {
+        self.message = Some(Err(error));
+    }
+
+    fn draft(&self) -> Result<Rule, String> {
+        let action = if self.action == "tag" {
+            format!("tag:{}", self.tag)
+        } else {
+            self.action.to_string()
+        };
+        let rule = Rule {
+            conditions: self
+                .conditions
+                .iter()
+                .filter(|c| !c.pattern.is_empty())
+                .cloned()
+                .collect(),
+            action: action.parse::<RuleAction>()?,
+        };
+        rule.matcher().map_err(|e| e.to_string())?;
+        Ok(rule)
+    }
+}
+
+/// What the caller should do to the store.
+pub enum RulesAction {
+    Add(Rule),
+    /// Remove rule N (1-based).
+    Remove(usize),
+}
+
+/// Draw the editor. The caller applies the returned action and saves.
+pub fn show(
+    ui: &mut egui::Ui,
+    store: &SubscriptionList,
+    state: &mut RulesEditorState,
+) -> Option<RulesAction> {
+    let mut action = None;
+
+    ui.label("Rules run on new items, in this order:");
+    if store.rules.is_empty() {
+        ui.weak("No rules yet.");
+    }
+    for (i, rule) in store.rules.iter().enumerate() {
+        ui.horizontal(|ui| {
+            if ui.small_button("Remove").clicked() {
+                action = Some(RulesAction::Remove(i + 1));
+            }
+            ui.label(format!("{}. {}", i + 1, rule));
+        });
+    }
+
+    ui.separator();
+    ui.label("New rule (all conditions must match):");
+    let mut remove = None;
+    for (i, condition) in state.conditions.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(("rule_field", i))
+                .selected_text(condition.field.as_str())
+                .show_ui(ui, |ui| {
+                    for field in RuleField::ALL {
+                        ui.selectable_value(&mut condition.field, field, field.as_str());
+                    }
+                });
+            ui.add(
+                egui::TextEdit::singleline(&mut condition.pattern)
+                    .hint_text("contains…")
+                    .desired_width(200.0),
+            );
+            ui.checkbox(&mut condition.regex, "Regex");
+            if ui.small_button("✕").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = remove {
+        state.conditions.remove(i);
+    }
+    if ui.small_button("+ Condition").clicked() {
+        state.conditions.push(empty_condition());
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Action:");
+        egui::ComboBox::from_id_salt("rule_action")
+            .selected_text(state.action)
+            .show_ui(ui, |ui| {
+                for name in ACTIONS {
+                    ui.selectable_value(&mut state.action, name, name);
+                }
+            });
+        if state.action == "tag" {
+            ui.add(
+                egui::TextEdit::singleline(&mut state.tag)
+                    .hint_text("tag")
+                    .desired_width(120.0),
+            );
+        }
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Test").clicked() {
+            state.message = Some(state.draft().and_then(|rule| {
+                store
+                    .test_rule(&rule)
+                    .map(|items| format!("{} cached item(s) match.", items.len()))
+                    .map_err(|e| e.to_string())
+            }));
+        }
+        if ui.button("Add rule").clicked() {
+            match state.draft() {
+                Ok(rule) => {
+                    action = Some(RulesAction::Add(rule));
+                    *state = RulesEditorState::default();
+                }
+                Err(e) => state.message = Some(Err(e)),
+            }
+        }
+    });
+    match &state.message {
+        Some(Ok(msg)) => {
+            ui.label(msg);
+        }
+        Some(Err(msg)) => {
+            ui.colored_label(egui::Color32::RED, msg);
+        }
+        None => {}
+    }
+
+    action
+}
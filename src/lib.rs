@@ -4,17 +4,46 @@
 //! pretty-format content, and support media enclosures. Exposed via CLI.
 
 pub mod cli;
+pub mod discover;
 pub mod error;
 pub mod feed;
 pub mod fetch;
 pub mod format;
 pub mod gui;
 pub mod media;
+pub mod opml;
+pub mod refresh;
+pub mod rules;
+pub mod schedule;
+pub mod search;
+pub mod server;
+pub mod settings;
 pub mod store;
+pub mod sync;
+pub mod tui;
 
+pub use discover::{discover_feed, find_feed_links, Discovery, FeedCandidate};
 pub use error::{Error, Result};
-pub use feed::{Feed, FeedItem, MediaEnclosure};
-pub use fetch::fetch_feed;
-pub use format::format_article;
+pub use feed::{
+    format_duration, parse_duration, DownloadPolicy, Feed, FeedItem, ItemLink, MediaEnclosure,
+    PodcastEpisode, PodcastFile, PodcastPerson, RefreshHints,
+};
+pub use fetch::{fetch_feed, fetch_feed_conditional, FetchOutcome, HttpOptions};
+pub use format::{format_article, format_article_styled, ColorChoice};
 pub use media::{download_enclosure, open_enclosure, open_or_download_enclosure};
-pub use store::{SubscriptionList, SubscriptionList as Store};
+pub use opml::{export_opml, import_opml, parse_opml};
+pub use refresh::{refresh_feeds, FeedRefresh, RefreshOptions};
+pub use schedule::{due_feeds, next_refresh, refresh_interval, ScheduleOptions};
+pub use search::{SearchHit, SearchIndex, SearchQuery};
+pub use store::{
+    Download, DownloadStatus, ItemQuery, JsonStorage, PlaybackPosition, SortKey, SqliteStorage,
+    Storage, SubscriptionList, SubscriptionList as Store,
+};
+
+/// 64-bit FNV-1a. Unlike std's hasher it is fixed across Rust releases, so it can name
+/// files and derive ids that must stay the same from one run to the next.
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,388 @@
+//! Download manager: streams enclosures to disk, resumes interrupted downloads with HTTP
+//! Range requests, and works through the store's download queue.
+//!
+//! A download is written to `.<hash of url>.part` in the target directory and renamed
+//! when complete, so an interrupted one is found (and resumed) by its url alone. The
+//! response's ETag or Last-Modified is kept in `.<hash of url>.validator` and sent as
+//! If-Range on resume, so a file that changed on the server is downloaded afresh. The
+//! file is named from the Content-Disposition header, else the title, else the url.
+
+use crate::fetch::HttpOptions;
+use crate::store::{Download, Storage};
+use crate::{fnv1a, Error, FeedItem, SubscriptionList};
+use chrono::Utc;
+use fs2::FileExt;
+use reqwest::blocking::Response;
+use reqwest::header::{
+    CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::StatusCode;
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Longest file name stem, in characters and in bytes (file systems allow 255 bytes per
+/// name, and [`unique_path`] may append " (N)").
+const MAX_STEM: (usize, usize) = (100, 200);
+
+/// Download `url` into `dir`, resuming a previous partial download. `progress` is called
+/// with the bytes written so far and the total size, when known. Returns the new file.
+pub fn download_file(
+    url: &str,
+    dir: &Path,
+    title: Option<&str>,
+    http: &HttpOptions,
+    progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<PathBuf, Error> {
+    std::fs::create_dir_all(dir)?;
+    let part = dir.join(format!(".{:016x}.part", fnv1a(url)));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(&part)?;
+    file.try_lock_exclusive()
+        .map_err(|_| Error::InvalidInput(format!("{} is already being downloaded", url)))?;
+    let validator_path = part.with_extension("validator");
+
+    let client = http.download_client()?;
+    let (mut response, offset) = loop {
+        let offset = file.metadata()?.len();
+        // Without a validator the partial file may belong to another version: start over.
+        let validator = match offset {
+            0 => None,
+            _ => std::fs::read_to_string(&validator_path).ok(),
+        };
+        let mut request = client.get(url);
+        if let Some(validator) = &validator {
+            request = request
+                .header(RANGE, format!("bytes={}-", offset))
+                .header(IF_RANGE, validator.as_str());
+        }
+        let response = request.send()?;
+        if validator.is_some() {
+            let status = response.status();
+            if status == StatusCode::PARTIAL_CONTENT && range_start(&response) == Some(offset) {
+                break (response, offset);
+            }
+            if status == StatusCode::PARTIAL_CONTENT || status == StatusCode::RANGE_NOT_SATISFIABLE
+            {
+                // Not the range asked for: start over.
+                file.set_len(0)?;
+                continue;
+            }
+        }
+        let response = response.error_for_status()?;
+        file.set_len(0)?;
+        match validator_of(&response) {
+            Some(validator) => std::fs::write(&validator_path, validator)?,
+            None => remove_if_present(&validator_path)?,
+        }
+        break (response, 0);
+    };
+    file.seek(SeekFrom::Start(offset))?;
+    let total = response.content_length().map(|len| len + offset);
+    let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok());
+    let name = file_name(
+        header(CONTENT_DISPOSITION),
+        title,
+        url,
+        header(CONTENT_TYPE),
+    );
+
+    let mut written = offset;
+    progress(written, total);
+    if total != Some(offset) {
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = response.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n])?;
+            written += n as u64;
+            progress(written, total);
+        }
+    }
+    file.flush()?;
+    if total.is_some_and(|t| written < t) {
+        return Err(Error::Io(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!(
+                "download interrupted at {} of {} bytes",
+                written,
+                total.unwrap_or(0)
+            ),
+        )));
+    }
+    drop(file);
+    let path = unique_path(dir, &name);
+    std::fs::rename(&part, &path)?;
+    remove_if_present(&validator_path)?;
+    Ok(path)
+}
+
+/// The first byte of a 206 response's Content-Range (`bytes <start>-<end>/<size>`).
+fn range_start(response: &Response) -> Option<u64> {
+    let range = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    range
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The response's strong ETag, else its Last-Modified date, for If-Range.
+fn validator_of(response: &Response) -> Option<String> {
+    let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok());
+    header(ETAG)
+        .filter(|tag| !tag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+        .map(str::to_string)
+}
+
+/// Remove `path`, which need not exist.
+fn remove_if_present(path: &Path) -> Result<(), Error> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Download the queued and failed entries of `store`'s queue into `dir` (at most
+/// `limit`), saving the store after each. Failed entries are retried at once with
+/// `retry_failed`, otherwise only when their retry time has come. Returns the entries as
+/// they ended up.
+pub fn run_queue(
+    store: &mut SubscriptionList,
+    storage: &dyn Storage,
+    dir: &Path,
+    limit: Option<usize>,
+    retry_failed: bool,
+    http: &HttpOptions,
+    progress: &mut dyn FnMut(&Download, u64, Option<u64>),
+) -> Result<Vec<Download>, Error> {
+    let pending = if retry_failed {
+        store.pending_downloads()
+    } else {
+        store.due_downloads(Utc::now())
+    };
+    let mut finished = Vec::new();
+    for download in pending.into_iter().take(limit.unwrap_or(usize::MAX)) {
+        let result = download_file(
+            &download.url,
+            dir,
+            download.title.as_deref(),
+            http,
+            &mut |written, total| progress(&download, written, total),
+        )
+        .and_then(|path| {
+            let bytes = std::fs::metadata(&path)?.len();
+            Ok((path, bytes))
+        })
+        .map_err(|e| e.to_string());
+        store.finish_download(&download.url, result);
+        storage.save(store)?;
+        finished.extend(store.download(&download.url).cloned());
+    }
+    Ok(finished)
+}
+
+/// The local file of enclosure `index` of `item`: its finished download, else the
+/// enclosure downloaded into `dir` now and recorded as finished (the store is saved).
+pub fn local_file(
+    store: &mut SubscriptionList,
+    storage: &dyn Storage,
+    item: &FeedItem,
+    index: usize,
+    dir: &Path,
+    http: &HttpOptions,
+    progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<PathBuf, Error> {
+    let enclosure = item
+        .enclosures
+        .get(index)
+        .ok_or_else(|| Error::NotFound(format!("enclosure index {} not found", index)))?;
+    if let Some(path) = store.downloaded_file(&enclosure.url) {
+        return Ok(path.to_path_buf());
+    }
+    let title = enclosure.title.as_deref().unwrap_or(&item.title);
+    let path = download_file(&enclosure.url, dir, Some(title), http, progress)?;
+    let bytes = std::fs::metadata(&path)?.len();
+    store.record_download(item, index, path.clone(), bytes);
+    storage.save(store)?;
+    Ok(path)
+}
+
+/// Delete the files of downloads the feeds' policies no longer keep and drop their queue
+/// entries. Returns the entries removed.
+pub fn apply_retention(store: &mut SubscriptionList) -> Result<Vec<Download>, Error> {
+    let expired: Vec<Download> = store.expired_downloads().into_iter().cloned().collect();
+    for download in &expired {
+        if let Some(path) = &download.path {
+            remove_if_present(path)?;
+        }
+        store.remove_download(&download.url);
+    }
+    Ok(expired)
+}
+
+/// File name from Content-Disposition, else `title` with the url's (or media type's)
+/// extension, else the url's last segment.
+fn file_name(
+    disposition: Option<&str>,
+    title: Option<&str>,
+    url: &str,
+    media_type: Option<&str>,
+) -> String {
+    if let Some(name) = disposition
+        .and_then(disposition_name)
+        .and_then(|n| sanitize(&n))
+    {
+        return shorten(&name);
+    }
+    let segment = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .rsplit('/')
+        .next()
+        .map(percent_decode)
+        .and_then(|s| sanitize(&s));
+    if let Some(stem) = title.and_then(sanitize) {
+        let stem = truncate_stem(&stem);
+        let extension = segment
+            .as_deref()
+            .and_then(|s| s.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_string())
+            .filter(|ext| ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+            .or_else(|| media_type.and_then(extension_for).map(str::to_string));
+        return match extension {
+            Some(ext) => format!("{}.{}", stem.trim_end_matches('.'), ext),
+            None => stem.to_string(),
+        };
+    }
+    segment
+        .map(|s| shorten(&s))
+        .unwrap_or_else(|| "enclosure".to_string())
+}
+
+/// The longest prefix of `stem` within [`MAX_STEM`].
+fn truncate_stem(stem: &str) -> &str {
+    let mut end = 0;
+    for (i, (at, c)) in stem.char_indices().enumerate() {
+        if i == MAX_STEM.0 || at + c.len_utf8() > MAX_STEM.1 {
+            break;
+        }
+        end = at + c.len_utf8();
+    }
+    stem[..end].trim_end()
+}
+
+/// File name `name` with its stem truncated to [`MAX_STEM`], keeping a short extension.
+fn shorten(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && ext.len() <= 5 => {
+            format!("{}.{}", truncate_stem(stem).trim_end_matches('.'), ext)
+        }
+        _ => truncate_stem(name).to_string(),
+    }
+}
+
+/// The `filename*` (RFC 5987) or `filename` parameter of a Content-Disposition header.
+fn disposition_name(header: &str) -> Option<String> {
+    let params: Vec<(String, &str)> = header
+        .split(';')
+        .skip(1)
+        .filter_map(|p| {
+            let (key, value) = p.split_once('=')?;
+            Some((key.trim().to_lowercase(), value.trim()))
+        })
+        .collect();
+    let param = |key: &str| params.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+    // filename*=charset'language'percent-encoded
+    if let Some(encoded) = param("filename*").and_then(|v| v.splitn(3, '\'').nth(2)) {
+        return Some(percent_decode(encoded));
+    }
+    param("filename").map(|v| v.trim_matches('"').replace("\\\"", "\""))
+}
+
+/// Decode `%XX` escapes (invalid UTF-8 is replaced).
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `name` made safe as a single file name: no directories, no characters Windows or
+/// shells choke on, no leading dots. None if nothing is left.
+fn sanitize(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim();
+    (!cleaned.is_empty()).then(|| cleaned.to_string())
+}
+
+fn extension_for(media_type: &str) -> Option<&'static str> {
+    let essence = media_type.split(';').next()?.trim().to_lowercase();
+    Some(match essence.as_str() {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/x-m4a" | "audio/m4a" => "m4a",
+        "audio/aac" => "aac",
+        "audio/ogg" | "application/ogg" => "ogg",
+        "audio/opus" => "opus",
+        "audio/flac" | "audio/x-flac" => "flac",
+        "audio/wav" | "audio/x-wav" => "wav",
+        "video/mp4" => "mp4",
+        "video/webm" => "webm",
+        "video/quicktime" => "mov",
+        "application/pdf" => "pdf",
+        _ => return None,
+    })
+}
+
+/// `dir/name`, or `dir/stem (2).ext`, ... if that exists.
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+        _ => (name, None),
+    };
+    (2..)
+        .map(|n| match ext {
+            Some(ext) => dir.join(format!("{} ({}).{}", stem, n, ext)),
+            None => dir.join(format!("{} ({})", stem, n)),
+        })
+        .find(|p| !p.exists())
+        .expect("unbounded range")
+}
@@ -1,43 +1,60 @@
-//! Enclosure handling: list, open URL in default app, or download to file.
+//! Enclosure handling: list, open URL in default app, download to file, or play audio
+//! with the built-in player (feature `player`).
 
 use crate::feed::MediaEnclosure;
+use crate::fetch::HttpOptions;
 use crate::Error;
 use std::path::Path;
 
+pub mod download;
+#[cfg(feature = "player")]
+pub mod player;
+
+/// Whether `enclosure` is audio, by its media type or else its file extension.
+pub fn is_audio(enclosure: &MediaEnclosure) -> bool {
+    match enclosure.media_type.as_deref() {
+        Some(media_type) => media_type.trim().to_lowercase().starts_with("audio/"),
+        None => {
+            let path = enclosure.url.split(['?', '#']).next().unwrap_or_default();
+            let extension = path.rsplit_once('.').map(|(_, e)| e.to_lowercase());
+            matches!(
+                extension.as_deref(),
+                Some("mp3" | "m4a" | "aac" | "ogg" | "oga" | "opus" | "flac" | "wav")
+            )
+        }
+    }
+}
+
 /// Open enclosure URL in the system default app (browser, player, etc.).
 pub fn open_enclosure(enclosure: &MediaEnclosure) -> Result<(), Error> {
     open::that(&enclosure.url).map_err(|e| Error::Store(format!("open failed: {}", e)))
 }
 
-/// Download enclosure to a file in `dest_dir` (or current dir if None).
-/// Returns the path of the downloaded file.
+/// Download enclosure to a file in `dest_dir` (or current dir if None), streaming and
+/// resuming as [`download::download_file`] does. Returns the path of the downloaded file.
 pub fn download_enclosure(
     enclosure: &MediaEnclosure,
     dest_dir: Option<&Path>,
+    http: &HttpOptions,
 ) -> Result<std::path::PathBuf, Error> {
     let dir = dest_dir.unwrap_or(Path::new("."));
-    let filename = enclosure
-        .url
-        .rsplit('/')
-        .next()
-        .unwrap_or("enclosure")
-        .split('?')
-        .next()
-        .unwrap_or("enclosure");
-    let path = dir.join(filename);
-    let response = reqwest::blocking::get(&enclosure.url)?;
-    let bytes = response.bytes()?;
-    std::fs::write(&path, &bytes).map_err(Error::Io)?;
-    Ok(path)
+    download::download_file(
+        &enclosure.url,
+        dir,
+        enclosure.title.as_deref(),
+        http,
+        &mut |_, _| {},
+    )
 }
 
 /// Open enclosure URL in external app or download to path (per spec: open or download).
 pub fn open_or_download_enclosure(
     enclosure: &MediaEnclosure,
     dest_dir: Option<&Path>,
+    http: &HttpOptions,
 ) -> Result<(), Error> {
     if dest_dir.is_some() {
-        download_enclosure(enclosure, dest_dir).map(|_| ())
+        download_enclosure(enclosure, dest_dir, http).map(|_| ())
     } else {
         open_enclosure(enclosure)
     }
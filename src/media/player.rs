@@ -0,0 +1,101 @@
+//! Built-in audio player (feature `player`): plays a local audio file on the default
+//! output device with pause, seek and playback speed.
+
+use crate::Error;
+use rodio::{Decoder, OutputStream, Sink, Source};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Duration;
+
+/// Slowest and fastest playback speed.
+pub const SPEED_RANGE: (f32, f32) = (0.5, 3.0);
+
+/// A file being played. Dropping it stops playback.
+pub struct Player {
+    sink: Sink,
+    /// Keeps the output device open.
+    _stream: OutputStream,
+    duration: Option<Duration>,
+}
+
+impl Player {
+    /// Start playing the audio file at `path` from `start`.
+    pub fn open(path: &Path, start: Duration) -> Result<Self, Error> {
+        let (stream, handle) =
+            OutputStream::try_default().map_err(|e| Error::InvalidInput(e.to_string()))?;
+        let sink = Sink::try_new(&handle).map_err(|e| Error::InvalidInput(e.to_string()))?;
+        let decoder = Decoder::new(BufReader::new(File::open(path)?))
+            .map_err(|e| Error::Parse(format!("{}: {}", path.display(), e)))?;
+        let duration = decoder.total_duration();
+        sink.append(decoder);
+        let player = Self {
+            sink,
+            _stream: stream,
+            duration,
+        };
+        if !start.is_zero() {
+            player.seek(start)?;
+        }
+        Ok(player)
+    }
+
+    /// Length of the audio, if the format tells. Approximate: the decoder's fraction of a
+    /// second can add up to a few seconds, so prefer the feed's duration when given.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Position in the audio (not wall-clock time played).
+    pub fn position(&self) -> Duration {
+        // The sink counts played time, which runs `speed` times slower than the audio;
+        // `seek` and `set_speed` keep that relation exact.
+        self.sink.get_pos().mul_f32(self.sink.speed())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    /// Pause, or resume when paused.
+    pub fn toggle_pause(&self) {
+        if self.sink.is_paused() {
+            self.sink.play();
+        } else {
+            self.sink.pause();
+        }
+    }
+
+    /// Whether the end was reached.
+    pub fn is_finished(&self) -> bool {
+        self.sink.empty()
+    }
+
+    /// Jump to `to` in the audio (clamped to its length).
+    pub fn seek(&self, to: Duration) -> Result<(), Error> {
+        let to = self.duration.map_or(to, |d| to.min(d));
+        // The sink scales seek targets by the speed; undo that.
+        self.sink
+            .try_seek(to.div_f32(self.sink.speed()))
+            .map_err(|e| Error::InvalidInput(format!("cannot seek: {}", e)))
+    }
+
+    /// Jump `secs` seconds forward (or back, when negative).
+    pub fn skip(&self, secs: i64) -> Result<(), Error> {
+        let position = self.position().as_secs() as i64;
+        self.seek(Duration::from_secs((position + secs).max(0) as u64))
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.sink.speed()
+    }
+
+    /// Set the playback speed (1.0 is normal), clamped to [`SPEED_RANGE`]. The audio is
+    /// resampled, so the pitch shifts with the speed.
+    pub fn set_speed(&self, speed: f32) -> Result<(), Error> {
+        let position = self.position();
+        self.sink
+            .set_speed(speed.clamp(SPEED_RANGE.0, SPEED_RANGE.1));
+        self.seek(position)
+    }
+}
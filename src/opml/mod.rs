@@ -0,0 +1,200 @@
+//! OPML import and export of the subscription list (titles and folder hierarchy).
+//!
+//! Nested `<outline>` elements without an `xmlUrl` are folders; their titles form the
+//! `/`-separated [`Feed::folder`] path of the feeds they contain. Feed tags travel in the
+//! comma-separated `category` attribute.
+
+use crate::feed::Feed;
+use crate::{Error, SubscriptionList};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::BTreeMap;
+
+/// Outcome of [`import_opml`].
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    /// Feeds newly added to the subscription list.
+    pub added: usize,
+    /// Feeds skipped because they were already subscribed.
+    pub skipped: usize,
+}
+
+/// Parse an OPML document into feeds (url, title, folder, tags). Items are not fetched.
+pub fn parse_opml(xml: &str) -> Result<Vec<Feed>, Error> {
+    parse_outlines(xml).map(|(feeds, _)| feeds)
+}
+
+/// Feeds and every folder path (including empty folders) of an OPML document.
+fn parse_outlines(xml: &str) -> Result<(Vec<Feed>, Vec<String>), Error> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    // One entry per open <outline>: Some(title) for folders, None for feeds.
+    let mut stack: Vec<Option<String>> = Vec::new();
+    let mut feeds = Vec::new();
+    let mut folders = Vec::new();
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| Error::Parse(format!("OPML: {}", e)))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) if e.name().as_ref() == b"outline" => {
+                let attrs = outline_attributes(e, &reader)?;
+                let is_empty = matches!(event, Event::Empty(_));
+                let title = attrs.get("title").or_else(|| attrs.get("text")).cloned();
+                if let Some(url) = attrs.get("xmlurl") {
+                    let folder: Vec<&str> = stack.iter().flatten().map(|s| s.as_str()).collect();
+                    feeds.push(Feed {
+                        url: url.clone(),
+                        title,
+                        description: attrs.get("description").cloned(),
+                        folder: (!folder.is_empty()).then(|| folder.join("/")),
+                        tags: attrs
+                            .get("category")
+                            .map(|c| parse_tags(c))
+                            .unwrap_or_default(),
+                        ..Default::default()
+                    });
+                    if !is_empty {
+                        stack.push(None);
+                    }
+                } else if !is_empty {
+                    stack.push(Some(title.unwrap_or_default()));
+                    let path: Vec<&str> = stack.iter().flatten().map(|s| s.as_str()).collect();
+                    folders.push(path.join("/"));
+                }
+            }
+            Event::End(ref e) if e.name().as_ref() == b"outline" => {
+                stack.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok((feeds, folders))
+}
+
+/// Tags from a `category` attribute: comma-separated, leading `/` dropped.
+fn parse_tags(category: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in category
+        .split(',')
+        .map(|t| t.trim().trim_start_matches('/'))
+    {
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Attributes of an `<outline>`, keyed by lower-cased name (OPML files disagree on case).
+fn outline_attributes(
+    e: &BytesStart<'_>,
+    reader: &Reader<&[u8]>,
+) -> Result<BTreeMap<String, String>, Error> {
+    let mut out = BTreeMap::new();
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| Error::Parse(format!("OPML: {}", e)))?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_lowercase();
+        let value = attr
+            .decode_and_unescape_value(reader)
+            .map_err(|e| Error::Parse(format!("OPML: {}", e)))?;
+        out.insert(key, value.into_owned());
+    }
+    Ok(out)
+}
+
+/// Add every feed from an OPML document that is not already subscribed; empty folders are
+/// created too.
+pub fn import_opml(store: &mut SubscriptionList, xml: &str) -> Result<ImportReport, Error> {
+    let mut report = ImportReport::default();
+    let (feeds, folders) = parse_outlines(xml)?;
+    for feed in feeds {
+        if store.subscribe(feed) {
+            report.added += 1;
+        } else {
+            report.skipped += 1;
+        }
+    }
+    for folder in folders {
+        // Folders with feeds already exist; untitled folders are not importable.
+        let _ = store.create_folder(&folder);
+    }
+    Ok(report)
+}
+
+/// Folder tree used while exporting; BTreeMap keeps folders sorted by name.
+#[derive(Default)]
+struct FolderNode<'a> {
+    feeds: Vec<&'a Feed>,
+    children: BTreeMap<String, FolderNode<'a>>,
+}
+
+/// Serialize the subscription list as OPML 2.0, nesting feeds under their folders.
+pub fn export_opml(store: &SubscriptionList) -> String {
+    let mut root = FolderNode::default();
+    for path in store.folders() {
+        let mut node = &mut root;
+        for segment in path.split('/') {
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+    }
+    for feed in &store.feeds {
+        let mut node = &mut root;
+        for segment in feed.folder.iter().flat_map(|f| f.split('/')) {
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+        node.feeds.push(feed);
+    }
+    let mut w = Writer::new_with_indent(Vec::new(), b' ', 2);
+    // Writing into a Vec cannot fail.
+    write_document(&mut w, &root).expect("OPML write to memory");
+    String::from_utf8(w.into_inner()).expect("OPML output is UTF-8")
+}
+
+fn write_document(w: &mut Writer<Vec<u8>>, root: &FolderNode<'_>) -> quick_xml::Result<()> {
+    w.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    w.write_event(Event::Start(
+        BytesStart::new("opml").with_attributes([("version", "2.0")]),
+    ))?;
+    w.write_event(Event::Start(BytesStart::new("head")))?;
+    w.write_event(Event::Start(BytesStart::new("title")))?;
+    w.write_event(Event::Text(BytesText::new("rss-reader subscriptions")))?;
+    w.write_event(Event::End(BytesEnd::new("title")))?;
+    w.write_event(Event::End(BytesEnd::new("head")))?;
+    w.write_event(Event::Start(BytesStart::new("body")))?;
+    write_folder(w, root)?;
+    w.write_event(Event::End(BytesEnd::new("body")))?;
+    w.write_event(Event::End(BytesEnd::new("opml")))?;
+    Ok(())
+}
+
+fn write_folder(w: &mut Writer<Vec<u8>>, folder: &FolderNode<'_>) -> quick_xml::Result<()> {
+    for (name, child) in &folder.children {
+        let start = BytesStart::new("outline")
+            .with_attributes([("text", name.as_str()), ("title", name.as_str())]);
+        w.write_event(Event::Start(start))?;
+        write_folder(w, child)?;
+        w.write_event(Event::End(BytesEnd::new("outline")))?;
+    }
+    for feed in &folder.feeds {
+        let title = feed.title.as_deref().unwrap_or(&feed.url);
+        let mut attrs = vec![
+            ("type", "rss"),
+            ("text", title),
+            ("title", title),
+            ("xmlUrl", feed.url.as_str()),
+        ];
+        if let Some(d) = &feed.description {
+            attrs.push(("description", d.as_str()));
+        }
+        let category = feed.tags.join(",");
+        if !category.is_empty() {
+            attrs.push(("category", category.as_str()));
+        }
+        w.write_event(Event::Empty(
+            BytesStart::new("outline").with_attributes(attrs),
+        ))?;
+    }
+    Ok(())
+}
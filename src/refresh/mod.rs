@@ -0,0 +1,142 @@
+//! Refresh engine shared by CLI and GUI: fetches many feeds at once with a global
+//! concurrency limit and a per-host politeness limit, streaming each result as it finishes.
+
+use crate::feed::Feed;
+use crate::fetch::{fetch_feed_conditional, FetchOutcome, HttpOptions};
+use crate::{Error, SubscriptionList};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+
+/// Limits for a refresh run.
+#[derive(Clone, Debug)]
+pub struct RefreshOptions {
+    /// Maximum number of feeds fetched at the same time.
+    pub concurrency: usize,
+    /// Maximum number of simultaneous requests to one host.
+    pub per_host: usize,
+    pub http: HttpOptions,
+}
+
+impl Default for RefreshOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            per_host: 2,
+            http: HttpOptions::default(),
+        }
+    }
+}
+
+/// Result of refreshing one feed.
+#[derive(Debug)]
+pub struct FeedRefresh {
+    pub url: String,
+    pub result: Result<FetchOutcome, Error>,
+}
+
+impl FeedRefresh {
+    /// Merge this result into the store: new items on update, `last_fetched`, resent
+    /// validators and `max-age` on 304, error, failure count and `Retry-After` on error.
+    pub fn apply(self, store: &mut SubscriptionList) {
+        match self.result {
+            Ok(FetchOutcome::Updated(feed, items)) => store.add_feed(feed, items),
+            Ok(FetchOutcome::NotModified {
+                etag,
+                last_modified,
+                max_age_secs,
+            }) => {
+                store.touch_feed(&self.url);
+                if let Some(f) = store.feeds.iter_mut().find(|f| f.url == self.url) {
+                    f.etag = etag.or(f.etag.take());
+                    f.last_modified = last_modified.or(f.last_modified.take());
+                    f.refresh_hints.max_age_secs = max_age_secs;
+                }
+            }
+            Err(e) => {
+                store.record_failure(&self.url, &e.to_string());
+                let retry_after = match e {
+                    Error::RetryAfter(_, secs) => Some(secs),
+                    _ => None,
+                };
+                if let Some(f) = store.feeds.iter_mut().find(|f| f.url == self.url) {
+                    f.refresh_hints.retry_after_secs = retry_after;
+                }
+            }
+        }
+    }
+}
+
+struct Queue {
+    pending: VecDeque<Feed>,
+    in_flight: HashMap<String, usize>,
+}
+
+fn host_of(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_default()
+}
+
+/// Start refreshing `feeds` on background threads. Results arrive on the returned
+/// channel in completion order; the channel closes once every feed has been attempted.
+pub fn refresh_feeds(feeds: Vec<Feed>, options: &RefreshOptions) -> mpsc::Receiver<FeedRefresh> {
+    let (tx, rx) = mpsc::channel();
+    let workers = options.concurrency.max(1).min(feeds.len());
+    let per_host = options.per_host.max(1);
+    let shared = Arc::new((
+        Mutex::new(Queue {
+            pending: feeds.into(),
+            in_flight: HashMap::new(),
+        }),
+        Condvar::new(),
+    ));
+
+    for _ in 0..workers {
+        let shared = Arc::clone(&shared);
+        let tx = tx.clone();
+        let http = options.http.clone();
+        std::thread::spawn(move || {
+            let (lock, cvar) = &*shared;
+            loop {
+                // Take the first queued feed whose host is below the politeness limit.
+                let (feed, host) = {
+                    let mut q = lock.lock().unwrap();
+                    loop {
+                        if q.pending.is_empty() {
+                            return;
+                        }
+                        let ready = q.pending.iter().position(|f| {
+                            q.in_flight.get(&host_of(&f.url)).copied().unwrap_or(0) < per_host
+                        });
+                        if let Some(pos) = ready {
+                            let feed = q.pending.remove(pos).unwrap();
+                            let host = host_of(&feed.url);
+                            *q.in_flight.entry(host.clone()).or_insert(0) += 1;
+                            break (feed, host);
+                        }
+                        q = cvar.wait(q).unwrap();
+                    }
+                };
+
+                let result = fetch_feed_conditional(&feed, &http);
+
+                {
+                    let mut q = lock.lock().unwrap();
+                    if let Some(n) = q.in_flight.get_mut(&host) {
+                        *n -= 1;
+                    }
+                    cvar.notify_all();
+                }
+                let done = FeedRefresh {
+                    url: feed.url,
+                    result,
+                };
+                if tx.send(done).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    rx
+}
@@ -0,0 +1,310 @@
+//! Filter rules run on new items as [`SubscriptionList::add_feed`] merges them: mute noisy
+//! posts, mark them read, star or tag them, or drop them before they are cached.
+//!
+//! A [`Rule`] matches when all of its [`Condition`]s do. Each condition looks at one item
+//! field (feed, title, content, author, category or enclosure type) and is either a
+//! case-insensitive substring or a regular expression. Rules run in order and every
+//! matching rule's action is applied; `drop` wins over everything else.
+
+use crate::feed::{Feed, FeedItem};
+use crate::store::SubscriptionList;
+use crate::{Error, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Item field a [`Condition`] looks at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleField {
+    /// The feed's url or title.
+    Feed,
+    Title,
+    /// Content or summary (HTML as published).
+    Content,
+    /// Any author name.
+    Author,
+    /// Any category.
+    Category,
+    /// Any enclosure's media type (e.g. `audio/mpeg`).
+    Enclosure,
+}
+
+impl RuleField {
+    pub const ALL: [RuleField; 6] = [
+        RuleField::Feed,
+        RuleField::Title,
+        RuleField::Content,
+        RuleField::Author,
+        RuleField::Category,
+        RuleField::Enclosure,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RuleField::Feed => "feed",
+            RuleField::Title => "title",
+            RuleField::Content => "content",
+            RuleField::Author => "author",
+            RuleField::Category => "category",
+            RuleField::Enclosure => "enclosure",
+        }
+    }
+}
+
+impl FromStr for RuleField {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        RuleField::ALL
+            .into_iter()
+            .find(|f| f.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown rule field {:?} (expected feed, title, content, author, category or enclosure)",
+                    s
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for RuleField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a matching rule does to a new item.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleAction {
+    /// Keep the item but leave it out of item lists and unread counts.
+    Hide,
+    MarkRead,
+    Star,
+    /// Give the item this tag (listed with `--tag` like feed tags).
+    Tag(String),
+    /// Do not cache the item at all.
+    Drop,
+}
+
+impl FromStr for RuleAction {
+    type Err = String;
+
+    /// `hide`, `mark-read`, `star`, `drop` or `tag:<name>`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some((kind, tag)) = s.split_once(':') {
+            if kind.eq_ignore_ascii_case("tag") && !tag.trim().is_empty() {
+                return Ok(RuleAction::Tag(tag.trim().to_string()));
+            }
+        }
+        match s.to_ascii_lowercase().as_str() {
+            "hide" => Ok(RuleAction::Hide),
+            "mark-read" | "read" => Ok(RuleAction::MarkRead),
+            "star" => Ok(RuleAction::Star),
+            "drop" => Ok(RuleAction::Drop),
+            _ => Err(format!(
+                "unknown rule action {:?} (expected hide, mark-read, star, drop or tag:<name>)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for RuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleAction::Hide => f.write_str("hide"),
+            RuleAction::MarkRead => f.write_str("mark-read"),
+            RuleAction::Star => f.write_str("star"),
+            RuleAction::Tag(tag) => write!(f, "tag:{}", tag),
+            RuleAction::Drop => f.write_str("drop"),
+        }
+    }
+}
+
+/// One test on an item field.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub field: RuleField,
+    pub pattern: String,
+    /// Treat `pattern` as a regular expression instead of a case-insensitive substring.
+    #[serde(default)]
+    pub regex: bool,
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.regex {
+            write!(f, "{} =~ /{}/", self.field, self.pattern)
+        } else {
+            write!(f, "{} contains {:?}", self.field, self.pattern)
+        }
+    }
+}
+
+/// Conditions that must all match, and the action to take.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub conditions: Vec<Condition>,
+    pub action: RuleAction,
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let conditions: Vec<String> = self.conditions.iter().map(|c| c.to_string()).collect();
+        write!(f, "{} → {}", conditions.join(" and "), self.action)
+    }
+}
+
+impl Rule {
+    /// Compile the rule, checking that it has conditions, a non-empty tag and valid regexes.
+    pub fn matcher(&self) -> Result<RuleMatcher> {
+        if self.conditions.is_empty() {
+            return Err(Error::InvalidInput(
+                "a rule needs at least one condition".to_string(),
+            ));
+        }
+        if matches!(&self.action, RuleAction::Tag(t) if t.trim().is_empty()) {
+            return Err(Error::InvalidInput("empty tag".to_string()));
+        }
+        let tests = self
+            .conditions
+            .iter()
+            .map(|c| {
+                let pattern = if c.regex {
+                    Pattern::Regex(Regex::new(&c.pattern).map_err(|e| {
+                        Error::InvalidInput(format!("invalid regex {:?}: {}", c.pattern, e))
+                    })?)
+                } else {
+                    Pattern::Substring(c.pattern.to_lowercase())
+                };
+                Ok((c.field, pattern))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RuleMatcher {
+            tests,
+            action: self.action.clone(),
+        })
+    }
+}
+
+enum Pattern {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Pattern::Substring(needle) => text.to_lowercase().contains(needle),
+            Pattern::Regex(re) => re.is_match(text),
+        }
+    }
+}
+
+/// A compiled [`Rule`].
+pub struct RuleMatcher {
+    tests: Vec<(RuleField, Pattern)>,
+    action: RuleAction,
+}
+
+impl RuleMatcher {
+    pub fn action(&self) -> &RuleAction {
+        &self.action
+    }
+
+    /// Whether every condition matches `item` of `feed` (None for an unsubscribed feed).
+    pub fn matches(&self, feed: Option<&Feed>, item: &FeedItem) -> bool {
+        self.tests.iter().all(|(field, pattern)| match field {
+            RuleField::Feed => {
+                pattern.is_match(&item.feed_url)
+                    || feed
+                        .and_then(|f| f.title.as_deref())
+                        .is_some_and(|t| pattern.is_match(t))
+            }
+            RuleField::Title => pattern.is_match(&item.title),
+            RuleField::Content => [&item.content, &item.summary]
+                .into_iter()
+                .flatten()
+                .any(|text| pattern.is_match(text)),
+            RuleField::Author => item.authors.iter().any(|a| pattern.is_match(a)),
+            RuleField::Category => item.categories.iter().any(|c| pattern.is_match(c)),
+            RuleField::Enclosure => item
+                .enclosures
+                .iter()
+                .filter_map(|e| e.media_type.as_deref())
+                .any(|t| pattern.is_match(t)),
+        })
+    }
+}
+
+/// Compile `rules`, skipping any that do not compile (they are checked when added).
+pub(crate) fn compile(rules: &[Rule]) -> Vec<RuleMatcher> {
+    rules.iter().filter_map(|r| r.matcher().ok()).collect()
+}
+
+/// Apply every matching rule to the new `item`. Returns false when a rule drops it.
+pub(crate) fn apply(
+    store: &mut SubscriptionList,
+    rules: &[RuleMatcher],
+    feed: &Feed,
+    item: &FeedItem,
+) -> bool {
+    let actions: Vec<&RuleAction> = rules
+        .iter()
+        .filter(|r| r.matches(Some(feed), item))
+        .map(|r| r.action())
+        .collect();
+    if actions.contains(&&RuleAction::Drop) {
+        return false;
+    }
+    for action in actions {
+        match action {
+            RuleAction::Hide => store.set_hidden_unchecked(&item.id),
+            RuleAction::MarkRead => {
+                store.mark_all_read([item.id.clone()]);
+            }
+            RuleAction::Star => {
+                let read = store.is_read(&item.id);
+                store.set_state_unchecked(&item.id, read, true);
+            }
+            RuleAction::Tag(tag) => store.tag_item_unchecked(&item.id, tag),
+            RuleAction::Drop => {}
+        }
+    }
+    true
+}
+
+impl SubscriptionList {
+    /// Append `rule` (run after the existing ones).
+    pub fn add_rule(&mut self, rule: Rule) -> Result<()> {
+        rule.matcher()?;
+        self.rules.push(rule);
+        self.changes.rules = true;
+        Ok(())
+    }
+
+    /// Remove rule `number` (1-based, as listed) and return it.
+    pub fn remove_rule(&mut self, number: usize) -> Result<Rule> {
+        if number == 0 || number > self.rules.len() {
+            return Err(Error::NotFound(format!("no rule {}", number)));
+        }
+        self.changes.rules = true;
+        Ok(self.rules.remove(number - 1))
+    }
+
+    /// Cached items `rule` matches, newest first (to try a rule before adding it).
+    pub fn test_rule(&self, rule: &Rule) -> Result<Vec<&FeedItem>> {
+        let matcher = rule.matcher()?;
+        Ok(self
+            .items(None)
+            .into_iter()
+            .filter(|i| {
+                let feed = self.feeds.iter().find(|f| f.url == i.feed_url);
+                matcher.matches(feed, i)
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,94 @@
+//! Per-feed refresh schedule shared by the `daemon` command and the GUI's auto-refresh.
+//!
+//! A healthy feed is due one interval after its last fetch. The interval is the longest of its
+//! [`RefreshHints`](crate::feed::RefreshHints) (`<ttl>`, `sy:updatePeriod`,
+//! `Cache-Control: max-age`), or the default when it has none, clamped to the configured
+//! bounds. A failing feed backs off exponentially and never retries before `Retry-After`.
+
+use crate::feed::Feed;
+use crate::SubscriptionList;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Bounds for [`refresh_interval`] and [`next_refresh`].
+#[derive(Clone, Debug)]
+pub struct ScheduleOptions {
+    /// Interval for feeds without hints.
+    pub default_interval: Duration,
+    /// Shortest interval, whatever the hints say.
+    pub min_interval: Duration,
+    /// Longest interval, whatever the hints say.
+    pub max_interval: Duration,
+    /// Upper bound of the failure backoff (a server's `Retry-After` may exceed it).
+    pub max_backoff: Duration,
+}
+
+impl Default for ScheduleOptions {
+    fn default() -> Self {
+        Self {
+            default_interval: Duration::from_secs(30 * 60),
+            min_interval: Duration::from_secs(5 * 60),
+            max_interval: Duration::from_secs(24 * 3600),
+            max_backoff: Duration::from_secs(24 * 3600),
+        }
+    }
+}
+
+/// Interval between refreshes of a healthy `feed`.
+pub fn refresh_interval(feed: &Feed, options: &ScheduleOptions) -> Duration {
+    let hints = &feed.refresh_hints;
+    let hinted = [hints.ttl_secs, hints.update_period_secs, hints.max_age_secs]
+        .into_iter()
+        .flatten()
+        .max()
+        .map(Duration::from_secs);
+    hinted.unwrap_or(options.default_interval).clamp(
+        options.min_interval,
+        options.max_interval.max(options.min_interval),
+    )
+}
+
+/// Delay after the last fetch before `feed` is tried again: the refresh interval, doubled
+/// for every failure after the first (up to `max_backoff`), and at least `Retry-After`.
+pub fn retry_delay(feed: &Feed, options: &ScheduleOptions) -> Duration {
+    let interval = refresh_interval(feed, options);
+    if !feed.is_failing() {
+        return interval;
+    }
+    let doublings = (feed.consecutive_failures - 1).min(16);
+    let backoff = interval
+        .saturating_mul(1 << doublings)
+        .min(options.max_backoff.max(interval));
+    let retry_after = Duration::from_secs(feed.refresh_hints.retry_after_secs.unwrap_or(0));
+    backoff.max(retry_after)
+}
+
+/// When `feed` is next due; None if it was never fetched (due now).
+pub fn next_refresh(feed: &Feed, options: &ScheduleOptions) -> Option<DateTime<Utc>> {
+    let last = feed.last_fetched?;
+    let delay = chrono::Duration::from_std(retry_delay(feed, options))
+        .unwrap_or_else(|_| chrono::Duration::days(365));
+    Some(last + delay)
+}
+
+/// Feeds of `store` due at `now`, in subscription order.
+pub fn due_feeds<'a>(
+    store: &'a SubscriptionList,
+    now: DateTime<Utc>,
+    options: &ScheduleOptions,
+) -> Vec<&'a Feed> {
+    store
+        .feeds
+        .iter()
+        .filter(|f| next_refresh(f, options).map_or(true, |at| at <= now))
+        .collect()
+}
+
+/// Earliest time any feed of `store` is due; None when there are no feeds.
+pub fn next_wake(store: &SubscriptionList, options: &ScheduleOptions) -> Option<DateTime<Utc>> {
+    store
+        .feeds
+        .iter()
+        .map(|f| next_refresh(f, options).unwrap_or(DateTime::<Utc>::MIN_UTC))
+        .min()
+}
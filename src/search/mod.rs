@@ -0,0 +1,466 @@
+//! Full-text search over cached items.
+//!
+//! [`SearchIndex`] is an inverted index of item titles and of summary/content text (HTML
+//! stripped), with term positions so quoted phrases match exactly. Hits are ranked by
+//! tf-idf with title matches weighted higher, and carry a snippet of the body around the
+//! first match with the matched words marked.
+//!
+//! The CLI keeps the index in a file next to the store (see [`SearchIndex::cached`]),
+//! rebuilt only after the store's items change; the GUI and TUI keep it in memory.
+
+mod query;
+
+pub use query::{Clause, Scope, SearchQuery};
+
+use crate::{FeedItem, SubscriptionList};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Title matches count this many times as much as body matches.
+const TITLE_WEIGHT: f64 = 3.0;
+/// Words of context kept before the first match in a snippet.
+const SNIPPET_LEAD: usize = 8;
+/// Maximum words in a snippet.
+const SNIPPET_WORDS: usize = 30;
+/// Hits listed by the GUI and TUI.
+pub const LIST_LIMIT: usize = 200;
+/// Version of the cache file format; files of another version are rebuilt.
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum Field {
+    #[serde(rename = "t")]
+    Title,
+    #[serde(rename = "b")]
+    Body,
+}
+
+/// Occurrences of one term in one field of one document.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Posting {
+    #[serde(rename = "d")]
+    doc: u32,
+    #[serde(rename = "f")]
+    field: Field,
+    #[serde(rename = "p")]
+    positions: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Doc {
+    id: String,
+    feed_url: String,
+    title: String,
+    /// Plain text of summary and content.
+    text: String,
+    published: Option<DateTime<Utc>>,
+}
+
+/// Text of a hit's body around the first match; `highlights` are byte ranges into `text`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snippet {
+    pub text: String,
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    /// The snippet text with every highlight wrapped in `open` / `close`.
+    pub fn render(&self, open: &str, close: &str) -> String {
+        let mut out = String::with_capacity(self.text.len());
+        let mut last = 0;
+        for r in &self.highlights {
+            out.push_str(&self.text[last..r.start]);
+            out.push_str(open);
+            out.push_str(&self.text[r.clone()]);
+            out.push_str(close);
+            last = r.end;
+        }
+        out.push_str(&self.text[last..]);
+        out
+    }
+}
+
+/// One search result.
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+    pub item_id: String,
+    pub feed_url: String,
+    pub title: String,
+    pub published: Option<DateTime<Utc>>,
+    pub score: f64,
+    pub snippet: Snippet,
+}
+
+/// Inverted index over the items of a [`SubscriptionList`]. It is a snapshot: rebuild it
+/// after the store's items change.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    docs: Vec<Doc>,
+    /// Postings per term, ordered by (doc, field).
+    postings: HashMap<String, Vec<Posting>>,
+    /// Lower-cased "title url" of each feed, for `feed:` clauses.
+    feeds: HashMap<String, String>,
+}
+
+impl SearchIndex {
+    /// Index every cached item of `store`.
+    pub fn build(store: &SubscriptionList) -> Self {
+        let mut index = Self {
+            feeds: store
+                .feeds
+                .iter()
+                .map(|f| {
+                    let name = format!("{} {}", f.title.as_deref().unwrap_or(""), f.url);
+                    (f.url.clone(), name.to_lowercase())
+                })
+                .collect(),
+            ..Self::default()
+        };
+        for item in store.items(None) {
+            let doc = index.docs.len() as u32;
+            let text = [item.summary.as_deref(), item.content.as_deref()]
+                .into_iter()
+                .flatten()
+                .map(plain_text)
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            index.add_field(doc, Field::Title, &item.title);
+            index.add_field(doc, Field::Body, &text);
+            index.docs.push(Doc {
+                id: item.id.clone(),
+                feed_url: item.feed_url.clone(),
+                title: item.title.clone(),
+                text,
+                published: item.published,
+            });
+        }
+        index
+    }
+
+    /// The index of `store`, read from the cache file at `path` if it was built from the
+    /// same items, else built and written there. Writing is best effort: a cache that
+    /// cannot be written only means the next search builds the index again.
+    pub fn cached(store: &SubscriptionList, path: &Path) -> Self {
+        let stamp = cache_stamp(store);
+        if let Some(index) = read_cache(path, &stamp) {
+            return index;
+        }
+        let index = Self::build(store);
+        if let Ok(body) = serde_json::to_string(&index) {
+            let contents = format!("{}\n{}", stamp, body);
+            let _ = crate::store::write_atomic(path, contents.as_bytes());
+        }
+        index
+    }
+
+    fn add_field(&mut self, doc: u32, field: Field, text: &str) {
+        let mut positions: HashMap<String, Vec<u32>> = HashMap::new();
+        for (pos, (_, term)) in terms(text).enumerate() {
+            positions.entry(term).or_default().push(pos as u32);
+        }
+        for (term, positions) in positions {
+            self.postings.entry(term).or_default().push(Posting {
+                doc,
+                field,
+                positions,
+            });
+        }
+    }
+
+    /// Number of indexed items.
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    /// Items matching every clause of `query`, best first (newest first on equal score).
+    /// At most `limit` hits are returned.
+    pub fn search(&self, query: &SearchQuery, limit: usize) -> Vec<SearchHit> {
+        self.search_where(query, limit, |_| true)
+    }
+
+    /// Like [`search`](Self::search), but only items whose id passes `keep` are ranked, so
+    /// the limit applies after filtering.
+    pub fn search_where(
+        &self,
+        query: &SearchQuery,
+        limit: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Vec<SearchHit> {
+        let mut scores: Option<HashMap<u32, f64>> = None;
+        for clause in &query.clauses {
+            let Clause::Text { scope, terms } = clause else {
+                continue;
+            };
+            let matches = self.match_text(*scope, terms);
+            let idf = (1.0 + self.docs.len() as f64 / matches.len().max(1) as f64).ln();
+            scores = Some(match scores {
+                None => matches
+                    .into_iter()
+                    .map(|(doc, tf)| (doc, idf * tf_weight(tf)))
+                    .collect(),
+                Some(prev) => prev
+                    .into_iter()
+                    .filter_map(|(doc, s)| {
+                        matches.get(&doc).map(|tf| (doc, s + idf * tf_weight(*tf)))
+                    })
+                    .collect(),
+            });
+        }
+        let feed_filters: Vec<&str> = query
+            .clauses
+            .iter()
+            .filter_map(|c| match c {
+                Clause::Feed(f) => Some(f.as_str()),
+                Clause::Text { .. } => None,
+            })
+            .collect();
+        if scores.is_none() && feed_filters.is_empty() {
+            return Vec::new();
+        }
+        let scores =
+            scores.unwrap_or_else(|| (0..self.docs.len() as u32).map(|d| (d, 0.0)).collect());
+
+        let mut ranked: Vec<(u32, f64)> = scores
+            .into_iter()
+            .filter(|(doc, _)| keep(&self.docs[*doc as usize].id))
+            .filter(|(doc, _)| {
+                if feed_filters.is_empty() {
+                    return true;
+                }
+                let url = &self.docs[*doc as usize].feed_url;
+                let name = self
+                    .feeds
+                    .get(url)
+                    .cloned()
+                    .unwrap_or_else(|| url.to_lowercase());
+                feed_filters.iter().all(|f| name.contains(f))
+            })
+            .collect();
+        ranked.sort_by(|(a, sa), (b, sb)| {
+            sb.total_cmp(sa).then_with(|| {
+                self.docs[*b as usize]
+                    .published
+                    .cmp(&self.docs[*a as usize].published)
+            })
+        });
+        ranked.truncate(limit);
+
+        let highlight: HashSet<&str> = query.terms().collect();
+        ranked
+            .into_iter()
+            .map(|(doc, score)| {
+                let d = &self.docs[doc as usize];
+                // Items without body text get their title as snippet.
+                let source = if d.text.is_empty() { &d.title } else { &d.text };
+                let snip = snippet(source, &highlight);
+                SearchHit {
+                    item_id: d.id.clone(),
+                    feed_url: d.feed_url.clone(),
+                    title: d.title.clone(),
+                    published: d.published,
+                    score,
+                    snippet: snip,
+                }
+            })
+            .collect()
+    }
+
+    /// Documents where `terms` occur consecutively within `scope`, with the number of
+    /// occurrences per field as (title, body).
+    fn match_text(&self, scope: Scope, terms: &[String]) -> HashMap<u32, (u32, u32)> {
+        let mut out: HashMap<u32, (u32, u32)> = HashMap::new();
+        let Some(first) = self.postings.get(&terms[0]) else {
+            return out;
+        };
+        let rest: Option<Vec<&Vec<Posting>>> =
+            terms[1..].iter().map(|t| self.postings.get(t)).collect();
+        let Some(rest) = rest else {
+            return out;
+        };
+        for p in first {
+            if scope == Scope::Title && p.field != Field::Title {
+                continue;
+            }
+            let others: Option<Vec<&Posting>> = rest
+                .iter()
+                .map(|list| {
+                    list.binary_search_by_key(&(p.doc, p.field), |q| (q.doc, q.field))
+                        .ok()
+                        .map(|i| &list[i])
+                })
+                .collect();
+            let Some(others) = others else {
+                continue;
+            };
+            let count = p
+                .positions
+                .iter()
+                .filter(|&&pos| {
+                    others
+                        .iter()
+                        .enumerate()
+                        .all(|(k, q)| q.positions.binary_search(&(pos + k as u32 + 1)).is_ok())
+                })
+                .count() as u32;
+            if count > 0 {
+                let entry = out.entry(p.doc).or_default();
+                match p.field {
+                    Field::Title => entry.0 += count,
+                    Field::Body => entry.1 += count,
+                }
+            }
+        }
+        out
+    }
+}
+
+/// The search index of a store that is being edited, as the GUI and TUI hold it. Keyed on
+/// the store's items revision, so it is rebuilt once after a save changed the items.
+#[derive(Default)]
+pub struct LiveIndex {
+    index: Option<(u64, SearchIndex)>,
+}
+
+impl LiveIndex {
+    /// The index of `store`, rebuilt if its items changed since.
+    pub fn get(&mut self, store: &SubscriptionList) -> &SearchIndex {
+        let revision = store.items_revision;
+        if self.index.as_ref().map(|(r, _)| *r) != Some(revision) {
+            self.index = Some((revision, SearchIndex::build(store)));
+        }
+        &self.index.as_ref().expect("index was just built").1
+    }
+}
+
+/// Hits of `query` among `items` (a selection after its filters), best first, at most
+/// `limit`, each with its item. Shared by the GUI and TUI article lists.
+pub fn search_items<'a>(
+    index: &SearchIndex,
+    query: &SearchQuery,
+    items: &[&'a FeedItem],
+    limit: usize,
+) -> Vec<(&'a FeedItem, SearchHit)> {
+    let by_id: HashMap<&str, &'a FeedItem> = items.iter().map(|i| (i.id.as_str(), *i)).collect();
+    index
+        .search_where(query, limit, |id| by_id.contains_key(id))
+        .into_iter()
+        .filter_map(|h| by_id.get(h.item_id.as_str()).map(|i| (*i, h)))
+        .collect()
+}
+
+/// Where the CLI caches the search index of the store at `store_path`.
+pub fn index_path(store_path: &Path) -> PathBuf {
+    store_path.with_extension("search-index")
+}
+
+/// First line of a cache file: what the index was built from.
+fn cache_stamp(store: &SubscriptionList) -> String {
+    let items: usize = store.items_by_feed.values().map(Vec::len).sum();
+    serde_json::json!({
+        "version": CACHE_VERSION,
+        "items_revision": store.items_revision,
+        "items": items,
+    })
+    .to_string()
+}
+
+/// The index cached at `path`, if it was built with `stamp`.
+fn read_cache(path: &Path, stamp: &str) -> Option<SearchIndex> {
+    let mut reader = BufReader::new(std::fs::File::open(path).ok()?);
+    let mut first = String::new();
+    reader.read_line(&mut first).ok()?;
+    if first.trim_end() != stamp {
+        return None;
+    }
+    serde_json::from_reader(reader).ok()
+}
+
+/// Score contribution of (title, body) occurrence counts, log-dampened.
+fn tf_weight((title, body): (u32, u32)) -> f64 {
+    let damp = |n: u32| if n == 0 { 0.0 } else { 1.0 + (n as f64).ln() };
+    TITLE_WEIGHT * damp(title) + damp(body)
+}
+
+/// Lower-cased alphanumeric words of `text` with their byte ranges.
+pub(crate) fn terms(text: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while chars.next_if(|(_, c)| !c.is_alphanumeric()).is_some() {}
+        let (start, _) = *chars.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = chars.next_if(|(_, c)| c.is_alphanumeric()) {
+            end = i + c.len_utf8();
+        }
+        Some((start..end, text[start..end].to_lowercase()))
+    })
+}
+
+/// Strip tags and common entities from HTML and collapse whitespace.
+fn plain_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                out.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    let out = out
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Up to [`SNIPPET_WORDS`] words of `text` starting a little before the first highlighted
+/// term, with "…" marking cut-off text.
+fn snippet(text: &str, highlight: &HashSet<&str>) -> Snippet {
+    let words: Vec<(Range<usize>, String)> = terms(text).collect();
+    if words.is_empty() {
+        return Snippet::default();
+    }
+    let first = words
+        .iter()
+        .position(|(_, t)| highlight.contains(t.as_str()))
+        .unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_LEAD);
+    let end = (start + SNIPPET_WORDS).min(words.len());
+    let from = if start == 0 { 0 } else { words[start].0.start };
+    let to = if end == words.len() {
+        text.len()
+    } else {
+        words[end - 1].0.end
+    };
+    let mut out = Snippet::default();
+    if from > 0 {
+        out.text.push('…');
+    }
+    let offset = out.text.len();
+    out.text.push_str(&text[from..to]);
+    out.highlights = words[start..end]
+        .iter()
+        .filter(|(_, t)| highlight.contains(t.as_str()))
+        .map(|(r, _)| r.start - from + offset..r.end - from + offset)
+        .collect();
+    if to < text.len() {
+        out.text.push('…');
+    }
+    out
+}
@@ -0,0 +1,93 @@
+//! Search query syntax: bare words, `"quoted phrases"`, and `title:` / `feed:` qualifiers,
+//! e.g. `title:"async rust" feed:lwn tokio`. Every clause must match.
+
+use super::terms;
+
+/// Which fields a text clause is matched against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Title, summary or content.
+    Any,
+    /// Title only (`title:`).
+    Title,
+}
+
+/// One condition of a [`SearchQuery`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Clause {
+    /// These terms, consecutively (a phrase if more than one), within `scope`.
+    Text { scope: Scope, terms: Vec<String> },
+    /// The item's feed title or url contains this text, case-insensitively (`feed:`).
+    Feed(String),
+}
+
+/// A parsed search query.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub clauses: Vec<Clause>,
+}
+
+impl SearchQuery {
+    /// Parse `input`. Unknown qualifiers are searched as plain text; an unterminated quote
+    /// runs to the end of the input.
+    pub fn parse(input: &str) -> Self {
+        let mut clauses = Vec::new();
+        let mut rest = input.trim_start();
+        while !rest.is_empty() {
+            let (qualifier, after) = match rest.split_once(':') {
+                Some((q, after)) if !q.is_empty() && q.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    match q.to_ascii_lowercase().as_str() {
+                        "title" => (Some(Scope::Title), after),
+                        "feed" => (None, after),
+                        _ => (Some(Scope::Any), rest),
+                    }
+                }
+                _ => (Some(Scope::Any), rest),
+            };
+            let (value, next) = take_value(after);
+            match qualifier {
+                Some(scope) => {
+                    let terms: Vec<String> = terms(value).map(|(_, t)| t).collect();
+                    if !terms.is_empty() {
+                        clauses.push(Clause::Text { scope, terms });
+                    }
+                }
+                None => {
+                    let value = value.trim().to_lowercase();
+                    if !value.is_empty() {
+                        clauses.push(Clause::Feed(value));
+                    }
+                }
+            }
+            rest = next.trim_start();
+        }
+        Self { clauses }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// All terms of the text clauses (used for highlighting).
+    pub fn terms(&self) -> impl Iterator<Item = &str> {
+        self.clauses
+            .iter()
+            .flat_map(|c| match c {
+                Clause::Text { terms, .. } => terms.as_slice(),
+                Clause::Feed(_) => &[],
+            })
+            .map(|t| t.as_str())
+    }
+}
+
+/// Split off one value: a quoted phrase or everything up to the next whitespace.
+fn take_value(s: &str) -> (&str, &str) {
+    if let Some(quoted) = s.strip_prefix('"') {
+        return match quoted.find('"') {
+            Some(end) => (&quoted[..end], &quoted[end + 1..]),
+            None => (quoted, ""),
+        };
+    }
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    s.split_at(end)
+}
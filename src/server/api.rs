@@ -0,0 +1,189 @@
+//! Routes of the JSON API. Bodies use the same shapes as the CLI's `-o json` output.
+//!
+//! | Method   | Path                          | Action                                       |
+//! |----------|-------------------------------|----------------------------------------------|
+//! | GET      | `/api/feeds`                  | `list-feeds`                                 |
+//! | POST     | `/api/feeds`                  | `add` (`{"url": .., "all": false}`)          |
+//! | DELETE   | `/api/feeds?url=..`           | `remove`                                     |
+//! | GET      | `/api/items?unread&feed=..`   | `list-items` (same filters as its options)   |
+//! | GET      | `/api/items/{id}`             | `show`                                       |
+//! | PUT      | `/api/items/{id}/read`        | `mark-read` (DELETE: `mark-unread`)          |
+//! | PUT      | `/api/items/{id}/starred`     | `star` (DELETE: `unstar`)                    |
+//! | POST     | `/api/refresh`                | `refresh` (`{"feed"/"folder"/"tag": ..}`)    |
+//!
+//! Item ids in paths are percent-encoded (ids are often urls).
+
+use super::{Request, Response};
+use crate::cli::{add, list_feeds, list_items, refresh, show};
+use crate::fetch::HttpOptions;
+use crate::refresh::RefreshOptions;
+use crate::store::{parse_date_bound, ItemQuery, Storage};
+use crate::{Error, Result};
+use percent_encoding::percent_decode_str;
+
+pub(super) fn handle(request: &Request, storage: &dyn Storage, http: &HttpOptions) -> Response {
+    route(request, storage, http).unwrap_or_else(Response::from)
+}
+
+fn route(request: &Request, storage: &dyn Storage, http: &HttpOptions) -> Result<Response> {
+    let segments: Vec<String> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .map(|s| percent_decode_str(s).decode_utf8_lossy().into_owned())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["api", "feeds"]) => Ok(Response::ok(list_feeds::feeds_json(&storage.load()?))),
+        ("POST", ["api", "feeds"]) => add_feed(request, storage, http),
+        ("DELETE", ["api", "feeds"]) => remove_feed(request, storage),
+        ("GET", ["api", "items"]) => {
+            let store = storage.load()?;
+            let items = item_query(request)?.run(&store);
+            Ok(Response::ok(list_items::items_json(&store, &items)))
+        }
+        ("GET", ["api", "items", id]) => {
+            let item = storage
+                .get_item(id)?
+                .ok_or_else(|| Error::NotFound(format!("item not found: {}", id)))?;
+            Ok(Response::ok(show::item_json(&item)))
+        }
+        (method @ ("PUT" | "DELETE"), ["api", "items", id, state @ ("read" | "starred")]) => {
+            let on = method == "PUT";
+            let mut store = storage.load()?;
+            let found = if *state == "read" {
+                store.set_read(id, on)
+            } else {
+                store.set_starred(id, on)
+            };
+            if !found {
+                return Err(Error::NotFound(format!("item not found: {}", id)));
+            }
+            storage.save(&mut store)?;
+            Ok(Response::ok(
+                serde_json::json!({ "success": true, "updated_count": 1 }),
+            ))
+        }
+        ("POST", ["api", "refresh"]) => {
+            let body = json_body(request)?;
+            let text = |name: &str| body[name].as_str().map(str::to_string);
+            let selection = ItemQuery {
+                feed: text("feed"),
+                folder: text("folder"),
+                tag: text("tag"),
+                ..ItemQuery::default()
+            };
+            let mut store = storage.load()?;
+            let report = refresh::refresh_selected(
+                &mut store,
+                &selection,
+                &RefreshOptions {
+                    http: http.clone(),
+                    ..RefreshOptions::default()
+                },
+                |_, _, _| {},
+            );
+            storage.save(&mut store)?;
+            Ok(Response::ok(report.json))
+        }
+        (_, ["api", "feeds"] | ["api", "items", ..] | ["api", "refresh"]) => {
+            Ok(Response::error(405, "method not allowed"))
+        }
+        _ => Ok(Response::error(404, "no such endpoint")),
+    }
+}
+
+fn add_feed(request: &Request, storage: &dyn Storage, http: &HttpOptions) -> Result<Response> {
+    let body = json_body(request)?;
+    let url = body["url"]
+        .as_str()
+        .ok_or_else(|| Error::InvalidInput("missing \"url\"".to_string()))?;
+    let all = body["all"].as_bool().unwrap_or(false);
+    let mut store = storage.load()?;
+    match add::add(&mut store, url, all, http)? {
+        add::Added::Feeds(urls) => {
+            storage.save(&mut store)?;
+            Ok(Response::json(201, add::added_json(&urls)))
+        }
+        add::Added::Candidates(candidates) => {
+            Ok(Response::ok(add::candidates_json(url.trim(), &candidates)))
+        }
+    }
+}
+
+fn remove_feed(request: &Request, storage: &dyn Storage) -> Result<Response> {
+    let url = match request.param("url") {
+        Some(url) => url.to_string(),
+        None => json_body(request)?["url"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| Error::InvalidInput("missing url".to_string()))?,
+    };
+    let mut store = storage.load()?;
+    if !store.remove_feed(&url) {
+        return Err(Error::NotFound(format!("feed not found: {}", url)));
+    }
+    storage.save(&mut store)?;
+    Ok(Response::ok(serde_json::json!({
+        "success": true,
+        "message": format!("Removed feed: {}", url),
+    })))
+}
+
+/// Request body as JSON; an empty body is an empty object.
+fn json_body(request: &Request) -> Result<serde_json::Value> {
+    if request.body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(serde_json::json!({}));
+    }
+    serde_json::from_slice(&request.body)
+        .map_err(|e| Error::InvalidInput(format!("invalid JSON body: {}", e)))
+}
+
+/// [`ItemQuery`] from `list-items`-style query parameters; flags are true when present
+/// (unless `false` or `0`).
+fn item_query(request: &Request) -> Result<ItemQuery> {
+    let text = |name: &str| request.param(name).map(str::to_string);
+    let flag = |name: &str| {
+        request
+            .param(name)
+            .is_some_and(|v| v != "0" && !v.eq_ignore_ascii_case("false"))
+    };
+    let number = |name: &str| {
+        request
+            .param(name)
+            .map(|v| {
+                v.parse::<usize>()
+                    .map_err(|_| Error::InvalidInput(format!("invalid {}: {}", name, v)))
+            })
+            .transpose()
+    };
+    Ok(ItemQuery {
+        feed: text("feed"),
+        folder: text("folder"),
+        tag: text("tag"),
+        unread: flag("unread"),
+        starred: flag("starred"),
+        since: request
+            .param("since")
+            .map(|s| parse_date_bound(s, false))
+            .transpose()?,
+        until: request
+            .param("until")
+            .map(|s| parse_date_bound(s, true))
+            .transpose()?,
+        has_enclosure: flag("has_enclosure"),
+        author: text("author"),
+        category: text("category"),
+        hidden: flag("hidden"),
+        sort: request
+            .param("sort")
+            .map(str::parse)
+            .transpose()
+            .map_err(Error::InvalidInput)?
+            .unwrap_or_default(),
+        reverse: flag("reverse"),
+        offset: number("offset")?.unwrap_or(0),
+        limit: number("limit")?,
+    })
+}
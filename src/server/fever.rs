@@ -0,0 +1,256 @@
+//! The Fever API (`/fever/?api`), spoken by Reeder, FeedMe, Unread and other mobile apps.
+//!
+//! Clients POST `api_key` (hex MD5 of `user:password`) and name what they want in the query
+//! string: `groups`, `feeds`, `favicons`, `links`, `items` (with `since_id`, `max_id` or
+//! `with_ids`), `unread_item_ids` and `saved_item_ids`; `mark=item|feed|group` with `as`,
+//! `id` and `before` changes read/saved state. Groups are folders and "saved" is starred.
+//! Every answer is one JSON object that includes `api_version` and `auth`.
+
+use super::ids::{feed_id, group_id, items_by_id, items_in_id_order};
+use super::{secure_eq, Config, Request, Response};
+use crate::feed::FeedItem;
+use crate::store::Storage;
+use crate::{Error, Result, SubscriptionList};
+use serde_json::{json, Map, Value};
+
+/// Items returned per `items` request, as in the reference implementation.
+const PAGE: usize = 50;
+
+pub(super) fn handle(request: &Request, config: &Config) -> Response {
+    let mut out = Map::new();
+    out.insert("api_version".into(), json!(3));
+    let authed = match (&config.login, request.param("api_key")) {
+        (Some(login), Some(key)) => secure_eq(&key.to_ascii_lowercase(), &login.fever_key()),
+        _ => false,
+    };
+    out.insert("auth".into(), json!(u8::from(authed)));
+    if authed {
+        if let Err(e) = respond(request, config, &mut out) {
+            return Response::from(e);
+        }
+    }
+    Response::ok(Value::Object(out))
+}
+
+fn respond(request: &Request, config: &Config, out: &mut Map<String, Value>) -> Result<()> {
+    let storage: &dyn Storage = &config.storage;
+    let mut store = storage.load()?;
+    if let Some(kind) = request.param("mark") {
+        mark(request, kind, &mut store)?;
+        storage.save(&mut store)?;
+    }
+
+    let last_refreshed = store
+        .feeds
+        .iter()
+        .filter_map(|f| f.last_success)
+        .max()
+        .map_or(0, |d| d.timestamp());
+    out.insert("last_refreshed_on_time".into(), json!(last_refreshed));
+
+    let wants = |name: &str| request.param(name).is_some();
+    if wants("groups") {
+        let groups: Vec<Value> = store
+            .folders()
+            .iter()
+            .map(|path| json!({ "id": group_id(path), "title": path }))
+            .collect();
+        out.insert("groups".into(), Value::Array(groups));
+    }
+    if wants("feeds") {
+        let feeds: Vec<Value> = store
+            .feeds
+            .iter()
+            .map(|f| {
+                json!({
+                    "id": feed_id(&f.url),
+                    "favicon_id": 0,
+                    "title": f.title.as_deref().unwrap_or(&f.url),
+                    "url": f.url,
+                    "site_url": f.site_url.as_deref().unwrap_or(&f.url),
+                    "is_spark": 0,
+                    "last_updated_on_time": f.last_success.map_or(0, |d| d.timestamp()),
+                })
+            })
+            .collect();
+        out.insert("feeds".into(), Value::Array(feeds));
+    }
+    if wants("groups") || wants("feeds") {
+        out.insert("feeds_groups".into(), feeds_groups(&store));
+    }
+    if wants("favicons") {
+        out.insert("favicons".into(), json!([]));
+    }
+    if wants("links") {
+        out.insert("links".into(), json!([]));
+    }
+    if wants("items") {
+        let items: Vec<Value> = select_items(request, &store)?
+            .into_iter()
+            .map(|(id, item)| item_json(id, item, &store))
+            .collect();
+        out.insert("items".into(), Value::Array(items));
+        out.insert("total_items".into(), json!(store.items(None).len()));
+    }
+    if wants("unread_item_ids") {
+        let ids = id_list(&store, |i| !store.is_read(&i.id));
+        out.insert("unread_item_ids".into(), Value::String(ids));
+    }
+    if wants("saved_item_ids") {
+        let ids = id_list(&store, |i| store.is_starred(&i.id));
+        out.insert("saved_item_ids".into(), Value::String(ids));
+    }
+    Ok(())
+}
+
+/// Which feeds are in each folder: `[{"group_id": .., "feed_ids": "1,2"}]`.
+fn feeds_groups(store: &SubscriptionList) -> Value {
+    let groups: Vec<Value> = store
+        .folders()
+        .iter()
+        .filter_map(|path| {
+            let feeds = store.feeds_in(Some(path));
+            (!feeds.is_empty()).then(|| {
+                let ids: Vec<String> = feeds.iter().map(|f| feed_id(&f.url).to_string()).collect();
+                json!({ "group_id": group_id(path), "feed_ids": ids.join(",") })
+            })
+        })
+        .collect();
+    Value::Array(groups)
+}
+
+/// Comma-separated ids of the items matching `keep`.
+fn id_list(store: &SubscriptionList, keep: impl Fn(&FeedItem) -> bool) -> String {
+    let ids: Vec<String> = items_in_id_order(store)
+        .into_iter()
+        .filter(|(_, i)| keep(i))
+        .map(|(id, _)| id.to_string())
+        .collect();
+    ids.join(",")
+}
+
+/// Up to [`PAGE`] items: those listed in `with_ids`, the next ones after `since_id`, the
+/// previous ones before `max_id`, or else the oldest.
+fn select_items<'a>(
+    request: &Request,
+    store: &'a SubscriptionList,
+) -> Result<Vec<(u64, &'a FeedItem)>> {
+    let number = |name: &str| {
+        request
+            .param(name)
+            .filter(|v| !v.is_empty())
+            .map(|v| parse_id(name, v))
+            .transpose()
+    };
+    if let Some(list) = request.param("with_ids") {
+        let by_id = items_by_id(store);
+        let mut items = Vec::new();
+        for v in list.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            let id = parse_id("with_ids", v)?;
+            if let Some(item) = by_id.get(&id) {
+                items.push((id, *item));
+            }
+        }
+        items.truncate(PAGE);
+        return Ok(items);
+    }
+    let all = items_in_id_order(store);
+    Ok(match (number("since_id")?, number("max_id")?) {
+        (Some(since), _) => all
+            .into_iter()
+            .filter(|(id, _)| *id > since)
+            .take(PAGE)
+            .collect(),
+        (None, Some(max)) => all
+            .into_iter()
+            .rev()
+            .filter(|(id, _)| *id < max)
+            .take(PAGE)
+            .collect(),
+        (None, None) => all.into_iter().take(PAGE).collect(),
+    })
+}
+
+fn item_json(id: u64, item: &FeedItem, store: &SubscriptionList) -> Value {
+    json!({
+        "id": id,
+        "feed_id": feed_id(&item.feed_url),
+        "title": item.title,
+        "author": "",
+        "html": item.content.as_deref().or(item.summary.as_deref()).unwrap_or(""),
+        "url": item.link.as_deref().unwrap_or(""),
+        "is_saved": u8::from(store.is_starred(&item.id)),
+        "is_read": u8::from(store.is_read(&item.id)),
+        "created_on_time": item.published.map_or(0, |d| d.timestamp()),
+    })
+}
+
+fn parse_id(name: &str, value: &str) -> Result<u64> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::InvalidInput(format!("invalid {}: {}", name, value)))
+}
+
+/// `mark=item` (`as=read|unread|saved|unsaved`) or `mark=feed|group` (`as=read`, items
+/// published up to `before`; group 0 is every feed).
+fn mark(request: &Request, kind: &str, store: &mut SubscriptionList) -> Result<()> {
+    let action = request.param("as").unwrap_or("");
+    let id = parse_id("id", request.param("id").unwrap_or(""))?;
+    match (kind, action) {
+        ("item", "read" | "unread" | "saved" | "unsaved") => {
+            let item_id = items_by_id(store)
+                .get(&id)
+                .map(|i| i.id.clone())
+                .ok_or_else(|| Error::NotFound(format!("item not found: {}", id)))?;
+            match action {
+                "read" | "unread" => store.set_read(&item_id, action == "read"),
+                _ => store.set_starred(&item_id, action == "saved"),
+            };
+            Ok(())
+        }
+        ("feed" | "group", "read") => {
+            let before = request
+                .param("before")
+                .map(|v| parse_id("before", v))
+                .transpose()?
+                .map_or(i64::MAX, |b| b as i64);
+            let urls: Vec<&str> = match kind {
+                "group" if id == 0 => store.feeds.iter().map(|f| f.url.as_str()).collect(),
+                "group" => {
+                    let path = store
+                        .folders()
+                        .into_iter()
+                        .find(|p| group_id(p) == id)
+                        .ok_or_else(|| Error::NotFound(format!("group not found: {}", id)))?;
+                    store
+                        .feeds_in(Some(&path))
+                        .into_iter()
+                        .map(|f| f.url.as_str())
+                        .collect()
+                }
+                _ => store
+                    .feeds
+                    .iter()
+                    .filter(|f| feed_id(&f.url) == id)
+                    .map(|f| f.url.as_str())
+                    .collect(),
+            };
+            if urls.is_empty() && kind == "feed" {
+                return Err(Error::NotFound(format!("feed not found: {}", id)));
+            }
+            let ids: Vec<String> = urls
+                .iter()
+                .flat_map(|url| store.feed_items(url))
+                .filter(|i| i.published.map_or(0, |d| d.timestamp()) <= before)
+                .map(|i| i.id.clone())
+                .collect();
+            store.mark_all_read(ids);
+            Ok(())
+        }
+        _ => Err(Error::InvalidInput(format!(
+            "cannot mark {} as {:?}",
+            kind, action
+        ))),
+    }
+}
@@ -0,0 +1,540 @@
+//! The Google Reader API under `/greader`, as implemented by FreshRSS and Miniflux and
+//! spoken by NetNewsWire, Reeder, FeedMe and others (point them at `http://host:port/greader`).
+//!
+//! Clients sign in with `accounts/ClientLogin` and send the token they get back as
+//! `Authorization: GoogleLogin auth=<token>`. Streams are `feed/<url>`,
+//! `user/-/label/<folder>` and the `reading-list`, `starred` and `read` states; labels
+//! are folders. Item ids are those of [`super::ids`], written in the long
+//! `tag:google.com,2005:reader/item/<hex>` form or as decimals.
+
+use super::ids::{items_by_id, items_in_id_order};
+use super::{secure_eq, Config, Request, Response};
+use crate::cli::add;
+use crate::feed::{Feed, FeedItem};
+use crate::fetch::HttpOptions;
+use crate::store::{normalize_folder, Storage};
+use crate::{Error, Result, SubscriptionList};
+use percent_encoding::percent_decode_str;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const READING_LIST: &str = "user/-/state/com.google/reading-list";
+const STARRED: &str = "user/-/state/com.google/starred";
+const READ: &str = "user/-/state/com.google/read";
+const ITEM_PREFIX: &str = "tag:google.com,2005:reader/item/";
+
+/// Items per stream page unless the client asks for `n`.
+const DEFAULT_PAGE: usize = 20;
+/// Largest page served, whatever `n` asks for.
+const MAX_PAGE: usize = 10_000;
+
+/// A stream id.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Stream {
+    ReadingList,
+    Starred,
+    Read,
+    Feed(String),
+    Label(String),
+}
+
+impl Stream {
+    fn parse(s: &str) -> Result<Self> {
+        if let Some(url) = s.strip_prefix("feed/") {
+            return Ok(Stream::Feed(url.to_string()));
+        }
+        // Clients write the user either as `-` or as the id from `user-info`.
+        let mut parts = s.splitn(3, '/');
+        let (Some("user"), Some(_), Some(rest)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(Error::InvalidInput(format!("unknown stream: {}", s)));
+        };
+        match rest {
+            "state/com.google/reading-list" => Ok(Stream::ReadingList),
+            "state/com.google/starred" => Ok(Stream::Starred),
+            "state/com.google/read" => Ok(Stream::Read),
+            _ => match rest.strip_prefix("label/") {
+                Some(label) => Ok(Stream::Label(label.to_string())),
+                None => Err(Error::InvalidInput(format!("unknown stream: {}", s))),
+            },
+        }
+    }
+
+    fn contains(
+        &self,
+        item: &FeedItem,
+        feeds: &HashMap<&str, &Feed>,
+        store: &SubscriptionList,
+    ) -> bool {
+        match self {
+            Stream::ReadingList => true,
+            Stream::Starred => store.is_starred(&item.id),
+            Stream::Read => store.is_read(&item.id),
+            Stream::Feed(url) => item.feed_url == *url,
+            Stream::Label(label) => feeds
+                .get(item.feed_url.as_str())
+                .is_some_and(|f| f.in_folder(label)),
+        }
+    }
+}
+
+fn label_id(folder: &str) -> String {
+    format!("user/-/label/{}", folder)
+}
+
+fn long_item_id(id: u64) -> String {
+    format!("{}{:016x}", ITEM_PREFIX, id)
+}
+
+/// An item id in the long (hex) form or as a decimal, which clients may send as a signed
+/// 64-bit number.
+fn parse_item_id(s: &str) -> Result<u64> {
+    let parsed = match s.strip_prefix(ITEM_PREFIX) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s
+            .parse::<u64>()
+            .ok()
+            .or_else(|| s.parse::<i64>().ok().map(|n| n as u64)),
+    };
+    parsed.ok_or_else(|| Error::InvalidInput(format!("invalid item id: {}", s)))
+}
+
+pub(super) fn handle(request: &Request, path: &str, config: &Config) -> Response {
+    let Some(login) = &config.login else {
+        return Response::text(401, "Unauthorized");
+    };
+    if path == "accounts/ClientLogin" {
+        let signed_in = request.param("Email").is_some_and(|u| u == login.user)
+            && request
+                .param("Passwd")
+                .is_some_and(|p| secure_eq(p, &login.password));
+        if !signed_in {
+            return Response::text(401, "Error=BadAuthentication\n");
+        }
+        let token = login.greader_token();
+        return Response::text(200, format!("SID={0}\nLSID=null\nAuth={0}\n", token));
+    }
+    let given = request
+        .authorization
+        .as_deref()
+        .and_then(|h| h.strip_prefix("GoogleLogin auth="))
+        .unwrap_or("");
+    if !secure_eq(given.trim(), &login.greader_token()) {
+        return Response::text(401, "Unauthorized");
+    }
+    let Some(call) = path.strip_prefix("reader/api/0/") else {
+        return Response::text(404, "Not Found");
+    };
+    route(request, call, config).unwrap_or_else(Response::from)
+}
+
+fn route(request: &Request, call: &str, config: &Config) -> Result<Response> {
+    let storage: &dyn Storage = &config.storage;
+    let mut store = storage.load()?;
+    let user = config.login.as_ref().map_or("", |l| l.user.as_str());
+
+    match call {
+        "token" => Ok(Response::text(200, "rss-reader")),
+        "user-info" => Ok(Response::ok(json!({
+            "userId": "1",
+            "userName": user,
+            "userProfileId": "1",
+            "userEmail": "",
+        }))),
+        "subscription/list" => Ok(Response::ok(subscriptions(&store))),
+        "tag/list" => {
+            let mut tags = vec![json!({ "id": STARRED })];
+            tags.extend(
+                store
+                    .folders()
+                    .iter()
+                    .map(|p| json!({ "id": label_id(p), "type": "folder" })),
+            );
+            Ok(Response::ok(json!({ "tags": tags })))
+        }
+        "unread-count" => Ok(Response::ok(unread_counts(&store))),
+        "stream/items/ids" => {
+            let page = select(request, &store, request.param("s"))?;
+            let refs: Vec<Value> = page
+                .items
+                .iter()
+                .map(|(id, item)| {
+                    json!({
+                        "id": id.to_string(),
+                        "directStreamIds": [],
+                        "timestampUsec": usec(item).to_string(),
+                    })
+                })
+                .collect();
+            Ok(Response::ok(with_continuation(
+                json!({ "itemRefs": refs }),
+                page.continuation,
+            )))
+        }
+        "stream/items/contents" => {
+            let by_id = items_by_id(&store);
+            let mut items = Vec::new();
+            for s in request.params("i") {
+                let id = parse_item_id(s)?;
+                if let Some(item) = by_id.get(&id) {
+                    items.push((id, *item));
+                }
+            }
+            Ok(Response::ok(contents(READING_LIST, &items, &store, None)))
+        }
+        _ if call == "stream/contents" || call.starts_with("stream/contents/") => {
+            // The stream id may be in the path (percent-encoded) or in `s`.
+            let in_path = call
+                .strip_prefix("stream/contents/")
+                .map(|s| percent_decode_str(s).decode_utf8_lossy().into_owned());
+            let stream = in_path
+                .or_else(|| request.param("s").map(str::to_string))
+                .unwrap_or_else(|| READING_LIST.to_string());
+            let page = select(request, &store, Some(&stream))?;
+            Ok(Response::ok(contents(
+                &stream,
+                &page.items,
+                &store,
+                page.continuation,
+            )))
+        }
+        "edit-tag" => {
+            let by_id = items_by_id(&store);
+            let mut ids = Vec::new();
+            for s in request.params("i") {
+                let id = parse_item_id(s)?;
+                let item = by_id
+                    .get(&id)
+                    .ok_or_else(|| Error::NotFound(format!("item not found: {}", s)))?;
+                ids.push(item.id.clone());
+            }
+            for (param, on) in [("a", true), ("r", false)] {
+                for tag in request.params(param) {
+                    for id in &ids {
+                        match Stream::parse(tag)? {
+                            Stream::Read => store.set_read(id, on),
+                            Stream::Starred => store.set_starred(id, on),
+                            // Labels on single items are not supported; ignore them.
+                            _ => true,
+                        };
+                    }
+                }
+            }
+            storage.save(&mut store)?;
+            Ok(Response::text(200, "OK"))
+        }
+        "mark-all-as-read" => {
+            let stream = Stream::parse(request.param("s").unwrap_or(READING_LIST))?;
+            let before = request
+                .param("ts")
+                .and_then(|ts| ts.parse::<i64>().ok())
+                .unwrap_or(i64::MAX);
+            let feeds = feeds_by_url(&store);
+            let ids: Vec<String> = store
+                .items(None)
+                .into_iter()
+                .filter(|i| stream.contains(i, &feeds, &store) && usec(i) <= before)
+                .map(|i| i.id.clone())
+                .collect();
+            store.mark_all_read(ids);
+            storage.save(&mut store)?;
+            Ok(Response::text(200, "OK"))
+        }
+        "subscription/quickadd" => {
+            let url = request
+                .param("quickadd")
+                .ok_or_else(|| Error::InvalidInput("missing quickadd".to_string()))?
+                .trim_start_matches("feed/")
+                .to_string();
+            Ok(Response::ok(
+                match subscribe(&mut store, &url, &config.http) {
+                    Ok(added) => {
+                        storage.save(&mut store)?;
+                        let title = store
+                            .feeds
+                            .iter()
+                            .find(|f| f.url == added)
+                            .and_then(|f| f.title.clone())
+                            .unwrap_or_else(|| added.clone());
+                        json!({
+                            "numResults": 1,
+                            "query": url,
+                            "streamId": format!("feed/{}", added),
+                            "streamName": title,
+                        })
+                    }
+                    Err(e) => json!({ "numResults": 0, "query": url, "error": e.to_string() }),
+                },
+            ))
+        }
+        "subscription/edit" => {
+            edit_subscriptions(request, &mut store, &config.http)?;
+            storage.save(&mut store)?;
+            Ok(Response::text(200, "OK"))
+        }
+        "rename-tag" | "disable-tag" => {
+            let label = match Stream::parse(request.param("s").unwrap_or(""))? {
+                Stream::Label(label) => label,
+                _ => return Err(Error::InvalidInput("not a label".to_string())),
+            };
+            if call == "rename-tag" {
+                let dest = match Stream::parse(request.param("dest").unwrap_or(""))? {
+                    Stream::Label(dest) => dest,
+                    _ => return Err(Error::InvalidInput("not a label".to_string())),
+                };
+                store.move_folder(&label, &dest)?;
+            } else {
+                store.remove_folder(&label)?;
+            }
+            storage.save(&mut store)?;
+            Ok(Response::text(200, "OK"))
+        }
+        _ => Ok(Response::text(404, "Not Found")),
+    }
+}
+
+fn feeds_by_url(store: &SubscriptionList) -> HashMap<&str, &Feed> {
+    store.feeds.iter().map(|f| (f.url.as_str(), f)).collect()
+}
+
+/// Publication time in microseconds (0 for undated items).
+fn usec(item: &FeedItem) -> i64 {
+    item.published.map_or(0, |d| d.timestamp_micros())
+}
+
+/// One page of a stream, with the continuation of the next page if there is one.
+struct Page<'a> {
+    items: Vec<(u64, &'a FeedItem)>,
+    continuation: Option<String>,
+}
+
+/// The page of items in `stream` (default the reading list) that the request asks for:
+/// without those in `xt`, only those in `it`, published between `ot` and `nt` (seconds),
+/// newest first (oldest with `r=o`), `n` items from continuation `c`.
+fn select<'a>(
+    request: &Request,
+    store: &'a SubscriptionList,
+    stream: Option<&str>,
+) -> Result<Page<'a>> {
+    let stream = Stream::parse(stream.unwrap_or(READING_LIST))?;
+    let exclude = request.param("xt").map(Stream::parse).transpose()?;
+    let include = request.param("it").map(Stream::parse).transpose()?;
+    let seconds = |name: &str| request.param(name).and_then(|v| v.parse::<i64>().ok());
+    let (newer_than, older_than) = (seconds("ot"), seconds("nt"));
+    let count = request
+        .param("n")
+        .and_then(|n| n.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_PAGE)
+        .clamp(1, MAX_PAGE);
+    let offset = request
+        .param("c")
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let feeds = feeds_by_url(store);
+    let mut items: Vec<(u64, &FeedItem)> = items_in_id_order(store)
+        .into_iter()
+        .filter(|(_, i)| stream.contains(i, &feeds, store))
+        .filter(|(_, i)| {
+            exclude
+                .as_ref()
+                .map_or(true, |s| !s.contains(i, &feeds, store))
+        })
+        .filter(|(_, i)| {
+            include
+                .as_ref()
+                .map_or(true, |s| s.contains(i, &feeds, store))
+        })
+        .filter(|(_, i)| {
+            let secs = i.published.map_or(0, |d| d.timestamp());
+            newer_than.map_or(true, |t| secs >= t) && older_than.map_or(true, |t| secs < t)
+        })
+        .collect();
+    if request.param("r") != Some("o") {
+        items.reverse();
+    }
+    let more = items.len() > offset + count;
+    Ok(Page {
+        items: items.into_iter().skip(offset).take(count).collect(),
+        continuation: more.then(|| (offset + count).to_string()),
+    })
+}
+
+fn with_continuation(mut body: Value, continuation: Option<String>) -> Value {
+    if let Some(c) = continuation {
+        body["continuation"] = Value::String(c);
+    }
+    body
+}
+
+/// A stream page in the `stream/contents` format.
+fn contents(
+    stream: &str,
+    items: &[(u64, &FeedItem)],
+    store: &SubscriptionList,
+    continuation: Option<String>,
+) -> Value {
+    let feeds = feeds_by_url(store);
+    let items: Vec<Value> = items
+        .iter()
+        .map(|(id, item)| {
+            let feed = feeds.get(item.feed_url.as_str());
+            let mut categories = vec![READING_LIST.to_string()];
+            if store.is_read(&item.id) {
+                categories.push(READ.to_string());
+            }
+            if store.is_starred(&item.id) {
+                categories.push(STARRED.to_string());
+            }
+            categories.extend(feed.and_then(|f| f.folder.as_deref()).map(label_id));
+            let links: Vec<Value> = item
+                .link
+                .iter()
+                .map(|href| json!({ "href": href, "type": "text/html" }))
+                .collect();
+            let secs = item.published.map_or(0, |d| d.timestamp());
+            json!({
+                "id": long_item_id(*id),
+                "crawlTimeMsec": (usec(item) / 1000).to_string(),
+                "timestampUsec": usec(item).to_string(),
+                "published": secs,
+                "updated": secs,
+                "title": item.title,
+                "author": "",
+                "canonical": links,
+                "alternate": links,
+                "summary": {
+                    "direction": "ltr",
+                    "content": item.content.as_deref().or(item.summary.as_deref()).unwrap_or(""),
+                },
+                "categories": categories,
+                "origin": {
+                    "streamId": format!("feed/{}", item.feed_url),
+                    "title": feed.and_then(|f| f.title.as_deref()).unwrap_or(&item.feed_url),
+                    "htmlUrl": item.feed_url,
+                },
+            })
+        })
+        .collect();
+    with_continuation(
+        json!({
+            "id": stream,
+            "updated": chrono::Utc::now().timestamp(),
+            "items": items,
+        }),
+        continuation,
+    )
+}
+
+fn subscriptions(store: &SubscriptionList) -> Value {
+    let subscriptions: Vec<Value> = store
+        .feeds
+        .iter()
+        .map(|f| {
+            let categories: Vec<Value> = f
+                .folder
+                .iter()
+                .map(|p| json!({ "id": label_id(p), "label": p }))
+                .collect();
+            json!({
+                "id": format!("feed/{}", f.url),
+                "title": f.title.as_deref().unwrap_or(&f.url),
+                "categories": categories,
+                "url": f.url,
+                "htmlUrl": f.url,
+                "iconUrl": "",
+                "firstitemmsec": f.created_at.map_or(0, |d| d.timestamp_millis()).to_string(),
+            })
+        })
+        .collect();
+    json!({ "subscriptions": subscriptions })
+}
+
+/// Unread counts per feed, per folder and for the reading list.
+fn unread_counts(store: &SubscriptionList) -> Value {
+    // (count, newest timestamp) per stream id
+    let mut counts: HashMap<String, (usize, i64)> = HashMap::new();
+    let feeds = feeds_by_url(store);
+    for item in store.items(None) {
+        if store.is_read(&item.id) {
+            continue;
+        }
+        let mut streams = vec![READING_LIST.to_string(), format!("feed/{}", item.feed_url)];
+        if let Some(folder) = feeds
+            .get(item.feed_url.as_str())
+            .and_then(|f| f.folder.as_deref())
+        {
+            streams.push(label_id(folder));
+        }
+        for stream in streams {
+            let entry = counts.entry(stream).or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.max(usec(item));
+        }
+    }
+    let mut list: Vec<(String, (usize, i64))> = counts.into_iter().collect();
+    list.sort();
+    let unreadcounts: Vec<Value> = list
+        .into_iter()
+        .map(|(id, (count, newest))| {
+            json!({ "id": id, "count": count, "newestItemTimestampUsec": newest.to_string() })
+        })
+        .collect();
+    json!({ "max": MAX_PAGE, "unreadcounts": unreadcounts })
+}
+
+/// Subscribe to `url` (discovering the feed of a website, taking the first one offered);
+/// returns the url of the feed added.
+fn subscribe(store: &mut SubscriptionList, url: &str, http: &HttpOptions) -> Result<String> {
+    match add::add(store, url, false, http)? {
+        add::Added::Feeds(urls) => Ok(urls.into_iter().next().unwrap_or_default()),
+        add::Added::Candidates(candidates) => subscribe(store, &candidates[0].url, http),
+    }
+}
+
+/// `subscription/edit`: `ac=subscribe|unsubscribe|edit` for feeds `s`, with title `t`
+/// and folder label `a` to add or `r` to remove.
+fn edit_subscriptions(
+    request: &Request,
+    store: &mut SubscriptionList,
+    http: &HttpOptions,
+) -> Result<()> {
+    let action = request.param("ac").unwrap_or("edit");
+    let streams: Vec<String> = request.params("s").map(str::to_string).collect();
+    for stream in streams {
+        let Stream::Feed(mut url) = Stream::parse(&stream)? else {
+            return Err(Error::InvalidInput(format!("not a feed: {}", stream)));
+        };
+        match action {
+            "unsubscribe" => {
+                if !store.remove_feed(&url) {
+                    return Err(Error::NotFound(format!("feed not found: {}", url)));
+                }
+                continue;
+            }
+            "subscribe" => url = subscribe(store, &url, http)?,
+            _ => {}
+        }
+        if let Some(title) = request.param("t").filter(|t| !t.trim().is_empty()) {
+            let feed = store
+                .feeds
+                .iter_mut()
+                .find(|f| f.url == url)
+                .ok_or_else(|| Error::NotFound(format!("feed not found: {}", url)))?;
+            feed.title = Some(title.trim().to_string());
+            store.changes.feeds.insert(url.clone());
+        }
+        if let Some(Stream::Label(label)) = request.param("r").map(Stream::parse).transpose()? {
+            if store
+                .feeds
+                .iter()
+                .any(|f| f.url == url && f.folder.as_deref() == normalize_folder(&label).as_deref())
+            {
+                store.set_folder(&url, None)?;
+            }
+        }
+        if let Some(Stream::Label(label)) = request.param("a").map(Stream::parse).transpose()? {
+            store.set_folder(&url, Some(&label))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Numeric ids for the Fever and Google Reader APIs, whose clients expect integers where
+//! the store uses urls, folder paths and feed-provided item ids. They are derived from
+//! those rather than stored, so every server process hands out the same ones.
+
+use crate::feed::FeedItem;
+use crate::{fnv1a, SubscriptionList};
+use std::collections::HashMap;
+
+/// Id of the feed with `url` (31 bits, never 0).
+pub(super) fn feed_id(url: &str) -> u64 {
+    (fnv1a(url) & 0x7fff_ffff).max(1)
+}
+
+/// Id of folder `path` (31 bits, never 0: Fever uses group 0 for "all items").
+pub(super) fn group_id(path: &str) -> u64 {
+    (fnv1a(path) & 0x7fff_ffff).max(1)
+}
+
+/// Preferred id of `item`: its publication time in seconds above a 21-bit hash of its id.
+/// Ids thus grow with publication time, which clients rely on when paging with `since_id`,
+/// and stay below 2^53 so they are exact as JSON numbers. Undated items sort first.
+fn base_id(item: &FeedItem) -> u64 {
+    let secs = item
+        .published
+        .map_or(0, |d| d.timestamp().clamp(0, i64::from(u32::MAX >> 1))) as u64;
+    (secs << 21) | (fnv1a(&item.id) & 0x1f_ffff)
+}
+
+/// Every cached item by its id (see [`items_in_id_order`]).
+pub(super) fn items_by_id(store: &SubscriptionList) -> HashMap<u64, &FeedItem> {
+    items_in_id_order(store).into_iter().collect()
+}
+
+/// Every cached item with its id, oldest first. An item whose [`base_id`] is taken (e.g.
+/// by another undated item with the same hash) gets the next free id, in the order of
+/// feed url and item id, so ids are unique and the same for the same cache.
+pub(super) fn items_in_id_order(store: &SubscriptionList) -> Vec<(u64, &FeedItem)> {
+    let mut items: Vec<(u64, &FeedItem)> = store
+        .items(None)
+        .into_iter()
+        .map(|i| (base_id(i), i))
+        .collect();
+    items.sort_by(|(a, x), (b, y)| {
+        a.cmp(b)
+            .then_with(|| (&x.feed_url, &x.id).cmp(&(&y.feed_url, &y.id)))
+    });
+    let mut next = 0;
+    for (id, _) in &mut items {
+        *id = (*id).max(next);
+        next = *id + 1;
+    }
+    items
+}
@@ -0,0 +1,475 @@
+//! `serve`: a small HTTP/1.1 server over the store for local tooling and mobile apps.
+//!
+//! - `/api/...`: the JSON API (see `api`), guarded by an optional bearer token.
+//! - `/fever/`: the Fever API (see `fever`) and `/greader/...`: the Google Reader API
+//!   (see `greader`), for mobile clients; both need a login set with [`Server::with_login`].
+//!
+//! Connections are handled by a fixed pool of worker threads sharing one storage handle;
+//! saves merge with the CLI, GUI and daemon like any other process. When every worker is
+//! busy, new connections wait in the listen queue.
+//!
+//! Web pages the user visits can reach a loopback server too, so requests must name the
+//! server in `Host` (its address, `localhost` or a host added with
+//! [`Server::allow_host`]), a browser's `Origin` must be the server itself, and `/api`
+//! writes must be `application/json`, which browsers only send cross-site after a preflight
+//! the server never answers.
+
+mod api;
+mod fever;
+mod greader;
+mod ids;
+
+use crate::feed::FeedItem;
+use crate::fetch::HttpOptions;
+use crate::settings::Settings;
+use crate::store::Storage;
+use crate::{Error, SubscriptionList};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// Default listen address: loopback only.
+pub const DEFAULT_BIND: &str = "127.0.0.1:8080";
+
+/// Largest request body accepted (feed urls and small JSON objects only).
+const MAX_BODY: usize = 1 << 20;
+
+/// Connections handled at once.
+const WORKERS: usize = 16;
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A bound server; call [`Server::run`] to start accepting connections.
+pub struct Server {
+    listener: TcpListener,
+    config: Config,
+}
+
+struct Config {
+    storage: SharedStorage,
+    /// Address the listener is bound to; `Host` must name it.
+    addr: SocketAddr,
+    /// Extra host names accepted in `Host` (lowercase).
+    allowed_hosts: Vec<String>,
+    /// When set, every `/api` request must send `Authorization: Bearer <token>`.
+    token: Option<String>,
+    /// Account for the Fever and Google Reader APIs; they are disabled without one.
+    login: Option<Login>,
+    /// Client options for adding and refreshing feeds.
+    http: HttpOptions,
+}
+
+/// The server's storage handle, shared by the workers. Each call takes the lock only for
+/// its own duration, so a slow refresh does not block other requests.
+struct SharedStorage(Mutex<Box<dyn Storage>>);
+
+impl SharedStorage {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Box<dyn Storage>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Storage for SharedStorage {
+    fn load(&self) -> crate::Result<SubscriptionList> {
+        self.lock().load()
+    }
+
+    fn save(&self, list: &mut SubscriptionList) -> crate::Result<()> {
+        self.lock().save(list)
+    }
+
+    fn get_item(&self, id: &str) -> crate::Result<Option<FeedItem>> {
+        self.lock().get_item(id)
+    }
+
+    fn feed_items(&self, url: &str) -> crate::Result<Vec<FeedItem>> {
+        self.lock().feed_items(url)
+    }
+}
+
+/// User name and password mobile clients sign in with.
+struct Login {
+    user: String,
+    password: String,
+}
+
+impl Login {
+    /// Fever's `api_key`: hex MD5 of `user:password`, computed by the client.
+    fn fever_key(&self) -> String {
+        format!(
+            "{:x}",
+            md5::compute(format!("{}:{}", self.user, self.password))
+        )
+    }
+
+    /// Google Reader auth token handed out by `ClientLogin`. Derived from the login, so it
+    /// stays valid across restarts and changes with the password.
+    fn greader_token(&self) -> String {
+        let digest = md5::compute(format!("greader:{}:{}", self.user, self.password));
+        format!("{}/{:x}", self.user, digest)
+    }
+}
+
+impl Server {
+    /// Bind to `addr` (e.g. `127.0.0.1:8080`; port 0 picks a free one) serving the store
+    /// at `store_path`.
+    pub fn bind(addr: &str, store_path: PathBuf, token: Option<String>) -> crate::Result<Self> {
+        let settings = Settings {
+            data_path: store_path,
+            ..Settings::default()
+        };
+        Self::bind_with(addr, &settings, token)
+    }
+
+    /// Like [`bind`](Self::bind), serving the store at `settings.data_path` with the
+    /// per-feed item cap and HTTP client options of `settings`.
+    pub fn bind_with(
+        addr: &str,
+        settings: &Settings,
+        token: Option<String>,
+    ) -> crate::Result<Self> {
+        let storage = crate::store::open_with(&settings.data_path, &settings.store)?;
+        let storage = SharedStorage(Mutex::new(storage));
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        Ok(Self {
+            listener,
+            config: Config {
+                storage,
+                addr,
+                allowed_hosts: Vec::new(),
+                token: token.filter(|t| !t.is_empty()),
+                login: None,
+                http: settings.http_options(),
+            },
+        })
+    }
+
+    /// Enable the Fever and Google Reader APIs for `user` signing in with `password`.
+    pub fn with_login(mut self, user: &str, password: &str) -> Self {
+        self.config.login = Some(Login {
+            user: user.to_string(),
+            password: password.to_string(),
+        });
+        self
+    }
+
+    /// Also accept requests for `host` (a name the server is reached by, e.g. behind a
+    /// reverse proxy or on the local network). Its address and `localhost` always work.
+    pub fn allow_host(mut self, host: &str) -> Self {
+        self.config.allowed_hosts.push(host.to_lowercase());
+        self
+    }
+
+    /// Address the server listens on.
+    pub fn local_addr(&self) -> crate::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accept connections until the process exits.
+    pub fn run(self) -> crate::Result<()> {
+        let config = Arc::new(self.config);
+        // A rendezvous channel: accepting stops while all workers are busy.
+        let (tx, rx) = mpsc::sync_channel::<TcpStream>(0);
+        let rx = Arc::new(Mutex::new(rx));
+        for _ in 0..WORKERS {
+            let (config, rx) = (Arc::clone(&config), Arc::clone(&rx));
+            std::thread::spawn(move || loop {
+                let next = rx.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                let Ok(stream) = next else { break };
+                // A panic must not shrink the pool.
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    handle_connection(stream, &config)
+                }));
+            });
+        }
+        for stream in self.listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            if tx.send(stream).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed HTTP request.
+pub(crate) struct Request {
+    pub method: String,
+    /// Percent-encoded path without the query string.
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    /// Fields of an `application/x-www-form-urlencoded` body.
+    pub form: Vec<(String, String)>,
+    pub authorization: Option<String>,
+    pub host: Option<String>,
+    pub origin: Option<String>,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// First value of parameter `name`, from the query string or else a form body.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .chain(&self.form)
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value of parameter `name` (query string first, then form body).
+    pub fn params<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.query
+            .iter()
+            .chain(&self.form)
+            .filter(move |(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Status code, content type and body of a response.
+pub(crate) struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_string_pretty(&body).unwrap(),
+        }
+    }
+
+    pub fn ok(body: serde_json::Value) -> Self {
+        Self::json(200, body)
+    }
+
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(
+            status,
+            serde_json::json!({ "success": false, "error": message }),
+        )
+    }
+}
+
+impl From<Error> for Response {
+    fn from(e: Error) -> Self {
+        let status = match e {
+            Error::NotFound(_) => 404,
+            Error::InvalidUrl(_)
+            | Error::InvalidInput(_)
+            | Error::Parse(_)
+            | Error::MultipleFeeds(_) => 400,
+            Error::Fetch(_) | Error::RetryAfter(..) | Error::RefreshFailed(_) | Error::Sync(_) => {
+                502
+            }
+            Error::Io(_) | Error::Store(_) | Error::Config(_) => 500,
+        };
+        Response::error(status, &e.to_string())
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, config: &Config) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let response = match read_request(&mut stream) {
+        Ok(request) => route(&request, config),
+        Err(message) => Response::error(400, &message),
+    };
+    let _ = write_response(&mut stream, &response);
+}
+
+fn route(request: &Request, config: &Config) -> Response {
+    if let Err(message) = check_origin(request, config) {
+        return Response::error(403, message);
+    }
+    let path = request.path.as_str();
+    if path == "/fever" || path.starts_with("/fever/") {
+        return fever::handle(request, config);
+    }
+    if let Some(rest) = path.strip_prefix("/greader/") {
+        return greader::handle(request, rest, config);
+    }
+    if !authorized(request, config.token.as_deref()) {
+        return Response::error(401, "missing or invalid token");
+    }
+    let json = request
+        .content_type
+        .as_deref()
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("application/json"));
+    if matches!(request.method.as_str(), "POST" | "PUT" | "DELETE") && !json {
+        return Response::error(415, "requests that change data must be application/json");
+    }
+    api::handle(request, &config.storage, &config.http)
+}
+
+/// Refuse requests for another host (DNS rebinding) and cross-origin browser requests.
+fn check_origin(request: &Request, config: &Config) -> Result<(), &'static str> {
+    let host = request
+        .host
+        .as_deref()
+        .and_then(|h| url::Url::parse(&format!("http://{}", h)).ok())
+        .filter(|u| u.path() == "/" && u.username().is_empty())
+        .ok_or("missing or invalid Host header")?;
+    let bound = config.addr.ip();
+    let known = match host.host() {
+        Some(url::Host::Ipv4(ip)) => bound.is_unspecified() || bound == ip,
+        Some(url::Host::Ipv6(ip)) => bound.is_unspecified() || bound == ip,
+        Some(url::Host::Domain(name)) => {
+            (name == "localhost" && (bound.is_loopback() || bound.is_unspecified()))
+                || config.allowed_hosts.iter().any(|h| h == name)
+        }
+        None => false,
+    };
+    if !known || host.port_or_known_default() != Some(config.addr.port()) {
+        return Err("request is for another host");
+    }
+    if let Some(origin) = &request.origin {
+        let same = url::Url::parse(origin).is_ok_and(|o| {
+            o.scheme() == "http"
+                && o.host() == host.host()
+                && o.port_or_known_default() == host.port_or_known_default()
+        });
+        if !same {
+            return Err("cross-origin requests are not allowed");
+        }
+    }
+    Ok(())
+}
+
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    let Some(token) = token else { return true };
+    let given = request
+        .authorization
+        .as_deref()
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .unwrap_or("");
+    secure_eq(given, token)
+}
+
+/// String equality that compares every byte, so the time taken does not reveal the
+/// matching prefix of a secret.
+fn secure_eq(given: &str, secret: &str) -> bool {
+    given.len() == secret.len()
+        && given
+            .bytes()
+            .zip(secret.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed before end of headers".to_string());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut req = httparse::Request::new(&mut headers);
+        let header_len = match req.parse(&buf).map_err(|e| e.to_string())? {
+            httparse::Status::Complete(len) => len,
+            httparse::Status::Partial if buf.len() > MAX_BODY => {
+                return Err("request headers too large".to_string())
+            }
+            httparse::Status::Partial => continue,
+        };
+        let header = |name: &str| {
+            req.headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case(name))
+                .map(|h| String::from_utf8_lossy(h.value).trim().to_string())
+        };
+        let content_length: usize = header("content-length")
+            .map(|v| v.parse().map_err(|_| "invalid Content-Length".to_string()))
+            .transpose()?
+            .unwrap_or(0);
+        if content_length > MAX_BODY {
+            return Err("request body too large".to_string());
+        }
+        let form = header("content-type")
+            .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+        let target = req.path.unwrap_or("/").to_string();
+        let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+        let request = Request {
+            method: req.method.unwrap_or("GET").to_string(),
+            path: path.to_string(),
+            query: url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect(),
+            form: Vec::new(),
+            authorization: header("authorization"),
+            host: header("host"),
+            origin: header("origin"),
+            content_type: header("content-type"),
+            body: Vec::new(),
+        };
+        let mut body = buf.split_off(header_len);
+        while body.len() < content_length {
+            let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+            if n == 0 {
+                return Err("connection closed before end of body".to_string());
+            }
+            body.extend_from_slice(&chunk[..n]);
+        }
+        body.truncate(content_length);
+        let form = if form {
+            url::form_urlencoded::parse(&body).into_owned().collect()
+        } else {
+            Vec::new()
+        };
+        return Ok(Request {
+            form,
+            body,
+            ..request
+        });
+    }
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> std::io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        415 => "Unsupported Media Type",
+        502 => "Bad Gateway",
+        _ => "Internal Server Error",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n",
+        response.status,
+        reason,
+        response.content_type,
+        response.body.len()
+    );
+    if response.status == 401 && response.content_type == "application/json" {
+        head.push_str("WWW-Authenticate: Bearer\r\n");
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
@@ -0,0 +1,243 @@
+//! Reader preferences from a TOML file (`~/.config/rss-reader/config.toml` by default),
+//! overridden by `RSS_READER_*` environment variables and then by command-line flags.
+//!
+//! ```toml
+//! data_path = "~/.config/rss-reader/data.db"
+//! output = "human"            # or "json"
+//! width = 80                  # column width articles are wrapped to (default: the terminal's)
+//! date_format = "%Y-%m-%d %H:%M"
+//! download_dir = "~/Downloads"
+//!
+//! [fetch]
+//! timeout_secs = 30
+//! user_agent = "rss-reader/0.1"
+//!
+//! [store]
+//! max_items_per_feed = 500
+//! ```
+
+use crate::fetch::HttpOptions;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the settings file (like `--settings`).
+pub const SETTINGS_ENV: &str = "RSS_READER_SETTINGS";
+
+/// All reader preferences; missing keys take their defaults.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Store: an SQLite database for `.db` / `.sqlite` / `.sqlite3`, a JSON file otherwise.
+    pub data_path: PathBuf,
+    /// Default output format: `human` or `json`.
+    pub output: String,
+    /// Column width articles are wrapped to (at least 40); see [`article_width`](Self::article_width).
+    pub width: Option<usize>,
+    /// strftime format of dates in human output.
+    pub date_format: String,
+    /// Where enclosures are downloaded; the current directory if unset (see
+    /// [`download_queue_dir`](Self::download_queue_dir) for the queue).
+    pub download_dir: Option<PathBuf>,
+    pub fetch: FetchSettings,
+    pub store: StoreSettings,
+}
+
+/// HTTP client used for feeds, discovery and sync.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FetchSettings {
+    pub timeout_secs: u64,
+    pub user_agent: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreSettings {
+    /// Cached items kept per feed (starred items are always kept).
+    pub max_items_per_feed: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            data_path: config_dir().join("data.db"),
+            output: "human".to_string(),
+            width: None,
+            date_format: "%Y-%m-%d %H:%M".to_string(),
+            download_dir: None,
+            fetch: FetchSettings::default(),
+            store: StoreSettings::default(),
+        }
+    }
+}
+
+impl Default for FetchSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            user_agent: "rss-reader/0.1".to_string(),
+        }
+    }
+}
+
+impl Default for StoreSettings {
+    fn default() -> Self {
+        Self {
+            max_items_per_feed: 500,
+        }
+    }
+}
+
+/// `~/.config/rss-reader` (or the platform's config directory).
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rss-reader")
+}
+
+/// Default settings file: `config.toml` in [`config_dir`].
+pub fn default_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+/// Expand a leading `~/` to the home directory.
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
+
+impl Settings {
+    /// Parse settings from TOML text.
+    pub fn parse(text: &str) -> Result<Self> {
+        let settings: Settings = toml::from_str(text)
+            .map_err(|e| Error::Config(e.to_string().trim_end().to_string()))?;
+        settings.normalized()
+    }
+
+    /// Read the settings file at `path`, or the default file when None. A missing default
+    /// file means defaults; a missing explicit file is an error.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, explicit) = match path {
+            Some(p) => (p.to_path_buf(), true),
+            None => (default_path(), false),
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !explicit => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(Error::Config(format!("{}: {}", path.display(), e))),
+        };
+        Self::parse(&text).map_err(|e| match e {
+            Error::Config(msg) => Error::Config(format!("{}: {}", path.display(), msg)),
+            e => e,
+        })
+    }
+
+    /// Settings for this process: the file named by `path` (or `RSS_READER_SETTINGS`, or
+    /// the default file), then environment overrides.
+    pub fn from_env(path: Option<&Path>) -> Result<Self> {
+        let env_path = std::env::var_os(SETTINGS_ENV).map(PathBuf::from);
+        let mut settings = Self::load(path.or(env_path.as_deref()))?;
+        settings.apply_env(|name| std::env::var(name).ok())?;
+        Ok(settings)
+    }
+
+    /// Override settings from `RSS_READER_DATA`, `RSS_READER_OUTPUT`, `RSS_READER_WIDTH`,
+    /// `RSS_READER_DATE_FORMAT`, `RSS_READER_DOWNLOAD_DIR`, `RSS_READER_TIMEOUT`,
+    /// `RSS_READER_USER_AGENT` and `RSS_READER_MAX_ITEMS`, as looked up by `var`.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| Error::Config(format!("{}: not a number: {:?}", name, value)))
+        }
+        if let Some(v) = var("RSS_READER_DATA") {
+            self.data_path = PathBuf::from(v);
+        }
+        if let Some(v) = var("RSS_READER_OUTPUT") {
+            self.output = v;
+        }
+        if let Some(v) = var("RSS_READER_WIDTH") {
+            self.width = Some(number("RSS_READER_WIDTH", &v)?);
+        }
+        if let Some(v) = var("RSS_READER_DATE_FORMAT") {
+            self.date_format = v;
+        }
+        if let Some(v) = var("RSS_READER_DOWNLOAD_DIR") {
+            self.download_dir = Some(PathBuf::from(v));
+        }
+        if let Some(v) = var("RSS_READER_TIMEOUT") {
+            self.fetch.timeout_secs = number("RSS_READER_TIMEOUT", &v)?;
+        }
+        if let Some(v) = var("RSS_READER_USER_AGENT") {
+            self.fetch.user_agent = v;
+        }
+        if let Some(v) = var("RSS_READER_MAX_ITEMS") {
+            self.store.max_items_per_feed = number("RSS_READER_MAX_ITEMS", &v)?;
+        }
+        *self = std::mem::take(self).normalized()?;
+        Ok(())
+    }
+
+    /// Expand `~` in paths and check the values.
+    fn normalized(mut self) -> Result<Self> {
+        self.data_path = expand_home(self.data_path);
+        self.download_dir = self.download_dir.map(expand_home);
+        self.output = self.output.to_lowercase();
+        if !matches!(self.output.as_str(), "human" | "json") {
+            return Err(Error::Config(format!(
+                "output must be human or json, not {:?}",
+                self.output
+            )));
+        }
+        Ok(self)
+    }
+
+    /// Article width: [`width`](Self::width), else the terminal's width, else 80.
+    pub fn article_width(&self) -> usize {
+        self.width
+            .or_else(crate::format::terminal_width)
+            .unwrap_or(crate::format::DEFAULT_WIDTH)
+    }
+
+    /// Where the download queue saves files: [`download_dir`](Self::download_dir), else
+    /// `downloads` next to the store.
+    pub fn download_queue_dir(&self) -> PathBuf {
+        self.download_dir.clone().unwrap_or_else(|| {
+            self.data_path
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join("downloads")
+        })
+    }
+
+    /// Whether output is JSON by default.
+    pub fn output_json(&self) -> bool {
+        self.output == "json"
+    }
+
+    /// Format `date` with [`date_format`](Self::date_format).
+    pub fn format_date(&self, date: &chrono::DateTime<chrono::Utc>) -> String {
+        use std::fmt::Write;
+        let mut out = String::new();
+        // A bad format string makes chrono's Display fail; fall back to the default.
+        if write!(out, "{}", date.format(&self.date_format)).is_err() {
+            out.clear();
+            let _ = write!(out, "{}", date.format(&Settings::default().date_format));
+        }
+        out
+    }
+
+    /// Timeout and User-Agent for HTTP clients, from [`fetch`](Self::fetch).
+    pub fn http_options(&self) -> HttpOptions {
+        HttpOptions {
+            timeout: std::time::Duration::from_secs(self.fetch.timeout_secs),
+            user_agent: self.fetch.user_agent.clone(),
+        }
+    }
+}
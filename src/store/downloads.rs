@@ -0,0 +1,274 @@
+//! The enclosure download queue and per-feed retention.
+//!
+//! Entries are keyed by enclosure url. [`crate::media::download`] works through the
+//! queued (and failed) entries; a failed one is retried with exponential backoff, and
+//! after [`MAX_DOWNLOAD_ATTEMPTS`] failures only once queued again. [`DownloadPolicy`] decides
+//! which finished files expire.
+
+use super::SubscriptionList;
+use crate::feed::{DownloadPolicy, FeedItem};
+use crate::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Failed attempts after which a download is no longer retried until queued again.
+pub const MAX_DOWNLOAD_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry of a failed download; doubled after every further failure.
+const RETRY_DELAY: chrono::Duration = chrono::Duration::minutes(10);
+
+/// State of a [`Download`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Queued,
+    Done,
+    /// The last attempt failed; the next run resumes it.
+    Failed,
+}
+
+impl DownloadStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DownloadStatus::Queued => "queued",
+            DownloadStatus::Done => "done",
+            DownloadStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::fmt::Display for DownloadStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One enclosure in the download queue.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Download {
+    pub url: String,
+    pub item_id: String,
+    pub feed_url: String,
+    /// Names the file when the server sends no Content-Disposition file name.
+    pub title: Option<String>,
+    pub status: DownloadStatus,
+    /// The finished file.
+    pub path: Option<PathBuf>,
+    /// Size of the finished file.
+    #[serde(default)]
+    pub bytes: u64,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    /// Failed attempts since the entry was (re-)queued.
+    #[serde(default)]
+    pub attempts: u32,
+    /// When a failed entry is due for another attempt; None once it gave up.
+    #[serde(default)]
+    pub retry_at: Option<DateTime<Utc>>,
+    pub queued_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl SubscriptionList {
+    /// The queue entry of enclosure `url`.
+    pub fn download(&self, url: &str) -> Option<&Download> {
+        self.downloads.iter().find(|d| d.url == url)
+    }
+
+    /// The file of the finished download of enclosure `url`, if it still exists.
+    pub fn downloaded_file(&self, url: &str) -> Option<&Path> {
+        self.download(url)
+            .filter(|d| d.status == DownloadStatus::Done)
+            .and_then(|d| d.path.as_deref())
+            .filter(|p| p.exists())
+    }
+
+    /// Queue enclosure `index` of item `id`. Returns false if it is already queued or
+    /// downloaded; a failed download is queued again with its attempts reset.
+    pub fn queue_download(&mut self, id: &str, index: usize) -> Result<bool, Error> {
+        let item = self
+            .get_item(id, None)
+            .ok_or_else(|| Error::NotFound(format!("item not found: {}", id)))?
+            .clone();
+        if index >= item.enclosures.len() {
+            return Err(Error::NotFound(format!(
+                "enclosure index {} not found",
+                index
+            )));
+        }
+        Ok(self.queue_enclosure(&item, index))
+    }
+
+    /// Queue enclosure `index` of `item` (which need not be cached yet).
+    pub(crate) fn queue_enclosure(&mut self, item: &FeedItem, index: usize) -> bool {
+        let Some(enclosure) = item.enclosures.get(index) else {
+            return false;
+        };
+        let url = enclosure.url.clone();
+        if let Some(existing) = self.downloads.iter_mut().find(|d| d.url == url) {
+            if existing.status != DownloadStatus::Failed {
+                return false;
+            }
+            existing.status = DownloadStatus::Queued;
+            existing.error = None;
+            existing.attempts = 0;
+            existing.retry_at = None;
+        } else {
+            self.downloads.push(Download {
+                url: url.clone(),
+                item_id: item.id.clone(),
+                feed_url: item.feed_url.clone(),
+                title: enclosure.title.clone().or_else(|| Some(item.title.clone())),
+                status: DownloadStatus::Queued,
+                path: None,
+                bytes: 0,
+                error: None,
+                attempts: 0,
+                retry_at: None,
+                queued_at: Utc::now(),
+                finished_at: None,
+            });
+        }
+        self.changes.downloads.insert(url);
+        true
+    }
+
+    /// Queued downloads and failed ones still being retried, oldest first.
+    pub fn pending_downloads(&self) -> Vec<Download> {
+        self.downloads
+            .iter()
+            .filter(|d| match d.status {
+                DownloadStatus::Queued => true,
+                DownloadStatus::Failed => d.retry_at.is_some(),
+                DownloadStatus::Done => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// [`pending_downloads`](Self::pending_downloads) whose retry time has come by `now`.
+    pub fn due_downloads(&self, now: DateTime<Utc>) -> Vec<Download> {
+        self.pending_downloads()
+            .into_iter()
+            .filter(|d| d.retry_at.map_or(true, |at| at <= now))
+            .collect()
+    }
+
+    /// Record the outcome of downloading `url`: the file and its size, or the error and,
+    /// unless it was the last of [`MAX_DOWNLOAD_ATTEMPTS`], when to retry.
+    pub fn finish_download(&mut self, url: &str, result: Result<(PathBuf, u64), String>) {
+        let Some(download) = self.downloads.iter_mut().find(|d| d.url == url) else {
+            return;
+        };
+        match result {
+            Ok((path, bytes)) => {
+                download.status = DownloadStatus::Done;
+                download.path = Some(path);
+                download.bytes = bytes;
+                download.error = None;
+                download.attempts = 0;
+                download.retry_at = None;
+                download.finished_at = Some(Utc::now());
+            }
+            Err(e) => {
+                download.status = DownloadStatus::Failed;
+                download.error = Some(e);
+                download.attempts += 1;
+                download.retry_at = (download.attempts < MAX_DOWNLOAD_ATTEMPTS)
+                    .then(|| Utc::now() + RETRY_DELAY * (1 << (download.attempts - 1)));
+            }
+        }
+        self.changes.downloads.insert(url.to_string());
+    }
+
+    /// Record `path` (of `bytes`) as the finished download of enclosure `index` of `item`,
+    /// replacing any earlier entry.
+    pub(crate) fn record_download(
+        &mut self,
+        item: &FeedItem,
+        index: usize,
+        path: PathBuf,
+        bytes: u64,
+    ) {
+        let Some(enclosure) = item.enclosures.get(index) else {
+            return;
+        };
+        let url = enclosure.url.clone();
+        self.remove_download(&url);
+        self.queue_enclosure(item, index);
+        self.finish_download(&url, Ok((path, bytes)));
+    }
+
+    /// Remove the queue entry of enclosure `url` (the file is left alone).
+    pub fn remove_download(&mut self, url: &str) -> Option<Download> {
+        let index = self.downloads.iter().position(|d| d.url == url)?;
+        self.changes.downloads.insert(url.to_string());
+        Some(self.downloads.remove(index))
+    }
+
+    /// Remove the queue entries of item `id` (the files are left alone) and return them.
+    pub fn remove_downloads(&mut self, id: &str) -> Vec<Download> {
+        let urls: Vec<String> = self
+            .downloads
+            .iter()
+            .filter(|d| d.item_id == id)
+            .map(|d| d.url.clone())
+            .collect();
+        urls.iter()
+            .filter_map(|url| self.remove_download(url))
+            .collect()
+    }
+
+    /// Set the download policy of feed `url`.
+    pub fn set_download_policy(&mut self, url: &str, policy: DownloadPolicy) -> Result<(), Error> {
+        let feed = self
+            .feeds
+            .iter_mut()
+            .find(|f| f.url == url)
+            .ok_or_else(|| Error::NotFound(format!("feed not found: {}", url)))?;
+        feed.download_policy = policy;
+        self.changes.feeds.insert(url.to_string());
+        Ok(())
+    }
+
+    /// Finished downloads their feed's policy no longer keeps: beyond the `keep` newest
+    /// items, or (with `delete_played`) of items marked read. Starred items are kept.
+    pub fn expired_downloads(&self) -> Vec<&Download> {
+        let published: HashMap<(&str, &str), DateTime<Utc>> = self
+            .items_by_feed
+            .values()
+            .flatten()
+            .filter_map(|i| Some(((i.feed_url.as_str(), i.id.as_str()), i.published?)))
+            .collect();
+        let mut out = Vec::new();
+        for feed in &self.feeds {
+            let policy = &feed.download_policy;
+            if policy.keep.is_none() && !policy.delete_played {
+                continue;
+            }
+            let mut done: Vec<&Download> = self
+                .downloads
+                .iter()
+                .filter(|d| d.feed_url == feed.url && d.status == DownloadStatus::Done)
+                .filter(|d| !self.is_starred(&d.item_id))
+                .collect();
+            // Newest first by the item's date, else by when the download finished.
+            done.sort_by_cached_key(|d| {
+                let key = (d.feed_url.as_str(), d.item_id.as_str());
+                std::cmp::Reverse(published.get(&key).copied().or(d.finished_at))
+            });
+            let keep = policy.keep.unwrap_or(usize::MAX);
+            out.extend(
+                done.into_iter()
+                    .enumerate()
+                    .filter(|(n, d)| {
+                        *n >= keep || (policy.delete_played && self.is_read(&d.item_id))
+                    })
+                    .map(|(_, d)| d),
+            );
+        }
+        out
+    }
+}
@@ -0,0 +1,171 @@
+//! Folders and tags on subscribed feeds.
+//!
+//! A folder is a `/`-separated path such as `Tech/Rust`. It exists while a feed is in it
+//! (or in a subfolder) or while it is listed in [`SubscriptionList::explicit_folders`].
+
+use super::SubscriptionList;
+use crate::feed::Feed;
+use crate::Error;
+use std::collections::BTreeSet;
+
+/// Canonical form of a folder path: segments trimmed, empty segments dropped.
+/// Returns None for a path with no segments (the top level).
+pub fn normalize_folder(path: &str) -> Option<String> {
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
+/// `path` moved from under `from` to under `to`, if it is `from` or inside it.
+fn rebase_path(path: &str, from: &str, to: &str) -> Option<String> {
+    if path == from {
+        return Some(to.to_string());
+    }
+    let rest = path.strip_prefix(from)?.strip_prefix('/')?;
+    Some(format!("{}/{}", to, rest))
+}
+
+impl SubscriptionList {
+    /// Every folder (explicit, holding feeds, and their parents), sorted.
+    pub fn folders(&self) -> Vec<String> {
+        let mut out = BTreeSet::new();
+        let paths = self
+            .explicit_folders
+            .iter()
+            .chain(self.feeds.iter().filter_map(|f| f.folder.as_ref()));
+        for path in paths {
+            let mut prefix = String::new();
+            for segment in path.split('/') {
+                if !prefix.is_empty() {
+                    prefix.push('/');
+                }
+                prefix.push_str(segment);
+                out.insert(prefix.clone());
+            }
+        }
+        out.into_iter().collect()
+    }
+
+    /// Whether `path` is an existing folder.
+    pub fn has_folder(&self, path: &str) -> bool {
+        self.explicit_folders.contains(path) || self.feeds.iter().any(|f| f.in_folder(path))
+    }
+
+    /// Create an (empty) folder. Returns false if it already exists.
+    pub fn create_folder(&mut self, path: &str) -> Result<bool, Error> {
+        let path = normalize_folder(path)
+            .ok_or_else(|| Error::InvalidInput("empty folder name".to_string()))?;
+        if self.folders().contains(&path) {
+            return Ok(false);
+        }
+        self.explicit_folders.insert(path);
+        self.changes.folders = true;
+        Ok(true)
+    }
+
+    /// Move (or rename) folder `from` with its feeds and subfolders to `to`; an empty `to`
+    /// moves its contents to the top level. Returns the number of feeds moved.
+    pub fn move_folder(&mut self, from: &str, to: &str) -> Result<usize, Error> {
+        let from = normalize_folder(from)
+            .filter(|f| self.folders().contains(f))
+            .ok_or_else(|| Error::NotFound(format!("folder not found: {}", from)))?;
+        let to = normalize_folder(to);
+        if let Some(to) = &to {
+            if rebase_path(to, &from, "").is_some() {
+                return Err(Error::InvalidInput(format!(
+                    "cannot move folder {} into itself",
+                    from
+                )));
+            }
+        }
+        let moved = |path: &str| match &to {
+            Some(to) => rebase_path(path, &from, to),
+            // Moving to the top level drops the folder's own segment.
+            None => rebase_path(path, &from, "").map(|p| p.trim_start_matches('/').to_string()),
+        };
+
+        let mut count = 0;
+        for feed in &mut self.feeds {
+            if let Some(new) = feed.folder.as_deref().and_then(moved) {
+                feed.folder = normalize_folder(&new);
+                self.changes.feeds.insert(feed.url.clone());
+                count += 1;
+            }
+        }
+        self.explicit_folders = std::mem::take(&mut self.explicit_folders)
+            .into_iter()
+            .filter_map(|p| match moved(&p) {
+                Some(new) => normalize_folder(&new),
+                None => Some(p),
+            })
+            .collect();
+        self.changes.folders = true;
+        Ok(count)
+    }
+
+    /// Delete folder `path`; its feeds and subfolders move up to its parent.
+    /// Returns the number of feeds moved.
+    pub fn remove_folder(&mut self, path: &str) -> Result<usize, Error> {
+        let parent = normalize_folder(path)
+            .and_then(|p| p.rsplit_once('/').map(|(parent, _)| parent.to_string()))
+            .unwrap_or_default();
+        self.move_folder(path, &parent)
+    }
+
+    /// Put feed `url` in folder `folder` (None or empty = top level).
+    pub fn set_folder(&mut self, url: &str, folder: Option<&str>) -> Result<(), Error> {
+        let feed = self
+            .feeds
+            .iter_mut()
+            .find(|f| f.url == url)
+            .ok_or_else(|| Error::NotFound(format!("feed not found: {}", url)))?;
+        feed.folder = folder.and_then(normalize_folder);
+        self.changes.feeds.insert(url.to_string());
+        Ok(())
+    }
+
+    /// Add (or with `add == false` remove) `tags` on feed `url`.
+    pub fn tag_feed(&mut self, url: &str, tags: &[String], add: bool) -> Result<(), Error> {
+        let feed = self
+            .feeds
+            .iter_mut()
+            .find(|f| f.url == url)
+            .ok_or_else(|| Error::NotFound(format!("feed not found: {}", url)))?;
+        for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if add {
+                if !feed.has_tag(tag) {
+                    feed.tags.push(tag.to_string());
+                }
+            } else {
+                feed.tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+            }
+        }
+        feed.tags.sort_by_key(|t| t.to_lowercase());
+        self.changes.feeds.insert(url.to_string());
+        Ok(())
+    }
+
+    /// Every tag used on a feed or given to an item by a rule, sorted case-insensitively.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        let item_tags = self.item_tags.values().flatten();
+        for tag in self.feeds.iter().flat_map(|f| &f.tags).chain(item_tags) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+        tags.sort_by_key(|t| t.to_lowercase());
+        tags
+    }
+
+    /// Feeds directly in folder `path` (None = top level), in subscription order.
+    pub fn feeds_in(&self, path: Option<&str>) -> Vec<&Feed> {
+        self.feeds
+            .iter()
+            .filter(|f| f.folder.as_deref() == path)
+            .collect()
+    }
+}
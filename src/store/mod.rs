@@ -1,11 +1,34 @@
 //! Persistence for subscription list and cached items.
+//!
+//! [`SubscriptionList`] is the in-memory model. It is persisted through a [`Storage`]
+//! backend: a single JSON file or an SQLite database (see [`open()`]).
 
 use crate::feed::{Feed, FeedItem};
+use crate::rules::Rule;
+use crate::settings::StoreSettings;
+use crate::sync::SyncState;
 use crate::Error;
+use fs2::FileExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+mod downloads;
+mod folders;
+mod playback;
+mod query;
+mod selection;
+mod sqlite;
+
+pub use downloads::{Download, DownloadStatus, MAX_DOWNLOAD_ATTEMPTS};
+pub use folders::normalize_folder;
+pub use playback::PlaybackPosition;
+pub use query::{parse_date_bound, ItemQuery, SortKey};
+pub use selection::FeedSelection;
+pub use sqlite::SqliteStorage;
+
+/// Cached items kept per feed by [`SubscriptionList::add_feed`] unless set otherwise.
 const DEFAULT_CAP_PER_FEED: usize = 500;
 
 /// In-memory subscription list plus cache; persisted to disk.
@@ -14,6 +37,70 @@ pub struct SubscriptionList {
     pub feeds: Vec<Feed>,
     #[serde(default)]
     pub items_by_feed: HashMap<String, Vec<FeedItem>>,
+    /// Ids of items the user has read; kept separate so refresh merges never reset it.
+    #[serde(default)]
+    pub read_items: HashSet<String>,
+    /// Ids of starred items; these are exempt from the per-feed cap and survive `remove_feed`.
+    #[serde(default)]
+    pub starred_items: HashSet<String>,
+    /// Folders created explicitly, which may be empty. Folders holding feeds also exist
+    /// implicitly through [`Feed::folder`]; see [`SubscriptionList::folders`].
+    #[serde(default)]
+    pub explicit_folders: BTreeSet<String>,
+    /// Ids of items hidden by a rule: cached, but left out of item lists and unread counts.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub hidden_items: HashSet<String>,
+    /// Tags given to single items by rules (item id → sorted tags).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub item_tags: HashMap<String, Vec<String>>,
+    /// Filter rules run on new items by [`add_feed`](Self::add_feed) (see [`crate::rules`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<Rule>,
+    /// Enclosure download queue, including finished downloads.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub downloads: Vec<Download>,
+    /// Where playback stopped, by enclosure url.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub playback: HashMap<String, PlaybackPosition>,
+    /// What the last `sync` with a remote aggregator saw (see [`crate::sync`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncState>,
+    /// Incremented on every save through a [`Storage`]; a mismatch at save time means
+    /// another process wrote in between and the save merges instead of overwriting.
+    #[serde(default)]
+    pub(crate) revision: u64,
+    /// Revision of the last save that changed feeds or cached items; caches derived from
+    /// the items, such as the search index, are keyed on it.
+    #[serde(default)]
+    pub(crate) items_revision: u64,
+    /// Unstarred items kept per feed; set by the [`Storage`] the list is loaded from.
+    #[serde(skip)]
+    max_items_per_feed: Option<usize>,
+    /// What changed since the list was loaded from or saved to a [`Storage`].
+    #[serde(skip)]
+    pub(crate) changes: ChangeSet,
+}
+
+/// Change tracking that lets backends such as SQLite write only what changed.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChangeSet {
+    /// True once the list was loaded from or saved to its backend; until then a save
+    /// writes everything and never merges.
+    pub(crate) synced: bool,
+    /// Feeds whose entry or cached items changed (added, refreshed, removed).
+    pub(crate) feeds: HashSet<String>,
+    /// Item ids whose read, starred, hidden or tag state changed.
+    pub(crate) states: HashSet<String>,
+    /// Whether the explicit folder list changed.
+    pub(crate) folders: bool,
+    /// Whether the rules changed.
+    pub(crate) rules: bool,
+    /// Urls of downloads queued, updated or removed.
+    pub(crate) downloads: HashSet<String>,
+    /// Enclosure urls whose playback position was saved or cleared.
+    pub(crate) playback: HashSet<String>,
+    /// Whether the sync state changed.
+    pub(crate) sync: bool,
 }
 
 impl SubscriptionList {
@@ -27,56 +114,567 @@ impl SubscriptionList {
         }
     }
 
-    /// Save to a JSON file.
-    pub fn save(&self, path: &Path) -> Result<(), Error> {
+    /// Save to a JSON file through [`JsonStorage::save`], so the store lock is taken,
+    /// changes another process saved meanwhile are kept (for a list loaded from a
+    /// [`Storage`]) and this list's revision stays current.
+    pub fn save(&mut self, path: &Path) -> Result<(), Error> {
+        JsonStorage::new(path).save(self)
+    }
+
+    /// Write to a JSON file as is. The file is replaced atomically: a crash mid-write
+    /// leaves the previous version intact.
+    fn write(&self, path: &Path) -> Result<(), Error> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| Error::Store(e.to_string()))?;
         }
         let s = serde_json::to_string_pretty(self).map_err(|e| Error::Store(e.to_string()))?;
-        std::fs::write(path, s).map_err(|e| Error::Store(e.to_string()))?;
+        write_atomic(path, s.as_bytes()).map_err(|e| Error::Store(e.to_string()))?;
         Ok(())
     }
 
-    /// Add or replace feed; merge items with cap.
-    pub fn add_feed(&mut self, feed: Feed, items: Vec<FeedItem>) {
+    /// Keep at most `max` unstarred items per feed from now on (default 500).
+    pub fn set_max_items_per_feed(&mut self, max: usize) {
+        self.max_items_per_feed = Some(max.max(1));
+    }
+
+    /// Advance the revision for a save, making it the items' revision too when feeds or
+    /// items changed (or everything is written).
+    pub(crate) fn next_revision(&mut self) {
+        self.revision += 1;
+        if !self.changes.synced || !self.changes.feeds.is_empty() {
+            self.items_revision = self.revision;
+        }
+    }
+
+    /// Re-apply this list's unsaved changes on top of `base`, the version another process
+    /// saved in the meantime, and continue from the result. The changes stay recorded so
+    /// the following write includes them.
+    pub(crate) fn rebase(&mut self, mut base: SubscriptionList) {
+        for url in &self.changes.feeds {
+            match self.feeds.iter().find(|f| &f.url == url) {
+                Some(ours) => match base.feeds.iter_mut().find(|f| &f.url == url) {
+                    Some(slot) => *slot = ours.clone(),
+                    None => base.feeds.push(ours.clone()),
+                },
+                None => base.feeds.retain(|f| &f.url != url),
+            }
+            match self.items_by_feed.remove(url) {
+                Some(items) => base.items_by_feed.insert(url.clone(), items),
+                None => base.items_by_feed.remove(url),
+            };
+        }
+        for id in &self.changes.states {
+            for (ours, theirs) in [
+                (&self.read_items, &mut base.read_items),
+                (&self.starred_items, &mut base.starred_items),
+                (&self.hidden_items, &mut base.hidden_items),
+            ] {
+                if ours.contains(id) {
+                    theirs.insert(id.clone());
+                } else {
+                    theirs.remove(id);
+                }
+            }
+            match self.item_tags.remove(id) {
+                Some(tags) => base.item_tags.insert(id.clone(), tags),
+                None => base.item_tags.remove(id),
+            };
+        }
+        if self.changes.folders {
+            base.explicit_folders = std::mem::take(&mut self.explicit_folders);
+            base.changes.folders = true;
+        }
+        if self.changes.rules {
+            base.rules = std::mem::take(&mut self.rules);
+            base.changes.rules = true;
+        }
+        for url in &self.changes.downloads {
+            match self.downloads.iter().find(|d| &d.url == url) {
+                Some(ours) => match base.downloads.iter_mut().find(|d| &d.url == url) {
+                    Some(slot) => *slot = ours.clone(),
+                    None => base.downloads.push(ours.clone()),
+                },
+                None => base.downloads.retain(|d| &d.url != url),
+            }
+        }
+        for url in &self.changes.playback {
+            match self.playback.remove(url) {
+                Some(position) => base.playback.insert(url.clone(), position),
+                None => base.playback.remove(url),
+            };
+        }
+        if self.changes.sync {
+            base.sync = self.sync.take();
+            base.changes.sync = true;
+        }
+        base.changes.feeds = std::mem::take(&mut self.changes.feeds);
+        base.changes.states = std::mem::take(&mut self.changes.states);
+        base.changes.downloads = std::mem::take(&mut self.changes.downloads);
+        base.changes.playback = std::mem::take(&mut self.changes.playback);
+        base.max_items_per_feed = self.max_items_per_feed;
+        *self = base;
+    }
+
+    /// Add or replace feed; merge items with cap (starred items are never dropped).
+    /// Items not cached yet go through the [rules](crate::rules) first. If the feed's
+    /// policy says so, the first enclosure of each one newer than the cached items (any,
+    /// when none is dated) is queued for download unless a rule hid it or marked it read.
+    pub fn add_feed(&mut self, mut feed: Feed, items: Vec<FeedItem>) {
         let url = feed.url.clone();
+        if let Some(old) = self.feeds.iter().find(|f| f.url == url) {
+            feed.created_at = old.created_at.or(feed.created_at);
+            feed.title = feed.title.or_else(|| old.title.clone());
+            feed.folder = feed.folder.or_else(|| old.folder.clone());
+            feed.tags = old.tags.clone();
+            feed.download_policy = old.download_policy.clone();
+        }
         let existing = self.items_by_feed.remove(&url).unwrap_or_default();
-        let mut combined: Vec<FeedItem> = existing.into_iter().chain(items).collect();
+        let items: Vec<FeedItem> = if self.rules.is_empty() && !feed.download_policy.auto {
+            items
+        } else {
+            let rules = crate::rules::compile(&self.rules);
+            let known: HashSet<&str> = existing.iter().map(|i| i.id.as_str()).collect();
+            let (old, new): (Vec<FeedItem>, Vec<FeedItem>) = items
+                .into_iter()
+                .partition(|i| known.contains(i.id.as_str()));
+            let new: Vec<FeedItem> = new
+                .into_iter()
+                .filter(|i| crate::rules::apply(self, &rules, &feed, i))
+                .collect();
+            if feed.download_policy.auto {
+                // Items dropped by the cap come back as new; only newer ones are episodes.
+                let newest = existing.iter().filter_map(|i| i.published).max();
+                for item in &new {
+                    let newer = match (item.published, newest) {
+                        (_, None) => true,
+                        (Some(published), Some(newest)) => published > newest,
+                        (None, Some(_)) => false,
+                    };
+                    if newer && !self.is_hidden(&item.id) && !self.is_read(&item.id) {
+                        self.queue_enclosure(item, 0);
+                    }
+                }
+            }
+            new.into_iter().chain(old).collect()
+        };
+        // Fresh copies win over cached ones; dedup by id regardless of position.
+        let mut seen = HashSet::new();
+        let mut combined: Vec<FeedItem> = items
+            .into_iter()
+            .chain(existing)
+            .filter(|i| seen.insert(i.id.clone()))
+            .collect();
         combined.sort_by_key(|i| std::cmp::Reverse(i.published));
-        combined.dedup_by_key(|i| i.id.clone());
-        if combined.len() > DEFAULT_CAP_PER_FEED {
-            combined.truncate(DEFAULT_CAP_PER_FEED);
+        let cap = self.max_items_per_feed.unwrap_or(DEFAULT_CAP_PER_FEED);
+        let mut kept = 0usize;
+        let (combined, dropped): (Vec<FeedItem>, Vec<FeedItem>) =
+            combined.into_iter().partition(|i| {
+                if self.starred_items.contains(&i.id) {
+                    return true;
+                }
+                kept += 1;
+                kept <= cap
+            });
+        for i in dropped {
+            self.forget_state(&i.id);
         }
+        self.changes.feeds.insert(url.clone());
         self.items_by_feed.insert(url.clone(), combined);
-        self.feeds.retain(|f| f.url != url);
+        // Replace in place so refreshes (which finish in any order) keep the feed order.
+        match self.feeds.iter_mut().find(|f| f.url == url) {
+            Some(slot) => *slot = feed,
+            None => self.feeds.push(feed),
+        }
+    }
+
+    /// Add a feed without items (e.g. from OPML) unless its url is already subscribed.
+    /// Returns false if it was already present.
+    pub fn subscribe(&mut self, mut feed: Feed) -> bool {
+        if self.feeds.iter().any(|f| f.url == feed.url) {
+            return false;
+        }
+        feed.created_at = feed.created_at.or_else(|| Some(chrono::Utc::now()));
+        self.changes.feeds.insert(feed.url.clone());
         self.feeds.push(feed);
+        true
     }
 
-    /// Remove feed and its items.
+    /// Remove feed and its items. Starred items stay cached under the feed url.
     pub fn remove_feed(&mut self, url: &str) -> bool {
         let ok = self.feeds.iter().any(|f| f.url == url);
         self.feeds.retain(|f| f.url != url);
-        self.items_by_feed.remove(url);
+        self.changes.feeds.insert(url.to_string());
+        if let Some(items) = self.items_by_feed.remove(url) {
+            let (starred, removed): (Vec<FeedItem>, Vec<FeedItem>) = items
+                .into_iter()
+                .partition(|i| self.starred_items.contains(&i.id));
+            for i in removed {
+                self.forget_state(&i.id);
+            }
+            if !starred.is_empty() {
+                self.items_by_feed.insert(url.to_string(), starred);
+            }
+        }
         ok
     }
 
+    /// Record a fetch that found nothing new (e.g. HTTP 304): the feed is healthy
+    /// but its items are unchanged.
+    pub fn touch_feed(&mut self, url: &str) {
+        if let Some(f) = self.feeds.iter_mut().find(|f| f.url == url) {
+            let now = chrono::Utc::now();
+            f.last_fetched = Some(now);
+            f.last_success = Some(now);
+            f.last_error = None;
+            f.consecutive_failures = 0;
+            f.refresh_hints.retry_after_secs = None;
+            self.changes.feeds.insert(url.to_string());
+        }
+    }
+
+    /// Record a failed fetch: keep cached items, remember the error and count the failure.
+    pub fn record_failure(&mut self, url: &str, error: &str) {
+        if let Some(f) = self.feeds.iter_mut().find(|f| f.url == url) {
+            f.last_fetched = Some(chrono::Utc::now());
+            f.last_error = Some(error.to_string());
+            f.consecutive_failures += 1;
+            self.changes.feeds.insert(url.to_string());
+        }
+    }
+
     /// All items from all feeds, or for one feed if url is Some.
     pub fn items(&self, feed_url: Option<&str>) -> Vec<&FeedItem> {
-        let mut out: Vec<&FeedItem> = self
-            .items_by_feed
-            .iter()
-            .filter(|(u, _)| feed_url.map_or(true, |f| *u == f))
-            .flat_map(|(_, items)| items.iter())
-            .collect();
+        if let Some(url) = feed_url {
+            return self.feed_items(url).iter().collect();
+        }
+        let mut out: Vec<&FeedItem> = self.items_by_feed.values().flatten().collect();
         out.sort_by_key(|i| std::cmp::Reverse(i.published));
         out
     }
 
+    /// Cached items of feed `url`, newest first (as [`add_feed`](Self::add_feed) keeps them).
+    pub fn feed_items(&self, url: &str) -> &[FeedItem] {
+        self.items_by_feed.get(url).map_or(&[], Vec::as_slice)
+    }
+
     /// Find one item by id (and optionally feed url).
     pub fn get_item(&self, id: &str, feed_url: Option<&str>) -> Option<&FeedItem> {
         self.items(feed_url).into_iter().find(|i| i.id == id)
     }
+
+    /// The first of `ids` that no cached item has, looking each up in a set built once.
+    pub(crate) fn unknown_item<'a>(&self, ids: &'a [String]) -> Option<&'a String> {
+        let known: HashSet<&str> = self
+            .items_by_feed
+            .values()
+            .flatten()
+            .map(|i| i.id.as_str())
+            .collect();
+        ids.iter().find(|id| !known.contains(id.as_str()))
+    }
+
+    /// Whether the item with this id has been marked read.
+    pub fn is_read(&self, id: &str) -> bool {
+        self.read_items.contains(id)
+    }
+
+    /// Mark one item read or unread. Returns false if no cached item has this id.
+    pub fn set_read(&mut self, id: &str, read: bool) -> bool {
+        if self.get_item(id, None).is_none() {
+            return false;
+        }
+        self.set_read_unchecked(id, read);
+        true
+    }
+
+    /// [`set_read`](Self::set_read) without looking the id up, for ids taken from this list.
+    pub(crate) fn set_read_unchecked(&mut self, id: &str, read: bool) {
+        if read {
+            self.read_items.insert(id.to_string());
+        } else {
+            self.read_items.remove(id);
+        }
+        self.changes.states.insert(id.to_string());
+    }
+
+    /// Mark every item in `ids` read; returns how many were unread. Unlike
+    /// [`set_read`](Self::set_read) the ids are not looked up, so pass ids taken from this list.
+    pub(crate) fn mark_all_read(&mut self, ids: impl IntoIterator<Item = String>) -> usize {
+        let mut count = 0;
+        for id in ids {
+            if self.read_items.insert(id.clone()) {
+                self.changes.states.insert(id);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Set the read and starred state of item `id` without looking it up, for ids taken from
+    /// this list or of items about to be added to it.
+    pub(crate) fn set_state_unchecked(&mut self, id: &str, read: bool, starred: bool) {
+        for (set, on) in [
+            (&mut self.read_items, read),
+            (&mut self.starred_items, starred),
+        ] {
+            if on {
+                set.insert(id.to_string());
+            } else {
+                set.remove(id);
+            }
+        }
+        self.changes.states.insert(id.to_string());
+    }
+
+    /// Whether the item with this id has been starred.
+    pub fn is_starred(&self, id: &str) -> bool {
+        self.starred_items.contains(id)
+    }
+
+    /// Star or unstar one item. Returns false if no cached item has this id.
+    /// Unstarring an item whose feed was removed drops it from the cache.
+    pub fn set_starred(&mut self, id: &str, starred: bool) -> bool {
+        if self.get_item(id, None).is_none() {
+            return false;
+        }
+        self.set_starred_unchecked(id, starred);
+        true
+    }
+
+    /// [`set_starred`](Self::set_starred) without looking the id up, for ids taken from this
+    /// list.
+    pub(crate) fn set_starred_unchecked(&mut self, id: &str, starred: bool) {
+        self.changes.states.insert(id.to_string());
+        if starred {
+            self.starred_items.insert(id.to_string());
+            return;
+        }
+        self.starred_items.remove(id);
+        let orphaned: Vec<String> = self
+            .items_by_feed
+            .keys()
+            .filter(|u| !self.feeds.iter().any(|f| &f.url == *u))
+            .cloned()
+            .collect();
+        let mut dropped = false;
+        for url in orphaned {
+            if let Some(items) = self.items_by_feed.get_mut(&url) {
+                let before = items.len();
+                items.retain(|i| i.id != id);
+                if items.len() != before {
+                    dropped = true;
+                    self.changes.feeds.insert(url.clone());
+                }
+                if items.is_empty() {
+                    self.items_by_feed.remove(&url);
+                }
+            }
+        }
+        if dropped && self.get_item(id, None).is_none() {
+            self.forget_state(id);
+        }
+    }
+
+    /// Drop the read, hidden and tag state of an item that is no longer cached.
+    fn forget_state(&mut self, id: &str) {
+        self.read_items.remove(id);
+        self.hidden_items.remove(id);
+        self.item_tags.remove(id);
+        self.changes.states.insert(id.to_string());
+    }
+
+    /// Whether a rule hid the item with this id.
+    pub fn is_hidden(&self, id: &str) -> bool {
+        self.hidden_items.contains(id)
+    }
+
+    /// Hide item `id` without looking it up (for rules on items about to be added).
+    pub(crate) fn set_hidden_unchecked(&mut self, id: &str) {
+        self.hidden_items.insert(id.to_string());
+        self.changes.states.insert(id.to_string());
+    }
+
+    /// Tags rules gave the item with this id.
+    pub fn item_tags(&self, id: &str) -> &[String] {
+        self.item_tags.get(id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether the item with this id has `tag` (case-insensitive), given by a rule.
+    pub fn item_has_tag(&self, id: &str, tag: &str) -> bool {
+        self.item_tags(id)
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Tag item `id` without looking it up (for rules on items about to be added).
+    pub(crate) fn tag_item_unchecked(&mut self, id: &str, tag: &str) {
+        if self.item_has_tag(id, tag) {
+            return;
+        }
+        let tags = self.item_tags.entry(id.to_string()).or_default();
+        tags.push(tag.to_string());
+        tags.sort_by_key(|t| t.to_lowercase());
+        self.changes.states.insert(id.to_string());
+    }
+
+    /// All starred items, newest first.
+    pub fn starred(&self) -> Vec<&FeedItem> {
+        self.items(None)
+            .into_iter()
+            .filter(|i| self.is_starred(&i.id))
+            .collect()
+    }
+
+    /// Number of unread items across all feeds, or for one feed if url is Some.
+    /// Hidden items are not counted.
+    pub fn unread_count(&self, feed_url: Option<&str>) -> usize {
+        self.items(feed_url)
+            .iter()
+            .filter(|i| !self.is_read(&i.id) && !self.is_hidden(&i.id))
+            .count()
+    }
 }
 
 /// Alias for compatibility with plan.
 pub type Store = SubscriptionList;
+
+/// Persistence backend for a [`SubscriptionList`].
+pub trait Storage: Send {
+    /// Load the whole subscription list (empty if nothing is stored yet).
+    fn load(&self) -> Result<SubscriptionList, Error>;
+
+    /// Persist `list`. Backends may write only what changed since it was loaded or last saved.
+    /// If another process saved in the meantime, `list`'s changes are merged onto that
+    /// version (and `list` is updated to the merged result) instead of overwriting it.
+    fn save(&self, list: &mut SubscriptionList) -> Result<(), Error>;
+
+    /// Look up one cached item by id without necessarily loading the whole store.
+    fn get_item(&self, id: &str) -> Result<Option<FeedItem>, Error>;
+
+    /// Cached items of feed `url`, newest first, without necessarily loading the whole store.
+    fn feed_items(&self, url: &str) -> Result<Vec<FeedItem>, Error>;
+}
+
+/// The original single-file JSON backend.
+#[derive(Clone, Debug)]
+pub struct JsonStorage {
+    path: PathBuf,
+    max_items_per_feed: usize,
+}
+
+impl JsonStorage {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            max_items_per_feed: DEFAULT_CAP_PER_FEED,
+        }
+    }
+
+    /// Lists loaded from this store keep at most `max` unstarred items per feed.
+    pub fn with_max_items_per_feed(mut self, max: usize) -> Self {
+        self.max_items_per_feed = max;
+        self
+    }
+}
+
+impl Storage for JsonStorage {
+    fn load(&self) -> Result<SubscriptionList, Error> {
+        let mut list = SubscriptionList::load(&self.path)?;
+        list.set_max_items_per_feed(self.max_items_per_feed);
+        list.changes.synced = true;
+        Ok(list)
+    }
+
+    fn save(&self, list: &mut SubscriptionList) -> Result<(), Error> {
+        let _lock = lock_exclusive(&self.path)?;
+        let current = SubscriptionList::load(&self.path)?;
+        if list.changes.synced && current.revision != list.revision {
+            list.rebase(current);
+        } else {
+            list.revision = list.revision.max(current.revision);
+        }
+        list.next_revision();
+        list.write(&self.path)?;
+        list.changes = ChangeSet {
+            synced: true,
+            ..ChangeSet::default()
+        };
+        Ok(())
+    }
+
+    fn get_item(&self, id: &str) -> Result<Option<FeedItem>, Error> {
+        Ok(self.load()?.get_item(id, None).cloned())
+    }
+
+    fn feed_items(&self, url: &str) -> Result<Vec<FeedItem>, Error> {
+        Ok(self.load()?.feed_items(url).to_vec())
+    }
+}
+
+/// Write `contents` to a temporary file next to `path`, flush it to disk, then rename it
+/// over `path`.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Take the advisory lock that serializes writers of the store at `path` (a `.lock` file
+/// beside it, since the store itself is replaced on every save). Released on drop.
+fn lock_exclusive(path: &Path) -> Result<std::fs::File, Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| Error::Store(e.to_string()))?;
+    }
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)
+        .map_err(|e| Error::Store(e.to_string()))?;
+    file.lock_exclusive()
+        .map_err(|e| Error::Store(e.to_string()))?;
+    Ok(file)
+}
+
+/// Open the backend for `path`: SQLite for `.db` / `.sqlite` / `.sqlite3`, JSON otherwise.
+///
+/// When an SQLite database does not exist yet but a JSON store with the same stem does
+/// (e.g. `data.json` next to `data.db`), the JSON store is migrated into it once.
+/// The JSON file is left in place as a backup.
+pub fn open(path: &Path) -> Result<Box<dyn Storage>, Error> {
+    open_with(path, &StoreSettings::default())
+}
+
+/// Like [`open()`], with the per-feed item cap of `settings`.
+pub fn open_with(path: &Path, settings: &StoreSettings) -> Result<Box<dyn Storage>, Error> {
+    let max = settings.max_items_per_feed;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    match ext.as_deref() {
+        Some("db" | "sqlite" | "sqlite3") => {
+            let json = path.with_extension("json");
+            let migrate = !path.exists() && json.exists();
+            let storage = SqliteStorage::open(path)?.with_max_items_per_feed(max);
+            if migrate {
+                storage.save(&mut SubscriptionList::load(&json)?)?;
+            }
+            Ok(Box::new(storage))
+        }
+        _ => Ok(Box::new(
+            JsonStorage::new(path).with_max_items_per_feed(max),
+        )),
+    }
+}
@@ -0,0 +1,64 @@
+//! Playback positions of enclosures, so listening resumes where it stopped.
+
+use super::SubscriptionList;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Within this many seconds of the end (or the last tenth of a short episode), an
+/// episode counts as played.
+const PLAYED_MARGIN_SECS: u64 = 10;
+
+/// Where playback of one enclosure stopped.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaybackPosition {
+    pub item_id: String,
+    pub position_secs: u64,
+    /// Length of the audio, when known.
+    pub duration_secs: Option<u64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SubscriptionList {
+    /// The saved position of enclosure `url`.
+    pub fn playback_position(&self, url: &str) -> Option<&PlaybackPosition> {
+        self.playback.get(url)
+    }
+
+    /// Save the position of enclosure `url` of item `item_id`. Near the end the episode is
+    /// played instead: the position is cleared and the item marked read. Returns true then.
+    pub fn set_playback_position(
+        &mut self,
+        item_id: &str,
+        url: &str,
+        position_secs: u64,
+        duration_secs: Option<u64>,
+    ) -> bool {
+        let played =
+            duration_secs.is_some_and(|d| position_secs + PLAYED_MARGIN_SECS.min(d / 10) >= d);
+        if played {
+            self.clear_playback_position(url);
+            self.set_read(item_id, true);
+            return true;
+        }
+        self.playback.insert(
+            url.to_string(),
+            PlaybackPosition {
+                item_id: item_id.to_string(),
+                position_secs,
+                duration_secs,
+                updated_at: Utc::now(),
+            },
+        );
+        self.changes.playback.insert(url.to_string());
+        false
+    }
+
+    /// Forget the position of enclosure `url`; returns false if none was saved.
+    pub fn clear_playback_position(&mut self, url: &str) -> bool {
+        if self.playback.remove(url).is_none() {
+            return false;
+        }
+        self.changes.playback.insert(url.to_string());
+        true
+    }
+}
@@ -0,0 +1,252 @@
+//! Item queries: filter, sort and page the cached items of a [`SubscriptionList`].
+//! Shared by `list-items` and the GUI article list.
+
+use super::SubscriptionList;
+use crate::feed::{Feed, FeedItem};
+use crate::Error;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Order of query results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// Newest first; undated items last.
+    #[default]
+    Date,
+    /// Title, alphabetically (case-insensitive).
+    Title,
+    /// Feed title (or url), alphabetically; newest first within a feed.
+    Feed,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 3] = [SortKey::Date, SortKey::Title, SortKey::Feed];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Date => "date",
+            SortKey::Title => "title",
+            SortKey::Feed => "feed",
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SortKey::ALL
+            .into_iter()
+            .find(|k| k.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown sort key {:?} (expected date, title or feed)", s))
+    }
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Which items to list and in what order. The default lists every item, newest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemQuery {
+    /// Feed url, or text the feed title contains (case-insensitive).
+    pub feed: Option<String>,
+    /// Only feeds in this folder or its subfolders.
+    pub folder: Option<String>,
+    /// Only feeds with this tag, or items a rule gave it (case-insensitive).
+    pub tag: Option<String>,
+    /// Only items not marked read.
+    pub unread: bool,
+    /// Only starred items.
+    pub starred: bool,
+    /// Only items published at or after this time (undated items are excluded).
+    pub since: Option<DateTime<Utc>>,
+    /// Only items published at or before this time (undated items are excluded).
+    pub until: Option<DateTime<Utc>>,
+    /// Only items with at least one media enclosure.
+    pub has_enclosure: bool,
+    /// Only items with an author whose name contains this text (case-insensitive).
+    pub author: Option<String>,
+    /// Only items with a category containing this text (case-insensitive).
+    pub category: Option<String>,
+    /// Also list items hidden by rules.
+    pub hidden: bool,
+    pub sort: SortKey,
+    /// Reverse the sort order.
+    pub reverse: bool,
+    /// Number of matching items to skip.
+    pub offset: usize,
+    /// Maximum number of items returned.
+    pub limit: Option<usize>,
+}
+
+impl ItemQuery {
+    /// Whether the query restricts which feeds' items are listed.
+    fn selects_feeds(&self) -> bool {
+        self.feed.is_some() || self.folder.is_some() || self.tag.is_some()
+    }
+
+    /// Subscribed feeds matching the `feed`, `folder` and `tag` criteria (all feeds if
+    /// none is set), in subscription order. Used to pick feeds to refresh.
+    pub fn feeds<'a>(&self, store: &'a SubscriptionList) -> Vec<&'a Feed> {
+        let needle = self.feed.as_deref().map(str::to_lowercase);
+        let title_matches = |f: &Feed, needle: &str| {
+            f.title
+                .as_deref()
+                .is_some_and(|t| t.to_lowercase().contains(needle))
+        };
+        store
+            .feeds
+            .iter()
+            .filter(|f| match (&self.feed, &needle) {
+                (Some(wanted), Some(needle)) => f.url == *wanted || title_matches(f, needle),
+                _ => true,
+            })
+            .filter(|f| self.folder.as_deref().map_or(true, |p| f.in_folder(p)))
+            .filter(|f| self.tag.as_deref().map_or(true, |t| f.has_tag(t)))
+            .collect()
+    }
+
+    /// Urls of the feeds whose items the query lists, or None for all.
+    fn feed_urls<'a>(&self, store: &'a SubscriptionList) -> Option<HashSet<&'a str>> {
+        self.selects_feeds().then(|| {
+            let mut urls: HashSet<&'a str> =
+                self.feeds(store).iter().map(|f| f.url.as_str()).collect();
+            // Items kept from a removed feed (starred) are still found by its url.
+            if self.folder.is_none() && self.tag.is_none() {
+                if let Some(feed) = &self.feed {
+                    if let Some((url, _)) = store.items_by_feed.get_key_value(feed) {
+                        urls.insert(url.as_str());
+                    }
+                }
+            }
+            urls
+        })
+    }
+
+    /// Run the query against `store`.
+    pub fn run<'a>(&self, store: &'a SubscriptionList) -> Vec<&'a FeedItem> {
+        let feeds = self.feed_urls(store);
+        // Items tagged by a rule match `tag` in any feed the other criteria select.
+        let untagged = self.tag.as_ref().map(|_| {
+            ItemQuery {
+                tag: None,
+                ..self.clone()
+            }
+            .feed_urls(store)
+        });
+        let in_feeds = |urls: &Option<HashSet<&str>>, i: &FeedItem| {
+            urls.as_ref()
+                .map_or(true, |f| f.contains(i.feed_url.as_str()))
+        };
+        let any_contains = |values: &[String], needle: &Option<String>| {
+            needle.as_deref().map(str::to_lowercase).map_or(true, |n| {
+                values.iter().any(|v| v.to_lowercase().contains(&n))
+            })
+        };
+        // Without tags, only the selected feeds' items are candidates.
+        let candidates = match (&feeds, &untagged) {
+            (Some(urls), None) => {
+                let mut items: Vec<&FeedItem> =
+                    urls.iter().flat_map(|url| store.feed_items(url)).collect();
+                items.sort_by_key(|i| std::cmp::Reverse(i.published));
+                items
+            }
+            _ => store.items(None),
+        };
+        let mut items: Vec<&FeedItem> = candidates
+            .into_iter()
+            .filter(|i| {
+                in_feeds(&feeds, i)
+                    || untagged.as_ref().is_some_and(|urls| {
+                        in_feeds(urls, i)
+                            && self
+                                .tag
+                                .as_deref()
+                                .is_some_and(|t| store.item_has_tag(&i.id, t))
+                    })
+            })
+            .filter(|i| self.hidden || !store.is_hidden(&i.id))
+            .filter(|i| !self.unread || !store.is_read(&i.id))
+            .filter(|i| !self.starred || store.is_starred(&i.id))
+            .filter(|i| {
+                self.since
+                    .map_or(true, |s| i.published.is_some_and(|p| p >= s))
+            })
+            .filter(|i| {
+                self.until
+                    .map_or(true, |u| i.published.is_some_and(|p| p <= u))
+            })
+            .filter(|i| !self.has_enclosure || !i.enclosures.is_empty())
+            .filter(|i| any_contains(&i.authors, &self.author))
+            .filter(|i| any_contains(&i.categories, &self.category))
+            .collect();
+
+        // `items()` is already newest first; the sorts below are stable.
+        match self.sort {
+            SortKey::Date => {}
+            SortKey::Title => items.sort_by_cached_key(|i| i.title.to_lowercase()),
+            SortKey::Feed => {
+                let titles: HashMap<&str, String> = store
+                    .feeds
+                    .iter()
+                    .filter_map(|f| Some((f.url.as_str(), f.title.as_deref()?.to_lowercase())))
+                    .collect();
+                items.sort_by_cached_key(|i| {
+                    titles
+                        .get(i.feed_url.as_str())
+                        .cloned()
+                        .unwrap_or_else(|| i.feed_url.to_lowercase())
+                });
+            }
+        }
+        if self.reverse {
+            items.reverse();
+        }
+        items
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// Parse a date bound for [`ItemQuery::since`] / [`ItemQuery::until`]: RFC 3339,
+/// `YYYY-MM-DD HH:MM` or `YYYY-MM-DD` (UTC), or an age such as `36h`, `7d` or `2w`.
+/// A bare date means the start of that day, or its end when `end_of_day` is set.
+pub fn parse_date_bound(s: &str, end_of_day: bool) -> Result<DateTime<Utc>, Error> {
+    let s = s.trim();
+    if let Ok(d) = DateTime::parse_from_rfc3339(s) {
+        return Ok(d.with_timezone(&Utc));
+    }
+    if let Ok(d) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M") {
+        return Ok(Utc.from_utc_datetime(&d));
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let start = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap_or_default());
+        return Ok(if end_of_day {
+            start + Duration::days(1) - Duration::seconds(1)
+        } else {
+            start
+        });
+    }
+    let (amount, unit) = match s.char_indices().last() {
+        Some((i, c)) => (s[..i].parse::<i64>(), c),
+        None => ("".parse::<i64>(), ' '),
+    };
+    let age = match (amount, unit) {
+        (Ok(n), 'h') => Some(Duration::hours(n)),
+        (Ok(n), 'd') => Some(Duration::days(n)),
+        (Ok(n), 'w') => Some(Duration::weeks(n)),
+        _ => None,
+    };
+    age.map(|a| Utc::now() - a).ok_or_else(|| {
+        Error::Parse(format!(
+            "invalid date {:?} (expected YYYY-MM-DD, YYYY-MM-DD HH:MM, RFC 3339 or an age like 7d)",
+            s
+        ))
+    })
+}
@@ -0,0 +1,88 @@
+//! Feed-list selection shared by the GUI and the TUI: all items, starred, one feed, a
+//! folder or a tag.
+
+use super::{ItemQuery, SubscriptionList};
+
+/// Entry selected in a feed list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FeedSelection {
+    /// Every cached item.
+    #[default]
+    All,
+    /// Starred items from any feed (including removed ones).
+    Starred,
+    /// One subscribed feed, by url.
+    Feed(String),
+    /// Feeds in a folder (and its subfolders), by path.
+    Folder(String),
+    /// Feeds with a tag.
+    Tag(String),
+}
+
+impl FeedSelection {
+    /// Feed url when a single feed is selected.
+    pub fn feed_url(&self) -> Option<&str> {
+        match self {
+            FeedSelection::Feed(url) => Some(url.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Query for the items of this selection; article-list filters are applied on top.
+    pub fn query(&self) -> ItemQuery {
+        match self {
+            FeedSelection::All => ItemQuery::default(),
+            FeedSelection::Starred => ItemQuery {
+                starred: true,
+                ..ItemQuery::default()
+            },
+            FeedSelection::Feed(url) => ItemQuery {
+                feed: Some(url.clone()),
+                ..ItemQuery::default()
+            },
+            FeedSelection::Folder(path) => ItemQuery {
+                folder: Some(path.clone()),
+                ..ItemQuery::default()
+            },
+            FeedSelection::Tag(tag) => ItemQuery {
+                tag: Some(tag.clone()),
+                ..ItemQuery::default()
+            },
+        }
+    }
+
+    /// `filters` (unread, dates, sort, ...) narrowed to this selection.
+    pub fn filter(&self, filters: &ItemQuery) -> ItemQuery {
+        let base = self.query();
+        ItemQuery {
+            feed: base.feed,
+            folder: base.folder,
+            tag: base.tag,
+            starred: base.starred || filters.starred,
+            ..filters.clone()
+        }
+    }
+
+    /// Selectable entries in display order (used for arrow-key navigation): folders
+    /// depth-first with their feeds, then top-level feeds, then tags.
+    pub fn entries(store: &SubscriptionList) -> Vec<FeedSelection> {
+        let mut out = vec![FeedSelection::All, FeedSelection::Starred];
+        for path in store.folders() {
+            out.push(FeedSelection::Folder(path.clone()));
+            out.extend(
+                store
+                    .feeds_in(Some(&path))
+                    .into_iter()
+                    .map(|f| FeedSelection::Feed(f.url.clone())),
+            );
+        }
+        out.extend(
+            store
+                .feeds_in(None)
+                .into_iter()
+                .map(|f| FeedSelection::Feed(f.url.clone())),
+        );
+        out.extend(store.tags().into_iter().map(FeedSelection::Tag));
+        out
+    }
+}
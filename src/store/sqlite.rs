@@ -0,0 +1,481 @@
+//! SQLite backend: feeds, items, item states, downloads and playback positions in
+//! indexed tables.
+//!
+//! Rows hold the serialized `Feed` / `FeedItem` / `Download` so new fields need no schema
+//! change; the columns next to them exist for lookups. The remaining small fields (folders,
+//! rules, sync state, revisions) are one `meta` row each. Saves write only what the list's
+//! change set records, inside an immediate transaction so concurrent writers (CLI and GUI)
+//! are serialized by SQLite's own locking.
+
+use super::{ChangeSet, Storage, SubscriptionList};
+use crate::feed::{Feed, FeedItem};
+use crate::Error;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde_json::{Map, Value};
+use std::path::Path;
+use std::time::Duration;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS feeds (
+    url      TEXT PRIMARY KEY,
+    position INTEGER NOT NULL,
+    data     TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS items (
+    feed_url  TEXT NOT NULL,
+    id        TEXT NOT NULL,
+    position  INTEGER NOT NULL,
+    published TEXT,
+    data      TEXT NOT NULL,
+    PRIMARY KEY (feed_url, id)
+);
+CREATE INDEX IF NOT EXISTS items_by_id ON items (id);
+CREATE TABLE IF NOT EXISTS read_items (id TEXT PRIMARY KEY);
+CREATE TABLE IF NOT EXISTS starred_items (id TEXT PRIMARY KEY);
+CREATE TABLE IF NOT EXISTS hidden_items (id TEXT PRIMARY KEY);
+CREATE TABLE IF NOT EXISTS item_tags (id TEXT PRIMARY KEY, tags TEXT NOT NULL);
+CREATE TABLE IF NOT EXISTS downloads (
+    url      TEXT PRIMARY KEY,
+    position INTEGER NOT NULL,
+    data     TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS playback (url TEXT PRIMARY KEY, data TEXT NOT NULL);
+CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+";
+
+/// Top-level `SubscriptionList` fields that have their own tables; every other field is
+/// a row in `meta`.
+const TABLE_FIELDS: [&str; 8] = [
+    "feeds",
+    "items_by_feed",
+    "read_items",
+    "starred_items",
+    "hidden_items",
+    "item_tags",
+    "downloads",
+    "playback",
+];
+
+/// Id sets stored one id per row.
+const ID_TABLES: [&str; 3] = ["read_items", "starred_items", "hidden_items"];
+
+/// `meta` row of databases written before each field had its own row: all of them as
+/// one JSON object. Migrated by [`SqliteStorage::open`].
+const LEGACY_EXTRA: &str = "extra";
+
+fn store_err(e: impl std::fmt::Display) -> Error {
+    Error::Store(e.to_string())
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String, Error> {
+    serde_json::to_string(value).map_err(store_err)
+}
+
+/// Subscription list stored in an SQLite database file.
+pub struct SqliteStorage {
+    conn: Connection,
+    max_items_per_feed: usize,
+}
+
+impl SqliteStorage {
+    /// Open (creating if needed) the database at `path`.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(store_err)?;
+        }
+        let conn = Connection::open(path).map_err(store_err)?;
+        conn.busy_timeout(Duration::from_secs(5))
+            .map_err(store_err)?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(store_err)?;
+        conn.execute_batch(SCHEMA).map_err(store_err)?;
+        let storage = Self {
+            conn,
+            max_items_per_feed: super::DEFAULT_CAP_PER_FEED,
+        };
+        storage.migrate_extra()?;
+        Ok(storage)
+    }
+
+    /// Lists loaded from this database keep at most `max` unstarred items per feed.
+    pub fn with_max_items_per_feed(mut self, max: usize) -> Self {
+        self.max_items_per_feed = max;
+        self
+    }
+
+    /// Move the fields of a legacy `extra` row into their tables and rows.
+    fn migrate_extra(&self) -> Result<(), Error> {
+        if read_meta_row(&self.conn, LEGACY_EXTRA)?.is_none() {
+            return Ok(());
+        }
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)
+            .map_err(store_err)?;
+        if read_meta_row(&tx, LEGACY_EXTRA)?.is_some() {
+            let mut list = read_list(&tx)?;
+            list.changes.synced = false;
+            write_list(&tx, &mut list)?;
+            tx.execute("DELETE FROM meta WHERE key = ?1", [LEGACY_EXTRA])
+                .map_err(store_err)?;
+        }
+        tx.commit().map_err(store_err)
+    }
+}
+
+fn insert_items(tx: &Transaction<'_>, url: &str, items: &[FeedItem]) -> Result<(), Error> {
+    let mut stmt = tx
+        .prepare_cached(
+            "INSERT OR REPLACE INTO items (feed_url, id, position, published, data)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .map_err(store_err)?;
+    for (pos, item) in items.iter().enumerate() {
+        stmt.execute(params![
+            url,
+            item.id,
+            pos as i64,
+            item.published.map(|d| d.to_rfc3339()),
+            to_json(item)?
+        ])
+        .map_err(store_err)?;
+    }
+    Ok(())
+}
+
+fn write_state(tx: &Transaction<'_>, table: &str, id: &str, on: bool) -> Result<(), Error> {
+    let sql = if on {
+        format!("INSERT OR IGNORE INTO {} (id) VALUES (?1)", table)
+    } else {
+        format!("DELETE FROM {} WHERE id = ?1", table)
+    };
+    tx.prepare_cached(&sql)
+        .and_then(|mut stmt| stmt.execute([id]))
+        .map_err(store_err)?;
+    Ok(())
+}
+
+/// Write (or with `data == None` delete) the row keyed `key` of a two-column table.
+fn write_row(
+    tx: &Transaction<'_>,
+    table: &str,
+    key: &str,
+    data: Option<String>,
+) -> Result<(), Error> {
+    match data {
+        Some(data) => tx
+            .prepare_cached(&format!("INSERT OR REPLACE INTO {} VALUES (?1, ?2)", table))
+            .and_then(|mut stmt| stmt.execute([key, &data])),
+        None => tx
+            .prepare_cached(&format!(
+                "DELETE FROM {} WHERE {} = ?1",
+                table,
+                key_column(table)
+            ))
+            .and_then(|mut stmt| stmt.execute([key])),
+    }
+    .map_err(store_err)?;
+    Ok(())
+}
+
+/// Name of the key column of `table`.
+fn key_column(table: &str) -> &'static str {
+    match table {
+        "item_tags" => "id",
+        "meta" => "key",
+        _ => "url",
+    }
+}
+
+/// Insert the rows of an emptied ordered table, in order.
+fn insert_ordered<'a>(
+    tx: &Transaction<'_>,
+    table: &str,
+    rows: impl Iterator<Item = (&'a str, Result<String, Error>)>,
+) -> Result<(), Error> {
+    let sql = format!(
+        "INSERT INTO {} (url, position, data) VALUES (?1, ?2, ?3)",
+        table
+    );
+    let mut stmt = tx.prepare_cached(&sql).map_err(store_err)?;
+    for (pos, (url, data)) in rows.enumerate() {
+        stmt.execute(params![url, pos as i64, data?])
+            .map_err(store_err)?;
+    }
+    Ok(())
+}
+
+/// Write (or with `data == None` delete) the row keyed `url` of an ordered table. A new
+/// row goes after the others; an existing one keeps its place.
+fn write_ordered(
+    tx: &Transaction<'_>,
+    table: &str,
+    url: &str,
+    data: Option<String>,
+) -> Result<(), Error> {
+    let Some(data) = data else {
+        return write_row(tx, table, url, None);
+    };
+    let sql = format!(
+        "INSERT INTO {0} (url, position, data)
+         VALUES (?1, (SELECT COALESCE(MAX(position) + 1, 0) FROM {0}), ?2)
+         ON CONFLICT (url) DO UPDATE SET data = excluded.data",
+        table
+    );
+    tx.prepare_cached(&sql)
+        .and_then(|mut stmt| stmt.execute([url, &data]))
+        .map_err(store_err)?;
+    Ok(())
+}
+
+/// Fields of `list` that are not stored in their own table, by name.
+fn meta_fields(list: &mut SubscriptionList) -> Result<Map<String, Value>, Error> {
+    // Move the large collections out so serializing the rest is cheap.
+    let feeds = std::mem::take(&mut list.feeds);
+    let items = std::mem::take(&mut list.items_by_feed);
+    let read = std::mem::take(&mut list.read_items);
+    let starred = std::mem::take(&mut list.starred_items);
+    let hidden = std::mem::take(&mut list.hidden_items);
+    let tags = std::mem::take(&mut list.item_tags);
+    let downloads = std::mem::take(&mut list.downloads);
+    let playback = std::mem::take(&mut list.playback);
+    let value = serde_json::to_value(&*list);
+    list.feeds = feeds;
+    list.items_by_feed = items;
+    list.read_items = read;
+    list.starred_items = starred;
+    list.hidden_items = hidden;
+    list.item_tags = tags;
+    list.downloads = downloads;
+    list.playback = playback;
+
+    let Value::Object(mut fields) = value.map_err(store_err)? else {
+        return Err(store_err("subscription list is not a JSON object"));
+    };
+    for key in TABLE_FIELDS {
+        fields.remove(key);
+    }
+    Ok(fields)
+}
+
+/// Whether meta field `key` has to be written for `changes`.
+fn meta_changed(changes: &ChangeSet, key: &str) -> bool {
+    match key {
+        "explicit_folders" => changes.folders,
+        "rules" => changes.rules,
+        "sync" => changes.sync,
+        // Revisions, and fields added later that have no change flag yet.
+        _ => true,
+    }
+}
+
+fn read_meta_row(conn: &Connection, key: &str) -> Result<Option<String>, Error> {
+    conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |r| r.get(0))
+        .optional()
+        .map_err(store_err)
+}
+
+/// The `meta` fields as one JSON object (a legacy `extra` row is expanded into it).
+fn read_meta(conn: &Connection) -> Result<Map<String, Value>, Error> {
+    let mut fields = Map::new();
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM meta")
+        .map_err(store_err)?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+        .map_err(store_err)?;
+    for row in rows {
+        let (key, value) = row.map_err(store_err)?;
+        match serde_json::from_str(&value).map_err(store_err)? {
+            Value::Object(extra) if key == LEGACY_EXTRA => fields.extend(extra),
+            value => {
+                fields.insert(key, value);
+            }
+        }
+    }
+    Ok(fields)
+}
+
+/// Each row of `sql` (two text columns) as a pair.
+fn query_pairs(conn: &Connection, sql: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut stmt = conn.prepare(sql).map_err(store_err)?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+        .map_err(store_err)?;
+    rows.collect::<Result<_, _>>().map_err(store_err)
+}
+
+/// Read the whole subscription list through `conn` (a connection or open transaction).
+fn read_list(conn: &Connection) -> Result<SubscriptionList, Error> {
+    let mut fields = read_meta(conn)?;
+    fields.insert("feeds".to_string(), Value::Array(vec![]));
+    let mut list: SubscriptionList =
+        serde_json::from_value(Value::Object(fields)).map_err(store_err)?;
+
+    for (_, data) in query_pairs(conn, "SELECT url, data FROM feeds ORDER BY position")? {
+        let feed: Feed = serde_json::from_str(&data).map_err(store_err)?;
+        list.feeds.push(feed);
+    }
+    let items = query_pairs(
+        conn,
+        "SELECT feed_url, data FROM items ORDER BY feed_url, position",
+    )?;
+    for (url, data) in items {
+        let item: FeedItem = serde_json::from_str(&data).map_err(store_err)?;
+        list.items_by_feed.entry(url).or_default().push(item);
+    }
+    for (table, set) in [
+        ("read_items", &mut list.read_items),
+        ("starred_items", &mut list.starred_items),
+        ("hidden_items", &mut list.hidden_items),
+    ] {
+        for (id, _) in query_pairs(conn, &format!("SELECT id, id FROM {}", table))? {
+            set.insert(id);
+        }
+    }
+    for (id, tags) in query_pairs(conn, "SELECT id, tags FROM item_tags")? {
+        let tags = serde_json::from_str(&tags).map_err(store_err)?;
+        list.item_tags.insert(id, tags);
+    }
+    for (_, data) in query_pairs(conn, "SELECT url, data FROM downloads ORDER BY position")? {
+        list.downloads
+            .push(serde_json::from_str(&data).map_err(store_err)?);
+    }
+    for (url, data) in query_pairs(conn, "SELECT url, data FROM playback")? {
+        let position = serde_json::from_str(&data).map_err(store_err)?;
+        list.playback.insert(url, position);
+    }
+
+    list.changes.synced = true;
+    Ok(list)
+}
+
+/// Write what `list.changes` records, or everything if the list is not synced yet.
+fn write_list(tx: &Transaction<'_>, list: &mut SubscriptionList) -> Result<(), Error> {
+    let meta = meta_fields(list)?;
+    let changes = &list.changes;
+    if changes.synced {
+        for url in &changes.feeds {
+            let feed = list.feeds.iter().find(|f| &f.url == url);
+            write_ordered(tx, "feeds", url, feed.map(to_json).transpose()?)?;
+            tx.execute("DELETE FROM items WHERE feed_url = ?1", [url])
+                .map_err(store_err)?;
+            if let Some(items) = list.items_by_feed.get(url) {
+                insert_items(tx, url, items)?;
+            }
+        }
+        for id in &changes.states {
+            for (table, set) in ID_TABLES.into_iter().zip([
+                &list.read_items,
+                &list.starred_items,
+                &list.hidden_items,
+            ]) {
+                write_state(tx, table, id, set.contains(id))?;
+            }
+            let tags = list.item_tags.get(id).map(to_json).transpose()?;
+            write_row(tx, "item_tags", id, tags)?;
+        }
+        for url in &changes.downloads {
+            let download = list.downloads.iter().find(|d| &d.url == url);
+            write_ordered(tx, "downloads", url, download.map(to_json).transpose()?)?;
+        }
+        for url in &changes.playback {
+            let position = list.playback.get(url).map(to_json).transpose()?;
+            write_row(tx, "playback", url, position)?;
+        }
+    } else {
+        tx.execute_batch(
+            "DELETE FROM feeds; DELETE FROM items; DELETE FROM read_items;
+             DELETE FROM starred_items; DELETE FROM hidden_items; DELETE FROM item_tags;
+             DELETE FROM downloads; DELETE FROM playback; DELETE FROM meta;",
+        )
+        .map_err(store_err)?;
+        let feeds = list.feeds.iter().map(|f| (f.url.as_str(), to_json(f)));
+        insert_ordered(tx, "feeds", feeds)?;
+        let downloads = list.downloads.iter().map(|d| (d.url.as_str(), to_json(d)));
+        insert_ordered(tx, "downloads", downloads)?;
+        for (url, items) in &list.items_by_feed {
+            insert_items(tx, url, items)?;
+        }
+        for (table, set) in
+            ID_TABLES
+                .into_iter()
+                .zip([&list.read_items, &list.starred_items, &list.hidden_items])
+        {
+            for id in set {
+                write_state(tx, table, id, true)?;
+            }
+        }
+        for (id, tags) in &list.item_tags {
+            write_row(tx, "item_tags", id, Some(to_json(tags)?))?;
+        }
+        for (url, position) in &list.playback {
+            write_row(tx, "playback", url, Some(to_json(position)?))?;
+        }
+    }
+
+    // Absent fields (skipped when empty) are deleted.
+    for key in ["explicit_folders", "rules", "sync"] {
+        if !meta.contains_key(key) && meta_changed(changes, key) {
+            write_row(tx, "meta", key, None)?;
+        }
+    }
+    for (key, value) in meta {
+        if !changes.synced || meta_changed(changes, &key) {
+            write_row(tx, "meta", &key, Some(value.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> Result<SubscriptionList, Error> {
+        let mut list = read_list(&self.conn)?;
+        list.set_max_items_per_feed(self.max_items_per_feed);
+        Ok(list)
+    }
+
+    fn save(&self, list: &mut SubscriptionList) -> Result<(), Error> {
+        // IMMEDIATE takes the write lock up front, so the revision check cannot race.
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)
+            .map_err(store_err)?;
+        let current = read_meta_row(&tx, "revision")?
+            .and_then(|r| r.parse::<u64>().ok())
+            .unwrap_or(0);
+        if list.changes.synced && current != list.revision {
+            list.rebase(read_list(&tx)?);
+        } else {
+            list.revision = list.revision.max(current);
+        }
+        list.next_revision();
+        write_list(&tx, list)?;
+        tx.commit().map_err(store_err)?;
+        list.changes = ChangeSet {
+            synced: true,
+            ..ChangeSet::default()
+        };
+        Ok(())
+    }
+
+    fn get_item(&self, id: &str) -> Result<Option<FeedItem>, Error> {
+        let data: Option<String> = self
+            .conn
+            .query_row("SELECT data FROM items WHERE id = ?1 LIMIT 1", [id], |r| {
+                r.get(0)
+            })
+            .optional()
+            .map_err(store_err)?;
+        data.map(|d| serde_json::from_str(&d).map_err(store_err))
+            .transpose()
+    }
+
+    fn feed_items(&self, url: &str) -> Result<Vec<FeedItem>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT data FROM items WHERE feed_url = ?1 ORDER BY position")
+            .map_err(store_err)?;
+        let rows = stmt
+            .query_map([url], |r| r.get::<_, String>(0))
+            .map_err(store_err)?;
+        rows.map(|row| serde_json::from_str(&row.map_err(store_err)?).map_err(store_err))
+            .collect()
+    }
+}
@@ -0,0 +1,314 @@
+//! Google Reader API client (FreshRSS, and this reader's own `serve --user`). Signs in with
+//! `accounts/ClientLogin`; folders are `user/-/label/<name>` labels.
+
+use super::http::{check, join, json};
+use super::{Account, RemoteEntry, RemoteFeed, SyncService};
+use crate::fetch::HttpOptions;
+use crate::{Error, Result};
+use chrono::{TimeZone, Utc};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::Method;
+use serde::Deserialize;
+use std::cell::OnceCell;
+
+const READING_LIST: &str = "user/-/state/com.google/reading-list";
+const READ: &str = "user/-/state/com.google/read";
+const STARRED: &str = "user/-/state/com.google/starred";
+/// Items per `stream/contents` page.
+const PAGE: usize = 250;
+/// Item ids per `edit-tag` request.
+const EDIT_BATCH: usize = 100;
+
+pub struct GReaderClient {
+    http: Client,
+    base: String,
+    auth: String,
+    /// Action token sent as `T` with every edit, fetched on first use.
+    token: OnceCell<String>,
+}
+
+#[derive(Deserialize)]
+struct Category {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Subscription {
+    id: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    categories: Vec<Category>,
+}
+
+#[derive(Deserialize)]
+struct Subscriptions {
+    subscriptions: Vec<Subscription>,
+}
+
+#[derive(Deserialize)]
+struct Link {
+    href: String,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Origin {
+    stream_id: String,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    id: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    published: Option<i64>,
+    #[serde(default)]
+    canonical: Vec<Link>,
+    #[serde(default)]
+    alternate: Vec<Link>,
+    #[serde(default)]
+    summary: Option<Content>,
+    #[serde(default)]
+    content: Option<Content>,
+    #[serde(default)]
+    categories: Vec<String>,
+    origin: Origin,
+}
+
+#[derive(Deserialize)]
+struct Stream {
+    #[serde(default)]
+    items: Vec<Item>,
+    #[serde(default)]
+    continuation: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuickAdd {
+    #[serde(default)]
+    num_results: u32,
+    #[serde(default)]
+    stream_id: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn label(folder: &str) -> String {
+    format!("user/-/label/{}", folder)
+}
+
+/// Whether a category names state `state` (e.g. `.../com.google/read`) for any user.
+fn has_state(categories: &[String], state: &str) -> bool {
+    let suffix = state.trim_start_matches("user/-");
+    categories
+        .iter()
+        .any(|c| c.starts_with("user/") && c.ends_with(suffix))
+}
+
+impl GReaderClient {
+    /// Sign in with the account's user and password.
+    pub fn login(account: &Account, http: &HttpOptions) -> Result<Self> {
+        let (user, password) = account.credentials()?;
+        let http = http.client()?;
+        let body = check(
+            http.post(join(&account.url, "accounts/ClientLogin"))
+                .form(&[("Email", user), ("Passwd", password)])
+                .send()?,
+        )?
+        .text()?;
+        let auth = body
+            .lines()
+            .find_map(|l| l.strip_prefix("Auth="))
+            .ok_or_else(|| Error::Sync("login response has no Auth token".to_string()))?
+            .trim()
+            .to_string();
+        Ok(Self {
+            http,
+            base: account.url.clone(),
+            auth,
+            token: OnceCell::new(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, join(&self.base, &format!("reader/api/0/{}", path)))
+            .header("Authorization", format!("GoogleLogin auth={}", self.auth))
+    }
+
+    fn action_token(&self) -> Result<&str> {
+        if let Some(token) = self.token.get() {
+            return Ok(token);
+        }
+        let token = check(self.request(Method::GET, "token").send()?)?
+            .text()?
+            .trim()
+            .to_string();
+        Ok(self.token.get_or_init(|| token))
+    }
+
+    /// POST form `fields` (plus the action token) to `path`.
+    fn edit(&self, path: &str, fields: &[(&str, &str)]) -> Result<()> {
+        let mut form: Vec<(&str, &str)> = fields.to_vec();
+        form.push(("T", self.action_token()?));
+        check(self.request(Method::POST, path).form(&form).send()?)?;
+        Ok(())
+    }
+
+    fn edit_tag(&self, ids: &[String], tag: &str, add: bool) -> Result<()> {
+        for batch in ids.chunks(EDIT_BATCH) {
+            let mut fields: Vec<(&str, &str)> = batch.iter().map(|id| ("i", id.as_str())).collect();
+            fields.push((if add { "a" } else { "r" }, tag));
+            self.edit("edit-tag", &fields)?;
+        }
+        Ok(())
+    }
+}
+
+impl SyncService for GReaderClient {
+    fn feeds(&self) -> Result<Vec<RemoteFeed>> {
+        let list: Subscriptions = json(
+            self.request(Method::GET, "subscription/list?output=json")
+                .send()?,
+        )?;
+        Ok(list
+            .subscriptions
+            .into_iter()
+            .map(|s| RemoteFeed {
+                url: s
+                    .url
+                    .clone()
+                    .filter(|u| !u.is_empty())
+                    .unwrap_or_else(|| s.id.trim_start_matches("feed/").to_string()),
+                folder: s.categories.into_iter().find_map(|c| {
+                    c.label
+                        .or_else(|| c.id.split_once("/label/").map(|(_, name)| name.to_string()))
+                }),
+                id: s.id,
+                title: s.title,
+            })
+            .collect())
+    }
+
+    fn entries(&self, max: usize) -> Result<Vec<RemoteEntry>> {
+        let mut out = Vec::new();
+        let mut continuation: Option<String> = None;
+        while out.len() < max {
+            let mut path = format!(
+                "stream/contents/{}?output=json&n={}",
+                READING_LIST,
+                PAGE.min(max - out.len())
+            );
+            if let Some(c) = &continuation {
+                path.push_str(&format!("&c={}", c));
+            }
+            let page: Stream = json(self.request(Method::GET, &path).send()?)?;
+            let empty = page.items.is_empty();
+            out.extend(page.items.into_iter().map(|i| {
+                RemoteEntry {
+                    read: has_state(&i.categories, READ),
+                    starred: has_state(&i.categories, STARRED),
+                    feed_id: i.origin.stream_id,
+                    guid: None,
+                    url: i
+                        .canonical
+                        .into_iter()
+                        .chain(i.alternate)
+                        .map(|l| l.href)
+                        .next(),
+                    title: i.title.unwrap_or_default(),
+                    content: i
+                        .content
+                        .and_then(|c| c.content)
+                        .or_else(|| i.summary.and_then(|c| c.content)),
+                    published: i
+                        .published
+                        .and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+                    id: i.id,
+                }
+            }));
+            continuation = page.continuation;
+            if empty || continuation.is_none() {
+                break;
+            }
+        }
+        Ok(out)
+    }
+
+    fn subscribe(&self, url: &str, folder: Option<&str>) -> Result<RemoteFeed> {
+        let path = format!(
+            "subscription/quickadd?quickadd={}",
+            url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>()
+        );
+        let token = self.action_token()?.to_string();
+        let added: QuickAdd = json(
+            self.request(Method::POST, &path)
+                .form(&[("T", token.as_str())])
+                .send()?,
+        )?;
+        let stream_id = match (added.num_results, added.stream_id) {
+            (n, Some(id)) if n > 0 => id,
+            _ => {
+                return Err(Error::Sync(format!(
+                    "could not subscribe to {}{}",
+                    url,
+                    added.error.map(|e| format!(": {}", e)).unwrap_or_default()
+                )))
+            }
+        };
+        let feed = RemoteFeed {
+            url: stream_id.trim_start_matches("feed/").to_string(),
+            id: stream_id,
+            title: None,
+            folder: None,
+        };
+        if folder.is_some() {
+            self.set_folder(&feed, folder)?;
+        }
+        Ok(RemoteFeed {
+            folder: folder.map(str::to_string),
+            ..feed
+        })
+    }
+
+    fn unsubscribe(&self, feed: &RemoteFeed) -> Result<()> {
+        self.edit(
+            "subscription/edit",
+            &[("ac", "unsubscribe"), ("s", &feed.id)],
+        )
+    }
+
+    fn set_folder(&self, feed: &RemoteFeed, folder: Option<&str>) -> Result<()> {
+        let mut fields = vec![("ac", "edit".to_string()), ("s", feed.id.clone())];
+        if let Some(old) = &feed.folder {
+            fields.push(("r", label(old)));
+        }
+        if let Some(new) = folder {
+            fields.push(("a", label(new)));
+        }
+        let fields: Vec<(&str, &str)> = fields.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        self.edit("subscription/edit", &fields)
+    }
+
+    fn set_read(&self, ids: &[String], read: bool) -> Result<()> {
+        self.edit_tag(ids, READ, read)
+    }
+
+    fn set_starred(&self, ids: &[String], starred: bool) -> Result<()> {
+        self.edit_tag(ids, STARRED, starred)
+    }
+}
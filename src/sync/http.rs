@@ -0,0 +1,45 @@
+//! HTTP helpers shared by the service clients.
+
+use crate::{Error, Result};
+use reqwest::blocking::Response;
+
+/// `path` under the service's base url.
+pub(super) fn join(base: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// The response if its status is a success, otherwise an error naming the url, the status
+/// and the start of the body.
+pub(super) fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let url = response.url().to_string();
+    let body = response.text().unwrap_or_default();
+    let detail: String = body
+        .trim()
+        .lines()
+        .next()
+        .unwrap_or("")
+        .chars()
+        .take(200)
+        .collect();
+    Err(Error::Sync(match status.as_u16() {
+        401 | 403 => format!("{} rejected the credentials ({})", url, status),
+        _ if detail.is_empty() => format!("{} returned {}", url, status),
+        _ => format!("{} returned {}: {}", url, status, detail),
+    }))
+}
+
+/// Decode a successful JSON response into `T`.
+pub(super) fn json<T: serde::de::DeserializeOwned>(response: Response) -> Result<T> {
+    let url = response.url().to_string();
+    check(response)?
+        .json()
+        .map_err(|e| Error::Sync(format!("unexpected response from {}: {}", url, e)))
+}
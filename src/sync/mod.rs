@@ -0,0 +1,623 @@
+//! Two-way sync with a self-hosted aggregator: Miniflux, a Google Reader API server such
+//! as FreshRSS, or Nextcloud News.
+//!
+//! [`sync`] pulls the server's subscriptions, newest entries and their read/starred state
+//! into a [`SubscriptionList`] and pushes local changes back. What both sides agreed on at
+//! the end of the previous run is kept in [`SyncState`], so each difference can be told
+//! apart as a local or a remote change (a three-way merge). Differences with no common
+//! base, such as on the first run or when a folder changed on both sides, are conflicts
+//! resolved by [`ConflictPolicy`].
+
+mod greader;
+mod http;
+mod miniflux;
+mod nextcloud;
+
+use crate::feed::{Feed, FeedItem};
+use crate::fetch::HttpOptions;
+use crate::store::normalize_folder;
+use crate::{Error, Result, SubscriptionList};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+pub use greader::GReaderClient;
+pub use miniflux::MinifluxClient;
+pub use nextcloud::NextcloudClient;
+
+/// Kind of server API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceKind {
+    Miniflux,
+    /// Google Reader API (FreshRSS, Inoreader-style servers, this reader's own `serve`).
+    GReader,
+    Nextcloud,
+}
+
+impl ServiceKind {
+    pub const ALL: [ServiceKind; 3] = [
+        ServiceKind::Miniflux,
+        ServiceKind::GReader,
+        ServiceKind::Nextcloud,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ServiceKind::Miniflux => "miniflux",
+            ServiceKind::GReader => "greader",
+            ServiceKind::Nextcloud => "nextcloud",
+        }
+    }
+}
+
+impl FromStr for ServiceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ServiceKind::ALL
+            .into_iter()
+            .find(|k| k.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown service {:?} (expected miniflux, greader or nextcloud)",
+                    s
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for ServiceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Which side wins a conflict.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The server's value (the default: the aggregator is the shared source of truth).
+    #[default]
+    Remote,
+    /// The local value.
+    Local,
+}
+
+impl ConflictPolicy {
+    fn pick<T>(self, local: T, remote: T) -> T {
+        match self {
+            ConflictPolicy::Remote => remote,
+            ConflictPolicy::Local => local,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConflictPolicy::Remote => "remote",
+            ConflictPolicy::Local => "local",
+        }
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "remote" => Ok(ConflictPolicy::Remote),
+            "local" => Ok(ConflictPolicy::Local),
+            _ => Err(format!(
+                "unknown conflict policy {:?} (expected remote or local)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Server to sync with and how to sign in.
+#[derive(Clone, Debug)]
+pub struct Account {
+    pub service: ServiceKind,
+    /// Base url: the Miniflux or Nextcloud root, or the Google Reader API root
+    /// (e.g. `https://rss.example.com/api/greader.php`).
+    pub url: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// Miniflux API key, used instead of user and password.
+    pub api_token: Option<String>,
+}
+
+impl Account {
+    /// Sign in and return a client for the account's service.
+    pub fn connect(&self, http: &HttpOptions) -> Result<Box<dyn SyncService>> {
+        Ok(match self.service {
+            ServiceKind::Miniflux => Box::new(MinifluxClient::new(self, http)?),
+            ServiceKind::GReader => Box::new(GReaderClient::login(self, http)?),
+            ServiceKind::Nextcloud => Box::new(NextcloudClient::new(self, http)?),
+        })
+    }
+
+    /// User and password, or an error naming the service that needs them.
+    fn credentials(&self) -> Result<(&str, &str)> {
+        self.user
+            .as_deref()
+            .zip(self.password.as_deref())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("{} needs --user and --password", self.service))
+            })
+    }
+}
+
+/// A subscription on the server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteFeed {
+    pub id: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub folder: Option<String>,
+}
+
+/// An entry on the server with its read/starred state.
+#[derive(Clone, Debug)]
+pub struct RemoteEntry {
+    pub id: String,
+    /// [`RemoteFeed::id`] of its feed.
+    pub feed_id: String,
+    /// The feed's own id for the entry, when the server exposes it.
+    pub guid: Option<String>,
+    pub url: Option<String>,
+    pub title: String,
+    pub content: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub read: bool,
+    pub starred: bool,
+}
+
+impl RemoteEntry {
+    /// A local item for an entry not cached yet.
+    fn to_item(&self, feed_url: &str) -> FeedItem {
+        FeedItem {
+            id: self
+                .guid
+                .clone()
+                .or_else(|| self.url.clone())
+                .unwrap_or_else(|| format!("sync:{}", self.id)),
+            feed_url: feed_url.to_string(),
+            title: self.title.clone(),
+            link: self.url.clone(),
+            published: self.published,
+            summary: None,
+            content: self.content.clone(),
+            enclosures: vec![],
+            ..FeedItem::default()
+        }
+    }
+}
+
+/// Operations a server API provides for syncing.
+pub trait SyncService {
+    fn feeds(&self) -> Result<Vec<RemoteFeed>>;
+    /// Up to `max` of the newest entries, read or not.
+    fn entries(&self, max: usize) -> Result<Vec<RemoteEntry>>;
+    /// Subscribe to `url` in `folder` (None = top level).
+    fn subscribe(&self, url: &str, folder: Option<&str>) -> Result<RemoteFeed>;
+    fn unsubscribe(&self, feed: &RemoteFeed) -> Result<()>;
+    fn set_folder(&self, feed: &RemoteFeed, folder: Option<&str>) -> Result<()>;
+    /// Mark entries (by [`RemoteEntry::id`]) read or unread.
+    fn set_read(&self, ids: &[String], read: bool) -> Result<()>;
+    /// Star or unstar entries; only entries whose state differs are passed.
+    fn set_starred(&self, ids: &[String], starred: bool) -> Result<()>;
+}
+
+/// What both sides agreed on at the end of the last sync, persisted in the store.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncState {
+    pub service: ServiceKind,
+    pub url: String,
+    pub user: Option<String>,
+    pub last_sync: Option<DateTime<Utc>>,
+    /// Remote feed id → feed.
+    #[serde(default)]
+    pub feeds: BTreeMap<String, FeedBase>,
+    /// Remote entry id → item.
+    #[serde(default)]
+    pub items: BTreeMap<String, ItemBase>,
+}
+
+/// A synced feed: its local url and folder.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedBase {
+    pub url: String,
+    pub folder: Option<String>,
+}
+
+/// A synced item: its local id and state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemBase {
+    pub id: String,
+    pub read: bool,
+    pub starred: bool,
+}
+
+/// Options for [`sync`].
+#[derive(Clone, Debug)]
+pub struct SyncOptions {
+    pub prefer: ConflictPolicy,
+    /// Newest server entries to pull (and to push local state for).
+    pub max_entries: usize,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            prefer: ConflictPolicy::Remote,
+            max_entries: 1000,
+        }
+    }
+}
+
+/// What a [`sync`] run changed on each side.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Feeds subscribed locally / on the server.
+    pub feeds_added_locally: usize,
+    pub feeds_added_remotely: usize,
+    /// Feeds unsubscribed locally / on the server.
+    pub feeds_removed_locally: usize,
+    pub feeds_removed_remotely: usize,
+    /// Items pulled from the server that were not cached yet.
+    pub new_items: usize,
+    /// Read/starred/folder changes applied locally / on the server.
+    pub pulled_changes: usize,
+    pub pushed_changes: usize,
+    /// One line per conflict and how it was resolved.
+    pub conflicts: Vec<String>,
+}
+
+/// Local changes not yet pushed, counted against the last sync.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pending {
+    pub feeds_added: usize,
+    pub feeds_removed: usize,
+    pub folders: usize,
+    pub states: usize,
+}
+
+/// Three-way merge of one value: a side that still has the base value takes the other
+/// side's change. Returns the merged value and whether it was a conflict.
+fn merge<T: PartialEq>(local: T, remote: T, base: Option<T>, prefer: ConflictPolicy) -> (T, bool) {
+    if local == remote {
+        return (local, false);
+    }
+    match base {
+        Some(base) if base == local => (remote, false),
+        Some(base) if base == remote => (local, false),
+        _ => (prefer.pick(local, remote), true),
+    }
+}
+
+fn folder_name(folder: &Option<String>) -> &str {
+    folder.as_deref().unwrap_or("top level")
+}
+
+/// Sync `store` with the server behind `service` (the account `account`). The store is
+/// changed but not saved; its sync state is updated for the next run.
+pub fn sync(
+    store: &mut SubscriptionList,
+    service: &dyn SyncService,
+    account: &Account,
+    options: &SyncOptions,
+) -> Result<SyncReport> {
+    let mut state = match store.sync.take() {
+        Some(state) if state.service == account.service && state.url == account.url => state,
+        // A different server: nothing is known to be in sync yet.
+        _ => SyncState {
+            service: account.service,
+            url: account.url.clone(),
+            user: account.user.clone(),
+            last_sync: None,
+            feeds: BTreeMap::new(),
+            items: BTreeMap::new(),
+        },
+    };
+    state.user = account.user.clone();
+    let mut report = SyncReport::default();
+    let remote_feeds = service.feeds()?;
+    let feed_urls = sync_feeds(
+        store,
+        service,
+        &remote_feeds,
+        &mut state,
+        options,
+        &mut report,
+    )?;
+    let entries = service.entries(options.max_entries)?;
+    sync_items(
+        store,
+        service,
+        &entries,
+        &feed_urls,
+        &mut state,
+        options,
+        &mut report,
+    )?;
+
+    state.last_sync = Some(Utc::now());
+    store.sync = Some(state);
+    store.changes.sync = true;
+    Ok(report)
+}
+
+/// Reconcile subscriptions and their folders. Returns the local url of every remote feed.
+fn sync_feeds(
+    store: &mut SubscriptionList,
+    service: &dyn SyncService,
+    remote_feeds: &[RemoteFeed],
+    state: &mut SyncState,
+    options: &SyncOptions,
+    report: &mut SyncReport,
+) -> Result<HashMap<String, String>> {
+    let mut feed_urls = HashMap::new();
+    let mut matched: HashSet<String> = HashSet::new();
+    for remote in remote_feeds {
+        let remote_folder = remote.folder.as_deref().and_then(normalize_folder);
+        let base = state.feeds.get(&remote.id).cloned();
+        // The server may have normalized the url it was given, so try ours first.
+        let local = base
+            .as_ref()
+            .and_then(|b| store.feeds.iter().find(|f| f.url == b.url))
+            .or_else(|| store.feeds.iter().find(|f| f.url == remote.url))
+            .map(|f| (f.url.clone(), f.folder.clone()));
+        let (url, folder) = match (local, base) {
+            (None, Some(_)) => {
+                service.unsubscribe(remote)?;
+                state.feeds.remove(&remote.id);
+                report.feeds_removed_remotely += 1;
+                continue;
+            }
+            (None, None) => {
+                store.subscribe(Feed {
+                    url: remote.url.clone(),
+                    title: remote.title.clone(),
+                    folder: remote_folder.clone(),
+                    ..Feed::default()
+                });
+                report.feeds_added_locally += 1;
+                (remote.url.clone(), remote_folder)
+            }
+            (Some((url, local_folder)), base) => {
+                let (folder, conflict) = merge(
+                    local_folder.clone(),
+                    remote_folder.clone(),
+                    base.map(|b| b.folder),
+                    options.prefer,
+                );
+                if conflict {
+                    report.conflicts.push(format!(
+                        "feed {}: folder {} here, {} on the server; kept {}",
+                        url,
+                        folder_name(&local_folder),
+                        folder_name(&remote_folder),
+                        folder_name(&folder)
+                    ));
+                }
+                if folder != local_folder {
+                    store.set_folder(&url, folder.as_deref())?;
+                    report.pulled_changes += 1;
+                }
+                if folder != remote_folder {
+                    service.set_folder(remote, folder.as_deref())?;
+                    report.pushed_changes += 1;
+                }
+                (url, folder)
+            }
+        };
+        matched.insert(url.clone());
+        feed_urls.insert(remote.id.clone(), url.clone());
+        state
+            .feeds
+            .insert(remote.id.clone(), FeedBase { url, folder });
+    }
+
+    // Synced before but gone from the server: unsubscribed there.
+    let remote_ids: HashSet<&str> = remote_feeds.iter().map(|f| f.id.as_str()).collect();
+    let gone: Vec<String> = state
+        .feeds
+        .keys()
+        .filter(|id| !remote_ids.contains(id.as_str()))
+        .cloned()
+        .collect();
+    for id in gone {
+        if let Some(base) = state.feeds.remove(&id) {
+            if store.remove_feed(&base.url) {
+                report.feeds_removed_locally += 1;
+            }
+            matched.insert(base.url);
+        }
+    }
+
+    // Subscribed here only.
+    let new: Vec<(String, Option<String>)> = store
+        .feeds
+        .iter()
+        .filter(|f| !matched.contains(&f.url))
+        .map(|f| (f.url.clone(), f.folder.clone()))
+        .collect();
+    for (url, folder) in new {
+        let remote = service.subscribe(&url, folder.as_deref())?;
+        report.feeds_added_remotely += 1;
+        feed_urls.insert(remote.id.clone(), url.clone());
+        state.feeds.insert(remote.id, FeedBase { url, folder });
+    }
+    Ok(feed_urls)
+}
+
+/// Pull new entries and reconcile read/starred state.
+fn sync_items(
+    store: &mut SubscriptionList,
+    service: &dyn SyncService,
+    entries: &[RemoteEntry],
+    feed_urls: &HashMap<String, String>,
+    state: &mut SyncState,
+    options: &SyncOptions,
+    report: &mut SyncReport,
+) -> Result<()> {
+    // Local items by id and by link, to recognize entries cached before the first sync.
+    let mut ids: HashSet<String> = HashSet::new();
+    let mut by_link: HashMap<(String, String), String> = HashMap::new();
+    for item in store.items(None) {
+        ids.insert(item.id.clone());
+        if let Some(link) = &item.link {
+            by_link.insert((item.feed_url.clone(), link.clone()), item.id.clone());
+        }
+    }
+
+    let mut new_items: HashMap<String, Vec<FeedItem>> = HashMap::new();
+    let mut local_states: Vec<(String, bool, bool)> = Vec::new();
+    // Remote entry ids to mark read, unread, starred and unstarred.
+    let (mut read_ids, mut unread_ids) = (Vec::new(), Vec::new());
+    let (mut star_ids, mut unstar_ids) = (Vec::new(), Vec::new());
+    for entry in entries {
+        let Some(url) = feed_urls.get(&entry.feed_id) else {
+            continue;
+        };
+        let base = state.items.get(&entry.id);
+        let known = base
+            .map(|b| b.id.clone())
+            .filter(|id| ids.contains(id))
+            .or_else(|| entry.guid.clone().filter(|g| ids.contains(g)))
+            .or_else(|| {
+                let link = entry.url.clone()?;
+                by_link.get(&(url.clone(), link)).cloned()
+            });
+        let Some(id) = known else {
+            let item = entry.to_item(url);
+            local_states.push((item.id.clone(), entry.read, entry.starred));
+            ids.insert(item.id.clone());
+            state.items.insert(
+                entry.id.clone(),
+                ItemBase {
+                    id: item.id.clone(),
+                    read: entry.read,
+                    starred: entry.starred,
+                },
+            );
+            new_items.entry(url.clone()).or_default().push(item);
+            report.new_items += 1;
+            continue;
+        };
+
+        let (local_read, local_starred) = (store.is_read(&id), store.is_starred(&id));
+        let (read, read_conflict) =
+            merge(local_read, entry.read, base.map(|b| b.read), options.prefer);
+        let (starred, star_conflict) = merge(
+            local_starred,
+            entry.starred,
+            base.map(|b| b.starred),
+            options.prefer,
+        );
+        for (conflict, what, local, remote, kept) in [
+            (read_conflict, "read", local_read, entry.read, read),
+            (
+                star_conflict,
+                "starred",
+                local_starred,
+                entry.starred,
+                starred,
+            ),
+        ] {
+            if conflict {
+                report.conflicts.push(format!(
+                    "item {}: {} {} here, {} on the server; kept {}",
+                    id, what, local, remote, kept
+                ));
+            }
+        }
+        if (read, starred) != (local_read, local_starred) {
+            local_states.push((id.clone(), read, starred));
+            report.pulled_changes +=
+                usize::from(read != local_read) + usize::from(starred != local_starred);
+        }
+        if read != entry.read {
+            let ids = if read { &mut read_ids } else { &mut unread_ids };
+            ids.push(entry.id.clone());
+        }
+        if starred != entry.starred {
+            let ids = if starred {
+                &mut star_ids
+            } else {
+                &mut unstar_ids
+            };
+            ids.push(entry.id.clone());
+        }
+        state
+            .items
+            .insert(entry.id.clone(), ItemBase { id, read, starred });
+    }
+
+    // States first, so starred items pulled in are kept past the per-feed cap.
+    for (id, read, starred) in local_states {
+        store.set_state_unchecked(&id, read, starred);
+    }
+    for (url, items) in new_items {
+        let Some(feed) = store.feeds.iter().find(|f| f.url == url).cloned() else {
+            continue;
+        };
+        store.add_feed(feed, items);
+    }
+    for (ids, read) in [(&read_ids, true), (&unread_ids, false)] {
+        if !ids.is_empty() {
+            service.set_read(ids, read)?;
+            report.pushed_changes += ids.len();
+        }
+    }
+    for (ids, starred) in [(&star_ids, true), (&unstar_ids, false)] {
+        if !ids.is_empty() {
+            service.set_starred(ids, starred)?;
+            report.pushed_changes += ids.len();
+        }
+    }
+
+    // Forget entries that are neither on the server's recent list nor cached here.
+    let pulled: HashSet<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+    let cached: HashSet<String> = store.items(None).iter().map(|i| i.id.clone()).collect();
+    state
+        .items
+        .retain(|id, base| pulled.contains(id.as_str()) || cached.contains(&base.id));
+    Ok(())
+}
+
+/// Local changes made since the last sync of `state`.
+pub fn pending(store: &SubscriptionList, state: &SyncState) -> Pending {
+    let synced: HashSet<&str> = state.feeds.values().map(|b| b.url.as_str()).collect();
+    let mut pending = Pending {
+        feeds_added: store
+            .feeds
+            .iter()
+            .filter(|f| !synced.contains(f.url.as_str()))
+            .count(),
+        ..Pending::default()
+    };
+    for base in state.feeds.values() {
+        match store.feeds.iter().find(|f| f.url == base.url) {
+            None => pending.feeds_removed += 1,
+            Some(f) if f.folder != base.folder => pending.folders += 1,
+            Some(_) => {}
+        }
+    }
+    let cached: HashSet<&str> = store.items(None).iter().map(|i| i.id.as_str()).collect();
+    pending.states = state
+        .items
+        .values()
+        .filter(|b| cached.contains(b.id.as_str()))
+        .filter(|b| store.is_read(&b.id) != b.read || store.is_starred(&b.id) != b.starred)
+        .count();
+    pending
+}
@@ -0,0 +1,234 @@
+//! Nextcloud News API v1.3, with HTTP basic auth. The account url is the Nextcloud root
+//! (the API lives under `index.php/apps/news/api/v1-3`) or the API root itself.
+
+use super::http::{check, join, json};
+use super::{Account, RemoteEntry, RemoteFeed, SyncService};
+use crate::fetch::HttpOptions;
+use crate::{Error, Result};
+use chrono::{TimeZone, Utc};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::Method;
+use serde::Deserialize;
+
+const API_PATH: &str = "index.php/apps/news/api/v1-3";
+
+pub struct NextcloudClient {
+    http: Client,
+    base: String,
+    user: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: i64,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Folders {
+    folders: Vec<Folder>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeedJson {
+    id: i64,
+    url: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    folder_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Feeds {
+    feeds: Vec<FeedJson>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemJson {
+    id: i64,
+    feed_id: i64,
+    #[serde(default)]
+    guid: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    pub_date: Option<i64>,
+    #[serde(default)]
+    unread: bool,
+    #[serde(default)]
+    starred: bool,
+}
+
+#[derive(Deserialize)]
+struct Items {
+    items: Vec<ItemJson>,
+}
+
+impl NextcloudClient {
+    pub fn new(account: &Account, http: &HttpOptions) -> Result<Self> {
+        let (user, password) = account.credentials()?;
+        let base = if account.url.contains("/apps/news/api/") {
+            account.url.clone()
+        } else {
+            join(&account.url, API_PATH)
+        };
+        Ok(Self {
+            http: http.client()?,
+            base,
+            user: user.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, join(&self.base, path))
+            .basic_auth(&self.user, Some(&self.password))
+    }
+
+    /// Id of the folder named `folder` (None = top level), created if missing.
+    fn folder_id(&self, folder: Option<&str>) -> Result<Option<i64>> {
+        let Some(name) = folder else { return Ok(None) };
+        let folders: Folders = json(self.request(Method::GET, "folders").send()?)?;
+        if let Some(f) = folders.folders.iter().find(|f| f.name == name) {
+            return Ok(Some(f.id));
+        }
+        let created: Folders = json(
+            self.request(Method::POST, "folders")
+                .json(&serde_json::json!({ "name": name }))
+                .send()?,
+        )?;
+        created
+            .folders
+            .first()
+            .map(|f| Some(f.id))
+            .ok_or_else(|| Error::Sync(format!("could not create folder {}", name)))
+    }
+
+    fn post_items(&self, path: &str, ids: &[String]) -> Result<()> {
+        let ids = ids
+            .iter()
+            .map(|id| numeric(id))
+            .collect::<Result<Vec<i64>>>()?;
+        check(
+            self.request(Method::POST, path)
+                .json(&serde_json::json!({ "itemIds": ids }))
+                .send()?,
+        )?;
+        Ok(())
+    }
+}
+
+fn numeric(id: &str) -> Result<i64> {
+    id.parse()
+        .map_err(|_| Error::Sync(format!("not a Nextcloud News id: {}", id)))
+}
+
+impl SyncService for NextcloudClient {
+    fn feeds(&self) -> Result<Vec<RemoteFeed>> {
+        let folders: Folders = json(self.request(Method::GET, "folders").send()?)?;
+        let feeds: Feeds = json(self.request(Method::GET, "feeds").send()?)?;
+        Ok(feeds
+            .feeds
+            .into_iter()
+            .map(|f| RemoteFeed {
+                id: f.id.to_string(),
+                url: f.url,
+                title: f.title,
+                folder: f.folder_id.and_then(|id| {
+                    folders
+                        .folders
+                        .iter()
+                        .find(|folder| folder.id == id)
+                        .map(|folder| folder.name.clone())
+                }),
+            })
+            .collect())
+    }
+
+    fn entries(&self, max: usize) -> Result<Vec<RemoteEntry>> {
+        // type=3 is "all items"; getRead includes read ones; newest first by default.
+        let path = format!("items?batchSize={}&offset=0&type=3&id=0&getRead=true", max);
+        let items: Items = json(self.request(Method::GET, &path).send()?)?;
+        Ok(items
+            .items
+            .into_iter()
+            .map(|i| RemoteEntry {
+                id: i.id.to_string(),
+                feed_id: i.feed_id.to_string(),
+                guid: i.guid,
+                url: i.url.filter(|u| !u.is_empty()),
+                title: i.title.unwrap_or_default(),
+                content: i.body,
+                published: i
+                    .pub_date
+                    .and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+                read: !i.unread,
+                starred: i.starred,
+            })
+            .collect())
+    }
+
+    fn subscribe(&self, url: &str, folder: Option<&str>) -> Result<RemoteFeed> {
+        let folder_id = self.folder_id(folder)?;
+        let created: Feeds = json(
+            self.request(Method::POST, "feeds")
+                .json(&serde_json::json!({ "url": url, "folderId": folder_id }))
+                .send()?,
+        )?;
+        let feed = created
+            .feeds
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Sync(format!("could not subscribe to {}", url)))?;
+        Ok(RemoteFeed {
+            id: feed.id.to_string(),
+            url: feed.url,
+            title: feed.title,
+            folder: folder.map(str::to_string),
+        })
+    }
+
+    fn unsubscribe(&self, feed: &RemoteFeed) -> Result<()> {
+        let path = format!("feeds/{}", numeric(&feed.id)?);
+        check(self.request(Method::DELETE, &path).send()?)?;
+        Ok(())
+    }
+
+    fn set_folder(&self, feed: &RemoteFeed, folder: Option<&str>) -> Result<()> {
+        let folder_id = self.folder_id(folder)?;
+        let path = format!("feeds/{}/move", numeric(&feed.id)?);
+        check(
+            self.request(Method::PUT, &path)
+                .json(&serde_json::json!({ "folderId": folder_id }))
+                .send()?,
+        )?;
+        Ok(())
+    }
+
+    fn set_read(&self, ids: &[String], read: bool) -> Result<()> {
+        let path = if read {
+            "items/read/multiple"
+        } else {
+            "items/unread/multiple"
+        };
+        self.post_items(path, ids)
+    }
+
+    fn set_starred(&self, ids: &[String], starred: bool) -> Result<()> {
+        let path = if starred {
+            "items/star/multiple"
+        } else {
+            "items/unstar/multiple"
+        };
+        self.post_items(path, ids)
+    }
+}
@@ -0,0 +1,463 @@
+//! TUI state and vim-style key handling. Drawing is in [`super::view`].
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Frame;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+use crate::refresh::{refresh_feeds, FeedRefresh, RefreshOptions};
+use crate::search::{search_items, LiveIndex, SearchQuery, LIST_LIMIT};
+use crate::settings::Settings;
+use crate::store::{FeedSelection, ItemQuery, Storage};
+use crate::{Feed, FeedItem, SubscriptionList};
+
+/// Pane with keyboard focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Feeds,
+    Articles,
+    Reader,
+}
+
+/// Line being typed at the bottom of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Prompt {
+    Search,
+}
+
+/// Full-screen reader over a [`SubscriptionList`]: feed, article and reader panes.
+pub struct Tui {
+    pub(super) store: SubscriptionList,
+    storage: Box<dyn Storage>,
+    /// Date format and download directory.
+    pub(super) settings: Settings,
+    pub(super) focus: Pane,
+    /// Feed-list entries in display order and the cursor in them.
+    pub(super) entries: Vec<FeedSelection>,
+    pub(super) feed_cursor: usize,
+    /// Filters applied on top of the selection (`u` toggles unread-only).
+    pub(super) filters: ItemQuery,
+    pub(super) search: String,
+    /// Rebuilt once a refresh has saved its items, not per finished feed.
+    index: LiveIndex,
+    pub(super) prompt: Option<Prompt>,
+    /// Ids of the listed articles and the cursor in them.
+    pub(super) articles: Vec<String>,
+    pub(super) article_cursor: usize,
+    pub(super) reader_scroll: u16,
+    /// Rows of the reader pane, for paging.
+    pub(super) reader_height: u16,
+    /// Vim count typed before a command (`5j`, `2e`).
+    pub(super) count: Option<usize>,
+    pub(super) status: Option<String>,
+    pending: Option<mpsc::Receiver<FeedRefresh>>,
+    /// (finished, total) feeds of the running refresh.
+    pub(super) progress: (usize, usize),
+    refresh_errors: usize,
+    quit: bool,
+}
+
+impl Tui {
+    pub fn new(store: SubscriptionList, storage: Box<dyn Storage>) -> Self {
+        let mut tui = Self {
+            store,
+            storage,
+            settings: Settings::default(),
+            focus: Pane::Feeds,
+            entries: Vec::new(),
+            feed_cursor: 0,
+            filters: ItemQuery::default(),
+            search: String::new(),
+            index: LiveIndex::default(),
+            prompt: None,
+            articles: Vec::new(),
+            article_cursor: 0,
+            reader_scroll: 0,
+            reader_height: 0,
+            count: None,
+            status: None,
+            pending: None,
+            progress: (0, 0),
+            refresh_errors: 0,
+            quit: false,
+        };
+        tui.reload();
+        tui
+    }
+
+    /// Use `settings` for dates and downloads.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Save enclosures downloaded with `d` into `dir`.
+    pub fn with_download_dir(mut self, dir: PathBuf) -> Self {
+        self.settings.download_dir = Some(dir);
+        self
+    }
+
+    /// Whether `q` was pressed.
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn focus(&self) -> Pane {
+        self.focus
+    }
+
+    pub fn store(&self) -> &SubscriptionList {
+        &self.store
+    }
+
+    /// Selected feed-list entry.
+    pub fn selection(&self) -> &FeedSelection {
+        &self.entries[self.feed_cursor]
+    }
+
+    /// Item under the article cursor.
+    pub fn selected_item(&self) -> Option<&FeedItem> {
+        let id = self.articles.get(self.article_cursor)?;
+        self.store.get_item(id, self.selection().feed_url())
+    }
+
+    /// Draw the three panes and the status line.
+    pub fn draw(&mut self, frame: &mut Frame) {
+        super::view::draw(self, frame);
+    }
+
+    /// Rebuild the feed list and article list after the store, selection or filters changed,
+    /// keeping both cursors on the same entry and item where possible.
+    fn reload(&mut self) {
+        let selected = self.entries.get(self.feed_cursor).cloned();
+        self.entries = FeedSelection::entries(&self.store);
+        self.feed_cursor = selected
+            .and_then(|s| self.entries.iter().position(|e| *e == s))
+            .unwrap_or(0);
+        self.reload_articles();
+    }
+
+    fn reload_articles(&mut self) {
+        let current = self.articles.get(self.article_cursor).cloned();
+        let items = self.selection().filter(&self.filters).run(&self.store);
+        let query = SearchQuery::parse(&self.search);
+        self.articles = if query.is_empty() {
+            items.into_iter().map(|i| i.id.clone()).collect()
+        } else {
+            search_items(self.index.get(&self.store), &query, &items, LIST_LIMIT)
+                .into_iter()
+                .map(|(item, _)| item.id.clone())
+                .collect()
+        };
+        match current.and_then(|id| self.articles.iter().position(|a| *a == id)) {
+            Some(i) => self.article_cursor = i,
+            None => {
+                self.article_cursor = self
+                    .article_cursor
+                    .min(self.articles.len().saturating_sub(1));
+                self.reader_scroll = 0;
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = self.storage.save(&mut self.store) {
+            self.status = Some(format!("Save failed: {}", e));
+        }
+    }
+
+    /// Merge finished refreshes into the store; call between key events.
+    pub fn tick(&mut self) {
+        let Some(rx) = self.pending.take() else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(done) => {
+                    self.progress.0 += 1;
+                    self.refresh_errors += usize::from(done.result.is_err());
+                    done.apply(&mut self.store);
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    self.pending = Some(rx);
+                    break;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.save();
+                    self.status = Some(match self.refresh_errors {
+                        0 => format!("Refreshed {} feed(s)", self.progress.1),
+                        n => format!("Refreshed {} feed(s), {} failed", self.progress.1, n),
+                    });
+                    break;
+                }
+            }
+        }
+        self.reload();
+    }
+
+    /// Whether a refresh is running.
+    pub fn refreshing(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn start_refresh(&mut self, feeds: Vec<Feed>) {
+        if self.refreshing() {
+            return;
+        }
+        if feeds.is_empty() {
+            self.status = Some("No feeds to refresh".to_string());
+            return;
+        }
+        self.progress = (0, feeds.len());
+        self.refresh_errors = 0;
+        let options = RefreshOptions {
+            http: self.settings.http_options(),
+            ..RefreshOptions::default()
+        };
+        self.pending = Some(refresh_feeds(feeds, &options));
+    }
+
+    /// Handle one key press. Returns false when the key did nothing.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.prompt.is_some() {
+            self.prompt_key(key);
+            return true;
+        }
+        if let KeyCode::Char(c @ '0'..='9') = key.code {
+            let digit = c as usize - '0' as usize;
+            if digit > 0 || self.count.is_some() {
+                self.count = Some(self.count.unwrap_or(0).saturating_mul(10) + digit);
+                return true;
+            }
+        }
+        let count = self.count.take();
+        let n = count.unwrap_or(1);
+        self.status = None;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('c') if ctrl => self.quit = true,
+            KeyCode::Char('d') if ctrl => self.page(true),
+            KeyCode::Char('u') if ctrl => self.page(false),
+            KeyCode::Char('f') if ctrl => self.page(true),
+            KeyCode::Char('b') if ctrl => self.page(false),
+            KeyCode::Char('j') | KeyCode::Down => self.move_cursor(n as isize),
+            KeyCode::Char('k') | KeyCode::Up => self.move_cursor(-(n as isize)),
+            KeyCode::Char('g') | KeyCode::Home => self.move_to(count.map_or(0, |c| c - 1)),
+            KeyCode::Char('G') | KeyCode::End => self.move_to(count.map_or(usize::MAX, |c| c - 1)),
+            KeyCode::Char(' ') | KeyCode::PageDown => self.page(true),
+            KeyCode::PageUp => self.page(false),
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter | KeyCode::Tab => {
+                self.focus_next()
+            }
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Esc | KeyCode::BackTab => {
+                self.focus_prev()
+            }
+            KeyCode::Char('J') => self.next_article(1),
+            KeyCode::Char('K') => self.next_article(-1),
+            KeyCode::Char('m') => self.toggle_read(),
+            KeyCode::Char('s') => self.toggle_star(),
+            KeyCode::Char('A') => self.mark_all_read(),
+            KeyCode::Char('u') => {
+                self.filters.unread = !self.filters.unread;
+                self.reload_articles();
+            }
+            KeyCode::Char('/') => self.prompt = Some(Prompt::Search),
+            KeyCode::Char('o') => self.open_link(),
+            KeyCode::Char('e') => self.enclosure(n, false),
+            KeyCode::Char('d') => self.enclosure(n, true),
+            KeyCode::Char('r') => {
+                let feeds = self
+                    .selection()
+                    .query()
+                    .feeds(&self.store)
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                self.start_refresh(feeds);
+            }
+            KeyCode::Char('R') => self.start_refresh(self.store.feeds.clone()),
+            _ => return false,
+        }
+        true
+    }
+
+    fn prompt_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.prompt = None,
+            KeyCode::Esc => {
+                self.prompt = None;
+                self.search.clear();
+            }
+            KeyCode::Backspace => {
+                self.search.pop();
+            }
+            KeyCode::Char(c) => self.search.push(c),
+            _ => return,
+        }
+        self.reload_articles();
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        match self.focus {
+            Pane::Feeds => {
+                let cursor = self.feed_cursor.saturating_add_signed(delta);
+                self.select_feed(cursor);
+            }
+            Pane::Articles => {
+                let cursor = self.article_cursor.saturating_add_signed(delta);
+                self.select_article(cursor);
+            }
+            Pane::Reader => {
+                let lines = i16::try_from(delta).unwrap_or(i16::MAX);
+                self.reader_scroll = self.reader_scroll.saturating_add_signed(lines);
+            }
+        }
+    }
+
+    fn move_to(&mut self, index: usize) {
+        match self.focus {
+            Pane::Feeds => self.select_feed(index),
+            Pane::Articles => self.select_article(index),
+            Pane::Reader => self.reader_scroll = u16::try_from(index).unwrap_or(u16::MAX),
+        }
+    }
+
+    fn page(&mut self, down: bool) {
+        let rows = usize::from(self.reader_height.max(2) / 2);
+        let delta = if down {
+            rows as isize
+        } else {
+            -(rows as isize)
+        };
+        self.move_cursor(delta);
+    }
+
+    fn select_feed(&mut self, index: usize) {
+        let index = index.min(self.entries.len() - 1);
+        if index != self.feed_cursor {
+            self.feed_cursor = index;
+            self.article_cursor = 0;
+            self.reader_scroll = 0;
+            self.articles.clear();
+            self.reload_articles();
+        }
+    }
+
+    fn select_article(&mut self, index: usize) {
+        let index = index.min(self.articles.len().saturating_sub(1));
+        if index != self.article_cursor {
+            self.article_cursor = index;
+            self.reader_scroll = 0;
+        }
+    }
+
+    /// Move to the next (or previous) article and open it, from any pane.
+    fn next_article(&mut self, delta: isize) {
+        self.select_article(self.article_cursor.saturating_add_signed(delta));
+        self.focus = Pane::Reader;
+        self.mark_opened();
+    }
+
+    fn focus_next(&mut self) {
+        self.focus = match self.focus {
+            Pane::Feeds => Pane::Articles,
+            Pane::Articles | Pane::Reader => Pane::Reader,
+        };
+        if self.focus == Pane::Reader {
+            self.mark_opened();
+        }
+    }
+
+    fn focus_prev(&mut self) {
+        self.focus = match self.focus {
+            Pane::Reader => Pane::Articles,
+            Pane::Articles | Pane::Feeds => Pane::Feeds,
+        };
+    }
+
+    /// Opening an article in the reader marks it read; only on opening so `m` sticks.
+    fn mark_opened(&mut self) {
+        let Some(id) = self.articles.get(self.article_cursor).cloned() else {
+            return;
+        };
+        if !self.store.is_read(&id) && self.store.set_read(&id, true) {
+            self.save();
+        }
+    }
+
+    fn toggle_read(&mut self) {
+        let Some(id) = self.articles.get(self.article_cursor).cloned() else {
+            return;
+        };
+        let read = !self.store.is_read(&id);
+        if self.store.set_read(&id, read) {
+            self.save();
+            self.reload_articles();
+        }
+    }
+
+    fn toggle_star(&mut self) {
+        let Some(id) = self.articles.get(self.article_cursor).cloned() else {
+            return;
+        };
+        let starred = !self.store.is_starred(&id);
+        if self.store.set_starred(&id, starred) {
+            self.save();
+            self.reload();
+        }
+    }
+
+    /// Mark every listed article read.
+    fn mark_all_read(&mut self) {
+        let unread: Vec<String> = self
+            .articles
+            .iter()
+            .filter(|id| !self.store.is_read(id))
+            .cloned()
+            .collect();
+        let count = self.store.mark_all_read(unread);
+        if count > 0 {
+            self.save();
+            self.reload_articles();
+        }
+        self.status = Some(format!("Marked {} article(s) read", count));
+    }
+
+    fn open_link(&mut self) {
+        let Some(link) = self.selected_item().and_then(|i| i.link.clone()) else {
+            self.status = Some("No link".to_string());
+            return;
+        };
+        self.status = Some(match open::that(&link) {
+            Ok(()) => format!("Opened {}", link),
+            Err(e) => format!("Open failed: {}", e),
+        });
+    }
+
+    /// Open or download enclosure `number` (1-based, from a count such as `2e`).
+    fn enclosure(&mut self, number: usize, download: bool) {
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        let Some(enclosure) = item.enclosures.get(number.saturating_sub(1)).cloned() else {
+            self.status = Some(format!("No enclosure {}", number));
+            return;
+        };
+        self.status = Some(if download {
+            match crate::media::download_enclosure(
+                &enclosure,
+                self.settings.download_dir.as_deref(),
+                &self.settings.http_options(),
+            ) {
+                Ok(path) => format!("Downloaded to {}", path.display()),
+                Err(e) => format!("Download failed: {}", e),
+            }
+        } else {
+            match crate::media::open_enclosure(&enclosure) {
+                Ok(()) => format!("Opened {}", enclosure.url),
+                Err(e) => e.to_string(),
+            }
+        });
+    }
+}
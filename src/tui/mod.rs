@@ -0,0 +1,39 @@
+//! Full-screen terminal UI (ratatui/crossterm) for use over SSH. Shares storage with the
+//! CLI and the feed-list selection model ([`FeedSelection`](crate::store::FeedSelection))
+//! with the GUI.
+
+mod app;
+mod view;
+
+pub use app::{Pane, Tui};
+
+use crate::settings::Settings;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::time::Duration;
+
+/// How often finished refreshes are merged while waiting for keys.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Run the TUI on the store at `settings.data_path` until `q` is pressed.
+pub fn run(settings: &Settings) -> crate::Result<()> {
+    let storage = crate::store::open_with(&settings.data_path, &settings.store)?;
+    let store = storage.load()?;
+    let mut tui = Tui::new(store, storage).with_settings(settings.clone());
+    let mut terminal = ratatui::init();
+    let result = (|| -> crate::Result<()> {
+        while !tui.should_quit() {
+            terminal.draw(|frame| tui.draw(frame))?;
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        tui.handle_key(key);
+                    }
+                }
+            }
+            tui.tick();
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result
+}
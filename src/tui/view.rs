@@ -0,0 +1,264 @@
+//! Drawing: feed pane, article pane, reader pane and the status line.
+
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+use super::app::{Pane, Prompt, Tui};
+use crate::format_article;
+use crate::store::FeedSelection;
+
+/// Key hints shown when there is no status message.
+const HINTS: &str = "j/k move  h/l pane  J/K next/prev  m read  s star  u unread  / search  \
+                     o link  e/d media  r/R refresh  A all read  q quit";
+
+pub(super) fn draw(tui: &mut Tui, frame: &mut Frame) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+    let [feeds, articles, reader] = Layout::horizontal([
+        Constraint::Percentage(22),
+        Constraint::Percentage(33),
+        Constraint::Percentage(45),
+    ])
+    .areas(main);
+    draw_feeds(tui, frame, feeds);
+    draw_articles(tui, frame, articles);
+    draw_reader(tui, frame, reader);
+    draw_status(tui, frame, status);
+}
+
+fn pane(title: String, focused: bool) -> Block<'static> {
+    let style = if focused {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(style)
+        .title(title)
+}
+
+fn highlight(focused: bool) -> Style {
+    if focused {
+        Style::default().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default().add_modifier(Modifier::UNDERLINED)
+    }
+}
+
+/// Label of a feed-list entry, indented under its folder.
+fn entry_line(tui: &Tui, entry: &FeedSelection) -> Line<'static> {
+    let store = &tui.store;
+    let count = |n: usize| {
+        if n > 0 {
+            format!(" ({})", n)
+        } else {
+            String::new()
+        }
+    };
+    match entry {
+        FeedSelection::All => Line::from(format!("All{}", count(store.unread_count(None)))),
+        FeedSelection::Starred => Line::from(format!("★ Starred ({})", store.starred_items.len())),
+        FeedSelection::Folder(path) => {
+            let depth = path.matches('/').count();
+            let name = path.rsplit('/').next().unwrap_or(path);
+            let unread: usize = store
+                .feeds
+                .iter()
+                .filter(|f| f.in_folder(path))
+                .map(|f| store.unread_count(Some(&f.url)))
+                .sum();
+            Line::from(Span::styled(
+                format!("{}▾ {}{}", "  ".repeat(depth), name, count(unread)),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))
+        }
+        FeedSelection::Feed(url) => {
+            let feed = store.feeds.iter().find(|f| f.url == *url);
+            let depth = feed
+                .and_then(|f| f.folder.as_deref())
+                .map_or(0, |path| path.matches('/').count() + 1);
+            let title = feed
+                .and_then(|f| f.title.clone())
+                .unwrap_or_else(|| url.clone());
+            let text = format!(
+                "{}{}{}",
+                "  ".repeat(depth),
+                title,
+                count(store.unread_count(Some(url)))
+            );
+            match feed.filter(|f| f.is_failing()) {
+                Some(_) => Line::from(Span::styled(
+                    format!("⚠ {}", text),
+                    Style::default().fg(Color::Red),
+                )),
+                None => Line::from(text),
+            }
+        }
+        FeedSelection::Tag(tag) => Line::from(Span::styled(
+            format!("# {}", tag),
+            Style::default().fg(Color::Yellow),
+        )),
+    }
+}
+
+fn selection_label(tui: &Tui) -> String {
+    match tui.selection() {
+        FeedSelection::All => "All".to_string(),
+        FeedSelection::Starred => "Starred".to_string(),
+        FeedSelection::Feed(url) => tui
+            .store
+            .feeds
+            .iter()
+            .find(|f| f.url == *url)
+            .and_then(|f| f.title.clone())
+            .unwrap_or_else(|| url.clone()),
+        FeedSelection::Folder(path) => path.clone(),
+        FeedSelection::Tag(tag) => format!("#{}", tag),
+    }
+}
+
+fn draw_feeds(tui: &Tui, frame: &mut Frame, area: Rect) {
+    let focused = tui.focus == Pane::Feeds;
+    let items: Vec<ListItem> = tui
+        .entries
+        .iter()
+        .map(|e| ListItem::new(entry_line(tui, e)))
+        .collect();
+    let list = List::new(items)
+        .block(pane(" Feeds ".to_string(), focused))
+        .highlight_style(highlight(focused));
+    let mut state = ListState::default().with_selected(Some(tui.feed_cursor));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_articles(tui: &Tui, frame: &mut Frame, area: Rect) {
+    let focused = tui.focus == Pane::Articles;
+    let mut title = format!(" {} ", selection_label(tui));
+    if tui.filters.unread {
+        title.push_str("[unread] ");
+    }
+    if !tui.search.is_empty() {
+        title.push_str(&format!("[/{}] ", tui.search));
+    }
+    let store = &tui.store;
+    let items: Vec<ListItem> = tui
+        .articles
+        .iter()
+        .filter_map(|id| store.get_item(id, tui.selection().feed_url()))
+        .map(|item| {
+            let date = item
+                .published
+                .map(|d| d.format("%m-%d").to_string())
+                .unwrap_or_else(|| "     ".to_string());
+            let star = if store.is_starred(&item.id) {
+                "★"
+            } else {
+                " "
+            };
+            let style = if store.is_read(&item.id) {
+                Style::default().fg(Color::Gray)
+            } else {
+                Style::default().add_modifier(Modifier::BOLD)
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{} {} ", star, date),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(item.title.clone(), style),
+            ]))
+        })
+        .collect();
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(pane(title, focused))
+        .highlight_style(highlight(focused));
+    let mut state = ListState::default().with_selected((!empty).then_some(tui.article_cursor));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_reader(tui: &mut Tui, frame: &mut Frame, area: Rect) {
+    let focused = tui.focus == Pane::Reader;
+    let block = pane(" Reader ".to_string(), focused);
+    let inner = block.inner(area);
+    tui.reader_height = inner.height;
+    let Some(item) = tui.selected_item() else {
+        frame.render_widget(Paragraph::new("No article selected.").block(block), area);
+        return;
+    };
+    let label = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            item.title.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(vec![
+            Span::styled("Date:   ", label),
+            Span::raw(
+                item.published
+                    .map(|d| tui.settings.format_date(&d))
+                    .unwrap_or_else(|| "?".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Source: ", label),
+            Span::raw(item.feed_url.clone()),
+        ]),
+    ];
+    if let Some(link) = item.link.as_ref().filter(|l| !l.is_empty()) {
+        lines.push(Line::from(vec![
+            Span::styled("Link:   ", label),
+            Span::raw(link.clone()),
+        ]));
+    }
+    lines.push(Line::default());
+    let body = item.content.as_deref().or(item.summary.as_deref());
+    lines.extend(
+        format_article(body, usize::from(inner.width))
+            .lines()
+            .map(|l| Line::from(l.to_string())),
+    );
+    if !item.enclosures.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled(
+            "Media (e open, d download, 2e for the second):",
+            label,
+        )));
+        for (i, enc) in item.enclosures.iter().enumerate() {
+            let mime = enc.media_type.as_deref().unwrap_or("?");
+            lines.push(Line::from(format!("[{}] {} ({})", i + 1, enc.url, mime)));
+        }
+    }
+    // Keep the last page on screen when scrolled past the end.
+    let max_scroll = u16::try_from(lines.len())
+        .unwrap_or(u16::MAX)
+        .saturating_sub(inner.height);
+    tui.reader_scroll = tui.reader_scroll.min(max_scroll);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((tui.reader_scroll, 0));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_status(tui: &Tui, frame: &mut Frame, area: Rect) {
+    let line = if tui.prompt == Some(Prompt::Search) {
+        Line::from(format!("/{}█", tui.search))
+    } else if tui.refreshing() {
+        let (done, total) = tui.progress;
+        Line::from(format!("Refreshing… {}/{} feeds", done, total))
+    } else if let Some(status) = &tui.status {
+        Line::from(status.clone())
+    } else {
+        let count = tui.count.map(|c| format!("{} ", c)).unwrap_or_default();
+        Line::from(Span::styled(
+            format!("{}{}", count, HINTS),
+            Style::default().fg(Color::DarkGray),
+        ))
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
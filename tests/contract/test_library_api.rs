@@ -10,6 +10,7 @@ fn feed_item_media_enclosure_types_exist_and_serialize() {
         description: None,
         last_fetched: None,
         created_at: None,
+        ..Default::default()
     };
     assert_eq!(f.url, "https://example.com/feed.xml");
 
@@ -18,6 +19,7 @@ fn feed_item_media_enclosure_types_exist_and_serialize() {
         media_type: Some("image/png".to_string()),
         length: Some(1024),
         title: None,
+        ..Default::default()
     };
     assert!(!e.url.is_empty());
 
@@ -30,6 +32,7 @@ fn feed_item_media_enclosure_types_exist_and_serialize() {
         summary: None,
         content: None,
         enclosures: vec![e],
+        ..Default::default()
     };
     assert_eq!(i.id, "1");
     assert_eq!(i.enclosures.len(), 1);
@@ -46,6 +49,7 @@ fn subscription_list_load_save_roundtrip() {
         description: None,
         last_fetched: None,
         created_at: None,
+        ..Default::default()
     });
     list.save(&path).unwrap();
     let loaded = SubscriptionList::load(&path).unwrap();
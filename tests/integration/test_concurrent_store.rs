@@ -0,0 +1,157 @@
+//! Integration test: saves are atomic and concurrent writers (CLI processes, GUI) merge
+//! their changes instead of overwriting each other, for both storage backends.
+
+use assert_cmd::Command;
+use chrono::{TimeZone, Utc};
+use rss_reader::store;
+use rss_reader::{Feed, FeedItem, SubscriptionList};
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config(name: &str) -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    (dir, path)
+}
+
+fn feed(url: &str) -> Feed {
+    Feed {
+        url: url.to_string(),
+        title: Some(format!("Feed {}", url)),
+        ..Default::default()
+    }
+}
+
+fn item(feed_url: &str, n: i64) -> FeedItem {
+    FeedItem {
+        id: format!("{}#{}", feed_url, n),
+        feed_url: feed_url.to_string(),
+        title: format!("Item {}", n),
+        link: None,
+        published: Some(Utc.timestamp_opt(n * 60, 0).unwrap()),
+        summary: None,
+        content: None,
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+const A: &str = "https://a.example.com/feed.xml";
+const B: &str = "https://b.example.com/feed.xml";
+
+fn seed(path: &Path) {
+    let storage = store::open(path).unwrap();
+    let mut list = SubscriptionList::default();
+    list.add_feed(feed(A), (0..10).map(|n| item(A, n)).collect());
+    storage.save(&mut list).unwrap();
+}
+
+/// Two writers load the same version; the second save must keep the first one's changes.
+fn assert_conflicting_saves_merge(path: &Path) {
+    seed(path);
+    let first = store::open(path).unwrap();
+    let second = store::open(path).unwrap();
+    let mut gui = first.load().unwrap();
+    let mut cli = second.load().unwrap();
+
+    cli.add_feed(feed(B), vec![item(B, 1)]);
+    cli.set_read(&format!("{}#1", A), true);
+    second.save(&mut cli).unwrap();
+
+    gui.set_starred(&format!("{}#2", A), true);
+    gui.set_read(&format!("{}#3", A), true);
+    first.save(&mut gui).unwrap();
+
+    // The writer that merged sees the other's changes without reloading.
+    assert_eq!(gui.feeds.len(), 2);
+    let merged = store::open(path).unwrap().load().unwrap();
+    assert_eq!(merged.feeds.len(), 2);
+    assert_eq!(merged.items(Some(B)).len(), 1);
+    assert!(merged.is_read(&format!("{}#1", A)));
+    assert!(merged.is_read(&format!("{}#3", A)));
+    assert!(merged.is_starred(&format!("{}#2", A)));
+}
+
+#[test]
+fn json_conflicting_saves_merge() {
+    let (_dir, path) = temp_config("data.json");
+    assert_conflicting_saves_merge(&path);
+}
+
+#[test]
+fn sqlite_conflicting_saves_merge() {
+    let (_dir, path) = temp_config("data.db");
+    assert_conflicting_saves_merge(&path);
+}
+
+#[test]
+fn list_save_merges_like_the_json_storage() {
+    let (_dir, path) = temp_config("data.json");
+    seed(&path);
+    let mut stale = store::open(&path).unwrap().load().unwrap();
+    let storage = store::open(&path).unwrap();
+    let mut other = storage.load().unwrap();
+    other.set_read(&format!("{}#1", A), true);
+    storage.save(&mut other).unwrap();
+
+    stale.set_starred(&format!("{}#2", A), true);
+    stale.save(&path).unwrap();
+    let merged = SubscriptionList::load(&path).unwrap();
+    assert!(merged.is_read(&format!("{}#1", A)));
+    assert!(merged.is_starred(&format!("{}#2", A)));
+    // The saved list is current, so saving it again keeps the other process's change.
+    assert!(stale.is_read(&format!("{}#1", A)));
+    stale.set_starred(&format!("{}#2", A), false);
+    stale.save(&path).unwrap();
+    let merged = SubscriptionList::load(&path).unwrap();
+    assert!(merged.is_read(&format!("{}#1", A)));
+    assert!(!merged.is_starred(&format!("{}#2", A)));
+}
+
+#[test]
+fn json_save_leaves_no_temp_files() {
+    let (dir, path) = temp_config("data.json");
+    seed(&path);
+    seed(&path);
+    let mut names: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["data.json", "data.json.lock"]);
+    SubscriptionList::load(&path).unwrap();
+}
+
+#[test]
+fn concurrent_cli_writers_do_not_lose_updates() {
+    for name in ["data.json", "data.db"] {
+        let (_dir, path) = temp_config(name);
+        seed(&path);
+        let children: Vec<std::process::Child> = (0..6)
+            .map(|n| {
+                std::process::Command::new(bin().get_program())
+                    .args(["--config", path.to_str().unwrap(), "mark-read"])
+                    .arg(format!("{}#{}", A, n))
+                    .stdout(std::process::Stdio::null())
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+        for mut child in children {
+            assert!(child.wait().unwrap().success());
+        }
+        let list = store::open(&path).unwrap().load().unwrap();
+        for n in 0..6 {
+            assert!(
+                list.is_read(&format!("{}#{}", A, n)),
+                "{}: item {}",
+                name,
+                n
+            );
+        }
+    }
+}
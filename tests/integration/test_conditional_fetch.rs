@@ -0,0 +1,137 @@
+//! Integration test: conditional GET with ETag / Last-Modified against a local HTTP server;
+//! a 304 updates the max-age hint; `refresh` reports fetched, unchanged and failed counts
+//! separately.
+
+use assert_cmd::Command;
+use rss_reader::{fetch_feed, fetch_feed_conditional, FetchOutcome, HttpOptions, SubscriptionList};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const FEED_XML: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Local Feed</title><link>http://localhost/</link>
+<description>test</description>
+<item><guid>local-1</guid><title>First</title><description>Hello</description></item>
+</channel></rss>"#;
+
+/// Serves `FEED_XML` with an ETag, Last-Modified and a one-minute max-age; answers 304
+/// with a 15-minute max-age when the request carries the matching `If-None-Match`. Returns the feed url and a counter of full (200) responses.
+fn spawn_server() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+    let full_responses = Arc::new(AtomicUsize::new(0));
+    let counter = full_responses.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&buf).to_lowercase();
+            let response = if request.contains("if-none-match: \"v1\"") {
+                "HTTP/1.1 304 Not Modified\r\nCache-Control: max-age=900\r\nContent-Length: 0\r\n\
+                 Connection: close\r\n\r\n"
+                    .to_string()
+            } else {
+                counter.fetch_add(1, Ordering::SeqCst);
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nETag: \"v1\"\r\n\
+                     Cache-Control: max-age=60\r\n\
+                     Last-Modified: Wed, 15 Jan 2025 12:00:00 GMT\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    FEED_XML.len(),
+                    FEED_XML
+                )
+            };
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (url, full_responses)
+}
+
+#[test]
+fn fetch_stores_validators_and_conditional_fetch_gets_not_modified() {
+    let (url, full_responses) = spawn_server();
+    let (feed, items) = fetch_feed(&url, &HttpOptions::default()).unwrap();
+    assert_eq!(feed.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(
+        feed.last_modified.as_deref(),
+        Some("Wed, 15 Jan 2025 12:00:00 GMT")
+    );
+    assert_eq!(items.len(), 1);
+
+    match fetch_feed_conditional(&feed, &HttpOptions::default()).unwrap() {
+        FetchOutcome::NotModified {
+            etag,
+            last_modified,
+            max_age_secs,
+        } => {
+            assert_eq!((etag, last_modified), (None, None));
+            assert_eq!(max_age_secs, Some(900));
+        }
+        FetchOutcome::Updated(..) => panic!("expected 304 Not Modified"),
+    }
+    assert_eq!(full_responses.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn refresh_json_reports_fetched_unchanged_and_failed() {
+    let (url, _) = spawn_server();
+    let (_dir, path) = temp_config();
+    let mut store = SubscriptionList::default();
+    store.feeds.push(rss_reader::Feed {
+        url: url.clone(),
+        ..Default::default()
+    });
+    store.feeds.push(rss_reader::Feed {
+        url: "http://127.0.0.1:1/unreachable.xml".to_string(),
+        ..Default::default()
+    });
+    store.save(&path).unwrap();
+
+    let refresh = || {
+        let output = bin()
+            .arg("--config")
+            .arg(&path)
+            .arg("-o")
+            .arg("json")
+            .arg("refresh")
+            .output()
+            .unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        (
+            v["fetched"].clone(),
+            v["unchanged"].clone(),
+            v["failed"].clone(),
+        )
+    };
+
+    assert_eq!(refresh(), (1.into(), 0.into(), 1.into()));
+    assert_eq!(refresh(), (0.into(), 1.into(), 1.into()));
+
+    let store = SubscriptionList::load(&path).unwrap();
+    assert_eq!(store.items(Some(&url)).len(), 1);
+    // The 304's max-age replaces the 200's; the validators it did not resend are kept.
+    let feed = store.feeds.iter().find(|f| f.url == url).unwrap();
+    assert_eq!(feed.refresh_hints.max_age_secs, Some(900));
+    assert_eq!(feed.etag.as_deref(), Some("\"v1\""));
+}
@@ -0,0 +1,224 @@
+//! Integration test: feed auto-discovery from website URLs (link tags, common paths) and the
+//! `add` choices (interactive, --all, JSON candidates).
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::{discover_feed, find_feed_links, Discovery, HttpOptions, SubscriptionList};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const HOME: &str = r#"<!DOCTYPE html>
+<html><head><title>Example</title>
+<link rel="stylesheet" href="/style.css">
+<LINK REL="alternate" TYPE="application/rss+xml" TITLE="All posts" HREF="/a.xml">
+<link rel='alternate' type='application/atom+xml' title='Comments &amp; replies' href='b.xml' />
+<link rel="alternate" type="text/html" hreflang="de" href="/de/">
+</head><body><a href="/feed">not a link tag</a></body></html>"#;
+
+const NO_LINKS: &str = "<html><head><title>Blog</title></head><body>Hi</body></html>";
+
+fn rss(title: &str) -> String {
+    format!(
+        r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>{}</title><link>http://localhost/</link>
+<description>test</description>
+<item><guid>{}-1</guid><title>First</title></item>
+</channel></rss>"#,
+        title, title
+    )
+}
+
+/// Serves a small website: `/` links two feeds, `/blog/` links none but `/rss.xml` exists,
+/// and `/news/` links none but has `/news/feed` (and a slow `/news/atom.xml`). Requests are
+/// handled in parallel.
+fn spawn_site() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut chunk).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request = String::from_utf8_lossy(&buf);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                if path == "/news/atom.xml" {
+                    std::thread::sleep(std::time::Duration::from_secs(3));
+                }
+                let (status, content_type, body) = match path.as_str() {
+                    "/" => ("200 OK", "text/html; charset=utf-8", HOME.to_string()),
+                    "/blog/" | "/news/" => ("200 OK", "text/html", NO_LINKS.to_string()),
+                    "/news/feed" => ("200 OK", "application/rss+xml", rss("News")),
+                    "/a.xml" => ("200 OK", "application/rss+xml", rss("Posts")),
+                    "/b.xml" => ("200 OK", "application/rss+xml", rss("Comments")),
+                    "/rss.xml" => ("200 OK", "application/rss+xml", rss("Site")),
+                    "/plain.txt" => ("200 OK", "text/plain", "just text".to_string()),
+                    _ => ("404 Not Found", "text/plain", "missing".to_string()),
+                };
+                let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            );
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+    format!("http://127.0.0.1:{}", port)
+}
+
+#[test]
+fn finds_alternate_feed_links() {
+    let base = url::Url::parse("https://example.com/blog/post.html").unwrap();
+    let links = find_feed_links(HOME, &base);
+    let found: Vec<(&str, Option<&str>, Option<&str>)> = links
+        .iter()
+        .map(|c| (c.url.as_str(), c.title.as_deref(), c.kind.as_deref()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("https://example.com/a.xml", Some("All posts"), Some("rss")),
+            (
+                "https://example.com/blog/b.xml",
+                Some("Comments & replies"),
+                Some("atom")
+            ),
+        ]
+    );
+    assert!(find_feed_links(NO_LINKS, &base).is_empty());
+}
+
+#[test]
+fn discovers_links_then_common_paths() {
+    let site = spawn_site();
+    match discover_feed(&format!("{}/a.xml", site), false, &HttpOptions::default()).unwrap() {
+        Discovery::Feed(feed, items) => {
+            assert_eq!(feed.title.as_deref(), Some("Posts"));
+            assert_eq!(items.len(), 1);
+        }
+        other => panic!("expected a feed, got {:?}", other),
+    }
+    match discover_feed(&format!("{}/", site), false, &HttpOptions::default()).unwrap() {
+        Discovery::Candidates(c) => assert_eq!(c.len(), 2),
+        other => panic!("expected candidates, got {:?}", other),
+    }
+    match discover_feed(&format!("{}/blog/", site), false, &HttpOptions::default()).unwrap() {
+        Discovery::Candidates(c) => {
+            assert_eq!(c.len(), 1);
+            assert_eq!(c[0].url, format!("{}/rss.xml", site));
+            assert_eq!(c[0].title.as_deref(), Some("Site"));
+        }
+        other => panic!("expected candidates, got {:?}", other),
+    }
+    // Probing stops at the first feed in path order without waiting for slower probes.
+    let started = std::time::Instant::now();
+    match discover_feed(&format!("{}/news/", site), false, &HttpOptions::default()).unwrap() {
+        Discovery::Candidates(c) => {
+            let urls: Vec<&str> = c.iter().map(|c| c.url.as_str()).collect();
+            assert_eq!(urls, [format!("{}/news/feed", site)]);
+        }
+        other => panic!("expected candidates, got {:?}", other),
+    }
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    match discover_feed(&format!("{}/news/", site), true, &HttpOptions::default()).unwrap() {
+        Discovery::Candidates(c) => {
+            let urls: Vec<&str> = c.iter().map(|c| c.url.as_str()).collect();
+            assert_eq!(
+                urls,
+                [format!("{}/news/feed", site), format!("{}/rss.xml", site)]
+            );
+        }
+        other => panic!("expected candidates, got {:?}", other),
+    }
+    // Neither a feed nor a web page: the parse error is kept.
+    assert!(discover_feed(
+        &format!("{}/plain.txt", site),
+        false,
+        &HttpOptions::default()
+    )
+    .is_err());
+}
+
+fn feed_urls(path: &Path) -> Vec<String> {
+    let store = SubscriptionList::load(path).unwrap();
+    let mut urls: Vec<String> = store.feeds.iter().map(|f| f.url.clone()).collect();
+    urls.sort();
+    urls
+}
+
+#[test]
+fn add_offers_discovered_feeds() {
+    let site = spawn_site();
+    let home = format!("{}/", site);
+
+    let (_dir, path) = temp_config();
+    let config = path.to_str().unwrap();
+    let out = bin()
+        .args(["--config", config, "-o", "json", "add", &home])
+        .output()
+        .unwrap();
+    assert!(!out.status.success(), "{:?}", out);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Found 2 feeds"));
+    let report: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(report["success"], false);
+    let candidates = report["candidates"].as_array().unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[1]["type"], "atom");
+    assert!(!path.exists() || feed_urls(&path).is_empty());
+
+    bin()
+        .args(["--config", config, "add", &home])
+        .write_stdin("7\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1) All posts"))
+        .stdout(predicate::str::contains("between 1 and 2"));
+    assert_eq!(feed_urls(&path), [format!("{}/b.xml", site)]);
+
+    bin()
+        .args(["--config", config, "add", &home])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No feed added."));
+    assert_eq!(feed_urls(&path).len(), 1);
+
+    bin()
+        .args(["--config", config, "add", "--all", &home])
+        .assert()
+        .success();
+    assert_eq!(
+        feed_urls(&path),
+        [format!("{}/a.xml", site), format!("{}/b.xml", site)]
+    );
+
+    // A single discovered feed is added without asking.
+    bin()
+        .args(["--config", config, "add", &format!("{}/blog/", site)])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added feed"));
+    assert_eq!(feed_urls(&path).len(), 3);
+}
@@ -0,0 +1,551 @@
+//! Integration test: the download manager streams enclosures to disk with progress,
+//! resumes interrupted downloads with Range requests, names files from Content-Disposition
+//! or the title, keeps a persistent queue (`downloads` CLI), auto-queues new episodes (not
+//! ones dropped by the cap, hidden or marked read), retries failed downloads with backoff
+//! and applies the keep-N / delete-played retention policy.
+
+use assert_cmd::Command;
+use chrono::{TimeZone, Utc};
+use predicates::prelude::*;
+use rss_reader::media::download::{apply_retention, download_file, run_queue};
+use rss_reader::rules::{Condition, Rule, RuleAction, RuleField};
+use rss_reader::store::MAX_DOWNLOAD_ATTEMPTS;
+use rss_reader::{
+    fetch_feed, DownloadPolicy, DownloadStatus, Feed, FeedItem, HttpOptions, JsonStorage,
+    MediaEnclosure, SubscriptionList,
+};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const FEED_XML: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"><channel>
+<title>Audio Show</title><link>https://show.example.com/</link><description>Show</description>
+<item><guid>ep-3</guid><title>Third</title><pubDate>Wed, 03 Jan 2024 06:00:00 GMT</pubDate>
+<enclosure url="BASE/ep3.mp3" length="300000" type="audio/mpeg"/></item>
+<item><guid>ep-2</guid><title>Second</title><pubDate>Tue, 02 Jan 2024 06:00:00 GMT</pubDate>
+<enclosure url="BASE/ep2.mp3" length="300000" type="audio/mpeg"/></item>
+<item><guid>ep-1</guid><title>First</title><pubDate>Mon, 01 Jan 2024 06:00:00 GMT</pubDate>
+<enclosure url="BASE/ep1.mp3" length="300000" type="audio/mpeg"/></item>
+</channel></rss>"#;
+
+const SIZE: usize = 300_000;
+
+fn audio() -> Vec<u8> {
+    (0..SIZE).map(|i| (i % 251) as u8).collect()
+}
+
+/// Serves the feed and its audio files with Range and If-Range support. `/flaky.mp3`,
+/// `/changed.mp3` and `/shifted.mp3` break off half-way the first time they are fetched
+/// without a Range header; then `/changed.mp3` gets a new ETag and `/shifted.mp3` answers
+/// ranges from the start. `/attachment` and `/long` name their file with
+/// Content-Disposition. Returns the base url and the request heads received.
+fn spawn_server() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let (log, feed) = (requests.clone(), FEED_XML.replace("BASE", &base));
+    std::thread::spawn(move || {
+        let body = audio();
+        let mut flaky_served = Vec::new();
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&buf).to_string();
+            log.lock().unwrap().push(request.clone());
+            if request.starts_with("GET /missing.mp3") {
+                let _ = stream.write_all(
+                    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+                continue;
+            }
+            if request.starts_with("GET /feed.xml") {
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    feed.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(feed.as_bytes());
+                continue;
+            }
+            let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+            let etag = if path == "/changed.mp3" && flaky_served.contains(&path) {
+                "\"v2\""
+            } else {
+                "\"v1\""
+            };
+            let header = |name: &str| {
+                request
+                    .lines()
+                    .find(|l| l.to_lowercase().starts_with(name))
+                    .map(|l| l[name.len()..].trim().to_string())
+            };
+            let range_start = header("range: bytes=")
+                .and_then(|r| r.trim_end_matches('-').parse().ok())
+                .filter(|_| header("if-range:").map_or(true, |v| v == etag));
+            let shifted = path == "/shifted.mp3" && range_start.is_some();
+            let disposition = if request.starts_with("GET /attachment") {
+                "Content-Disposition: attachment; filename=\"Show Notes.mp3\"\r\n".to_string()
+            } else if request.starts_with("GET /long") {
+                format!(
+                    "Content-Disposition: attachment; filename*=UTF-8''{}.mp3\r\n",
+                    "%C3%A9".repeat(300)
+                )
+            } else {
+                String::new()
+            };
+            let start = if shifted { 0 } else { range_start.unwrap_or(0) };
+            let head = match range_start {
+                Some(_) => format!(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Type: audio/mpeg\r\n{}\
+                     ETag: {}\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n",
+                    disposition,
+                    etag,
+                    start,
+                    SIZE - 1,
+                    SIZE,
+                    SIZE - start
+                ),
+                None => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\n{}ETag: {}\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    disposition, etag, SIZE
+                ),
+            };
+            let _ = stream.write_all(head.as_bytes());
+            let flaky = ["/flaky.mp3", "/changed.mp3", "/shifted.mp3"].contains(&path.as_str());
+            if flaky && range_start.is_none() && !flaky_served.contains(&path) {
+                flaky_served.push(path);
+                let _ = stream.write_all(&body[..SIZE / 2]);
+                let _ = stream.flush();
+                continue;
+            }
+            let _ = stream.write_all(&body[start..]);
+        }
+    });
+    (base, requests)
+}
+
+#[test]
+fn download_resumes_after_interruption_and_reports_progress() {
+    let (base, requests) = spawn_server();
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("{}/flaky.mp3", base);
+
+    let mut first = Vec::new();
+    let result = download_file(
+        &url,
+        dir.path(),
+        Some("Episode: One"),
+        &HttpOptions::default(),
+        &mut |w, t| first.push((w, t)),
+    );
+    assert!(result.is_err(), "truncated response must fail");
+    let partial = first.last().unwrap().0;
+    assert!(partial > 0 && partial < SIZE as u64);
+
+    let mut progress = Vec::new();
+    let path = download_file(
+        &url,
+        dir.path(),
+        Some("Episode: One"),
+        &HttpOptions::default(),
+        &mut |w, t| progress.push((w, t)),
+    )
+    .unwrap();
+    assert_eq!(path, dir.path().join("Episode_ One.mp3"));
+    assert_eq!(std::fs::read(&path).unwrap(), audio());
+    assert_eq!(progress[0], (partial, Some(SIZE as u64)));
+    assert_eq!(*progress.last().unwrap(), (SIZE as u64, Some(SIZE as u64)));
+    let range = format!("range: bytes={}-", partial);
+    assert!(requests
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|r| r.to_lowercase().contains(&range)));
+    // Only the finished file is left.
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn resume_restarts_when_the_file_changed_or_the_range_is_wrong() {
+    let (base, requests) = spawn_server();
+    for name in ["changed", "shifted"] {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("{}/{}.mp3", base, name);
+        let http = HttpOptions::default();
+        assert!(download_file(&url, dir.path(), None, &http, &mut |_, _| {}).is_err());
+
+        let mut progress = Vec::new();
+        let path = download_file(&url, dir.path(), None, &http, &mut |w, t| {
+            progress.push((w, t))
+        })
+        .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), audio(), "{}", name);
+        assert_eq!(progress[0].0, 0, "{}", name);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+    let requests = requests.lock().unwrap();
+    let resumes: Vec<&String> = requests
+        .iter()
+        .filter(|r| r.to_lowercase().contains("range: bytes="))
+        .collect();
+    assert_eq!(resumes.len(), 2);
+    assert!(resumes
+        .iter()
+        .all(|r| r.to_lowercase().contains("if-range: \"v1\"")));
+}
+
+#[test]
+fn download_names_from_content_disposition_without_overwriting() {
+    let (base, _) = spawn_server();
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("{}/attachment?id=7", base);
+    let first = download_file(
+        &url,
+        dir.path(),
+        Some("Title"),
+        &HttpOptions::default(),
+        &mut |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(first, dir.path().join("Show Notes.mp3"));
+    let second = download_file(
+        &url,
+        dir.path(),
+        Some("Title"),
+        &HttpOptions::default(),
+        &mut |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(second, dir.path().join("Show Notes (2).mp3"));
+
+    // Without a title or header the url's last segment names the file.
+    let plain = download_file(
+        &format!("{}/ep1.mp3", base),
+        dir.path(),
+        None,
+        &HttpOptions::default(),
+        &mut |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(plain, dir.path().join("ep1.mp3"));
+
+    // Over-long names are cut like titles (by characters and bytes), keeping the extension.
+    let long = download_file(
+        &format!("{}/long", base),
+        dir.path(),
+        None,
+        &HttpOptions::default(),
+        &mut |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(long, dir.path().join(format!("{}.mp3", "é".repeat(100))));
+}
+
+#[test]
+fn auto_download_queues_new_episodes_and_retention_deletes_old_ones() {
+    let (base, _) = spawn_server();
+    let dir = tempfile::tempdir().unwrap();
+    let storage = JsonStorage::new(&dir.path().join("data.json"));
+    let feed_url = format!("{}/feed.xml", base);
+    let (feed, items) = fetch_feed(&feed_url, &HttpOptions::default()).unwrap();
+
+    let mut store = SubscriptionList::default();
+    store.subscribe(feed.clone());
+    store
+        .set_download_policy(
+            &feed_url,
+            DownloadPolicy {
+                auto: true,
+                keep: Some(2),
+                delete_played: true,
+            },
+        )
+        .unwrap();
+    store.add_feed(feed.clone(), items.clone());
+    assert_eq!(store.pending_downloads().len(), 3);
+    // Known items are not queued again.
+    store.add_feed(feed, items);
+    assert_eq!(store.downloads.len(), 3);
+
+    let files = dir.path().join("files");
+    let done = run_queue(
+        &mut store,
+        &storage,
+        &files,
+        None,
+        true,
+        &HttpOptions::default(),
+        &mut |_, _, _| {},
+    )
+    .unwrap();
+    assert!(done.iter().all(|d| d.status == DownloadStatus::Done));
+    assert!(files.join("First.mp3").exists());
+
+    // Keep the 2 newest: the first episode goes.
+    let removed = apply_retention(&mut store).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].item_id, "ep-1");
+    assert!(!files.join("First.mp3").exists());
+    assert!(files.join("Second.mp3").exists());
+
+    // Played (read) episodes go too, unless starred.
+    store.set_read("ep-3", true);
+    store.set_read("ep-2", true);
+    store.set_starred("ep-2", true);
+    let removed = apply_retention(&mut store).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert!(!files.join("Third.mp3").exists());
+    assert!(files.join("Second.mp3").exists());
+    assert_eq!(store.downloads.len(), 1);
+}
+
+/// An episode of `FEED` published on day `day` of 2024, with one audio enclosure.
+fn episode(day: u32, title: &str) -> FeedItem {
+    FeedItem {
+        id: format!("ep-{}", day),
+        feed_url: FEED.to_string(),
+        title: title.to_string(),
+        published: Some(Utc.with_ymd_and_hms(2024, 1, day, 6, 0, 0).unwrap()),
+        enclosures: vec![MediaEnclosure {
+            url: format!("https://cdn.example.com/{}.mp3", day),
+            media_type: Some("audio/mpeg".to_string()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+const FEED: &str = "https://show.example.com/feed.xml";
+
+#[test]
+fn auto_download_skips_old_hidden_and_read_episodes() {
+    let feed = Feed {
+        url: FEED.to_string(),
+        ..Default::default()
+    };
+    let mut store = SubscriptionList::default();
+    store.set_max_items_per_feed(2);
+    store.subscribe(feed.clone());
+    store
+        .set_download_policy(
+            FEED,
+            DownloadPolicy {
+                auto: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let first = vec![episode(3, "Three"), episode(2, "Two"), episode(1, "One")];
+    store.add_feed(feed.clone(), first.clone());
+    assert_eq!(store.pending_downloads().len(), 3);
+    for url in ["1", "2", "3"] {
+        store.remove_download(&format!("https://cdn.example.com/{}.mp3", url));
+    }
+
+    // Episode 1 was dropped by the cap: fetched again it is not a new episode.
+    for (pattern, action) in [
+        ("Hidden", RuleAction::Hide),
+        ("Played", RuleAction::MarkRead),
+    ] {
+        store
+            .add_rule(Rule {
+                conditions: vec![Condition {
+                    field: RuleField::Title,
+                    pattern: pattern.to_string(),
+                    regex: false,
+                }],
+                action,
+            })
+            .unwrap();
+    }
+    let mut second = vec![
+        episode(6, "Hidden"),
+        episode(5, "Played"),
+        episode(4, "Four"),
+    ];
+    second.extend(first);
+    store.add_feed(feed, second);
+    let queued: Vec<String> = store
+        .pending_downloads()
+        .into_iter()
+        .map(|d| d.item_id)
+        .collect();
+    assert_eq!(queued, ["ep-4"]);
+}
+
+#[test]
+fn failed_downloads_back_off_and_give_up() {
+    let (base, requests) = spawn_server();
+    let dir = tempfile::tempdir().unwrap();
+    let storage = JsonStorage::new(&dir.path().join("data.json"));
+    let files = dir.path().join("files");
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: FEED.to_string(),
+            ..Default::default()
+        },
+        vec![FeedItem {
+            enclosures: vec![MediaEnclosure {
+                url: format!("{}/missing.mp3", base),
+                ..Default::default()
+            }],
+            ..episode(1, "Gone")
+        }],
+    );
+    store.queue_download("ep-1", 0).unwrap();
+    let run = |store: &mut SubscriptionList, retry_failed: bool| {
+        run_queue(
+            store,
+            &storage,
+            &files,
+            None,
+            retry_failed,
+            &HttpOptions::default(),
+            &mut |_, _, _| {},
+        )
+        .unwrap()
+    };
+
+    let failed = run(&mut store, false);
+    assert_eq!(failed[0].status, DownloadStatus::Failed);
+    assert_eq!(failed[0].attempts, 1);
+    let first_retry = failed[0].retry_at.unwrap();
+    assert!(first_retry > Utc::now());
+    // The daemon's pass skips it until then; `downloads run` retries at once.
+    assert!(run(&mut store, false).is_empty());
+    let failed = run(&mut store, true);
+    assert_eq!(failed[0].attempts, 2);
+    assert!(failed[0].retry_at.unwrap() - Utc::now() > first_retry - Utc::now());
+
+    for _ in 2..MAX_DOWNLOAD_ATTEMPTS {
+        run(&mut store, true);
+    }
+    let gave_up = store.download(&format!("{}/missing.mp3", base)).unwrap();
+    assert_eq!(gave_up.attempts, MAX_DOWNLOAD_ATTEMPTS);
+    assert_eq!(gave_up.retry_at, None);
+    assert!(run(&mut store, true).is_empty());
+    let attempts = |requests: &Arc<Mutex<Vec<String>>>| {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.starts_with("GET /missing.mp3"))
+            .count()
+    };
+    assert_eq!(attempts(&requests), MAX_DOWNLOAD_ATTEMPTS as usize);
+
+    // Queued again, it is tried afresh.
+    assert!(store.queue_download("ep-1", 0).unwrap());
+    assert_eq!(run(&mut store, false)[0].attempts, 1);
+    assert_eq!(attempts(&requests), MAX_DOWNLOAD_ATTEMPTS as usize + 1);
+}
+
+fn run(path: &Path, downloads: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+    bin()
+        .env("RSS_READER_DOWNLOAD_DIR", downloads)
+        .args(["--config", path.to_str().unwrap()])
+        .args(args)
+        .assert()
+}
+
+#[test]
+fn downloads_cli_queues_runs_and_cleans() {
+    let (base, _) = spawn_server();
+    let (dir, path) = temp_config();
+    let downloads = dir.path().join("podcasts");
+    let feed_url = format!("{}/feed.xml", base);
+    run(&path, &downloads, &["add", &feed_url]).success();
+
+    for id in ["ep-1", "ep-2", "ep-3"] {
+        run(&path, &downloads, &["downloads", "add", id])
+            .success()
+            .stdout(predicate::str::contains(format!(
+                "Queued enclosure 0 of {}",
+                id
+            )));
+    }
+    run(&path, &downloads, &["downloads", "add", "ep-1"])
+        .success()
+        .stdout(predicate::str::contains("Already queued"));
+    run(
+        &path,
+        &downloads,
+        &["downloads", "add", "ep-1", "--index", "3"],
+    )
+    .failure();
+    run(&path, &downloads, &["downloads", "list"])
+        .success()
+        .stdout(predicate::str::contains("queued | ep-1 | First |"));
+
+    run(&path, &downloads, &["downloads", "run", "--limit", "2"])
+        .success()
+        .stdout(predicate::str::contains("Downloaded"));
+    run(&path, &downloads, &["-o", "json", "downloads", "run"])
+        .success()
+        .stdout(predicate::str::contains("\"status\": \"done\""));
+    assert_eq!(std::fs::read(downloads.join("Third.mp3")).unwrap(), audio());
+
+    let output = bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "-o",
+            "json",
+            "downloads",
+            "list",
+        ])
+        .output()
+        .unwrap();
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let list = list.as_array().unwrap();
+    assert_eq!(list.len(), 3);
+    assert!(list.iter().all(|d| d["status"] == "done"));
+    assert_eq!(list[0]["bytes"], SIZE);
+
+    run(
+        &path,
+        &downloads,
+        &["downloads", "policy", &feed_url, "--keep", "1"],
+    )
+    .success()
+    .stdout(predicate::str::contains("keep 1"));
+    run(&path, &downloads, &["downloads", "clean"])
+        .success()
+        .stdout(predicate::str::contains("Deleted 2 download(s)"));
+    assert!(downloads.join("Third.mp3").exists());
+    assert!(!downloads.join("First.mp3").exists());
+
+    run(
+        &path,
+        &downloads,
+        &["downloads", "remove", "ep-3", "--delete-file"],
+    )
+    .success();
+    assert!(!downloads.join("Third.mp3").exists());
+    run(&path, &downloads, &["downloads", "remove", "ep-3"]).failure();
+}
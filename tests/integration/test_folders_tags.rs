@@ -0,0 +1,219 @@
+//! Integration test: folders and tags — folder/move-feed/tag CLI commands, list-items and
+//! refresh by folder or tag, and OPML nesting and categories.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::{export_opml, import_opml, Feed, SubscriptionList};
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+// Nothing listens on port 1, so refreshing these fails fast without network access.
+const RUST: &str = "http://127.0.0.1:1/rust.xml";
+const GO: &str = "http://127.0.0.1:1/go.xml";
+const NEWS: &str = "http://127.0.0.1:1/news.xml";
+
+fn store() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    for (url, folder) in [(RUST, Some("Tech/Rust")), (GO, Some("Tech")), (NEWS, None)] {
+        store.subscribe(Feed {
+            url: url.to_string(),
+            folder: folder.map(str::to_string),
+            ..Default::default()
+        });
+    }
+    store
+}
+
+fn run(path: &Path, args: &[&str]) -> serde_json::Value {
+    let out = bin()
+        .args(["--config", path.to_str().unwrap(), "-o", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    serde_json::from_slice(&out.stdout).unwrap()
+}
+
+fn folders(path: &Path) -> Vec<(String, u64)> {
+    run(path, &["folder", "list"])
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| {
+            (
+                v["path"].as_str().unwrap().to_string(),
+                v["feed_count"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn folder_of(path: &Path, url: &str) -> Option<String> {
+    let feeds = run(path, &["list-feeds"]);
+    let feed = feeds
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["url"] == url)
+        .unwrap()
+        .clone();
+    feed["folder"].as_str().map(str::to_string)
+}
+
+#[test]
+fn folder_commands_create_move_and_remove() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    assert_eq!(
+        folders(&path),
+        [("Tech".to_string(), 1), ("Tech/Rust".to_string(), 1)]
+    );
+
+    run(&path, &["folder", "create", " Archive / Old "]);
+    run(&path, &["move-feed", NEWS, "Archive/Old"]);
+    assert_eq!(folder_of(&path, NEWS).as_deref(), Some("Archive/Old"));
+
+    // Renaming carries subfolders and their feeds along.
+    run(&path, &["folder", "move", "Tech", "Dev"]);
+    assert_eq!(folder_of(&path, RUST).as_deref(), Some("Dev/Rust"));
+    assert_eq!(folder_of(&path, GO).as_deref(), Some("Dev"));
+
+    // Removing a folder moves its contents up to the parent.
+    run(&path, &["folder", "remove", "Dev"]);
+    assert_eq!(folder_of(&path, RUST).as_deref(), Some("Rust"));
+    assert_eq!(folder_of(&path, GO), None);
+
+    run(&path, &["move-feed", NEWS]);
+    assert_eq!(folder_of(&path, NEWS), None);
+    // An emptied explicit folder stays until removed.
+    assert!(folders(&path).contains(&("Archive/Old".to_string(), 0)));
+
+    let config = path.to_str().unwrap();
+    bin()
+        .args(["--config", config, "folder", "move", "Rust", "Rust/Inner"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("into itself"));
+    bin()
+        .args(["--config", config, "folder", "remove", "Missing"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn tag_and_untag_feeds() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let out = run(&path, &["tag", RUST, "lang", "Weekly", "LANG"]);
+    assert_eq!(out["tags"], serde_json::json!(["lang", "Weekly"]));
+    run(&path, &["tag", GO, "lang"]);
+    let out = run(&path, &["untag", RUST, "weekly"]);
+    assert_eq!(out["tags"], serde_json::json!(["lang"]));
+
+    bin()
+        .args(["--config", path.to_str().unwrap(), "list-feeds"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[folder: Tech/Rust] [tags: lang]"));
+    bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "tag",
+            "https://nope",
+            "x",
+        ])
+        .assert()
+        .failure();
+}
+
+fn refreshed(path: &Path, args: &[&str]) -> Vec<String> {
+    let out = bin()
+        .args(["--config", path.to_str().unwrap(), "-o", "json", "refresh"])
+        .args(args)
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let mut urls: Vec<String> = report["feeds"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["url"].as_str().unwrap().to_string())
+        .collect();
+    urls.sort();
+    urls
+}
+
+#[test]
+fn list_items_and_refresh_select_by_folder_or_tag() {
+    let (_dir, path) = temp_config();
+    let mut list = store();
+    list.tag_feed(NEWS, &["daily".to_string()], true).unwrap();
+    for url in [RUST, GO, NEWS] {
+        let feed = list.feeds.iter().find(|f| f.url == url).unwrap().clone();
+        let item = rss_reader::FeedItem {
+            id: format!("{}#1", url),
+            feed_url: url.to_string(),
+            title: "Item".to_string(),
+            link: None,
+            published: None,
+            summary: None,
+            content: None,
+            enclosures: vec![],
+            ..Default::default()
+        };
+        list.add_feed(feed, vec![item]);
+    }
+    list.save(&path).unwrap();
+
+    let ids = |args: &[&str]| -> Vec<String> {
+        let mut all: Vec<&str> = vec!["list-items"];
+        all.extend_from_slice(args);
+        let mut ids: Vec<String> = run(&path, &all)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+    assert_eq!(
+        ids(&["--folder", "Tech"]),
+        [format!("{}#1", GO), format!("{}#1", RUST)]
+    );
+    assert_eq!(ids(&["--folder", "Tech/Rust"]), [format!("{}#1", RUST)]);
+    assert_eq!(ids(&["--tag", "DAILY"]), [format!("{}#1", NEWS)]);
+    assert!(ids(&["--folder", "Te"]).is_empty());
+
+    assert_eq!(refreshed(&path, &["--folder", "Tech"]), [GO, RUST]);
+    assert_eq!(refreshed(&path, &["--tag", "daily"]), [NEWS]);
+}
+
+#[test]
+fn opml_nesting_maps_onto_folders_and_tags() {
+    let mut list = store();
+    list.create_folder("Empty/Nested").unwrap();
+    list.tag_feed(RUST, &["lang".to_string(), "weekly".to_string()], true)
+        .unwrap();
+    let xml = export_opml(&list);
+    assert!(xml.contains(r#"category="lang,weekly""#), "{}", xml);
+
+    let mut imported = SubscriptionList::default();
+    let report = import_opml(&mut imported, &xml).unwrap();
+    assert_eq!(report.added, 3);
+    assert_eq!(imported.folders(), list.folders());
+    let rust = imported.feeds.iter().find(|f| f.url == RUST).unwrap();
+    assert_eq!(rust.folder.as_deref(), Some("Tech/Rust"));
+    assert_eq!(rust.tags, ["lang", "weekly"]);
+}
@@ -1,4 +1,6 @@
-//! Integration test: show article with HTML structure (headings, paragraphs, links); assert output has structure.
+//! Integration test: show article with HTML structure (headings, paragraphs, links); assert output has structure,
+//! is styled with ANSI escapes on request, wraps to --width and never passes control
+//! characters from the feed through to the terminal.
 
 use assert_cmd::Command;
 use std::path::PathBuf;
@@ -91,3 +93,120 @@ fn show_article_with_html_structure_preserves_structure_in_output() {
         "output should have multiple lines (title, metadata, body)"
     );
 }
+
+#[test]
+fn show_styles_article_with_color_always() {
+    let (_dir, path) = temp_config();
+    write_store_with_html_content(&path, "styled-id");
+    let output = bin()
+        .args(["--config", path.to_str().unwrap(), "show", "styled-id"])
+        .args(["--color", "always"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Bold heading without the "##" markup; the link is an underlined OSC 8 hyperlink
+    // instead of a footnote.
+    assert!(stdout.contains("\x1b[1mFirst Section\x1b[0m"));
+    assert!(!stdout.contains("## "));
+    assert!(stdout.contains("\x1b]8;;https://example.com/link\x1b\\\x1b[4mlink\x1b[0m"));
+    assert!(!stdout.contains("[1] https://example.com/link"));
+
+    // Piped output (auto) and --color never stay plain.
+    for args in [&[][..], &["--color", "never"][..]] {
+        let output = bin()
+            .args(["--config", path.to_str().unwrap(), "show", "styled-id"])
+            .args(args)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(!stdout.contains('\x1b'));
+        assert!(stdout.contains("## First Section"));
+        assert!(stdout.contains("[1] https://example.com/link"));
+    }
+
+    bin()
+        .args(["--config", path.to_str().unwrap(), "show", "styled-id"])
+        .args(["--color", "sometimes"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn styled_quotes_and_code_blocks() {
+    let html = "<blockquote><p>Quoted words</p></blockquote><pre><code>let x = 1;</code></pre>\
+                <p>Inline <code>y</code> and <em>stress</em></p>";
+    let text = rss_reader::format_article_styled(Some(html), 80);
+    assert!(text.contains("\x1b[2m│ \x1b[0m\x1b[2mQuoted words\x1b[0m"));
+    assert!(text.contains("\x1b[36mlet x = 1;\x1b[0m"));
+    assert!(text.contains("\x1b[33my\x1b[0m"));
+    assert!(text.contains("\x1b[3mstress\x1b[0m"));
+    assert!(!text.contains('`'));
+    assert_eq!(
+        rss_reader::format_article_styled(Some("  "), 80),
+        "No content."
+    );
+}
+
+#[test]
+fn show_wraps_to_width() {
+    let (_dir, path) = temp_config();
+    let words = "lorem ipsum ".repeat(20);
+    let store = serde_json::json!({
+        "feeds": [{"url": "https://example.com/feed.xml"}],
+        "items_by_feed": {
+            "https://example.com/feed.xml": [{
+                "id": "wide",
+                "feed_url": "https://example.com/feed.xml",
+                "title": "Wide",
+                "link": null,
+                "published": null,
+                "summary": null,
+                "content": format!("<p>{}</p>", words),
+                "enclosures": []
+            }]
+        }
+    });
+    std::fs::write(&path, store.to_string()).unwrap();
+    let body_lines = |width: &str| {
+        let output = bin()
+            .args([
+                "--config",
+                path.to_str().unwrap(),
+                "show",
+                "wide",
+                "--width",
+                width,
+            ])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout
+            .lines()
+            .filter(|l| l.contains("lorem"))
+            .map(|l| l.chars().count())
+            .collect::<Vec<_>>()
+    };
+    let narrow = body_lines("50");
+    assert!(narrow.len() > 4);
+    assert!(narrow.iter().all(|&n| n <= 50));
+    assert!(body_lines("200").len() < narrow.len());
+}
+
+#[test]
+fn styled_links_cannot_inject_escape_sequences() {
+    let html = "<p><a href=\"https://e.com/&#27;\\&#27;]52;c;aGk=&#7;x\">click</a> \
+                <a href=\"javascript:alert(1)\">js</a> text&#27;]0;title&#7;</p>";
+    let text = rss_reader::format_article_styled(Some(html), 80);
+    assert!(text.contains("\x1b]8;;https://e.com/%1B\\%1B]52;c;aGk=%07x\x1b\\\x1b[4mclick"));
+    assert!(!text.contains('\x07'));
+    assert!(!text.contains("\x1b]52"));
+    assert!(!text.contains("\x1b]0;"));
+    // Only the http link becomes a hyperlink (opened and closed once).
+    assert_eq!(text.matches("\x1b]8;;").count(), 2);
+    assert!(text.contains("\x1b[4mjs\x1b[0m"));
+
+    let plain = rss_reader::format_article(Some(html), 80);
+    assert!(!plain.contains('\x1b') && !plain.contains('\x07'));
+}
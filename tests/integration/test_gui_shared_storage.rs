@@ -29,6 +29,7 @@ fn store_updated_via_library_appears_in_cli_list_feeds() {
         description: None,
         last_fetched: None,
         created_at: None,
+        ..Default::default()
     };
     let items = vec![];
     store.add_feed(feed, items);
@@ -0,0 +1,200 @@
+//! Integration test: `ItemQuery` and `list-items` filtering (feed title, dates, enclosures),
+//! sorting and paging.
+
+use assert_cmd::Command;
+use chrono::{TimeZone, Utc};
+use rss_reader::{Feed, FeedItem, ItemQuery, MediaEnclosure, SortKey, SubscriptionList};
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const PODCAST: &str = "https://pod.example.com/rss";
+const BLOG: &str = "https://blog.example.com/atom";
+
+/// Item published on day `day` of January 2024.
+fn item(feed_url: &str, id: &str, title: &str, day: u32, media: bool) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: title.to_string(),
+        link: None,
+        published: Some(Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap()),
+        summary: None,
+        content: None,
+        enclosures: if media {
+            vec![MediaEnclosure {
+                url: format!("https://pod.example.com/{}.mp3", id),
+                media_type: Some("audio/mpeg".to_string()),
+                length: None,
+                title: None,
+                ..Default::default()
+            }]
+        } else {
+            vec![]
+        },
+        ..Default::default()
+    }
+}
+
+fn store() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: PODCAST.to_string(),
+            title: Some("Weekly Podcast".to_string()),
+            ..Default::default()
+        },
+        vec![
+            item(PODCAST, "p1", "Episode one", 1, true),
+            item(PODCAST, "p2", "Episode two", 8, true),
+        ],
+    );
+    store.add_feed(
+        Feed {
+            url: BLOG.to_string(),
+            title: Some("Another Blog".to_string()),
+            ..Default::default()
+        },
+        vec![
+            item(BLOG, "b1", "zebra post", 3, false),
+            item(BLOG, "b2", "Apple post", 10, false),
+        ],
+    );
+    store
+}
+
+fn ids(query: ItemQuery) -> Vec<String> {
+    query
+        .run(&store())
+        .into_iter()
+        .map(|i| i.id.clone())
+        .collect()
+}
+
+#[test]
+fn filters_by_feed_title_dates_and_enclosures() {
+    assert_eq!(
+        ids(ItemQuery {
+            feed: Some("podcast".to_string()),
+            ..Default::default()
+        }),
+        ["p2", "p1"]
+    );
+    assert_eq!(
+        ids(ItemQuery {
+            feed: Some(BLOG.to_string()),
+            ..Default::default()
+        }),
+        ["b2", "b1"]
+    );
+    assert_eq!(
+        ids(ItemQuery {
+            since: Some(Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap()),
+            until: Some(Utc.with_ymd_and_hms(2024, 1, 8, 23, 59, 59).unwrap()),
+            ..Default::default()
+        }),
+        ["p2", "b1"]
+    );
+    assert_eq!(
+        ids(ItemQuery {
+            has_enclosure: true,
+            ..Default::default()
+        }),
+        ["p2", "p1"]
+    );
+}
+
+#[test]
+fn sorts_reverses_and_pages() {
+    assert_eq!(ids(ItemQuery::default()), ["b2", "p2", "b1", "p1"]);
+    assert_eq!(
+        ids(ItemQuery {
+            sort: SortKey::Title,
+            ..Default::default()
+        }),
+        ["b2", "p1", "p2", "b1"]
+    );
+    assert_eq!(
+        ids(ItemQuery {
+            sort: SortKey::Feed,
+            ..Default::default()
+        }),
+        ["b2", "b1", "p2", "p1"]
+    );
+    assert_eq!(
+        ids(ItemQuery {
+            reverse: true,
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        }),
+        ["b1", "p2"]
+    );
+}
+
+fn json_ids(path: &Path, args: &[&str]) -> Vec<String> {
+    let out = bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "-o",
+            "json",
+            "list-items",
+        ])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    let arr: Vec<serde_json::Value> = serde_json::from_slice(&out.stdout).unwrap();
+    arr.iter()
+        .map(|v| v["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn list_items_cli_options() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    assert_eq!(json_ids(&path, &["--feed", "Weekly"]), ["p2", "p1"]);
+    assert_eq!(
+        json_ids(&path, &["--since", "2024-01-03", "--until", "2024-01-08"]),
+        ["p2", "b1"]
+    );
+    assert_eq!(
+        json_ids(&path, &["--has-enclosure", "--reverse"]),
+        ["p1", "p2"]
+    );
+    assert_eq!(
+        json_ids(&path, &["--sort", "title", "--limit", "2", "--offset", "1"]),
+        ["p1", "p2"]
+    );
+    bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "list-items",
+            "--since",
+            "soon",
+        ])
+        .assert()
+        .failure();
+    bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "list-items",
+            "--sort",
+            "size",
+        ])
+        .assert()
+        .failure();
+}
@@ -0,0 +1,218 @@
+//! Integration test: authors, categories, extra links, updated date, language, rights and
+//! feed site/icon/logo/generator are kept from RSS and Atom feeds, shown by `show`,
+//! `list-items` and `list-feeds`, and filtered with `--author` / `--category`.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const ATOM_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="en">
+  <title>Atom Blog</title>
+  <id>urn:atom-blog</id>
+  <updated>2024-03-10T08:00:00Z</updated>
+  <link rel="self" href="http://localhost/atom.xml"/>
+  <link rel="alternate" href="https://atom.example.com/"/>
+  <icon>https://atom.example.com/favicon.ico</icon>
+  <logo>https://atom.example.com/logo.png</logo>
+  <generator uri="https://gohugo.io/" version="0.120">Hugo</generator>
+  <entry>
+    <id>urn:post-1</id>
+    <title>Release notes</title>
+    <published>2024-03-01T12:00:00Z</published>
+    <updated>2024-03-09T12:00:00Z</updated>
+    <link rel="alternate" href="https://atom.example.com/post-1"/>
+    <link rel="replies" type="text/html" title="Comments" href="https://atom.example.com/post-1#comments"/>
+    <author><name>Jane Doe</name></author>
+    <author><name>Max Mustermann</name></author>
+    <category term="rust" label="Rust"/>
+    <category term="release"/>
+    <rights>CC BY 4.0</rights>
+    <content type="html">&lt;p&gt;Hello&lt;/p&gt;</content>
+  </entry>
+  <entry>
+    <id>urn:post-2</id>
+    <title>Travel diary</title>
+    <published>2024-03-02T12:00:00Z</published>
+    <link href="https://atom.example.com/post-2"/>
+    <author><name>Max Mustermann</name></author>
+    <category term="travel"/>
+    <content type="html">&lt;p&gt;Away&lt;/p&gt;</content>
+  </entry>
+</feed>"#;
+
+const RSS_XML: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel>
+<title>RSS News</title><link>https://news.example.com/</link><description>News</description>
+<language>de-DE</language><copyright>(c) News Corp</copyright>
+<generator>WordPress 6.4</generator>
+<image><url>https://news.example.com/logo.png</url><title>RSS News</title><link>https://news.example.com/</link></image>
+<item><guid>news-1</guid><title>Election night</title><dc:creator>Erika Musterfrau</dc:creator>
+<category>Politics</category><pubDate>Mon, 04 Mar 2024 10:00:00 GMT</pubDate>
+<description>Results</description></item>
+</channel></rss>"#;
+
+/// Serves `/atom.xml` and `/rss.xml`; returns the base url.
+fn spawn_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&buf);
+            let body = if request.starts_with("GET /atom.xml") {
+                ATOM_XML
+            } else {
+                RSS_XML
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/xml\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    base
+}
+
+fn setup() -> (tempfile::TempDir, PathBuf, String) {
+    let (dir, path) = temp_config();
+    let base = spawn_server();
+    for feed in ["atom.xml", "rss.xml"] {
+        bin()
+            .args(["--config", path.to_str().unwrap(), "add"])
+            .arg(format!("{}/{}", base, feed))
+            .assert()
+            .success();
+    }
+    (dir, path, base)
+}
+
+fn json(path: &Path, args: &[&str]) -> serde_json::Value {
+    let output = bin()
+        .args(["--config", path.to_str().unwrap(), "-o", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn show_prints_item_metadata() {
+    let (_dir, path, _) = setup();
+    bin()
+        .args(["--config", path.to_str().unwrap(), "show", "urn:post-1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated: 2024-03-09"))
+        .stdout(predicate::str::contains(
+            "Link:   https://atom.example.com/post-1\n",
+        ))
+        .stdout(predicate::str::contains("Author: Jane Doe, Max Mustermann"))
+        .stdout(predicate::str::contains("Categories: Rust, release"))
+        .stdout(predicate::str::contains("Language: en"))
+        .stdout(predicate::str::contains("Rights: CC BY 4.0"))
+        .stdout(predicate::str::contains(
+            "Links:\n  https://atom.example.com/post-1#comments [replies] Comments",
+        ));
+
+    let item = json(&path, &["show", "urn:post-1"]);
+    assert_eq!(item["link"], "https://atom.example.com/post-1");
+    assert_eq!(
+        item["authors"],
+        serde_json::json!(["Jane Doe", "Max Mustermann"])
+    );
+    assert_eq!(item["categories"], serde_json::json!(["Rust", "release"]));
+    assert_eq!(item["updated"], "2024-03-09T12:00:00+00:00");
+    assert_eq!(item["rights"], "CC BY 4.0");
+    assert_eq!(item["links"][0]["rel"], "replies");
+    assert_eq!(item["links"][0]["media_type"], "text/html");
+
+    // RSS items take the channel's language and copyright.
+    let item = json(&path, &["show", "news-1"]);
+    assert_eq!(item["authors"], serde_json::json!(["Erika Musterfrau"]));
+    assert_eq!(item["categories"], serde_json::json!(["Politics"]));
+    assert_eq!(item["language"], "de-de");
+    assert_eq!(item["rights"], "(c) News Corp");
+    assert_eq!(item["updated"], serde_json::Value::Null);
+}
+
+#[test]
+fn list_items_shows_and_filters_by_author_and_category() {
+    let (_dir, path, _) = setup();
+    bin()
+        .args(["--config", path.to_str().unwrap(), "list-items"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "| Election night | http://127.0.0.1",
+        ))
+        .stdout(predicate::str::contains("| by Erika Musterfrau\n"));
+
+    let ids = |args: &[&str]| -> Vec<String> {
+        let mut all = vec!["list-items"];
+        all.extend_from_slice(args);
+        json(&path, &all)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(ids(&["--author", "max"]), ["urn:post-2", "urn:post-1"]);
+    assert_eq!(ids(&["--author", "jane doe"]), ["urn:post-1"]);
+    assert_eq!(ids(&["--category", "RUST"]), ["urn:post-1"]);
+    assert_eq!(
+        ids(&["--author", "max", "--category", "travel"]),
+        ["urn:post-2"]
+    );
+    assert!(ids(&["--author", "nobody"]).is_empty());
+
+    let items = json(&path, &["list-items", "--category", "politics"]);
+    assert_eq!(items[0]["authors"][0], "Erika Musterfrau");
+    assert_eq!(items[0]["categories"][0], "Politics");
+}
+
+#[test]
+fn list_feeds_shows_feed_metadata() {
+    let (_dir, path, base) = setup();
+    let feeds = json(&path, &["list-feeds"]);
+    let atom = &feeds[0];
+    assert_eq!(atom["url"], format!("{}/atom.xml", base));
+    assert_eq!(atom["site_url"], "https://atom.example.com/");
+    assert_eq!(atom["icon"], "https://atom.example.com/favicon.ico");
+    assert_eq!(atom["logo"], "https://atom.example.com/logo.png");
+    assert_eq!(atom["language"], "en");
+    assert_eq!(atom["generator"], "Hugo 0.120");
+    let rss = &feeds[1];
+    assert_eq!(rss["site_url"], "https://news.example.com/");
+    assert_eq!(rss["logo"], "https://news.example.com/logo.png");
+    assert_eq!(rss["language"], "de-de");
+    assert_eq!(rss["generator"], "WordPress 6.4");
+    assert!(rss.get("icon").is_none());
+}
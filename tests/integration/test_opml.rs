@@ -0,0 +1,151 @@
+//! Integration test: OPML import keeps titles and folder hierarchy; export round-trips it.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::{export_opml, parse_opml, Feed, SubscriptionList};
+use std::path::PathBuf;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const OPML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="1.0">
+  <head><title>Export from another reader</title></head>
+  <body>
+    <outline text="Top Level" type="rss" xmlUrl="https://example.com/top.xml"/>
+    <outline text="Tech" title="Tech">
+      <outline text="Rust Blog" title="Rust Blog" type="rss" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+      <outline text="Languages">
+        <outline text="Go &amp; Friends" type="rss" xmlurl="https://go.dev/blog/feed.atom"/>
+      </outline>
+    </outline>
+  </body>
+</opml>"#;
+
+fn by_url<'a>(feeds: &'a [Feed], url: &str) -> &'a Feed {
+    feeds.iter().find(|f| f.url == url).unwrap()
+}
+
+#[test]
+fn parse_opml_keeps_titles_and_nested_folders() {
+    let feeds = parse_opml(OPML).unwrap();
+    assert_eq!(feeds.len(), 3);
+
+    let top = by_url(&feeds, "https://example.com/top.xml");
+    assert_eq!(top.title.as_deref(), Some("Top Level"));
+    assert_eq!(top.folder, None);
+
+    let rust = by_url(&feeds, "https://blog.rust-lang.org/feed.xml");
+    assert_eq!(rust.title.as_deref(), Some("Rust Blog"));
+    assert_eq!(rust.folder.as_deref(), Some("Tech"));
+
+    let go = by_url(&feeds, "https://go.dev/blog/feed.atom");
+    assert_eq!(go.title.as_deref(), Some("Go & Friends"));
+    assert_eq!(go.folder.as_deref(), Some("Tech/Languages"));
+}
+
+#[test]
+fn export_then_parse_round_trips_folders() {
+    let mut store = SubscriptionList::default();
+    for feed in parse_opml(OPML).unwrap() {
+        store.subscribe(feed);
+    }
+    let exported = export_opml(&store);
+    let reparsed = parse_opml(&exported).unwrap();
+    assert_eq!(reparsed.len(), 3);
+    for feed in &store.feeds {
+        let again = by_url(&reparsed, &feed.url);
+        assert_eq!(again.title, feed.title);
+        assert_eq!(again.folder, feed.folder);
+    }
+}
+
+#[test]
+fn refresh_merge_keeps_imported_folder() {
+    let mut store = SubscriptionList::default();
+    store.subscribe(Feed {
+        url: "https://example.com/feed.xml".to_string(),
+        folder: Some("News".to_string()),
+        ..Default::default()
+    });
+    // A fetched feed never carries a folder.
+    store.add_feed(
+        Feed {
+            url: "https://example.com/feed.xml".to_string(),
+            title: Some("Fetched Title".to_string()),
+            ..Default::default()
+        },
+        vec![],
+    );
+    assert_eq!(store.feeds[0].folder.as_deref(), Some("News"));
+    assert_eq!(store.feeds[0].title.as_deref(), Some("Fetched Title"));
+}
+
+#[test]
+fn import_opml_and_export_opml_commands() {
+    let (dir, path) = temp_config();
+    let opml_in = dir.path().join("in.opml");
+    let opml_out = dir.path().join("out.opml");
+    std::fs::write(&opml_in, OPML).unwrap();
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("import-opml")
+        .arg(&opml_in)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 3 feed(s)"));
+
+    // Importing again skips feeds that are already subscribed.
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("-o")
+        .arg("json")
+        .arg("import-opml")
+        .arg(&opml_in)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"skipped\": 3"));
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("list-feeds")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rust Blog"));
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("export-opml")
+        .arg(&opml_out)
+        .assert()
+        .success();
+    let exported = parse_opml(&std::fs::read_to_string(&opml_out).unwrap()).unwrap();
+    assert_eq!(
+        by_url(&exported, "https://go.dev/blog/feed.atom")
+            .folder
+            .as_deref(),
+        Some("Tech/Languages")
+    );
+
+    // Without a file, OPML goes to stdout.
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("export-opml")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("<opml").and(predicate::str::contains("xmlUrl")));
+}
@@ -0,0 +1,116 @@
+//! Integration test: the refresh engine fetches feeds concurrently, streams one result per feed,
+//! and never exceeds the per-host politeness limit.
+
+use rss_reader::{refresh_feeds, Feed, FetchOutcome, RefreshOptions, SubscriptionList};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const FEED_XML: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Slow Feed</title><link>http://localhost/</link>
+<description>test</description>
+<item><guid>slow-1</guid><title>First</title></item>
+</channel></rss>"#;
+
+/// Per-host (current, max) in-flight requests seen by the server, keyed by `Host` header
+/// without port.
+type InFlight = Arc<Mutex<HashMap<String, (usize, usize)>>>;
+
+/// Serves `FEED_XML` after a delay on every path, one thread per connection.
+/// Returns the port and the in-flight tracker.
+fn spawn_slow_server(delay: Duration) -> (u16, InFlight) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let in_flight: InFlight = Arc::new(Mutex::new(HashMap::new()));
+    let tracker = in_flight.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let tracker = tracker.clone();
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut chunk).unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request = String::from_utf8_lossy(&buf).to_lowercase();
+                let host = request
+                    .lines()
+                    .find_map(|l| l.strip_prefix("host: "))
+                    .and_then(|h| h.split(':').next())
+                    .unwrap_or("")
+                    .to_string();
+                {
+                    let mut m = tracker.lock().unwrap();
+                    let e = m.entry(host.clone()).or_insert((0, 0));
+                    e.0 += 1;
+                    e.1 = e.1.max(e.0);
+                }
+                std::thread::sleep(delay);
+                tracker.lock().unwrap().get_mut(&host).unwrap().0 -= 1;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    FEED_XML.len(),
+                    FEED_XML
+                );
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+    (port, in_flight)
+}
+
+fn feed(url: String) -> Feed {
+    Feed {
+        url,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn refresh_engine_respects_per_host_limit_and_streams_every_feed() {
+    let (port, in_flight) = spawn_slow_server(Duration::from_millis(200));
+    let feeds: Vec<Feed> = (0..6)
+        .map(|i| feed(format!("http://127.0.0.1:{}/a{}.xml", port, i)))
+        .chain((0..6).map(|i| feed(format!("http://localhost:{}/b{}.xml", port, i))))
+        .collect();
+    let options = RefreshOptions {
+        concurrency: 8,
+        per_host: 2,
+        ..RefreshOptions::default()
+    };
+
+    let mut store = SubscriptionList::default();
+    let mut seen = 0;
+    for done in refresh_feeds(feeds, &options) {
+        assert!(
+            matches!(done.result, Ok(FetchOutcome::Updated(..))),
+            "{}: {:?}",
+            done.url,
+            done.result
+        );
+        done.apply(&mut store);
+        seen += 1;
+    }
+    assert_eq!(seen, 12);
+    assert_eq!(store.feeds.len(), 12);
+
+    let m = in_flight.lock().unwrap();
+    for (host, (_, max)) in m.iter() {
+        assert!(*max <= 2, "host {} saw {} concurrent requests", host, max);
+    }
+    assert_eq!(m.values().map(|(_, max)| *max).max(), Some(2));
+}
+
+#[test]
+fn refresh_engine_with_no_feeds_closes_immediately() {
+    let rx = refresh_feeds(vec![], &RefreshOptions::default());
+    assert!(rx.recv().is_err());
+}
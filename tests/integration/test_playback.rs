@@ -0,0 +1,278 @@
+//! Integration test: playback positions of enclosures are saved, merged across writers and
+//! persisted by both backends; near the end an episode counts as played. `local_file`
+//! downloads an enclosure once for the player, and `play` rejects what it cannot play.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::media::{self, download::local_file};
+use rss_reader::store;
+use rss_reader::{
+    parse_duration, DownloadStatus, Feed, FeedItem, HttpOptions, JsonStorage, MediaEnclosure,
+    Storage, SubscriptionList,
+};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config(name: &str) -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    (dir, path)
+}
+
+const FEED_URL: &str = "https://pod.example.com/feed.xml";
+
+fn enclosure(url: &str, media_type: Option<&str>) -> MediaEnclosure {
+    MediaEnclosure {
+        url: url.to_string(),
+        media_type: media_type.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn episode(n: u32, url: &str) -> FeedItem {
+    FeedItem {
+        id: format!("ep-{}", n),
+        feed_url: FEED_URL.to_string(),
+        title: format!("Episode {}", n),
+        enclosures: vec![MediaEnclosure {
+            duration_secs: Some(3600),
+            ..enclosure(url, Some("audio/mpeg"))
+        }],
+        ..Default::default()
+    }
+}
+
+fn sample() -> SubscriptionList {
+    let mut list = SubscriptionList::default();
+    list.add_feed(
+        Feed {
+            url: FEED_URL.to_string(),
+            title: Some("Pod".to_string()),
+            ..Default::default()
+        },
+        vec![
+            episode(1, "https://pod.example.com/1.mp3"),
+            episode(2, "https://pod.example.com/2.mp3"),
+        ],
+    );
+    list
+}
+
+#[test]
+fn positions_are_saved_and_the_end_marks_played() {
+    let mut list = sample();
+    let url = "https://pod.example.com/1.mp3";
+    assert!(list.playback_position(url).is_none());
+
+    assert!(!list.set_playback_position("ep-1", url, 754, Some(3600)));
+    let saved = list.playback_position(url).unwrap();
+    assert_eq!(saved.item_id, "ep-1");
+    assert_eq!(saved.position_secs, 754);
+    assert_eq!(saved.duration_secs, Some(3600));
+    assert!(!list.is_read("ep-1"));
+
+    // Within the last 10 seconds the episode is played.
+    assert!(list.set_playback_position("ep-1", url, 3591, Some(3600)));
+    assert!(list.playback_position(url).is_none());
+    assert!(list.is_read("ep-1"));
+
+    // Short clips use the last tenth; without a duration nothing counts as the end.
+    assert!(!list.set_playback_position("ep-2", "https://x/short.mp3", 25, Some(30)));
+    assert!(list.set_playback_position("ep-2", "https://x/short.mp3", 27, Some(30)));
+    assert!(!list.set_playback_position("ep-2", "https://x/long.mp3", 99_999, None));
+
+    assert!(list.clear_playback_position("https://x/long.mp3"));
+    assert!(!list.clear_playback_position("https://x/long.mp3"));
+}
+
+fn assert_positions_persist_and_merge(path: &Path) {
+    let storage = store::open(path).unwrap();
+    storage.save(&mut sample()).unwrap();
+
+    // Two writers save positions of different episodes from the same version.
+    let other = store::open(path).unwrap();
+    let mut gui = storage.load().unwrap();
+    let mut cli = other.load().unwrap();
+    cli.set_playback_position("ep-1", "https://pod.example.com/1.mp3", 120, Some(3600));
+    other.save(&mut cli).unwrap();
+    gui.set_playback_position("ep-2", "https://pod.example.com/2.mp3", 300, Some(3600));
+    storage.save(&mut gui).unwrap();
+
+    let merged = store::open(path).unwrap().load().unwrap();
+    let first = merged
+        .playback_position("https://pod.example.com/1.mp3")
+        .unwrap();
+    assert_eq!(first.position_secs, 120);
+    let second = merged
+        .playback_position("https://pod.example.com/2.mp3")
+        .unwrap();
+    assert_eq!(
+        (second.item_id.as_str(), second.position_secs),
+        ("ep-2", 300)
+    );
+
+    // Clearing is merged too.
+    let mut list = merged;
+    list.clear_playback_position("https://pod.example.com/1.mp3");
+    storage.save(&mut list).unwrap();
+    let reloaded = store::open(path).unwrap().load().unwrap();
+    assert!(reloaded
+        .playback_position("https://pod.example.com/1.mp3")
+        .is_none());
+    assert!(reloaded
+        .playback_position("https://pod.example.com/2.mp3")
+        .is_some());
+}
+
+#[test]
+fn json_positions_persist_and_merge() {
+    let (_dir, path) = temp_config("data.json");
+    assert_positions_persist_and_merge(&path);
+}
+
+#[test]
+fn sqlite_positions_persist_and_merge() {
+    let (_dir, path) = temp_config("data.db");
+    assert_positions_persist_and_merge(&path);
+}
+
+#[test]
+fn durations_parse_and_audio_is_recognized() {
+    assert_eq!(parse_duration("1:02:03"), Some(3723));
+    assert_eq!(parse_duration("12:30"), Some(750));
+    assert_eq!(parse_duration("95"), Some(95));
+    assert_eq!(parse_duration("soon"), None);
+
+    assert!(media::is_audio(&enclosure(
+        "https://x/a",
+        Some("audio/mpeg")
+    )));
+    assert!(!media::is_audio(&enclosure(
+        "https://x/a.mp3",
+        Some("video/mp4")
+    )));
+    assert!(media::is_audio(&enclosure("https://x/a.M4A?t=1", None)));
+    assert!(!media::is_audio(&enclosure("https://x/a.pdf", None)));
+}
+
+/// Serves a fixed body for every request; returns the base url and the request count.
+fn spawn_server(body: &'static [u8]) -> (String, Arc<Mutex<usize>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let count = Arc::new(Mutex::new(0));
+    let seen = count.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            *seen.lock().unwrap() += 1;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(body);
+        }
+    });
+    (base, count)
+}
+
+#[test]
+fn local_file_downloads_once_and_records_it() {
+    let (base, requests) = spawn_server(b"ID3 not really audio");
+    let dir = tempfile::tempdir().unwrap();
+    let storage = JsonStorage::new(&dir.path().join("data.json"));
+    let mut list = SubscriptionList::default();
+    let item = episode(1, &format!("{}/1.mp3", base));
+    list.add_feed(Feed::default(), vec![item.clone()]);
+    let files = dir.path().join("files");
+
+    let path = local_file(
+        &mut list,
+        &storage,
+        &item,
+        0,
+        &files,
+        &HttpOptions::default(),
+        &mut |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(path, files.join("Episode 1.mp3"));
+    assert_eq!(std::fs::read(&path).unwrap(), b"ID3 not really audio");
+    let download = list.download(&item.enclosures[0].url).unwrap();
+    assert_eq!(download.status, DownloadStatus::Done);
+    // Saved, so the next run finds the file without downloading again.
+    let mut reloaded = storage.load().unwrap();
+    let again = local_file(
+        &mut reloaded,
+        &storage,
+        &item,
+        0,
+        &files,
+        &HttpOptions::default(),
+        &mut |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(again, path);
+    assert_eq!(*requests.lock().unwrap(), 1);
+}
+
+#[test]
+fn play_rejects_missing_and_non_audio_enclosures() {
+    let (dir, path) = temp_config("data.json");
+    let mut list = sample();
+    list.add_feed(
+        Feed {
+            url: FEED_URL.to_string(),
+            ..Default::default()
+        },
+        vec![FeedItem {
+            id: "video".to_string(),
+            feed_url: FEED_URL.to_string(),
+            title: "Video".to_string(),
+            enclosures: vec![enclosure(
+                "https://pod.example.com/v.mp4",
+                Some("video/mp4"),
+            )],
+            ..Default::default()
+        }],
+    );
+    list.save(&dir.path().join("data.json")).unwrap();
+    let play = |args: &[&str]| {
+        bin()
+            .args(["--config", path.to_str().unwrap(), "play"])
+            .args(args)
+            .assert()
+    };
+
+    play(&["video"])
+        .failure()
+        .stderr(predicate::str::contains("not audio (video/mp4)"));
+    play(&["ep-1", "--index", "2"])
+        .failure()
+        .stderr(predicate::str::contains("enclosure index 2 not found"));
+    play(&["missing"]).failure();
+    play(&["ep-1", "--start", "soon"])
+        .failure()
+        .stderr(predicate::str::contains("not a time"));
+    #[cfg(not(feature = "player"))]
+    play(&["ep-1"])
+        .failure()
+        .stderr(predicate::str::contains("--features player"));
+}
@@ -0,0 +1,165 @@
+//! Integration test: RSS `<enclosure>`s with iTunes title, duration and artwork, and the
+//! episode's `itunes:episode`/`season` and Podcasting 2.0 chapters, transcripts and
+//! persons are parsed and shown by `show` (human and JSON).
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::{fetch_feed, format_duration, HttpOptions, PodcastFile};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+const PODCAST_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"
+     xmlns:podcast="https://podcastindex.org/namespace/1.0">
+<channel>
+  <title>Rust Talk</title><link>https://pod.example.com/</link><description>Talk</description>
+  <item>
+    <guid>ep-5</guid>
+    <title>Async all the way</title>
+    <pubDate>Tue, 05 Mar 2024 06:00:00 GMT</pubDate>
+    <enclosure url="https://pod.example.com/ep5.mp3" length="48000000" type="audio/mpeg"/>
+    <itunes:title>Async &amp; you</itunes:title>
+    <itunes:duration>1:02:03</itunes:duration>
+    <itunes:image href="https://pod.example.com/ep5.jpg"/>
+    <itunes:season>2</itunes:season>
+    <itunes:episode>5</itunes:episode>
+    <podcast:chapters url="https://pod.example.com/ep5.json" type="application/json+chapters"/>
+    <podcast:transcript url="https://pod.example.com/ep5.vtt" type="text/vtt" language="en" rel="captions"/>
+    <podcast:transcript url="https://pod.example.com/ep5.srt" type="application/srt"/>
+    <podcast:person href="https://jane.example.com" img="https://pod.example.com/jane.jpg">Jane Doe</podcast:person>
+    <podcast:person role="guest">Max Mustermann</podcast:person>
+  </item>
+  <item>
+    <guid>ep-4</guid>
+    <title>Plain episode</title>
+    <enclosure url="https://pod.example.com/ep4.mp3" length="1000" type="audio/mpeg"/>
+    <itunes:duration>754</itunes:duration>
+  </item>
+</channel></rss>"#;
+
+fn spawn_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/podcast.xml", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                PODCAST_XML.len(),
+                PODCAST_XML
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+#[test]
+fn fetch_keeps_enclosure_and_podcast_metadata() {
+    let (_, items) = fetch_feed(&spawn_server(), &HttpOptions::default()).unwrap();
+    let ep5 = items.iter().find(|i| i.id == "ep-5").unwrap();
+    let enc = &ep5.enclosures[0];
+    assert_eq!(enc.url, "https://pod.example.com/ep5.mp3");
+    assert_eq!(enc.length, Some(48_000_000));
+    assert_eq!(enc.title.as_deref(), Some("Async & you"));
+    assert_eq!(enc.duration_secs, Some(3723));
+    assert_eq!(
+        enc.thumbnail.as_deref(),
+        Some("https://pod.example.com/ep5.jpg")
+    );
+
+    let podcast = &ep5.podcast;
+    assert_eq!((podcast.season, podcast.episode), (Some(2), Some(5)));
+    assert_eq!(
+        podcast.chapters.as_ref().map(|c| c.url.as_str()),
+        Some("https://pod.example.com/ep5.json")
+    );
+    assert_eq!(
+        podcast.transcripts[0],
+        PodcastFile {
+            url: "https://pod.example.com/ep5.vtt".to_string(),
+            media_type: Some("text/vtt".to_string()),
+            language: Some("en".to_string()),
+            rel: Some("captions".to_string()),
+        }
+    );
+    assert_eq!(podcast.transcripts.len(), 2);
+    let labels: Vec<String> = podcast.persons.iter().map(|p| p.label()).collect();
+    assert_eq!(labels, ["Jane Doe (host)", "Max Mustermann (guest)"]);
+    assert_eq!(
+        podcast.persons[0].href.as_deref(),
+        Some("https://jane.example.com")
+    );
+
+    let ep4 = items.iter().find(|i| i.id == "ep-4").unwrap();
+    assert!(ep4.podcast.is_empty());
+    assert_eq!(ep4.enclosures[0].duration_secs, Some(754));
+    assert_eq!(format_duration(754), "12:34");
+    assert_eq!(format_duration(3723), "1:02:03");
+}
+
+#[test]
+fn show_prints_podcast_details() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("data.json");
+    let config = config.to_str().unwrap();
+    bin()
+        .args(["--config", config, "add", &spawn_server()])
+        .assert()
+        .success();
+
+    bin()
+        .args(["--config", config, "show", "ep-5"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Episode: Season 2, episode 5"))
+        .stdout(predicate::str::contains(
+            "People: Jane Doe (host), Max Mustermann (guest)",
+        ))
+        .stdout(predicate::str::contains(
+            "      Async & you · 1:02:03 · image: https://pod.example.com/ep5.jpg",
+        ))
+        .stdout(predicate::str::contains(
+            "  Chapters:   https://pod.example.com/ep5.json",
+        ))
+        .stdout(predicate::str::contains(
+            "  Transcript: https://pod.example.com/ep5.vtt (text/vtt, en)",
+        ));
+
+    let output = bin()
+        .args(["--config", config, "-o", "json", "show", "ep-5"])
+        .output()
+        .unwrap();
+    let item: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(item["enclosures"][0]["duration_secs"], 3723);
+    assert_eq!(item["enclosures"][0]["title"], "Async & you");
+    assert_eq!(item["podcast"]["season"], 2);
+    assert_eq!(item["podcast"]["episode"], 5);
+    assert_eq!(
+        item["podcast"]["transcripts"][1]["media_type"],
+        "application/srt"
+    );
+    assert_eq!(item["podcast"]["persons"][1]["role"], "guest");
+
+    let output = bin()
+        .args(["--config", config, "-o", "json", "show", "ep-4"])
+        .output()
+        .unwrap();
+    let item: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(item.get("podcast").is_none());
+}
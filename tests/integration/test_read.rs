@@ -0,0 +1,178 @@
+//! Integration test: `read` walks unread items oldest first (next, previous, quit) and
+//! marks the viewed ones read on exit; `show` prints directly when stdout is not a terminal
+//! and otherwise runs `$PAGER` through the shell.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::{Feed, FeedItem, SubscriptionList};
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const BLOG: &str = "https://blog.example.com/feed.xml";
+const NEWS: &str = "https://news.example.com/rss";
+
+fn item(feed_url: &str, id: &str, day: u32) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: format!("Title of {}", id),
+        link: None,
+        published: Some(
+            chrono::DateTime::parse_from_rfc3339(&format!("2024-03-{:02}T12:00:00Z", day))
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        ),
+        summary: None,
+        content: Some(format!("<p>Body of {}</p>", id)),
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+/// Blog: b1 (day 1), b2 (day 3, already read); News: n1 (day 2), n2 (day 4).
+fn write_store(path: &Path) {
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: BLOG.to_string(),
+            title: Some("Blog".to_string()),
+            ..Default::default()
+        },
+        vec![item(BLOG, "b1", 1), item(BLOG, "b2", 3)],
+    );
+    store.add_feed(
+        Feed {
+            url: NEWS.to_string(),
+            title: Some("News".to_string()),
+            ..Default::default()
+        },
+        vec![item(NEWS, "n1", 2), item(NEWS, "n2", 4)],
+    );
+    store.set_read("b2", true);
+    store.save(path).unwrap();
+}
+
+fn read(path: &Path, args: &[&str], input: &str) -> String {
+    let output = bin()
+        .args(["--config", path.to_str().unwrap(), "read"])
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Titles in the order they were shown.
+fn shown(stdout: &str) -> Vec<&str> {
+    stdout
+        .lines()
+        .filter_map(|l| l.strip_prefix("Title of "))
+        .collect()
+}
+
+#[test]
+fn read_walks_unread_items_and_marks_viewed_read() {
+    let (_dir, path) = temp_config();
+    write_store(&path);
+
+    // n, p, n, q: b1, n1, b1 again, n1 again; n2 is never shown.
+    let stdout = read(&path, &[], "n\np\nn\nq\n");
+    assert_eq!(shown(&stdout), ["b1", "n1", "b1", "n1"]);
+    assert!(stdout.contains("Body of b1"));
+    assert!(stdout.contains("Marked 2 item(s) read"));
+    let store = SubscriptionList::load(&path).unwrap();
+    assert!(store.is_read("b1"));
+    assert!(store.is_read("n1"));
+    assert!(!store.is_read("n2"));
+
+    // Enter moves on; end of input quits. Only n2 is left.
+    let stdout = read(&path, &[], "\n");
+    assert_eq!(shown(&stdout), ["n2"]);
+    assert!(stdout.contains("Marked 1 item(s) read"));
+    assert!(read(&path, &[], "").contains("No unread items."));
+}
+
+#[test]
+fn read_filters_by_feed_and_reports_json() {
+    let (_dir, path) = temp_config();
+    write_store(&path);
+
+    // Unknown commands (and p on the first item) prompt again without re-showing.
+    let stdout = read(&path, &["--feed", "news"], "x\np\nn\nn\n");
+    assert_eq!(shown(&stdout), ["n1", "n2"]);
+    let store = SubscriptionList::load(&path).unwrap();
+    assert!(!store.is_read("b1"));
+    assert_eq!(store.unread_count(Some(NEWS)), 0);
+
+    let output = bin()
+        .args(["--config", path.to_str().unwrap(), "-o", "json", "read"])
+        .write_stdin("q\n")
+        .output()
+        .unwrap();
+    let summary = String::from_utf8(output.stdout).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&summary[summary.find("{\n").unwrap()..]).unwrap();
+    assert_eq!(json["updated_count"], 1);
+}
+
+#[test]
+fn show_prints_directly_when_not_a_terminal() {
+    let (_dir, path) = temp_config();
+    write_store(&path);
+    for args in [&["show", "b1"][..], &["show", "b1", "--no-pager"][..]] {
+        bin()
+            .env("PAGER", "false")
+            .args(["--config", path.to_str().unwrap()])
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Body of b1"));
+    }
+}
+
+/// Output of `rss-reader --config <path> <args>` run with stdout on a pseudo-terminal
+/// (util-linux `script`) and `PAGER` set to `pager`.
+#[allow(deprecated)] // see bin()
+fn on_terminal(path: &Path, pager: &str, args: &str) -> String {
+    let command = format!(
+        "{} --config {} {}",
+        assert_cmd::cargo::cargo_bin("rss-reader").display(),
+        path.display(),
+        args
+    );
+    let output = std::process::Command::new("script")
+        .args(["-qec", &command, "/dev/null"])
+        .env("PAGER", pager)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn show_runs_the_pager_through_the_shell() {
+    let (dir, path) = temp_config();
+    write_store(&path);
+    let paged = dir.path().join("paged out.txt");
+    let pager = format!("cat > '{}'", paged.display());
+    on_terminal(&path, &pager, "show b1");
+    assert!(std::fs::read_to_string(&paged)
+        .unwrap()
+        .contains("Body of b1"));
+
+    // A pager that cannot be found is reported and the text printed instead.
+    let out = on_terminal(&path, "no-such-pager --quiet", "show b1");
+    assert!(out.contains("not found"), "{}", out);
+    assert!(out.contains("Body of b1"), "{}", out);
+}
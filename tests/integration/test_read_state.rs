@@ -0,0 +1,147 @@
+//! Integration test: mark-read / mark-unread persist, survive refresh merges, and drive
+//! `list-items --unread` and the unread counts in `list-feeds`.
+
+use assert_cmd::Command;
+use rss_reader::{Feed, FeedItem, SubscriptionList};
+use std::path::PathBuf;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const FEED_URL: &str = "https://example.com/feed.xml";
+
+fn feed() -> Feed {
+    Feed {
+        url: FEED_URL.to_string(),
+        title: Some("Example Feed".to_string()),
+        description: None,
+        last_fetched: None,
+        created_at: None,
+        ..Default::default()
+    }
+}
+
+fn item(id: &str) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: FEED_URL.to_string(),
+        title: format!("Item {}", id),
+        link: None,
+        published: None,
+        summary: None,
+        content: None,
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+fn json_stdout(path: &PathBuf, args: &[&str]) -> serde_json::Value {
+    let output = bin()
+        .arg("--config")
+        .arg(path)
+        .arg("-o")
+        .arg("json")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?} failed: {:?}", args, output);
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn read_state_survives_add_feed_merge() {
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(), vec![item("a"), item("b")]);
+    assert!(store.set_read("a", true));
+    assert!(!store.set_read("missing", true));
+
+    // Simulate a refresh returning the same items again.
+    store.add_feed(feed(), vec![item("a"), item("b")]);
+    assert!(store.is_read("a"));
+    assert!(!store.is_read("b"));
+    assert_eq!(store.unread_count(Some(FEED_URL)), 1);
+}
+
+#[test]
+fn mark_read_filters_list_items_and_updates_unread_count() {
+    let (_dir, path) = temp_config();
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(), vec![item("a"), item("b")]);
+    store.save(&path).unwrap();
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("mark-read")
+        .arg("a")
+        .assert()
+        .success();
+
+    let unread = json_stdout(&path, &["list-items", "--unread"]);
+    let ids: Vec<&str> = unread
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec!["b"]);
+
+    let feeds = json_stdout(&path, &["list-feeds"]);
+    assert_eq!(feeds[0]["unread_count"], 1);
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("mark-unread")
+        .arg("a")
+        .assert()
+        .success();
+    let feeds = json_stdout(&path, &["list-feeds"]);
+    assert_eq!(feeds[0]["unread_count"], 2);
+}
+
+#[test]
+fn mark_read_unknown_item_fails() {
+    let (_dir, path) = temp_config();
+    let output = bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("mark-read")
+        .arg("nonexistent-id")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+}
+
+#[test]
+fn mark_read_checks_every_id_before_changing_any() {
+    let (_dir, path) = temp_config();
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(), vec![item("a"), item("b"), item("c")]);
+    store.save(&path).unwrap();
+
+    let output = bin()
+        .arg("--config")
+        .arg(&path)
+        .args(["mark-read", "a", "missing", "b"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("item not found: missing"));
+    assert!(!SubscriptionList::load(&path).unwrap().is_read("a"));
+
+    let marked = json_stdout(&path, &["mark-read", "--feed", FEED_URL]);
+    assert_eq!(marked["updated_count"], 3);
+    json_stdout(&path, &["mark-unread", "a", "c"]);
+    let store = SubscriptionList::load(&path).unwrap();
+    assert!(!store.is_read("a") && store.is_read("b") && !store.is_read("c"));
+}
@@ -0,0 +1,101 @@
+//! Integration test: failed fetches are recorded per feed, `refresh` exits non-zero with a
+//! per-feed status, and `list-feeds` marks the broken feed.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::{Feed, SubscriptionList};
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const DEAD_URL: &str = "http://127.0.0.1:1/dead.xml";
+
+fn write_store_with_dead_feed(path: &Path) {
+    let mut store = SubscriptionList::default();
+    store.feeds.push(Feed {
+        url: DEAD_URL.to_string(),
+        title: Some("Dead Feed".to_string()),
+        ..Default::default()
+    });
+    store.save(path).unwrap();
+}
+
+#[test]
+fn refresh_with_failing_feed_exits_nonzero_and_prints_status_row() {
+    let (_dir, path) = temp_config();
+    write_store_with_dead_feed(&path);
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("refresh")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("failed").and(predicate::str::contains("Dead Feed")))
+        .stderr(predicate::str::contains("Refresh failed for 1 feed(s)"));
+}
+
+#[test]
+fn consecutive_failures_are_counted_and_shown_in_list_feeds() {
+    let (_dir, path) = temp_config();
+    write_store_with_dead_feed(&path);
+
+    for _ in 0..2 {
+        bin()
+            .arg("--config")
+            .arg(&path)
+            .arg("refresh")
+            .assert()
+            .failure();
+    }
+
+    let output = bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("-o")
+        .arg("json")
+        .arg("list-feeds")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let feeds: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(feeds[0]["failing"], true);
+    assert_eq!(feeds[0]["consecutive_failures"], 2);
+    assert!(feeds[0]["last_error"]
+        .as_str()
+        .is_some_and(|e| !e.is_empty()));
+    assert!(feeds[0]["last_success"].is_null());
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("list-feeds")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("FAILING x2"));
+}
+
+#[test]
+fn successful_fetch_clears_failure_state() {
+    let mut store = SubscriptionList::default();
+    store.feeds.push(Feed {
+        url: DEAD_URL.to_string(),
+        ..Default::default()
+    });
+    store.record_failure(DEAD_URL, "connection refused");
+    assert!(store.feeds[0].is_failing());
+
+    store.touch_feed(DEAD_URL);
+    assert!(!store.feeds[0].is_failing());
+    assert!(store.feeds[0].last_error.is_none());
+    assert!(store.feeds[0].last_success.is_some());
+}
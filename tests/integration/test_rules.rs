@@ -0,0 +1,273 @@
+//! Integration test: filter rules run on new items (hide, mark-read, star, tag, drop),
+//! match on every field by substring or regex, persist with both backends, and are
+//! managed through `rules add/list/remove/test`.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::rules::{Condition, Rule, RuleAction, RuleField};
+use rss_reader::{Feed, FeedItem, MediaEnclosure, SqliteStorage, Storage, SubscriptionList};
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const FEED_URL: &str = "https://blog.example.com/feed.xml";
+
+fn feed() -> Feed {
+    Feed {
+        url: FEED_URL.to_string(),
+        title: Some("Example Blog".to_string()),
+        ..Default::default()
+    }
+}
+
+fn item(id: &str, title: &str) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: FEED_URL.to_string(),
+        title: title.to_string(),
+        content: Some(format!("<p>Body of {}</p>", id)),
+        ..Default::default()
+    }
+}
+
+fn rule(field: RuleField, pattern: &str, regex: bool, action: RuleAction) -> Rule {
+    Rule {
+        conditions: vec![Condition {
+            field,
+            pattern: pattern.to_string(),
+            regex,
+        }],
+        action,
+    }
+}
+
+fn items() -> Vec<FeedItem> {
+    vec![
+        item("sponsored", "Sponsored: buy things"),
+        FeedItem {
+            authors: vec!["Jane Doe".to_string()],
+            ..item("jane", "Weekly notes")
+        },
+        FeedItem {
+            categories: vec!["Release".to_string()],
+            ..item("release", "Version 2.0")
+        },
+        FeedItem {
+            enclosures: vec![MediaEnclosure {
+                url: "https://blog.example.com/ep1.mp4".to_string(),
+                media_type: Some("video/mp4".to_string()),
+                length: None,
+                title: None,
+                ..Default::default()
+            }],
+            ..item("video", "Episode 1")
+        },
+        item("ad", "[AD] Great offer"),
+        item("plain", "Nothing special"),
+    ]
+}
+
+fn store_with_rules() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    for r in [
+        rule(RuleField::Title, "sponsored", false, RuleAction::Hide),
+        rule(RuleField::Author, "jane", false, RuleAction::Star),
+        rule(
+            RuleField::Category,
+            "release",
+            false,
+            RuleAction::Tag("releases".to_string()),
+        ),
+        rule(RuleField::Enclosure, "video/", false, RuleAction::MarkRead),
+        rule(RuleField::Title, r"^\[AD\]", true, RuleAction::Drop),
+    ] {
+        store.add_rule(r).unwrap();
+    }
+    store.add_feed(feed(), items());
+    store
+}
+
+fn check_rules_applied(store: &SubscriptionList) {
+    assert!(store.get_item("ad", None).is_none(), "dropped");
+    assert!(store.is_hidden("sponsored"));
+    assert!(store.is_starred("jane"));
+    assert!(store.item_has_tag("release", "releases"));
+    assert!(store.is_read("video"));
+    for id in ["plain", "jane", "release"] {
+        assert!(!store.is_hidden(id) && !store.is_read(id), "{}", id);
+    }
+    // sponsored is hidden, video read: jane, release and plain are unread.
+    assert_eq!(store.unread_count(None), 3);
+}
+
+#[test]
+fn rules_apply_to_new_items_only() {
+    let mut store = store_with_rules();
+    check_rules_applied(&store);
+
+    // Known items are not re-ruled: un-hiding sticks and a new rule leaves them alone.
+    store
+        .add_rule(rule(
+            RuleField::Feed,
+            "example blog",
+            false,
+            RuleAction::Hide,
+        ))
+        .unwrap();
+    store.set_read("video", false);
+    store.add_feed(feed(), items());
+    assert!(!store.is_hidden("plain"));
+    assert!(!store.is_read("video"));
+    assert!(store.get_item("ad", None).is_none());
+
+    store.add_feed(feed(), vec![item("new", "Fresh post")]);
+    assert!(store.is_hidden("new"));
+}
+
+#[test]
+fn rules_and_their_effects_persist() {
+    let (dir, path) = temp_config();
+    store_with_rules().save(&path).unwrap();
+    let loaded = SubscriptionList::load(&path).unwrap();
+    assert_eq!(loaded.rules.len(), 5);
+    check_rules_applied(&loaded);
+
+    let db = dir.path().join("data.db");
+    let storage = SqliteStorage::open(&db).unwrap();
+    storage.save(&mut store_with_rules()).unwrap();
+    let loaded = SqliteStorage::open(&db).unwrap().load().unwrap();
+    assert_eq!(loaded.rules, store_with_rules().rules);
+    check_rules_applied(&loaded);
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    let mut store = SubscriptionList::default();
+    assert!(store
+        .add_rule(rule(RuleField::Title, "(", true, RuleAction::Hide))
+        .is_err());
+    assert!(store
+        .add_rule(Rule {
+            conditions: vec![],
+            action: RuleAction::Hide,
+        })
+        .is_err());
+    assert!("tag:".parse::<RuleAction>().is_err());
+    assert!("explode".parse::<RuleAction>().is_err());
+    assert_eq!(
+        "tag:later".parse::<RuleAction>(),
+        Ok(RuleAction::Tag("later".to_string()))
+    );
+    assert!(store.remove_rule(1).is_err());
+    assert!(store.rules.is_empty());
+}
+
+fn write_store(path: &Path) {
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(), items());
+    store.save(path).unwrap();
+}
+
+fn rules_cmd(path: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+    bin()
+        .args(["--config", path.to_str().unwrap(), "rules"])
+        .args(args)
+        .assert()
+}
+
+#[test]
+fn rules_cli_add_list_test_remove() {
+    let (_dir, path) = temp_config();
+    write_store(&path);
+
+    rules_cmd(&path, &["list"])
+        .success()
+        .stdout(predicate::str::contains("No rules."));
+    rules_cmd(&path, &["test", "--title", "sponsored"])
+        .success()
+        .stdout(predicate::str::contains("Sponsored: buy things"))
+        .stdout(predicate::str::contains("1 cached item(s) match"));
+    rules_cmd(&path, &["add", "--title", "sponsored", "--action", "hide"])
+        .success()
+        .stdout(predicate::str::contains(
+            "Added rule 1: title contains \"sponsored\" → hide",
+        ));
+    rules_cmd(
+        &path,
+        &["add", "--title", "^V", "--regex", "--action", "tag:v"],
+    )
+    .success();
+    rules_cmd(&path, &["list"])
+        .success()
+        .stdout(predicate::str::contains("2. title =~ /^V/ → tag:v"));
+    rules_cmd(&path, &["test", "2"])
+        .success()
+        .stdout(predicate::str::contains("Version 2.0"))
+        .stdout(predicate::str::contains("1 cached item(s) match"));
+
+    let output = bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "-o",
+            "json",
+            "rules",
+            "list",
+        ])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[1]["number"], 2);
+    assert_eq!(json[1]["conditions"][0]["regex"], true);
+    assert_eq!(json[1]["action"], "tag:v");
+
+    rules_cmd(&path, &["remove", "1"])
+        .success()
+        .stdout(predicate::str::contains("Removed rule 1"));
+    rules_cmd(&path, &["remove", "5"]).failure();
+    rules_cmd(
+        &path,
+        &["add", "--title", "(", "--regex", "--action", "hide"],
+    )
+    .failure()
+    .stderr(predicate::str::contains("invalid regex"));
+    rules_cmd(&path, &["add", "--title", "x", "--action", "explode"]).failure();
+    rules_cmd(&path, &["add", "--action", "hide"]).failure();
+
+    let store = SubscriptionList::load(&path).unwrap();
+    assert_eq!(store.rules.len(), 1);
+    assert_eq!(store.rules[0].action, RuleAction::Tag("v".to_string()));
+}
+
+#[test]
+fn hidden_and_tagged_items_in_list_items() {
+    let (_dir, path) = temp_config();
+    store_with_rules().save(&path).unwrap();
+    let list = |args: &[&str]| {
+        let output = bin()
+            .args(["--config", path.to_str().unwrap(), "list-items"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let all = list(&[]);
+    assert!(all.contains("Nothing special"));
+    assert!(!all.contains("Sponsored"));
+    assert!(list(&["--hidden"]).contains("Sponsored"));
+
+    let tagged = list(&["--tag", "releases"]);
+    assert!(tagged.contains("Version 2.0"));
+    assert!(!tagged.contains("Nothing special"));
+}
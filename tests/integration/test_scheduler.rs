@@ -0,0 +1,245 @@
+//! Integration test: per-feed refresh schedule (ttl, sy:updatePeriod, Cache-Control,
+//! Retry-After, failure backoff) and `daemon --once` refreshing only due feeds and
+//! logging with the configured date format.
+
+use assert_cmd::Command;
+use chrono::{Duration as Age, Utc};
+use rss_reader::{
+    fetch_feed, next_refresh, refresh_feeds, refresh_interval, Error, Feed, HttpOptions,
+    RefreshHints, RefreshOptions, ScheduleOptions, SubscriptionList,
+};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const HINTED_XML: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+<channel><title>Hinted</title><link>http://localhost/</link><description>test</description>
+<ttl>60</ttl>
+<sy:updatePeriod>daily</sy:updatePeriod>
+<sy:updateFrequency>4</sy:updateFrequency>
+<item><guid>hinted-1</guid><title>First</title></item>
+</channel></rss>"#;
+
+/// Serves `/hinted.xml` (with `Cache-Control: max-age=600`) and a 503 with `Retry-After`
+/// on `/busy.xml`. Returns the base url and the number of requests per path.
+fn spawn_server() -> (String, Arc<Mutex<HashMap<String, usize>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let hits = Arc::new(Mutex::new(HashMap::new()));
+    let counter = hits.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&buf);
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            *counter.lock().unwrap().entry(path.clone()).or_insert(0) += 1;
+            let response = match path.as_str() {
+                "/hinted.xml" => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\n\
+                     Cache-Control: public, max-age=600\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    HINTED_XML.len(),
+                    HINTED_XML
+                ),
+                "/busy.xml" => "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 7200\r\n\
+                                Content-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (base, hits)
+}
+
+fn secs(s: u64) -> Duration {
+    Duration::from_secs(s)
+}
+
+#[test]
+fn interval_follows_hints_within_bounds_and_backs_off() {
+    let options = ScheduleOptions::default();
+    let mut feed = Feed {
+        url: "https://example.com/feed.xml".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(refresh_interval(&feed, &options), secs(30 * 60));
+    assert_eq!(
+        next_refresh(&feed, &options),
+        None,
+        "never fetched = due now"
+    );
+
+    // The longest hint wins, clamped to [min, max].
+    feed.refresh_hints = RefreshHints {
+        ttl_secs: Some(3600),
+        max_age_secs: Some(600),
+        ..Default::default()
+    };
+    assert_eq!(refresh_interval(&feed, &options), secs(3600));
+    feed.refresh_hints.max_age_secs = Some(10);
+    feed.refresh_hints.ttl_secs = Some(60);
+    assert_eq!(refresh_interval(&feed, &options), secs(5 * 60));
+    feed.refresh_hints.update_period_secs = Some(30 * 86_400);
+    assert_eq!(refresh_interval(&feed, &options), secs(24 * 3600));
+    feed.refresh_hints = RefreshHints::default();
+
+    // Failures double the interval after the first, up to max_backoff.
+    let fetched = Utc::now();
+    feed.last_fetched = Some(fetched);
+    feed.consecutive_failures = 3;
+    assert_eq!(
+        next_refresh(&feed, &options),
+        Some(fetched + Age::minutes(120))
+    );
+    feed.consecutive_failures = 30;
+    assert_eq!(
+        next_refresh(&feed, &options),
+        Some(fetched + Age::hours(24))
+    );
+
+    // Retry-After is honored even beyond the backoff.
+    feed.consecutive_failures = 1;
+    feed.refresh_hints.retry_after_secs = Some(48 * 3600);
+    assert_eq!(
+        next_refresh(&feed, &options),
+        Some(fetched + Age::hours(48))
+    );
+}
+
+#[test]
+fn fetch_collects_hints_and_retry_after() {
+    let (base, _) = spawn_server();
+    let (feed, _) = fetch_feed(&format!("{}/hinted.xml", base), &HttpOptions::default()).unwrap();
+    assert_eq!(
+        feed.refresh_hints,
+        RefreshHints {
+            ttl_secs: Some(3600),
+            update_period_secs: Some(6 * 3600),
+            max_age_secs: Some(600),
+            retry_after_secs: None,
+        }
+    );
+
+    let busy = format!("{}/busy.xml", base);
+    match fetch_feed(&busy, &HttpOptions::default()) {
+        Err(Error::RetryAfter(_, secs)) => assert_eq!(secs, 7200),
+        other => panic!("expected RetryAfter, got {:?}", other.map(|_| ())),
+    }
+    let mut store = SubscriptionList::default();
+    store.subscribe(Feed {
+        url: busy.clone(),
+        ..Default::default()
+    });
+    for done in refresh_feeds(store.feeds.clone(), &RefreshOptions::default()) {
+        done.apply(&mut store);
+    }
+    let feed = &store.feeds[0];
+    assert_eq!(feed.consecutive_failures, 1);
+    assert_eq!(feed.refresh_hints.retry_after_secs, Some(7200));
+}
+
+#[test]
+fn daemon_once_refreshes_only_due_feeds() {
+    let (base, hits) = spawn_server();
+    let (_dir, path) = temp_config();
+    let mut store = SubscriptionList::default();
+    // Fetched a minute ago with a one-hour ttl: not due.
+    store.subscribe(Feed {
+        url: format!("{}/fresh.xml", base),
+        last_fetched: Some(Utc::now() - Age::minutes(1)),
+        refresh_hints: RefreshHints {
+            ttl_secs: Some(3600),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    // Never fetched: due.
+    store.subscribe(Feed {
+        url: format!("{}/hinted.xml", base),
+        ..Default::default()
+    });
+    store.save(&path).unwrap();
+
+    let out = bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "-o",
+            "json",
+            "daemon",
+            "--once",
+        ])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    let lines: Vec<serde_json::Value> = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["status"], "fetched");
+    assert!(lines[0]["next_refresh"].is_string());
+
+    let hits = hits.lock().unwrap();
+    assert_eq!(hits.get("/hinted.xml"), Some(&1));
+    assert_eq!(hits.get("/fresh.xml"), None);
+
+    let store = SubscriptionList::load(&path).unwrap();
+    let hinted = store
+        .feeds
+        .iter()
+        .find(|f| f.url.ends_with("/hinted.xml"))
+        .unwrap();
+    assert!(hinted.last_fetched.is_some());
+    assert_eq!(hinted.refresh_hints.update_period_secs, Some(6 * 3600));
+    assert_eq!(store.items(Some(&hinted.url)).len(), 1);
+}
+
+#[test]
+fn daemon_log_uses_the_date_format() {
+    let (base, _) = spawn_server();
+    let (_dir, path) = temp_config();
+    let mut store = SubscriptionList::default();
+    store.subscribe(Feed {
+        url: format!("{}/hinted.xml", base),
+        ..Default::default()
+    });
+    store.save(&path).unwrap();
+
+    let out = bin()
+        .env("RSS_READER_DATE_FORMAT", "day %j of %Y")
+        .args(["--config", path.to_str().unwrap(), "daemon", "--once"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let line = stdout.lines().next().unwrap();
+    assert!(line.starts_with("day "), "{}", line);
+    assert!(line.contains("(next: day "), "{}", line);
+}
@@ -0,0 +1,229 @@
+//! Integration test: full-text search ranks title matches first, honours phrases and
+//! `title:` / `feed:` qualifiers, and returns highlighted snippets (library and `search` CLI).
+//! The CLI reuses a cached index until the items change, and filters apply before the limit.
+
+use assert_cmd::Command;
+use chrono::{TimeZone, Utc};
+use predicates::prelude::*;
+use rss_reader::search::{index_path, search_items};
+use rss_reader::{
+    Feed, FeedItem, JsonStorage, SearchIndex, SearchQuery, Storage, SubscriptionList,
+};
+use std::path::PathBuf;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const RUST: &str = "https://blog.rust-lang.org/feed.xml";
+const NEWS: &str = "https://news.example.com/rss";
+
+fn item(feed_url: &str, id: &str, title: &str, content: &str, minute: i64) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: title.to_string(),
+        link: None,
+        published: Some(Utc.timestamp_opt(minute * 60, 0).unwrap()),
+        summary: None,
+        content: Some(content.to_string()),
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+fn store() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: RUST.to_string(),
+            title: Some("Rust Blog".to_string()),
+            ..Default::default()
+        },
+        vec![
+            item(
+                RUST,
+                "r1",
+                "Async closures stabilized",
+                "<p>Closures can now be <b>async</b>.</p>",
+                1,
+            ),
+            item(
+                RUST,
+                "r2",
+                "Release notes",
+                "<p>This release improves async closures and the borrow checker.</p>",
+                2,
+            ),
+        ],
+    );
+    store.add_feed(
+        Feed {
+            url: NEWS.to_string(),
+            title: Some("Daily News".to_string()),
+            ..Default::default()
+        },
+        vec![item(
+            NEWS,
+            "n1",
+            "Weather",
+            "<p>Closures of roads expected; async weather.</p>",
+            3,
+        )],
+    );
+    store
+}
+
+fn ids(query: &str) -> Vec<String> {
+    SearchIndex::build(&store())
+        .search(&SearchQuery::parse(query), 10)
+        .into_iter()
+        .map(|h| h.item_id)
+        .collect()
+}
+
+#[test]
+fn ranks_title_matches_first_and_requires_all_terms() {
+    assert_eq!(ids("async closures")[0], "r1");
+    assert_eq!(ids("async closures").len(), 3);
+    assert_eq!(ids("borrow async"), ["r2"]);
+    assert!(ids("nonexistent").is_empty());
+}
+
+#[test]
+fn phrases_and_qualifiers() {
+    // n1 has both words, but not as a phrase.
+    assert_eq!(ids("\"async closures\""), ["r1", "r2"]);
+    assert_eq!(ids("title:closures"), ["r1"]);
+    assert_eq!(ids("closures feed:news"), ["n1"]);
+    assert_eq!(ids("feed:\"rust blog\"").len(), 2);
+}
+
+#[test]
+fn snippet_highlights_matches() {
+    let hits = SearchIndex::build(&store()).search(&SearchQuery::parse("borrow"), 10);
+    let snippet = &hits[0].snippet;
+    assert_eq!(snippet.highlights.len(), 1);
+    assert_eq!(&snippet.text[snippet.highlights[0].clone()], "borrow");
+    assert!(snippet.render("[", "]").contains("the [borrow] checker"));
+}
+
+#[test]
+fn search_cli_human_and_json() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "search",
+            "borrow checker",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Release notes | r2"))
+        .stdout(predicate::str::contains("[borrow] [checker]"));
+
+    let out = bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "-o",
+            "json",
+            "search",
+            "title:async",
+        ])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let hits: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["id"], "r1");
+    assert!(hits[0]["highlights"].as_array().is_some());
+
+    bin()
+        .args(["--config", path.to_str().unwrap(), "search", "   "])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn filters_apply_before_the_limit() {
+    let index = SearchIndex::build(&store());
+    let query = SearchQuery::parse("async closures");
+    // n1 ranks last, but is the only hit kept.
+    let hits = index.search_where(&query, 1, |id| id == "n1");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].item_id, "n1");
+
+    let list = store();
+    let news: Vec<&FeedItem> = list.items_by_feed[NEWS].iter().collect();
+    let found = search_items(&index, &query, &news, 1);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0.id, "n1");
+}
+
+#[test]
+fn cached_index_is_reused_until_items_change() {
+    let (_dir, path) = temp_config();
+    let storage = JsonStorage::new(&path);
+    storage.save(&mut store()).unwrap();
+    let cache = index_path(&path);
+    let list = storage.load().unwrap();
+    assert_eq!(SearchIndex::cached(&list, &cache).len(), 3);
+
+    // Same stamp, different body: the cache is read instead of rebuilt.
+    let contents = std::fs::read_to_string(&cache).unwrap();
+    let stamp = contents.lines().next().unwrap();
+    let empty = serde_json::to_string(&SearchIndex::build(&SubscriptionList::default())).unwrap();
+    std::fs::write(&cache, format!("{}\n{}", stamp, empty)).unwrap();
+    assert!(SearchIndex::cached(&list, &cache).is_empty());
+
+    // Saving new items invalidates it.
+    let mut list = storage.load().unwrap();
+    list.add_feed(
+        Feed {
+            url: NEWS.to_string(),
+            ..Default::default()
+        },
+        vec![item(NEWS, "n2", "Traffic", "<p>Roads reopen.</p>", 4)],
+    );
+    storage.save(&mut list).unwrap();
+    let list = storage.load().unwrap();
+    assert_eq!(SearchIndex::cached(&list, &cache).len(), 4);
+}
+
+#[test]
+fn search_cli_skips_hidden_items() {
+    let (_dir, path) = temp_config();
+    let mut list = store();
+    list.hidden_items.insert("r2".to_string());
+    list.save(&path).unwrap();
+    let out = bin()
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "-o",
+            "json",
+            "search",
+            "async",
+        ])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let hits: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let ids: Vec<&str> = hits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| h["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["r1", "n1"]);
+}
@@ -0,0 +1,314 @@
+//! Integration test: `serve` JSON API — token auth, the same JSON shapes as the CLI's
+//! `-o json` output, read/star state, add/remove feed and refresh, and refusing requests
+//! web pages could forge (wrong Host, foreign Origin, non-JSON writes).
+
+use assert_cmd::Command;
+use rss_reader::server::Server;
+use rss_reader::{Feed, FeedItem, SubscriptionList};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const TOKEN: &str = "s3cret";
+const BLOG: &str = "https://blog.example.com/feed.xml";
+const POST: &str = "https://blog.example.com/posts/1";
+
+const FEED_XML: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Local Feed</title><link>http://localhost/</link>
+<description>test</description>
+<item><guid>local-1</guid><title>First</title></item>
+</channel></rss>"#;
+
+/// Serves `FEED_XML` on every path; returns its url.
+fn spawn_feed_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                FEED_XML.len(),
+                FEED_XML
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+fn store() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    let item = |id: &str, title: &str| FeedItem {
+        id: id.to_string(),
+        feed_url: BLOG.to_string(),
+        title: title.to_string(),
+        link: Some(id.to_string()),
+        published: None,
+        summary: None,
+        content: Some("<p>Body</p>".to_string()),
+        enclosures: vec![],
+        ..Default::default()
+    };
+    store.add_feed(
+        Feed {
+            url: BLOG.to_string(),
+            title: Some("Blog".to_string()),
+            ..Default::default()
+        },
+        vec![item(POST, "Post one"), item("post-2", "Post two")],
+    );
+    store
+}
+
+/// Start a server on a free port for the store at `path`; returns its base url.
+fn start(path: &Path, token: Option<&str>) -> String {
+    let server =
+        Server::bind("127.0.0.1:0", path.to_path_buf(), token.map(str::to_string)).unwrap();
+    let base = format!("http://{}/api", server.local_addr().unwrap());
+    std::thread::spawn(move || server.run());
+    base
+}
+
+fn cli_json(path: &Path, args: &[&str]) -> serde_json::Value {
+    let out = bin()
+        .args(["--config", path.to_str().unwrap(), "-o", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    serde_json::from_slice(&out.stdout).unwrap()
+}
+
+fn client() -> reqwest::blocking::Client {
+    reqwest::blocking::Client::new()
+}
+
+fn call(
+    method: reqwest::Method,
+    url: &str,
+    body: Option<serde_json::Value>,
+) -> (u16, serde_json::Value) {
+    let mut request = client().request(method, url).bearer_auth(TOKEN);
+    if let Some(body) = body {
+        request = request.json(&body);
+    } else {
+        request = request.header("Content-Type", "application/json");
+    }
+    let response = request.send().unwrap();
+    let status = response.status().as_u16();
+    (status, response.json().unwrap())
+}
+
+fn get(url: &str) -> (u16, serde_json::Value) {
+    call(reqwest::Method::GET, url, None)
+}
+
+#[test]
+fn requires_token_when_configured() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let base = start(&path, Some(TOKEN));
+    let feeds = format!("{}/feeds", base);
+    assert_eq!(client().get(&feeds).send().unwrap().status().as_u16(), 401);
+    let wrong = client().get(&feeds).bearer_auth("s3creT").send().unwrap();
+    assert_eq!(wrong.status().as_u16(), 401);
+    assert_eq!(get(&feeds).0, 200);
+
+    let open = start(&path, None);
+    let response = client().get(format!("{}/feeds", open)).send().unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[test]
+fn read_endpoints_match_cli_json() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let base = start(&path, Some(TOKEN));
+
+    assert_eq!(
+        get(&format!("{}/feeds", base)).1,
+        cli_json(&path, &["list-feeds"])
+    );
+    assert_eq!(
+        get(&format!("{}/items?unread&sort=title&limit=1", base)).1,
+        cli_json(
+            &path,
+            &["list-items", "--unread", "--sort", "title", "--limit", "1"]
+        )
+    );
+    let encoded = "https%3A%2F%2Fblog.example.com%2Fposts%2F1";
+    assert_eq!(
+        get(&format!("{}/items/{}", base, encoded)).1,
+        cli_json(&path, &["show", POST])
+    );
+
+    assert_eq!(get(&format!("{}/items/nope", base)).0, 404);
+    assert_eq!(get(&format!("{}/items?sort=size", base)).0, 400);
+    assert_eq!(get(&format!("{}/nothing", base)).0, 404);
+}
+
+#[test]
+fn read_star_add_remove_and_refresh() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let base = start(&path, Some(TOKEN));
+    let item = format!("{}/items/post-2", base);
+
+    let (status, body) = call(reqwest::Method::PUT, &format!("{}/read", item), None);
+    assert_eq!(status, 200);
+    assert_eq!(body["updated_count"], 1);
+    call(reqwest::Method::PUT, &format!("{}/starred", item), None);
+    let items = cli_json(&path, &["list-items", "--starred"]);
+    assert_eq!(items[0]["id"], "post-2");
+    assert_eq!(items[0]["read"], true);
+    call(reqwest::Method::DELETE, &format!("{}/read", item), None);
+    assert_eq!(
+        cli_json(&path, &["list-items", "--unread"])
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        call(
+            reqwest::Method::PUT,
+            &format!("{}/items/nope/read", base),
+            None
+        )
+        .0,
+        404
+    );
+
+    let feed_url = spawn_feed_server();
+    let feeds = format!("{}/feeds", base);
+    let (status, body) = call(
+        reqwest::Method::POST,
+        &feeds,
+        Some(serde_json::json!({ "url": feed_url })),
+    );
+    assert_eq!(status, 201, "{}", body);
+    assert_eq!(body["added"], serde_json::json!([feed_url]));
+    let (status, _) = call(
+        reqwest::Method::POST,
+        &feeds,
+        Some(serde_json::json!({ "link": feed_url })),
+    );
+    assert_eq!(status, 400);
+
+    let (status, report) = call(
+        reqwest::Method::POST,
+        &format!("{}/refresh", base),
+        Some(serde_json::json!({ "feed": feed_url })),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(report["success"], true);
+    assert_eq!(report["feeds"].as_array().unwrap().len(), 1);
+
+    let (status, body) = call(
+        reqwest::Method::DELETE,
+        &format!("{}?url={}", feeds, BLOG),
+        None,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["message"], format!("Removed feed: {}", BLOG));
+    let remaining = cli_json(&path, &["list-feeds"]);
+    assert_eq!(remaining.as_array().unwrap().len(), 1);
+    assert_eq!(remaining[0]["url"], feed_url);
+}
+
+#[test]
+fn refuses_cross_site_and_rebound_requests() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let base = start(&path, None);
+    let port = base.split(':').nth(2).unwrap().trim_end_matches("/api");
+    let feeds = format!("{}/feeds", base);
+
+    // A form or fetch() with a text/plain body needs no preflight, so it must not count.
+    let response = client()
+        .post(&feeds)
+        .header("Content-Type", "text/plain")
+        .body(r#"{"url": "http://10.0.0.1/admin"}"#)
+        .send()
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 415);
+    let response = client()
+        .put(format!("{}/items/post-2/read", base))
+        .send()
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 415);
+    assert_eq!(
+        cli_json(&path, &["list-feeds"]).as_array().unwrap().len(),
+        1
+    );
+    assert_eq!(
+        cli_json(&path, &["list-items", "--unread"])
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // DNS rebinding: the attacker's name resolves to 127.0.0.1 but stays in Host.
+    let status = |host: &str, origin: Option<&str>| {
+        let mut request = client().get(&feeds).header("Host", host);
+        if let Some(origin) = origin {
+            request = request.header("Origin", origin);
+        }
+        request.send().unwrap().status().as_u16()
+    };
+    assert_eq!(status(&format!("evil.example.com:{}", port), None), 403);
+    assert_eq!(status("127.0.0.1:1", None), 403);
+    assert_eq!(status(&format!("localhost:{}", port), None), 200);
+    assert_eq!(status(&format!("127.0.0.1:{}", port), None), 200);
+
+    // Browsers send Origin on cross-origin requests; only the server itself may call.
+    let host = format!("127.0.0.1:{}", port);
+    assert_eq!(status(&host, Some("http://evil.example.com")), 403);
+    assert_eq!(status(&host, Some("null")), 403);
+    assert_eq!(status(&host, Some(&format!("http://{}", host))), 200);
+}
+
+#[test]
+fn serves_more_clients_than_workers() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let base = start(&path, None);
+    let addr = base.trim_start_matches("http://").trim_end_matches("/api");
+    // Idle connections occupy workers only until they time out or close.
+    let idle: Vec<_> = (0..4)
+        .map(|_| std::net::TcpStream::connect(addr).unwrap())
+        .collect();
+    let clients: Vec<_> = (0..40)
+        .map(|_| {
+            let url = format!("{}/feeds", base);
+            std::thread::spawn(move || client().get(url).send().unwrap().status().as_u16())
+        })
+        .collect();
+    for client in clients {
+        assert_eq!(client.join().unwrap(), 200);
+    }
+    drop(idle);
+}
@@ -0,0 +1,258 @@
+//! Integration test: the TOML settings file — data path, output format, date format,
+//! article width, HTTP user agent and per-feed item cap — overridden by environment
+//! variables and command-line flags.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rss_reader::settings::{Settings, StoreSettings};
+use rss_reader::store;
+use rss_reader::{Feed, FeedItem, SubscriptionList};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+const FEED: &str = "https://blog.example.com/feed.xml";
+
+/// A temp dir holding a store with one article and a settings file with `extra` lines
+/// after `data_path`. Returns the dir and the settings path.
+fn setup(extra: &str) -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let data = dir.path().join("data.json");
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: FEED.to_string(),
+            title: Some("Blog".to_string()),
+            ..Default::default()
+        },
+        vec![FeedItem {
+            id: "a1".to_string(),
+            feed_url: FEED.to_string(),
+            title: "Settings matter".to_string(),
+            link: None,
+            published: Some(
+                chrono::DateTime::parse_from_rfc3339("2024-03-05T12:30:00Z")
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            ),
+            summary: None,
+            content: Some(format!("<p>{}</p>", "word ".repeat(30))),
+            enclosures: vec![],
+            ..Default::default()
+        }],
+    );
+    store.save(&data).unwrap();
+    let settings = dir.path().join("config.toml");
+    std::fs::write(
+        &settings,
+        format!("data_path = {:?}\n{}", data.display().to_string(), extra),
+    )
+    .unwrap();
+    (dir, settings)
+}
+
+fn settings_arg(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[test]
+fn settings_file_sets_data_path_and_output() {
+    let (_dir, settings) = setup("output = \"json\"\n");
+    let out = bin()
+        .args(["--settings", &settings_arg(&settings), "list-items"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let items: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(items[0]["id"], "a1");
+
+    // RSS_READER_SETTINGS names the file too; the flag and the environment override it.
+    bin()
+        .env("RSS_READER_SETTINGS", &settings)
+        .args(["-o", "human", "list-items"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Settings matter |"));
+    bin()
+        .env("RSS_READER_SETTINGS", &settings)
+        .env("RSS_READER_OUTPUT", "human")
+        .arg("list-items")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("*"));
+}
+
+#[test]
+fn date_format_and_width_shape_human_output() {
+    let (_dir, settings) = setup("date_format = \"%d/%m/%Y\"\nwidth = 40\n");
+    bin()
+        .args(["--settings", &settings_arg(&settings), "list-items"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("* 05/03/2024 | Settings matter"));
+
+    let out = bin()
+        .args(["--settings", &settings_arg(&settings), "show", "a1"])
+        .output()
+        .unwrap();
+    let text = String::from_utf8(out.stdout).unwrap();
+    assert!(text.contains("05/03/2024"));
+    let body: Vec<&str> = text.lines().filter(|l| l.contains("word")).collect();
+    assert!(body.len() > 1);
+    assert!(body.iter().all(|l| l.chars().count() <= 40));
+
+    // RSS_READER_WIDTH beats the file.
+    let out = bin()
+        .env("RSS_READER_WIDTH", "120")
+        .args(["--settings", &settings_arg(&settings), "show", "a1"])
+        .output()
+        .unwrap();
+    let text = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(text.lines().filter(|l| l.contains("word")).count(), 2);
+}
+
+/// Serves a feed with `count` items and records each request's User-Agent.
+fn spawn_feed(count: usize) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+    let agents = Arc::new(Mutex::new(Vec::new()));
+    let seen = agents.clone();
+    let items: String = (0..count)
+        .map(|i| format!("<item><guid>g{0}</guid><title>Item {0}</title></item>", i))
+        .collect();
+    let body = format!(
+        "<?xml version=\"1.0\"?><rss version=\"2.0\"><channel><title>Local</title>\
+         <link>http://localhost/</link><description>t</description>{}</channel></rss>",
+        items
+    );
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&buf).to_string();
+            if let Some(agent) = request.lines().find_map(|l| {
+                l.strip_prefix("user-agent: ")
+                    .or(l.strip_prefix("User-Agent: "))
+            }) {
+                seen.lock().unwrap().push(agent.to_string());
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    (url, agents)
+}
+
+#[test]
+fn fetch_and_store_settings_apply_to_refresh() {
+    let (url, agents) = spawn_feed(5);
+    let (dir, settings) = setup(
+        "[fetch]\nuser_agent = \"settings-test/1.0\"\ntimeout_secs = 5\n\n\
+         [store]\nmax_items_per_feed = 3\n",
+    );
+    bin()
+        .args(["--settings", &settings_arg(&settings), "add", &url])
+        .assert()
+        .success();
+    assert_eq!(agents.lock().unwrap().last().unwrap(), "settings-test/1.0");
+    let store = SubscriptionList::load(&dir.path().join("data.json")).unwrap();
+    assert_eq!(store.items_by_feed[&url].len(), 3);
+
+    // RSS_READER_USER_AGENT and RSS_READER_MAX_ITEMS override the file.
+    bin()
+        .env("RSS_READER_USER_AGENT", "env-agent")
+        .env("RSS_READER_MAX_ITEMS", "4")
+        .args(["--settings", &settings_arg(&settings), "refresh", &url])
+        .assert()
+        .success();
+    assert_eq!(agents.lock().unwrap().last().unwrap(), "env-agent");
+    let store = SubscriptionList::load(&dir.path().join("data.json")).unwrap();
+    assert_eq!(store.items_by_feed[&url].len(), 4);
+}
+
+#[test]
+fn stores_opened_with_different_caps_keep_their_own() {
+    let dir = tempfile::tempdir().unwrap();
+    let items = |feed: &str| -> Vec<FeedItem> {
+        (0..5)
+            .map(|i| FeedItem {
+                id: format!("{}-{}", feed, i),
+                feed_url: feed.to_string(),
+                title: format!("Item {}", i),
+                ..Default::default()
+            })
+            .collect()
+    };
+    let mut lists = Vec::new();
+    for (name, max) in [("small.json", 2), ("large.json", 4)] {
+        let settings = StoreSettings {
+            max_items_per_feed: max,
+        };
+        let storage = store::open_with(&dir.path().join(name), &settings).unwrap();
+        lists.push(storage.load().unwrap());
+    }
+    for list in &mut lists {
+        list.add_feed(
+            Feed {
+                url: FEED.to_string(),
+                ..Default::default()
+            },
+            items(FEED),
+        );
+    }
+    assert_eq!(lists[0].items_by_feed[FEED].len(), 2);
+    assert_eq!(lists[1].items_by_feed[FEED].len(), 4);
+}
+
+#[test]
+fn invalid_settings_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "widht = 100\n").unwrap();
+    bin()
+        .args(["--settings", &settings_arg(&path), "list-feeds"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("config.toml").and(predicate::str::contains("widht")));
+
+    bin()
+        .args([
+            "--settings",
+            &settings_arg(&dir.path().join("missing.toml")),
+            "list-feeds",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("missing.toml"));
+
+    assert!(Settings::parse("output = \"xml\"").is_err());
+    let defaults = Settings::parse("").unwrap();
+    assert_eq!(defaults, Settings::default());
+    let mut settings = Settings::parse("width = 100").unwrap();
+    settings
+        .apply_env(|name| (name == "RSS_READER_WIDTH").then(|| "60".to_string()))
+        .unwrap();
+    assert_eq!(settings.width, Some(60));
+    assert!(settings
+        .apply_env(|name| (name == "RSS_READER_TIMEOUT").then(|| "soon".to_string()))
+        .is_err());
+}
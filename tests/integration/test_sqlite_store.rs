@@ -0,0 +1,235 @@
+//! Integration test: SQLite backend round-trips the store, persists incremental changes
+//! (keeping feed and download order), looks up single items and a feed's items, and
+//! migrates an existing JSON store and the older single-row layout of its own fields.
+
+use assert_cmd::Command;
+use chrono::{TimeZone, Utc};
+use predicates::prelude::*;
+use rss_reader::store;
+use rss_reader::{
+    Feed, FeedItem, JsonStorage, MediaEnclosure, SqliteStorage, Storage, SubscriptionList,
+};
+use std::path::PathBuf;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.db");
+    (dir, path)
+}
+
+const FEED_URL: &str = "https://example.com/feed.xml";
+
+fn feed() -> Feed {
+    Feed {
+        url: FEED_URL.to_string(),
+        title: Some("Example Feed".to_string()),
+        folder: Some("News/Tech".to_string()),
+        ..Default::default()
+    }
+}
+
+fn item(n: i64) -> FeedItem {
+    FeedItem {
+        id: format!("item-{}", n),
+        feed_url: FEED_URL.to_string(),
+        title: format!("Item {}", n),
+        link: None,
+        published: Some(Utc.timestamp_opt(n * 60, 0).unwrap()),
+        summary: None,
+        content: Some("<p>Body</p>".to_string()),
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+fn sample() -> SubscriptionList {
+    let mut list = SubscriptionList::default();
+    list.add_feed(feed(), (0..3).map(item).collect());
+    list.set_read("item-0", true);
+    list.set_starred("item-1", true);
+    list
+}
+
+#[test]
+fn sqlite_roundtrip_and_incremental_save() {
+    let (_dir, path) = temp_config();
+    let storage = SqliteStorage::open(&path).unwrap();
+    storage.save(&mut sample()).unwrap();
+
+    let mut loaded = storage.load().unwrap();
+    assert_eq!(loaded.feeds.len(), 1);
+    assert_eq!(loaded.feeds[0].folder.as_deref(), Some("News/Tech"));
+    assert_eq!(loaded.items(Some(FEED_URL)).len(), 3);
+    assert!(loaded.is_read("item-0"));
+    assert!(loaded.is_starred("item-1"));
+
+    // Only the changed state is written; everything else must survive untouched.
+    assert!(loaded.set_read("item-0", false));
+    assert!(loaded.set_read("item-2", true));
+    loaded.add_feed(feed(), vec![item(3)]);
+    storage.save(&mut loaded).unwrap();
+
+    let reopened = SqliteStorage::open(&path).unwrap().load().unwrap();
+    assert!(!reopened.is_read("item-0"));
+    assert!(reopened.is_read("item-2"));
+    assert!(reopened.is_starred("item-1"));
+    assert_eq!(reopened.items(Some(FEED_URL)).len(), 4);
+}
+
+#[test]
+fn sqlite_get_item_and_remove_feed() {
+    let (_dir, path) = temp_config();
+    let storage = SqliteStorage::open(&path).unwrap();
+    let mut list = sample();
+    storage.save(&mut list).unwrap();
+
+    let found = storage.get_item("item-2").unwrap().expect("item-2 stored");
+    assert_eq!(found.title, "Item 2");
+    assert!(storage.get_item("missing").unwrap().is_none());
+
+    list.remove_feed(FEED_URL);
+    storage.save(&mut list).unwrap();
+    assert!(storage.get_item("item-2").unwrap().is_none());
+    // Starred item stays cached after its feed is removed.
+    assert!(storage.get_item("item-1").unwrap().is_some());
+    assert!(storage.load().unwrap().feeds.is_empty());
+}
+
+#[test]
+fn open_migrates_json_store_and_cli_reads_it() {
+    let (dir, path) = temp_config();
+    sample().save(&dir.path().join("data.json")).unwrap();
+
+    let storage = store::open(&path).unwrap();
+    assert!(path.exists());
+    let loaded = storage.load().unwrap();
+    assert_eq!(loaded.items(None).len(), 3);
+    assert!(loaded.is_read("item-0"));
+
+    bin()
+        .args(["--config", path.to_str().unwrap(), "list-feeds"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Example Feed"));
+    bin()
+        .args(["--config", path.to_str().unwrap(), "show", "item-2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Item 2"));
+    bin()
+        .args(["--config", path.to_str().unwrap(), "show", "missing"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn feed_items_are_newest_first_in_both_backends() {
+    let (dir, path) = temp_config();
+    let backends: [Box<dyn Storage>; 2] = [
+        Box::new(SqliteStorage::open(&path).unwrap()),
+        Box::new(JsonStorage::new(&dir.path().join("data.json"))),
+    ];
+    for storage in backends {
+        storage.save(&mut sample()).unwrap();
+        let ids: Vec<String> = storage
+            .feed_items(FEED_URL)
+            .unwrap()
+            .into_iter()
+            .map(|i| i.id)
+            .collect();
+        assert_eq!(ids, ["item-2", "item-1", "item-0"]);
+        assert!(storage
+            .feed_items("https://other.example/")
+            .unwrap()
+            .is_empty());
+    }
+}
+
+#[test]
+fn item_states_downloads_and_positions_have_their_own_rows() {
+    let (_dir, path) = temp_config();
+    let storage = SqliteStorage::open(&path).unwrap();
+    let mut list = sample();
+    for name in ["b", "c"] {
+        list.subscribe(Feed {
+            url: format!("https://{}.example/feed", name),
+            ..Default::default()
+        });
+    }
+    list.hidden_items.insert("item-2".to_string());
+    list.item_tags
+        .insert("item-0".to_string(), vec!["rust".to_string()]);
+    storage.save(&mut list).unwrap();
+
+    // Incremental saves: remove a feed, add one, queue a download, save a position.
+    let mut list = storage.load().unwrap();
+    assert!(list.is_hidden("item-2"));
+    assert_eq!(list.item_tags("item-0"), ["rust"]);
+    list.remove_feed("https://b.example/feed");
+    list.subscribe(Feed {
+        url: "https://d.example/feed".to_string(),
+        ..Default::default()
+    });
+    let mut episode = item(9);
+    episode.enclosures = vec![MediaEnclosure {
+        url: "https://example.com/9.mp3".to_string(),
+        ..Default::default()
+    }];
+    list.add_feed(feed(), vec![episode]);
+    assert!(list.queue_download("item-9", 0).unwrap());
+    list.set_playback_position("item-9", "https://example.com/9.mp3", 42, Some(600));
+    storage.save(&mut list).unwrap();
+
+    let list = SqliteStorage::open(&path).unwrap().load().unwrap();
+    let urls: Vec<&str> = list.feeds.iter().map(|f| f.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        [FEED_URL, "https://c.example/feed", "https://d.example/feed"]
+    );
+    assert!(list.is_hidden("item-2"));
+    assert_eq!(list.item_tags("item-0"), ["rust"]);
+    assert!(list.download("https://example.com/9.mp3").is_some());
+    let position = list.playback_position("https://example.com/9.mp3").unwrap();
+    assert_eq!(position.position_secs, 42);
+}
+
+#[test]
+fn open_migrates_the_single_extra_row() {
+    let (_dir, path) = temp_config();
+    SqliteStorage::open(&path)
+        .unwrap()
+        .save(&mut sample())
+        .unwrap();
+    // The older layout kept every field without a table in one `extra` row.
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        r#"DELETE FROM meta;
+           INSERT INTO meta VALUES ('extra',
+               '{"revision":7,"explicit_folders":["Empty"],"hidden_items":["item-2"]}');"#,
+    )
+    .unwrap();
+    drop(conn);
+
+    let storage = SqliteStorage::open(&path).unwrap();
+    let mut list = storage.load().unwrap();
+    assert!(list.is_hidden("item-2"));
+    assert!(list.folders().iter().any(|f| f == "Empty"));
+    assert!(list.is_read("item-0"));
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let legacy: i64 = conn
+        .query_row("SELECT COUNT(*) FROM meta WHERE key = 'extra'", [], |r| {
+            r.get(0)
+        })
+        .unwrap();
+    assert_eq!(legacy, 0);
+
+    // Saves continue from the migrated revision.
+    assert!(list.set_read("item-1", true));
+    storage.save(&mut list).unwrap();
+    assert!(storage.load().unwrap().is_read("item-1"));
+}
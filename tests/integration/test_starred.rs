@@ -0,0 +1,123 @@
+//! Integration test: starred items are exempt from the per-feed cap, survive `remove_feed`,
+//! and are listed by `list-items --starred`.
+
+use assert_cmd::Command;
+use chrono::{TimeZone, Utc};
+use rss_reader::{Feed, FeedItem, SubscriptionList};
+use std::path::PathBuf;
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const FEED_URL: &str = "https://example.com/feed.xml";
+
+fn feed() -> Feed {
+    Feed {
+        url: FEED_URL.to_string(),
+        title: Some("Example Feed".to_string()),
+        description: None,
+        last_fetched: None,
+        created_at: None,
+        ..Default::default()
+    }
+}
+
+/// Item `n` is published `n` minutes after the epoch, so higher `n` is newer.
+fn item(n: i64) -> FeedItem {
+    FeedItem {
+        id: format!("item-{}", n),
+        feed_url: FEED_URL.to_string(),
+        title: format!("Item {}", n),
+        link: None,
+        published: Some(Utc.timestamp_opt(n * 60, 0).unwrap()),
+        summary: None,
+        content: None,
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn starred_item_is_not_dropped_by_cap() {
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(), vec![item(0)]);
+    assert!(store.set_starred("item-0", true));
+
+    // 500 newer items push item-0 past the cap.
+    store.add_feed(feed(), (1..=500).map(item).collect());
+    assert!(store.get_item("item-0", None).is_some());
+    assert_eq!(store.items(Some(FEED_URL)).len(), 501);
+
+    store.add_feed(feed(), vec![item(501)]);
+    assert!(
+        store.get_item("item-1", None).is_none(),
+        "oldest unstarred item is capped"
+    );
+    assert!(store.get_item("item-0", None).is_some());
+}
+
+#[test]
+fn starred_items_survive_remove_feed() {
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(), vec![item(1), item(2)]);
+    store.set_starred("item-1", true);
+    assert!(store.remove_feed(FEED_URL));
+
+    assert!(store.feeds.is_empty());
+    let starred: Vec<&str> = store.starred().iter().map(|i| i.id.as_str()).collect();
+    assert_eq!(starred, vec!["item-1"]);
+    assert!(store.get_item("item-2", None).is_none());
+
+    // Unstarring an orphaned item drops it.
+    store.set_starred("item-1", false);
+    assert!(store.items(None).is_empty());
+}
+
+#[test]
+fn star_and_unstar_commands_drive_list_items_starred() {
+    let (_dir, path) = temp_config();
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(), vec![item(1), item(2)]);
+    store.save(&path).unwrap();
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("star")
+        .arg("item-2")
+        .assert()
+        .success();
+
+    let output = bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("-o")
+        .arg("json")
+        .arg("list-items")
+        .arg("--starred")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let arr: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(arr.as_array().unwrap().len(), 1);
+    assert_eq!(arr[0]["id"], "item-2");
+    assert_eq!(arr[0]["starred"], true);
+
+    bin()
+        .arg("--config")
+        .arg(&path)
+        .arg("unstar")
+        .arg("item-2")
+        .assert()
+        .success();
+    let store = SubscriptionList::load(&path).unwrap();
+    assert!(!store.is_starred("item-2"));
+}
@@ -0,0 +1,469 @@
+//! Integration test: `sync` with self-hosted aggregators — against this reader's own
+//! Google Reader API and a mock Miniflux server — pulling feeds, items and state, pushing
+//! local changes, and resolving conflicts.
+
+use assert_cmd::Command;
+use rss_reader::server::Server;
+use rss_reader::sync::{sync, Account, ConflictPolicy, ServiceKind, SyncOptions};
+use rss_reader::{Feed, FeedItem, HttpOptions, SubscriptionList};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[allow(deprecated)] // CI uses default build dir; cargo_bin_cmd! needs extra setup
+fn bin() -> Command {
+    Command::cargo_bin("rss-reader").unwrap()
+}
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const USER: &str = "reader";
+const PASSWORD: &str = "correct horse";
+const BLOG: &str = "https://blog.example.com/feed.xml";
+const NEWS: &str = "https://news.example.com/rss";
+const LOCAL: &str = "https://local.example.com/feed.xml";
+
+fn item(feed_url: &str, id: &str, day: u32) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: format!("Title of {}", id),
+        link: Some(format!("{}/{}", feed_url, id)),
+        published: Some(
+            chrono::DateTime::parse_from_rfc3339(&format!("2024-03-{:02}T12:00:00Z", day))
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        ),
+        summary: None,
+        content: Some(format!("<p>{}</p>", id)),
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+fn feed(url: &str, title: &str, folder: Option<&str>) -> Feed {
+    Feed {
+        url: url.to_string(),
+        title: Some(title.to_string()),
+        folder: folder.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn sync_cli(path: &Path, args: &[&str]) -> Value {
+    let out = bin()
+        .args(["-c", path.to_str().unwrap(), "-o", "json", "sync"])
+        .args(args)
+        .env("RSS_READER_SYNC_PASSWORD", PASSWORD)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    serde_json::from_slice(&out.stdout).unwrap()
+}
+
+#[test]
+fn greader_sync_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let remote_path = dir.path().join("remote.json");
+    let mut remote = SubscriptionList::default();
+    remote.add_feed(
+        feed(BLOG, "Blog", Some("Tech")),
+        vec![item(BLOG, "b1", 1), item(BLOG, "b2", 2)],
+    );
+    remote.add_feed(feed(NEWS, "News", None), vec![item(NEWS, "n1", 3)]);
+    remote.set_read("b2", true);
+    remote.save(&remote_path).unwrap();
+    let server = Server::bind("127.0.0.1:0", remote_path.clone(), None)
+        .unwrap()
+        .with_login(USER, PASSWORD);
+    let url = format!("http://{}/greader", server.local_addr().unwrap());
+    std::thread::spawn(move || server.run());
+
+    let (_local_dir, path) = temp_config();
+    let status = sync_cli(&path, &["status"]);
+    assert_eq!(status["synced"], false);
+
+    let report = sync_cli(
+        &path,
+        &["run", "--service", "greader", "--url", &url, "--user", USER],
+    );
+    assert_eq!(report["feeds_added_locally"], 2);
+    assert_eq!(report["new_items"], 3);
+    assert_eq!(report["conflicts"].as_array().unwrap().len(), 0);
+    let local = SubscriptionList::load(&path).unwrap();
+    assert_eq!(local.feeds.len(), 2);
+    let blog = local.feeds.iter().find(|f| f.url == BLOG).unwrap();
+    assert_eq!(blog.folder.as_deref(), Some("Tech"));
+    let b2 = format!("{}/b2", BLOG);
+    assert!(local.is_read(&b2));
+    assert!(!local.is_read(&format!("{}/b1", BLOG)));
+
+    // A local change is pending until the next run pushes it.
+    let b1 = format!("{}/b1", BLOG);
+    bin()
+        .args(["-c", path.to_str().unwrap(), "mark-read", &b1])
+        .assert()
+        .success();
+    let status = sync_cli(&path, &["status"]);
+    assert_eq!(status["synced"], true);
+    assert_eq!(status["service"], "greader");
+    assert_eq!(status["pending"]["states"], 1);
+
+    // A remote change meanwhile: n1 starred on the server.
+    let mut remote = SubscriptionList::load(&remote_path).unwrap();
+    remote.set_starred("n1", true);
+    remote.save(&remote_path).unwrap();
+
+    // Service, url and user come from the last sync.
+    let report = sync_cli(&path, &["run"]);
+    assert_eq!(report["pushed_changes"], 1);
+    assert_eq!(report["pulled_changes"], 1);
+    assert_eq!(report["new_items"], 0);
+    let remote = SubscriptionList::load(&remote_path).unwrap();
+    assert!(remote.is_read("b1"));
+    let local = SubscriptionList::load(&path).unwrap();
+    assert!(local.is_starred(&format!("{}/n1", NEWS)));
+    assert_eq!(sync_cli(&path, &["status"])["pending"]["states"], 0);
+
+    let forgot = sync_cli(&path, &["forget"]);
+    assert_eq!(forgot["success"], true);
+    assert_eq!(sync_cli(&path, &["status"])["synced"], false);
+}
+
+#[test]
+fn greader_sync_rejects_bad_password() {
+    let dir = tempfile::tempdir().unwrap();
+    let remote_path = dir.path().join("remote.json");
+    SubscriptionList::default().save(&remote_path).unwrap();
+    let server = Server::bind("127.0.0.1:0", remote_path, None)
+        .unwrap()
+        .with_login(USER, PASSWORD);
+    let url = format!("http://{}/greader", server.local_addr().unwrap());
+    std::thread::spawn(move || server.run());
+
+    let (_local_dir, path) = temp_config();
+    let out = bin()
+        .args(["-c", path.to_str().unwrap(), "sync", "run"])
+        .args(["--service", "greader", "--url", &url, "--user", USER])
+        .args(["--password", "wrong"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("rejected the credentials"));
+}
+
+/// In-memory Miniflux: categories, feeds and entries, plus a log of write requests.
+#[derive(Default)]
+struct Miniflux {
+    categories: Vec<(i64, String)>,
+    /// (id, feed_url, title, category id)
+    feeds: Vec<(i64, String, String, i64)>,
+    /// (id, feed id, title, url, read, starred)
+    entries: Vec<(i64, i64, String, String, bool, bool)>,
+    writes: Vec<String>,
+}
+
+const TOKEN: &str = "miniflux-key";
+
+impl Miniflux {
+    fn category(&self, id: i64) -> Value {
+        let (id, title) = self.categories.iter().find(|c| c.0 == id).unwrap();
+        json!({ "id": id, "title": title })
+    }
+
+    fn handle(&mut self, method: &str, target: &str, body: &Value) -> (u16, Value) {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        if method != "GET" {
+            self.writes.push(format!("{} {} {}", method, path, body));
+        }
+        match (method, segments.as_slice()) {
+            ("GET", ["v1", "categories"]) => {
+                let list: Vec<Value> = self.categories.iter().map(|c| self.category(c.0)).collect();
+                (200, json!(list))
+            }
+            ("POST", ["v1", "categories"]) => {
+                let id = 100 + self.categories.len() as i64;
+                self.categories
+                    .push((id, body["title"].as_str().unwrap().to_string()));
+                (201, self.category(id))
+            }
+            ("GET", ["v1", "feeds"]) => {
+                let list: Vec<Value> = self
+                    .feeds
+                    .iter()
+                    .map(|(id, url, title, category)| {
+                        json!({ "id": id, "feed_url": url, "title": title,
+                                "category": self.category(*category) })
+                    })
+                    .collect();
+                (200, json!(list))
+            }
+            ("POST", ["v1", "feeds"]) => {
+                let id = 1000 + self.feeds.len() as i64;
+                let url = body["feed_url"].as_str().unwrap().to_string();
+                let category = body["category_id"].as_i64().unwrap();
+                self.feeds.push((id, url, "Added".to_string(), category));
+                (201, json!({ "feed_id": id }))
+            }
+            ("PUT", ["v1", "feeds", id]) => {
+                let id: i64 = id.parse().unwrap();
+                let feed = self.feeds.iter_mut().find(|f| f.0 == id).unwrap();
+                feed.3 = body["category_id"].as_i64().unwrap();
+                (201, json!({ "id": id }))
+            }
+            ("DELETE", ["v1", "feeds", id]) => {
+                let id: i64 = id.parse().unwrap();
+                self.feeds.retain(|f| f.0 != id);
+                (204, Value::Null)
+            }
+            ("GET", ["v1", "entries"]) => {
+                let param = |name: &str| {
+                    query
+                        .split('&')
+                        .find_map(|p| p.strip_prefix(&format!("{}=", name)))
+                        .and_then(|v| v.parse::<usize>().ok())
+                };
+                let (limit, offset) = (param("limit").unwrap_or(100), param("offset").unwrap_or(0));
+                let mut entries = self.entries.clone();
+                entries.sort_by_key(|e| std::cmp::Reverse(e.0));
+                let page: Vec<Value> = entries
+                    .iter()
+                    .skip(offset)
+                    .take(limit)
+                    .map(|(id, feed_id, title, url, read, starred)| {
+                        json!({ "id": id, "feed_id": feed_id, "title": title, "url": url,
+                                "content": "<p>body</p>",
+                                "published_at": format!("2024-03-{:02}T12:00:00Z", id % 28 + 1),
+                                "status": if *read { "read" } else { "unread" },
+                                "starred": starred })
+                    })
+                    .collect();
+                (200, json!({ "total": entries.len(), "entries": page }))
+            }
+            ("PUT", ["v1", "entries"]) => {
+                let read = body["status"] == "read";
+                for id in body["entry_ids"].as_array().unwrap() {
+                    let id = id.as_i64().unwrap();
+                    self.entries.iter_mut().find(|e| e.0 == id).unwrap().4 = read;
+                }
+                (204, Value::Null)
+            }
+            ("PUT", ["v1", "entries", id, "bookmark"]) => {
+                let id: i64 = id.parse().unwrap();
+                let entry = self.entries.iter_mut().find(|e| e.0 == id).unwrap();
+                entry.5 = !entry.5;
+                (204, Value::Null)
+            }
+            _ => (404, json!({ "error_message": "not found" })),
+        }
+    }
+}
+
+/// Serve `mock` over HTTP; returns its base url.
+fn serve_miniflux(mock: Arc<Mutex<Miniflux>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let mut parts = line.split_whitespace();
+            let (method, target) = (
+                parts.next().unwrap_or("").to_string(),
+                parts.next().unwrap_or("").to_string(),
+            );
+            let (mut length, mut authorized) = (0, false);
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                let (name, value) = header.split_once(':').unwrap();
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => length = value.trim().parse().unwrap(),
+                    "x-auth-token" => authorized = value.trim() == TOKEN,
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            let (status, reply) = if authorized {
+                mock.lock().unwrap().handle(&method, &target, &body)
+            } else {
+                (401, json!({ "error_message": "Access Unauthorized" }))
+            };
+            let reply = if reply.is_null() {
+                String::new()
+            } else {
+                reply.to_string()
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reply.len(),
+                reply
+            )
+            .unwrap();
+        }
+    });
+    base
+}
+
+fn miniflux() -> Miniflux {
+    Miniflux {
+        categories: vec![(1, "All".to_string()), (2, "Tech".to_string())],
+        feeds: vec![
+            (10, BLOG.to_string(), "Blog".to_string(), 2),
+            (11, NEWS.to_string(), "News".to_string(), 1),
+        ],
+        entries: vec![
+            (100, 10, "b1".into(), format!("{}/b1", BLOG), false, false),
+            (101, 10, "b2".into(), format!("{}/b2", BLOG), true, true),
+            (102, 11, "n1".into(), format!("{}/n1", NEWS), false, false),
+        ],
+        writes: vec![],
+    }
+}
+
+/// Local store with a feed the server lacks and NEWS (in another folder) with n1 read.
+fn local_store() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    store.add_feed(feed(LOCAL, "Local", None), vec![item(LOCAL, "l1", 1)]);
+    store.add_feed(feed(NEWS, "News", Some("Later")), vec![item(NEWS, "n1", 3)]);
+    store.set_read("n1", true);
+    store
+}
+
+fn account(url: &str) -> Account {
+    Account {
+        service: ServiceKind::Miniflux,
+        url: url.to_string(),
+        user: None,
+        password: None,
+        api_token: Some(TOKEN.to_string()),
+    }
+}
+
+fn run_sync(
+    store: &mut SubscriptionList,
+    account: &Account,
+    prefer: ConflictPolicy,
+) -> rss_reader::sync::SyncReport {
+    let options = SyncOptions {
+        prefer,
+        ..SyncOptions::default()
+    };
+    let service = account.connect(&HttpOptions::default()).unwrap();
+    sync(store, service.as_ref(), account, &options).unwrap()
+}
+
+#[test]
+fn miniflux_pull_push_and_remove() {
+    let mock = Arc::new(Mutex::new(miniflux()));
+    let account = account(&serve_miniflux(mock.clone()));
+    let mut store = local_store();
+
+    let report = run_sync(&mut store, &account, ConflictPolicy::Remote);
+    assert_eq!(report.feeds_added_locally, 1);
+    assert_eq!(report.feeds_added_remotely, 1);
+    assert_eq!(report.new_items, 2);
+    // No common base yet: NEWS's folder and n1's read state differ, the server wins.
+    assert_eq!(report.conflicts.len(), 2, "{:?}", report.conflicts);
+    let news = store.feeds.iter().find(|f| f.url == NEWS).unwrap();
+    assert_eq!(news.folder, None);
+    assert!(!store.is_read("n1"));
+    let blog = store.feeds.iter().find(|f| f.url == BLOG).unwrap();
+    assert_eq!(blog.folder.as_deref(), Some("Tech"));
+    let (b1, b2) = (format!("{}/b1", BLOG), format!("{}/b2", BLOG));
+    assert!(store.is_read(&b2) && store.is_starred(&b2));
+    {
+        let mock = mock.lock().unwrap();
+        let added = mock.feeds.iter().find(|f| f.1 == LOCAL).unwrap();
+        assert_eq!(added.3, 1, "top-level feeds go to the default category");
+    }
+
+    // Later changes on each side merge without conflicts.
+    store.set_read(&b1, true);
+    store.set_starred(&b2, false);
+    mock.lock().unwrap().entries[2].4 = true;
+    let report = run_sync(&mut store, &account, ConflictPolicy::Remote);
+    assert!(report.conflicts.is_empty(), "{:?}", report.conflicts);
+    assert_eq!(report.pushed_changes, 2);
+    assert_eq!(report.pulled_changes, 1);
+    assert!(store.is_read("n1"));
+    {
+        let mock = mock.lock().unwrap();
+        assert!(mock.entries[0].4, "b1 marked read on the server");
+        assert!(!mock.entries[1].5, "b2 unstarred on the server");
+    }
+
+    // Nothing changed: nothing is written.
+    mock.lock().unwrap().writes.clear();
+    let report = run_sync(&mut store, &account, ConflictPolicy::Remote);
+    assert_eq!(report.pushed_changes + report.pulled_changes, 0);
+    assert!(mock.lock().unwrap().writes.is_empty());
+
+    // Unsubscribing on either side carries over.
+    store.remove_feed(NEWS);
+    mock.lock().unwrap().feeds.retain(|f| f.1 != LOCAL);
+    let report = run_sync(&mut store, &account, ConflictPolicy::Remote);
+    assert_eq!(report.feeds_removed_remotely, 1);
+    assert_eq!(report.feeds_removed_locally, 1);
+    assert!(!mock.lock().unwrap().feeds.iter().any(|f| f.1 == NEWS));
+    let urls: Vec<&str> = store.feeds.iter().map(|f| f.url.as_str()).collect();
+    assert_eq!(urls, [BLOG]);
+}
+
+#[test]
+fn miniflux_prefer_local_pushes_conflicts() {
+    let mock = Arc::new(Mutex::new(miniflux()));
+    let account = account(&serve_miniflux(mock.clone()));
+    let mut store = local_store();
+
+    let report = run_sync(&mut store, &account, ConflictPolicy::Local);
+    assert_eq!(report.conflicts.len(), 2);
+    assert!(store.is_read("n1"));
+    let news = store.feeds.iter().find(|f| f.url == NEWS).unwrap();
+    assert_eq!(news.folder.as_deref(), Some("Later"));
+    let mock = mock.lock().unwrap();
+    assert!(mock.entries[2].4, "n1 marked read on the server");
+    let category = mock.feeds.iter().find(|f| f.1 == NEWS).unwrap().3;
+    assert_eq!(mock.category(category)["title"], "Later");
+}
+
+#[test]
+fn miniflux_bad_token_is_an_error() {
+    let mock = Arc::new(Mutex::new(miniflux()));
+    let mut account = account(&serve_miniflux(mock));
+    account.api_token = Some("wrong".to_string());
+    let service = account.connect(&HttpOptions::default()).unwrap();
+    let err = sync(
+        &mut SubscriptionList::default(),
+        service.as_ref(),
+        &account,
+        &SyncOptions::default(),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("rejected the credentials"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,343 @@
+//! Integration test: Fever and Google Reader APIs of `serve` — sign-in, listing feeds,
+//! groups and items, and syncing read/starred state back to the store.
+
+use rss_reader::server::Server;
+use rss_reader::{Feed, FeedItem, SubscriptionList};
+use std::path::{Path, PathBuf};
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const USER: &str = "reader";
+const PASSWORD: &str = "correct horse";
+const BLOG: &str = "https://blog.example.com/feed.xml";
+const NEWS: &str = "https://news.example.com/rss";
+
+fn item(feed_url: &str, id: &str, day: u32) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: format!("Title of {}", id),
+        link: Some(format!("{}/{}", feed_url, id)),
+        published: Some(
+            chrono::DateTime::parse_from_rfc3339(&format!("2024-03-{:02}T12:00:00Z", day))
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        ),
+        summary: None,
+        content: Some(format!("<p>{}</p>", id)),
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+fn store() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: BLOG.to_string(),
+            title: Some("Blog".to_string()),
+            site_url: Some("https://blog.example.com/".to_string()),
+            folder: Some("Tech".to_string()),
+            ..Default::default()
+        },
+        vec![item(BLOG, "b1", 1), item(BLOG, "b2", 2)],
+    );
+    store.add_feed(
+        Feed {
+            url: NEWS.to_string(),
+            title: Some("News".to_string()),
+            ..Default::default()
+        },
+        vec![item(NEWS, "n1", 3)],
+    );
+    store
+}
+
+fn start(path: &Path) -> String {
+    let server = Server::bind("127.0.0.1:0", path.to_path_buf(), Some("api".to_string()))
+        .unwrap()
+        .with_login(USER, PASSWORD);
+    let base = format!("http://{}", server.local_addr().unwrap());
+    std::thread::spawn(move || server.run());
+    base
+}
+
+fn fever(base: &str, query: &str, form: &[(&str, &str)]) -> serde_json::Value {
+    reqwest::blocking::Client::new()
+        .post(format!("{}/fever/?api&{}", base, query))
+        .form(form)
+        .send()
+        .unwrap()
+        .json()
+        .unwrap()
+}
+
+fn ids(list: &serde_json::Value) -> Vec<u64> {
+    let list = list.as_str().unwrap();
+    if list.is_empty() {
+        return vec![];
+    }
+    list.split(',').map(|s| s.parse().unwrap()).collect()
+}
+
+#[test]
+fn fever_lists_and_marks() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let base = start(&path);
+
+    let denied = fever(&base, "feeds", &[("api_key", "0000")]);
+    assert_eq!(denied["auth"], 0);
+    assert!(denied.get("feeds").is_none());
+
+    let api_key = fever_key();
+    let key = [("api_key", api_key.as_str())];
+    let lists = fever(&base, "groups&feeds", &key);
+    assert_eq!(lists["api_version"], 3);
+    assert_eq!(lists["auth"], 1);
+    assert_eq!(lists["groups"][0]["title"], "Tech");
+    let feeds = lists["feeds"].as_array().unwrap();
+    assert_eq!(feeds.len(), 2);
+    let blog = feeds.iter().find(|f| f["url"] == BLOG).unwrap();
+    assert_eq!(blog["site_url"], "https://blog.example.com/");
+    let news = feeds.iter().find(|f| f["url"] == NEWS).unwrap();
+    assert_eq!(news["site_url"], NEWS);
+    let blog_id = blog["id"].clone();
+    assert_eq!(
+        lists["feeds_groups"][0]["group_id"],
+        lists["groups"][0]["id"]
+    );
+    assert_eq!(lists["feeds_groups"][0]["feed_ids"], blog_id.to_string());
+
+    // Ids grow with publication time, so since_id pages forward.
+    let items = fever(&base, "items", &key);
+    assert_eq!(items["total_items"], 3);
+    let titles: Vec<&str> = items["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, ["Title of b1", "Title of b2", "Title of n1"]);
+    let first = items["items"][0]["id"].as_u64().unwrap();
+    let newer = fever(&base, &format!("items&since_id={}", first), &key);
+    assert_eq!(newer["items"].as_array().unwrap().len(), 2);
+
+    let unread = ids(&fever(&base, "unread_item_ids", &key)["unread_item_ids"]);
+    assert_eq!(unread.len(), 3);
+    let id = first.to_string();
+    fever(
+        &base,
+        "",
+        &[key[0], ("mark", "item"), ("as", "read"), ("id", &id)],
+    );
+    fever(
+        &base,
+        "",
+        &[key[0], ("mark", "item"), ("as", "saved"), ("id", &id)],
+    );
+    let store = SubscriptionList::load(&path).unwrap();
+    assert!(store.is_read("b1"));
+    assert!(store.is_starred("b1"));
+    let saved = ids(&fever(&base, "saved_item_ids", &key)["saved_item_ids"]);
+    assert_eq!(saved, [first]);
+
+    // Group 0 is everything; `before` limits marking to items published up to then.
+    let before = chrono::DateTime::parse_from_rfc3339("2024-03-02T12:00:00Z")
+        .unwrap()
+        .timestamp()
+        .to_string();
+    fever(
+        &base,
+        "",
+        &[
+            key[0],
+            ("mark", "group"),
+            ("as", "read"),
+            ("id", "0"),
+            ("before", &before),
+        ],
+    );
+    let store = SubscriptionList::load(&path).unwrap();
+    assert!(store.is_read("b2"));
+    assert!(!store.is_read("n1"));
+}
+
+#[test]
+fn undated_items_with_colliding_hashes_get_distinct_ids() {
+    let (_dir, path) = temp_config();
+    // These ids share the low 21 bits of their hash, so undated they would share an id.
+    let undated: Vec<FeedItem> = [
+        "https://example.com/post/5079",
+        "https://example.com/post/10484",
+    ]
+    .iter()
+    .map(|id| FeedItem {
+        published: None,
+        ..item(BLOG, id, 1)
+    })
+    .collect();
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: BLOG.to_string(),
+            ..Default::default()
+        },
+        undated,
+    );
+    store.save(&path).unwrap();
+    let base = start(&path);
+
+    let api_key = fever_key();
+    let key = [("api_key", api_key.as_str())];
+    let unread = ids(&fever(&base, "unread_item_ids", &key)["unread_item_ids"]);
+    assert_eq!(unread.len(), 2);
+    assert_ne!(unread[0], unread[1]);
+    let id = unread[1].to_string();
+    fever(
+        &base,
+        "",
+        &[key[0], ("mark", "item"), ("as", "read"), ("id", &id)],
+    );
+    let store = SubscriptionList::load(&path).unwrap();
+    assert_eq!(
+        [
+            "https://example.com/post/5079",
+            "https://example.com/post/10484"
+        ]
+        .iter()
+        .filter(|id| store.is_read(id))
+        .count(),
+        1
+    );
+}
+
+/// Hex MD5 of `user:password`, as a Fever client computes it.
+fn fever_key() -> String {
+    format!("{:x}", md5::compute(format!("{}:{}", USER, PASSWORD)))
+}
+
+#[test]
+fn greader_login_streams_and_edit_tag() {
+    let (_dir, path) = temp_config();
+    store().save(&path).unwrap();
+    let base = start(&path);
+    let client = reqwest::blocking::Client::new();
+    let login = |password: &str| {
+        client
+            .post(format!("{}/greader/accounts/ClientLogin", base))
+            .form(&[("Email", USER), ("Passwd", password)])
+            .send()
+            .unwrap()
+    };
+    assert_eq!(login("wrong").status().as_u16(), 401);
+    let body = login(PASSWORD).text().unwrap();
+    let auth = body
+        .lines()
+        .find_map(|l| l.strip_prefix("Auth="))
+        .unwrap()
+        .to_string();
+    let api = |call: &str| format!("{}/greader/reader/api/0/{}", base, call);
+    let get = |call: &str| -> serde_json::Value {
+        let response = client
+            .get(api(call))
+            .header("Authorization", format!("GoogleLogin auth={}", auth))
+            .send()
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200, "{}", call);
+        response.json().unwrap()
+    };
+    let post = |call: &str, form: &[(&str, &str)]| {
+        let response = client
+            .post(api(call))
+            .header("Authorization", format!("GoogleLogin auth={}", auth))
+            .form(form)
+            .send()
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200, "{}", call);
+        response.text().unwrap()
+    };
+
+    let anonymous = client.get(api("subscription/list")).send().unwrap();
+    assert_eq!(anonymous.status().as_u16(), 401);
+
+    let subs = get("subscription/list?output=json");
+    let blog = &subs["subscriptions"][0];
+    assert_eq!(blog["id"], format!("feed/{}", BLOG));
+    assert_eq!(blog["categories"][0]["id"], "user/-/label/Tech");
+
+    let counts = get("unread-count?output=json");
+    let count = |id: &str| {
+        counts["unreadcounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["id"] == id)
+            .map(|c| c["count"].as_u64().unwrap())
+    };
+    assert_eq!(count("user/-/state/com.google/reading-list"), Some(3));
+    assert_eq!(count("user/-/label/Tech"), Some(2));
+
+    // Newest first, two per page with a continuation.
+    let page = get("stream/items/ids?s=user/-/state/com.google/reading-list&n=2");
+    let refs = page["itemRefs"].as_array().unwrap();
+    assert_eq!(refs.len(), 2);
+    assert_eq!(page["continuation"], "2");
+    let rest = get("stream/items/ids?s=user/-/state/com.google/reading-list&n=2&c=2");
+    assert_eq!(rest["itemRefs"].as_array().unwrap().len(), 1);
+    assert!(rest.get("continuation").is_none());
+
+    let newest = refs[0]["id"].as_str().unwrap().to_string();
+    let contents = get(&format!("stream/items/contents?i={}", newest));
+    assert_eq!(contents["items"][0]["title"], "Title of n1");
+    let long_id = contents["items"][0]["id"].as_str().unwrap().to_string();
+    assert!(long_id.starts_with("tag:google.com,2005:reader/item/"));
+
+    assert_eq!(
+        post(
+            "edit-tag",
+            &[
+                ("i", long_id.as_str()),
+                ("a", "user/-/state/com.google/read"),
+                ("a", "user/-/state/com.google/starred"),
+            ],
+        ),
+        "OK"
+    );
+    let store = SubscriptionList::load(&path).unwrap();
+    assert!(store.is_read("n1"));
+    assert!(store.is_starred("n1"));
+
+    let unread = get(
+        "stream/items/ids?s=user/-/state/com.google/reading-list&xt=user/-/state/com.google/read",
+    );
+    assert_eq!(unread["itemRefs"].as_array().unwrap().len(), 2);
+    let tech = get("stream/contents/user%2F-%2Flabel%2FTech?r=o");
+    let titles: Vec<&str> = tech["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, ["Title of b1", "Title of b2"]);
+
+    post("mark-all-as-read", &[("s", &format!("feed/{}", BLOG))]);
+    post(
+        "subscription/edit",
+        &[
+            ("ac", "edit"),
+            ("s", &format!("feed/{}", NEWS)),
+            ("t", "World News"),
+            ("a", "user/-/label/Daily"),
+        ],
+    );
+    let store = SubscriptionList::load(&path).unwrap();
+    assert_eq!(store.unread_count(None), 0);
+    let news = store.feeds.iter().find(|f| f.url == NEWS).unwrap();
+    assert_eq!(news.title.as_deref(), Some("World News"));
+    assert_eq!(news.folder.as_deref(), Some("Daily"));
+}
@@ -0,0 +1,214 @@
+//! Integration test: terminal UI on a test backend — panes render, vim keys move between
+//! feeds and articles, opening an article marks it read, search stays within the selected
+//! feed, and `d` downloads an enclosure.
+
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Terminal;
+use rss_reader::tui::{Pane, Tui};
+use rss_reader::{Feed, FeedItem, MediaEnclosure, SubscriptionList};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+fn temp_config() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.json");
+    (dir, path)
+}
+
+const BLOG: &str = "https://blog.example.com/feed.xml";
+const NEWS: &str = "https://news.example.com/rss";
+
+fn item(feed_url: &str, id: &str, day: u32) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: format!("Title of {}", id),
+        link: Some(format!("{}/{}", feed_url, id)),
+        published: Some(
+            chrono::DateTime::parse_from_rfc3339(&format!("2024-03-{:02}T12:00:00Z", day))
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        ),
+        summary: None,
+        content: Some(format!("<p>Body of {}</p>", id)),
+        enclosures: vec![],
+        ..Default::default()
+    }
+}
+
+fn open(path: &Path, mut store: SubscriptionList) -> Tui {
+    store.save(path).unwrap();
+    let storage = rss_reader::store::open(path).unwrap();
+    let store = storage.load().unwrap();
+    Tui::new(store, storage)
+}
+
+fn press(tui: &mut Tui, keys: &str) {
+    for c in keys.chars() {
+        tui.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+}
+
+fn screen(tui: &mut Tui) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(140, 30)).unwrap();
+    terminal.draw(|frame| tui.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn store() -> SubscriptionList {
+    let mut store = SubscriptionList::default();
+    store.add_feed(
+        Feed {
+            url: BLOG.to_string(),
+            title: Some("Blog".to_string()),
+            folder: Some("Tech".to_string()),
+            ..Default::default()
+        },
+        vec![item(BLOG, "b1", 1), item(BLOG, "b2", 2)],
+    );
+    store.add_feed(
+        Feed {
+            url: NEWS.to_string(),
+            title: Some("News".to_string()),
+            ..Default::default()
+        },
+        vec![item(NEWS, "n1", 3)],
+    );
+    store
+}
+
+#[test]
+fn panes_render_and_keys_navigate() {
+    let (_dir, path) = temp_config();
+    let mut tui = open(&path, store());
+
+    let text = screen(&mut tui);
+    assert!(text.contains("All (3)"));
+    assert!(text.contains("▾ Tech (2)"));
+    assert!(text.contains("Blog (2)"));
+    // "All" is selected: newest first, and the reader shows the first one.
+    assert!(text.contains("Title of n1"));
+    assert!(text.contains("Body of n1"));
+
+    // Feeds: All, Starred, Tech, Blog, News. `3j` lands on Blog.
+    press(&mut tui, "3j");
+    assert_eq!(
+        tui.selection(),
+        &rss_reader::store::FeedSelection::Feed(BLOG.to_string())
+    );
+    let text = screen(&mut tui);
+    assert!(text.contains("Title of b2"));
+    assert!(!text.contains("Title of n1"));
+
+    // `l` focuses the articles, `j` moves to b1, `l` opens it in the reader and marks it read.
+    press(&mut tui, "ljl");
+    assert_eq!(tui.focus(), Pane::Reader);
+    assert_eq!(tui.selected_item().unwrap().id, "b1");
+    assert!(tui.store().is_read("b1"));
+    assert!(screen(&mut tui).contains("Body of b1"));
+
+    // `s` stars, `m` toggles read back; both are saved.
+    press(&mut tui, "sm");
+    let saved = SubscriptionList::load(&path).unwrap();
+    assert!(saved.is_starred("b1"));
+    assert!(!saved.is_read("b1"));
+
+    // `u` lists unread only; `A` marks the listed ones read.
+    press(&mut tui, "hhgu");
+    assert!(screen(&mut tui).contains("[unread]"));
+    press(&mut tui, "A");
+    assert_eq!(tui.store().unread_count(None), 0);
+    assert!(screen(&mut tui).contains("Marked 3 article(s) read"));
+
+    press(&mut tui, "q");
+    assert!(tui.should_quit());
+}
+
+#[test]
+fn search_narrows_articles() {
+    let (_dir, path) = temp_config();
+    let mut tui = open(&path, store());
+    press(&mut tui, "/b2");
+    tui.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    let text = screen(&mut tui);
+    assert!(text.contains("[/b2]"));
+    assert!(text.contains("Title of b2"));
+    assert!(!text.contains("Title of n1"));
+    tui.handle_key(KeyEvent::new(KeyCode::Char('/'), KeyModifiers::NONE));
+    tui.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    assert!(screen(&mut tui).contains("Title of n1"));
+}
+
+#[test]
+fn search_limit_applies_to_the_selected_feed() {
+    let (_dir, path) = temp_config();
+    let mut store = store();
+    // More newer matches in another feed than the hit limit.
+    let news = (0..250).map(|i| item(NEWS, &format!("n{}", i + 2), 28));
+    store.add_feed(
+        Feed {
+            url: NEWS.to_string(),
+            ..Default::default()
+        },
+        news.collect(),
+    );
+    let mut tui = open(&path, store);
+    press(&mut tui, "3j/title");
+    tui.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    let text = screen(&mut tui);
+    assert!(text.contains("Title of b1"));
+    assert!(text.contains("Title of b2"));
+}
+
+#[test]
+fn downloads_enclosure() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/episode.mp3", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf);
+        let _ = stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\nContent-Length: 5\r\nConnection: close\r\n\r\naudio",
+        );
+    });
+
+    let (dir, path) = temp_config();
+    let mut store = SubscriptionList::default();
+    let mut episode = item(NEWS, "e1", 1);
+    episode.enclosures = vec![MediaEnclosure {
+        url,
+        media_type: Some("audio/mpeg".to_string()),
+        length: Some(5),
+        title: None,
+        ..Default::default()
+    }];
+    store.add_feed(
+        Feed {
+            url: NEWS.to_string(),
+            ..Default::default()
+        },
+        vec![episode],
+    );
+    let mut tui = open(&path, store).with_download_dir(dir.path().to_path_buf());
+    assert!(screen(&mut tui).contains("[1] http://"));
+
+    press(&mut tui, "2d");
+    assert!(screen(&mut tui).contains("No enclosure 2"));
+    press(&mut tui, "d");
+    assert!(screen(&mut tui).contains("Downloaded to"));
+    assert_eq!(
+        std::fs::read(dir.path().join("episode.mp3")).unwrap(),
+        b"audio"
+    );
+}